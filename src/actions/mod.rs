@@ -0,0 +1,179 @@
+use anyhow::Result;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::client::{InstallAction, InstallReport};
+
+/// The file GitHub Actions renders as the job summary, see
+/// <https://docs.github.com/en/actions/using-workflows/workflow-commands-for-github-actions#adding-a-job-summary>.
+const STEP_SUMMARY: &str = "GITHUB_STEP_SUMMARY";
+
+/// The file GitHub Actions reads the outputs of a step from.
+const OUTPUT: &str = "GITHUB_OUTPUT";
+
+/// Returns the file an environment variable of GitHub Actions points to, if it is set.
+fn _env_file(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Render the dependencies that were installed, updated or failed as a markdown table.
+///
+/// Each report is preceded by a heading if it is named, i.e. for the members of a workspace.
+fn _markdown(reports: &[(&str, &InstallReport)]) -> String {
+    let mut markdown = String::from("## sink install\n");
+    for (name, report) in reports {
+        if !name.is_empty() {
+            markdown.push_str(&format!("\n### {name}\n"));
+        }
+
+        let rows: Vec<_> = report
+            .summary
+            .iter()
+            .filter(|row| row.action != InstallAction::Skipped)
+            .collect();
+        let skipped = report.summary.len() - rows.len();
+        if rows.is_empty() {
+            markdown.push_str("\nAll dependencies are up to date.\n");
+            continue;
+        }
+
+        markdown.push_str("\n| Dependency | Action | Version | Destination |\n");
+        markdown.push_str("| --- | --- | --- | --- |\n");
+        for row in rows {
+            // Pipes would end the cell early
+            let cells = [
+                row.pathspec.to_string(),
+                row.action.to_string(),
+                row.version.clone(),
+                row.destination.clone(),
+            ]
+            .map(|cell| format!("`{}`", cell.replace('|', "\\|")));
+            markdown.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        if skipped > 0 {
+            markdown.push_str(&format!("\n{skipped} dependencies are up to date.\n"));
+        }
+    }
+
+    markdown
+}
+
+/// Returns the step outputs as `name=value` lines.
+fn _outputs(reports: &[(&str, &InstallReport)]) -> String {
+    let count = |action: InstallAction| -> usize {
+        reports
+            .iter()
+            .flat_map(|(_, report)| report.summary.iter())
+            .filter(|row| row.action == action)
+            .count()
+    };
+    let installed = count(InstallAction::Installed);
+    let updated = count(InstallAction::Updated);
+    let failed = count(InstallAction::Failed);
+
+    format!(
+        "changed={}\ninstalled={installed}\nupdated={updated}\nfailed={failed}\n",
+        installed + updated > 0
+    )
+}
+
+/// Append to a file of GitHub Actions, which is shared by all steps of the job.
+fn _append(path: &Path, contents: &str) -> Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(contents.as_bytes())?;
+    Ok(())
+}
+
+fn _report(
+    reports: &[(&str, &InstallReport)],
+    summary: Option<&Path>,
+    output: Option<&Path>,
+) -> Result<()> {
+    if let Some(path) = summary {
+        debug!("Writing the job summary to '{}'", path.display());
+        _append(path, &_markdown(reports))?;
+    }
+    if let Some(path) = output {
+        debug!("Writing the step outputs to '{}'", path.display());
+        _append(path, &_outputs(reports))?;
+    }
+    Ok(())
+}
+/// Write the outcome of `sink install` as the job summary and step outputs of GitHub Actions.
+///
+/// Does nothing outside of GitHub Actions, i.e. if neither `GITHUB_STEP_SUMMARY` nor `GITHUB_OUTPUT` is set.
+/// The outputs are `changed` (whether anything was installed or updated) and the number of dependencies
+/// that were `installed`, `updated` and `failed`.
+pub fn report(reports: &[(&str, &InstallReport)]) -> Result<()> {
+    match _report(
+        reports,
+        _env_file(STEP_SUMMARY).as_deref(),
+        _env_file(OUTPUT).as_deref(),
+    ) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(e.context("Failed to write the GitHub Actions job summary!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::InstallSummary;
+    use crate::github::GitHubPathspec;
+    use crate::test_utils::TempProject;
+
+    fn row(pathspec: &str, action: InstallAction, version: &str) -> InstallSummary {
+        InstallSummary {
+            pathspec: GitHubPathspec::try_from(String::from(pathspec)).unwrap(),
+            action,
+            version: String::from(version),
+            destination: String::from("bin"),
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let project = TempProject::new("actions-report").unwrap();
+        let summary = project.root.join("summary.md");
+        let output = project.root.join("output");
+
+        let report = InstallReport {
+            summary: vec![
+                row("owner/new", InstallAction::Installed, "v1.0.0"),
+                row("owner/old", InstallAction::Skipped, "v2.0.0"),
+                row("owner/bumped:a|b", InstallAction::Updated, "v3.0.0"),
+            ],
+            ..Default::default()
+        };
+        _report(&[("", &report)], Some(&summary), Some(&output)).unwrap();
+
+        let markdown = std::fs::read_to_string(&summary).unwrap();
+        assert!(markdown.contains("| `owner/new` | `installed` | `v1.0.0` | `bin` |\n"));
+        assert!(markdown.contains("| `owner/bumped:a\\|b` | `updated` | `v3.0.0` | `bin` |\n"));
+        assert!(!markdown.contains("owner/old"));
+        assert!(markdown.contains("1 dependencies are up to date."));
+        assert_eq!(
+            std::fs::read_to_string(&output).unwrap(),
+            "changed=true\ninstalled=1\nupdated=1\nfailed=0\n"
+        );
+
+        // Later steps append to the same files
+        let unchanged = InstallReport {
+            summary: vec![row("owner/old", InstallAction::Skipped, "v2.0.0")],
+            ..Default::default()
+        };
+        _report(&[("member", &unchanged)], Some(&summary), Some(&output)).unwrap();
+
+        let markdown = std::fs::read_to_string(&summary).unwrap();
+        assert!(markdown.contains("owner/new"));
+        assert!(markdown.ends_with("### member\n\nAll dependencies are up to date.\n"));
+        assert!(std::fs::read_to_string(&output)
+            .unwrap()
+            .ends_with("changed=false\ninstalled=0\nupdated=0\nfailed=0\n"));
+    }
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Returns the path of the temporary file used to write the given path.
+///
+/// It is placed in the same directory, so renaming it is atomic.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let file_name = path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    path.with_file_name(format!(".{file_name}.sink-{}.tmp", std::process::id()))
+}
+
+fn _write(path: &Path, temp_path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = File::create(temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp_path, metadata.permissions())?;
+    }
+
+    fs::rename(temp_path, path)?;
+
+    Ok(())
+}
+/// Write the contents to the given path atomically.
+///
+/// The contents are written to a temporary file next to the target, which then replaces it.
+/// An interrupted write thus never leaves a truncated file behind. Permissions of an existing file are kept.
+pub fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let temp_path = temp_path_for(path);
+    debug!(
+        "Writing '{}' via '{}'...",
+        path.display(),
+        temp_path.display()
+    );
+
+    match _write(path, &temp_path, contents.as_ref()) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            // Never leave the temporary file behind
+            let _ = fs::remove_file(&temp_path);
+            Err(e.context(format!("Failed to write '{}'!", path.display())))
+        }
+    }
+}
+
+fn _symlink(target: &Path, link: &Path, temp_path: &Path) -> Result<()> {
+    if fs::symlink_metadata(link).is_ok_and(|metadata| !metadata.is_symlink()) {
+        return Err(anyhow::anyhow!(
+            "'{}' exists and is not a symlink!",
+            link.display()
+        ));
+    }
+
+    let _ = fs::remove_file(temp_path);
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(target, temp_path)?;
+    #[cfg(windows)]
+    std::os::windows::fs::symlink_file(target, temp_path)?;
+    fs::rename(temp_path, link)?;
+
+    Ok(())
+}
+/// Create or update the symlink at `link`, pointing to `target`, atomically.
+///
+/// The symlink is created next to `link` first and then replaces it, so `link` never dangles while being updated.
+/// Refuses to replace anything but a symlink.
+pub fn symlink(target: &Path, link: &Path) -> Result<()> {
+    let temp_path = temp_path_for(link);
+    debug!("Linking '{}' to '{}'...", link.display(), target.display());
+
+    match _symlink(target, link, &temp_path) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temp_path);
+            Err(e.context(format!("Failed to link '{}'!", link.display())))
+        }
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let dir = std::env::temp_dir().join(format!("sink-test-atomic-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("sink.toml");
+
+        write(&path, "first").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        }
+
+        write(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlink() {
+        let dir = std::env::temp_dir().join(format!("sink-test-symlink-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let link = dir.join("tool");
+
+        symlink(Path::new("tool-v1"), &link).unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("tool-v1"));
+        symlink(Path::new("tool-v2"), &link).unwrap();
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from("tool-v2"));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        // Files of the user are never replaced
+        let file = dir.join("file");
+        fs::write(&file, "mine").unwrap();
+        assert!(symlink(Path::new("tool-v2"), &file).is_err());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "mine");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
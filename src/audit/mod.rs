@@ -0,0 +1,300 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tracing::{debug, warn};
+
+use crate::github::{self, GitHubAdvisory, GitHubPathspec, GitHubVersion, Severity};
+use crate::lock::SinkLock;
+use crate::SinkTOML;
+
+/// A known vulnerability affecting the version of a dependency.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub pathspec: GitHubPathspec,
+
+    /// The audited version, i.e. the locked or pinned tag.
+    pub version: String,
+
+    pub ghsa_id: String,
+    pub cve_id: Option<String>,
+    pub severity: Severity,
+    pub summary: String,
+    pub url: String,
+
+    /// The versions fixing the vulnerability, if any are known.
+    pub patched_versions: Option<String>,
+}
+
+/// A dependency whose license is not allowed by the policy of the sink TOML.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LicenseViolation {
+    pub pathspec: GitHubPathspec,
+
+    /// The SPDX identifier of the license, `None` if it is unknown.
+    pub license: Option<String>,
+}
+
+/// The result of auditing all dependencies.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct AuditReport {
+    /// Known vulnerabilities, most severe first.
+    pub vulnerabilities: Vec<Finding>,
+
+    /// Dependencies violating the allowed licenses, see [`crate::policy::Policy::licenses`].
+    pub licenses: Vec<LicenseViolation>,
+}
+impl AuditReport {
+    /// Whether nothing was found.
+    pub fn is_empty(&self) -> bool {
+        self.vulnerabilities.is_empty() && self.licenses.is_empty()
+    }
+}
+
+/// Parse the numeric parts of a version, e.g. 'v1.2.3-rc.1' into `[1, 2, 3]`.
+///
+/// Returns `None` if the version does not start with a number, ignoring a leading 'v'.
+fn _numbers(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim().trim_start_matches(['v', 'V']);
+    let numbers: Vec<u64> = version
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map_while(|part| part.parse().ok())
+        .collect();
+
+    match numbers.is_empty() {
+        true => None,
+        false => Some(numbers),
+    }
+}
+
+/// Compare two versions by their numeric parts, missing parts count as zero.
+fn _compare(a: &[u64], b: &[u64]) -> std::cmp::Ordering {
+    let len = a.len().max(b.len());
+    let pad = |numbers: &[u64]| {
+        let mut numbers = numbers.to_vec();
+        numbers.resize(len, 0);
+        numbers
+    };
+    pad(a).cmp(&pad(b))
+}
+
+/// Whether the version lies within the range of an advisory, e.g. '>= 1.0.0, < 1.2.3'.
+///
+/// Returns `None` if the version or the range cannot be parsed.
+pub fn affects(range: &str, version: &str) -> Option<bool> {
+    let version = _numbers(version)?;
+    for constraint in range.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let (operator, bound) = match constraint.find(|c: char| !"<>=! ".contains(c)) {
+            Some(index) => constraint.split_at(index),
+            None => return None,
+        };
+        let ordering = _compare(&version, &_numbers(bound)?);
+        let satisfied = match operator.trim() {
+            "<" => ordering.is_lt(),
+            "<=" => ordering.is_le(),
+            ">" => ordering.is_gt(),
+            ">=" => ordering.is_ge(),
+            "=" | "==" | "" => ordering.is_eq(),
+            "!=" => ordering.is_ne(),
+            _ => return None,
+        };
+        if !satisfied {
+            return Some(false);
+        }
+    }
+
+    Some(true)
+}
+
+/// Returns the finding if the advisory affects the version of the dependency.
+fn _finding(
+    pathspec: &GitHubPathspec,
+    version: &str,
+    advisory: &GitHubAdvisory,
+) -> Option<Finding> {
+    advisory
+        .vulnerabilities
+        .iter()
+        .find(|vulnerability| {
+            let Some(range) = &vulnerability.vulnerable_version_range else {
+                return false;
+            };
+            match affects(range, version) {
+                Some(affected) => affected,
+                None => {
+                    warn!(
+                        "Cannot tell whether {} affects {pathspec}@{version}: unsupported range '{range}'!",
+                        advisory.ghsa_id
+                    );
+                    false
+                }
+            }
+        })
+        .map(|vulnerability| Finding {
+            pathspec: pathspec.clone(),
+            version: version.to_string(),
+            ghsa_id: advisory.ghsa_id.clone(),
+            cve_id: advisory.cve_id.clone(),
+            severity: advisory.severity,
+            summary: advisory.summary.clone(),
+            url: advisory.html_url.clone(),
+            patched_versions: vulnerability.patched_versions.clone(),
+        })
+}
+
+fn _audit(sink_toml: &SinkTOML, sink_lock: &SinkLock, severity: Severity) -> Result<AuditReport> {
+    let mut advisories: BTreeMap<String, Vec<GitHubAdvisory>> = BTreeMap::new();
+    let mut report = AuditReport::default();
+
+    let mut pathspecs: Vec<&GitHubPathspec> = sink_toml.dependencies.keys().collect();
+    pathspecs.sort();
+    for pathspec in pathspecs {
+        let locked = sink_lock.dependencies.get(pathspec);
+        if !sink_toml.policy.licenses.is_empty() {
+            let license = match locked.and_then(|locked| locked.license.clone()) {
+                Some(license) => Some(license),
+                None => github::api::api().view_license(&pathspec.get_full_origin())?,
+            };
+            if !sink_toml.policy.allows_license(license.as_deref()) {
+                report.licenses.push(LicenseViolation {
+                    pathspec: pathspec.clone(),
+                    license,
+                });
+            }
+        }
+
+        // Prefer the installed version over the requested one
+        let version = match locked {
+            Some(locked) => locked.version.clone(),
+            None => {
+                match sink_toml.dependencies[pathspec]
+                    .to_github_dependency(pathspec)?
+                    .version
+                {
+                    GitHubVersion::Tag(tag) | GitHubVersion::Draft(tag) => tag,
+                    version => {
+                        warn!("{pathspec} is not locked and not pinned to a tag ('{version}'), skipping!");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let origin = pathspec.get_full_origin();
+        if !advisories.contains_key(&origin) {
+            debug!("Fetching the security advisories of '{origin}'...");
+            advisories.insert(origin.clone(), github::api::api().list_advisories(&origin)?);
+        }
+        for advisory in advisories[&origin].iter() {
+            if advisory.severity >= severity {
+                report
+                    .vulnerabilities
+                    .extend(_finding(pathspec, &version, advisory));
+            }
+        }
+    }
+
+    // Most severe first
+    report
+        .vulnerabilities
+        .sort_by_key(|finding| std::cmp::Reverse(finding.severity));
+
+    Ok(report)
+}
+/// Check the versions of all dependencies against the published security advisories of their repositories.
+///
+/// The locked version of a dependency is audited, or else the tag it is pinned to.
+/// Only advisories of at least the given severity are reported.
+/// If the policy restricts the allowed licenses, the licenses of all dependencies are checked as well.
+pub fn audit(
+    sink_toml: &SinkTOML,
+    sink_lock: &SinkLock,
+    severity: Severity,
+) -> Result<AuditReport> {
+    match _audit(sink_toml, sink_lock, severity) {
+        Ok(report) => Ok(report),
+        Err(e) => Err(e.context("Failed to audit dependencies!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_affects() {
+        assert_eq!(affects(">= 1.0.0, < 1.2.3", "v1.2.2"), Some(true));
+        assert_eq!(affects(">= 1.0.0, < 1.2.3", "v1.2.3"), Some(false));
+        assert_eq!(affects("< 2", "1.9.9-rc.1"), Some(true));
+        assert_eq!(affects("= 1.0", "v1.0.0"), Some(true));
+        assert_eq!(affects("<= 0.9.0", "v1.0.0"), Some(false));
+        assert_eq!(affects("< 1.0.0", "nightly"), None);
+        assert_eq!(affects("~> 1.0", "v1.0.0"), None);
+    }
+
+    #[test]
+    fn test_audit() {
+        use crate::github::GitHubAdvisoryVulnerability;
+        use crate::test_utils::{MockReleaseServer, TempProject};
+
+        let advisory = |id: &str, severity: Severity, range: &str| GitHubAdvisory {
+            ghsa_id: String::from(id),
+            severity,
+            vulnerabilities: vec![GitHubAdvisoryVulnerability {
+                vulnerable_version_range: Some(String::from(range)),
+                patched_versions: None,
+            }],
+            ..Default::default()
+        };
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_advisory("owner/repo", advisory("GHSA-low", Severity::Low, "< 1.5.0"))
+            .with_advisory(
+                "owner/repo",
+                advisory("GHSA-high", Severity::High, "< 1.1.0"),
+            )
+            .with_advisory(
+                "owner/repo",
+                advisory("GHSA-fixed", Severity::Critical, "< 1.0.0"),
+            )
+            .with_license("owner/repo", "MIT");
+        let _guard = server.install();
+
+        let project = TempProject::new("audit").unwrap();
+        let mut sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:tool", "v1.0.0")
+            .dependency("owner/other:tool", "latest")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+
+        let ids = |severity: Severity| -> Vec<String> {
+            audit(&sink_toml, &sink_lock, severity)
+                .unwrap()
+                .vulnerabilities
+                .into_iter()
+                .map(|finding| finding.ghsa_id)
+                .collect()
+        };
+        assert_eq!(ids(Severity::Unknown), vec!["GHSA-high", "GHSA-low"]);
+        assert_eq!(ids(Severity::High), vec!["GHSA-high"]);
+        assert!(audit(&sink_toml, &sink_lock, Severity::Unknown)
+            .unwrap()
+            .licenses
+            .is_empty());
+
+        sink_toml.policy.licenses = vec![String::from("mit")];
+        let report = audit(&sink_toml, &sink_lock, Severity::Unknown).unwrap();
+        assert_eq!(
+            report.licenses,
+            vec![LicenseViolation {
+                pathspec: GitHubPathspec::try_from(String::from("owner/other:tool")).unwrap(),
+                license: None,
+            }]
+        );
+    }
+}
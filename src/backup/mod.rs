@@ -0,0 +1,165 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{debug, info};
+
+use crate::atomic;
+
+/// The number of backups kept per sink TOML, older ones are deleted.
+const MAX_BACKUPS: usize = 10;
+
+/// Returns the directory containing the backups of the given sink TOML.
+///
+/// This is `.sink/backups` next to the sink TOML.
+pub fn dir_for(sink_toml_path: &Path) -> PathBuf {
+    let root = match sink_toml_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+
+    root.join(".sink").join("backups")
+}
+
+/// Returns the backups of the given sink TOML, oldest first.
+fn list(sink_toml_path: &Path) -> Result<Vec<PathBuf>> {
+    let dir = dir_for(sink_toml_path);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let prefix = format!("{}.", file_name(sink_toml_path));
+    let mut backups: Vec<(u128, PathBuf)> = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let timestamp = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(&prefix))
+            .and_then(|timestamp| timestamp.parse::<u128>().ok());
+        if let Some(timestamp) = timestamp {
+            backups.push((timestamp, path));
+        }
+    }
+    backups.sort();
+
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Returns the file name of the sink TOML, e.g. 'sink.toml'.
+fn file_name(sink_toml_path: &Path) -> String {
+    sink_toml_path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_else(|| String::from("sink.toml"))
+}
+
+fn _create(sink_toml_path: &Path) -> Result<PathBuf> {
+    let dir = dir_for(sink_toml_path);
+    fs::create_dir_all(&dir)?;
+
+    // Keep the backups out of version control
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, "*\n")?;
+    }
+
+    let mut timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    if let Some(latest) = list(sink_toml_path)?.last() {
+        // Keep the order, even if two backups are created within the same millisecond
+        let latest = latest
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| extension.parse::<u128>().ok())
+            .unwrap_or_default();
+        timestamp = timestamp.max(latest + 1);
+    }
+
+    let path = dir.join(format!("{}.{timestamp}", file_name(sink_toml_path)));
+    fs::copy(sink_toml_path, &path)?;
+    debug!(
+        "Backed up '{}' to '{}'!",
+        sink_toml_path.display(),
+        path.display()
+    );
+
+    let backups = list(sink_toml_path)?;
+    for outdated in backups
+        .iter()
+        .take(backups.len().saturating_sub(MAX_BACKUPS))
+    {
+        debug!("Removing outdated backup '{}'...", outdated.display());
+        fs::remove_file(outdated)?;
+    }
+
+    Ok(path)
+}
+/// Save a timestamped backup of the given sink TOML.
+///
+/// Only the last few backups are kept. Returns the path of the backup.
+pub fn create(sink_toml_path: &Path) -> Result<PathBuf> {
+    match _create(sink_toml_path) {
+        Ok(path) => Ok(path),
+        Err(e) => Err(e.context("Failed to back up the sink TOML!")),
+    }
+}
+
+fn _restore(sink_toml_path: &Path) -> Result<PathBuf> {
+    let Some(latest) = list(sink_toml_path)?.pop() else {
+        return Err(anyhow::anyhow!(
+            "There is no backup of '{}'!",
+            sink_toml_path.display()
+        ));
+    };
+
+    info!(
+        "Restoring '{}' from '{}'...",
+        sink_toml_path.display(),
+        latest.display()
+    );
+    atomic::write(sink_toml_path, fs::read(&latest)?)?;
+    fs::remove_file(&latest)?;
+
+    Ok(latest)
+}
+/// Restore the given sink TOML from its latest backup, which is consumed.
+///
+/// Restoring repeatedly rolls back one change at a time. Returns the path of the restored backup.
+pub fn restore(sink_toml_path: &Path) -> Result<PathBuf> {
+    match _restore(sink_toml_path) {
+        Ok(path) => Ok(path),
+        Err(e) => Err(e.context("Failed to restore the sink TOML!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_restore() {
+        let root = std::env::temp_dir().join(format!("sink-test-backup-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("sink.toml");
+
+        fs::write(&path, "first").unwrap();
+        create(&path).unwrap();
+        fs::write(&path, "second").unwrap();
+        create(&path).unwrap();
+        fs::write(&path, "third").unwrap();
+
+        restore(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        restore(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+        assert!(restore(&path).is_err());
+
+        for _ in 0..MAX_BACKUPS + 2 {
+            create(&path).unwrap();
+        }
+        assert_eq!(list(&path).unwrap().len(), MAX_BACKUPS);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+#[cfg(any(test, feature = "test-utils"))]
+thread_local! {
+    /// The directory replacing the user-level one on this thread, see [`set_dir`].
+    static DIR: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Replace the cache directory of the current thread, so tests never touch the cache of the user.
+///
+/// Tests run on threads of their own, so they do not see the directories of each other.
+/// Passing `None` restores the default, see [`dir`].
+#[cfg(any(test, feature = "test-utils"))]
+pub fn set_dir(dir: Option<PathBuf>) {
+    DIR.with(|current| *current.borrow_mut() = dir);
+}
+
+/// Returns the user-level cache directory of sink.
+///
+/// This is `$XDG_CACHE_HOME/sink`, falling back to `~/.cache/sink`.
+pub fn dir() -> Option<PathBuf> {
+    #[cfg(any(test, feature = "test-utils"))]
+    if let Some(dir) = DIR.with(|dir| dir.borrow().clone()) {
+        return Some(dir);
+    }
+
+    if let Some(cache_home) = env::var_os("XDG_CACHE_HOME").filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(cache_home).join("sink"));
+    }
+
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(".cache").join("sink"))
+}
+
+/// How many characters of a key are kept readable in the file name of its entry.
+const READABLE: usize = 32;
+
+/// Returns the file name of the cache entry with the given key.
+///
+/// The name ends with the SHA-256 of the whole key, so different keys never share a file.
+/// It starts with the key itself, made safe for file names, to tell the entries apart at a glance.
+pub fn file_name(key: &str) -> String {
+    let readable: String = key
+        .chars()
+        .take(READABLE)
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+
+    format!("{readable}-{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Returns the path of the cache entry with the given key.
+pub fn path_for(key: &str) -> Option<PathBuf> {
+    dir().map(|dir| dir.join(file_name(key)))
+}
+
+/// Read the cache entry with the given key, if it exists and is younger than `ttl`.
+pub fn read(key: &str, ttl: Duration) -> Option<String> {
+    let path = path_for(key)?;
+    let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or_default();
+    if age > ttl {
+        debug!("Cache entry '{key}' is outdated!");
+        return None;
+    }
+
+    debug!("Using cache entry '{key}'!");
+    fs::read_to_string(path).ok()
+}
+
+/// Write the cache entry with the given key.
+pub fn write(key: &str, contents: &str) -> Result<()> {
+    let Some(path) = path_for(key) else {
+        return Err(anyhow::anyhow!("Unable to determine the cache directory!"));
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name() {
+        // Both keys are the same once made safe for file names
+        let a = file_name("release-github.com/owner-x/repo");
+        let b = file_name("release-github.com/owner/x-repo");
+        assert_ne!(a, b);
+        assert!(a.starts_with("release-github-com-owner-x-repo-"));
+        assert_eq!(a, file_name("release-github.com/owner-x/repo"));
+
+        let long = file_name(&"a".repeat(1000));
+        assert!(long.len() < 128);
+    }
+}
@@ -0,0 +1,152 @@
+use anyhow::Result;
+use regex::Regex;
+use std::ops::ControlFlow;
+use tracing::debug;
+
+use crate::github::{self, GitHubRelease};
+
+/// Render the markdown of release notes as plain terminal text.
+///
+/// Headings, emphasis, inline code, links, images and HTML comments are stripped, code fences are removed
+/// while keeping their contents. Consecutive blank lines are collapsed.
+pub fn render(markdown: &str) -> String {
+    let comments = Regex::new(r"(?s)<!--.*?-->").unwrap();
+    let images = Regex::new(r"!\[(?<alt>[^\]]*)\]\([^)]*\)").unwrap();
+    let links = Regex::new(r"\[(?<text>[^\]]*)\]\([^)]*\)").unwrap();
+    let emphasis = Regex::new(r"\*\*|__|~~|`").unwrap();
+    let heading = Regex::new(r"^#{1,6}\s+").unwrap();
+    let bullet = Regex::new(r"^(?<indent>\s*)[*+-]\s+").unwrap();
+
+    let markdown = markdown.replace("\r\n", "\n");
+    let markdown = comments.replace_all(&markdown, "");
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            continue;
+        }
+
+        let line = images.replace_all(line, "$alt");
+        let line = links.replace_all(&line, "$text");
+        let line = emphasis.replace_all(&line, "");
+        let line = heading.replace(&line, "");
+        let line = bullet.replace(&line, "$indent- ");
+        let line = line.trim_end();
+
+        // Collapse consecutive blank lines
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+fn _between(origin: &str, from: &str, to: &str) -> Result<Vec<GitHubRelease>> {
+    let api = github::api::api();
+
+    // Releases are listed newest first, so collect everything from the target down to the current tag
+    let mut between = Vec::new();
+    let mut collecting = false;
+    github::search_releases(origin, |release| {
+        if release.tag_name == from {
+            return Ok(ControlFlow::Break(()));
+        }
+        if release.tag_name == to {
+            collecting = true;
+        }
+        if !collecting || release.is_prerelease {
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        debug!("Fetching the release notes of '{}'...", release.tag_name);
+        between.push(api.view_release(origin, Some(&release.tag_name))?);
+        Ok(ControlFlow::Continue(()))
+    })?;
+
+    if !collecting {
+        return Err(anyhow::anyhow!(
+            "'{to}' is not among the {} most recent releases of '{origin}'!",
+            github::RELEASES_MAX
+        ));
+    }
+
+    Ok(between)
+}
+/// Returns the stable releases of the given 'owner/repository' after `from` up to and including `to`, newest first.
+pub fn between(origin: &str, from: &str, to: &str) -> Result<Vec<GitHubRelease>> {
+    match _between(origin, from, to) {
+        Ok(releases) => Ok(releases),
+        Err(e) => Err(e.context(format!(
+            "Failed to collect the release notes of '{origin}' between '{from}' and '{to}'!"
+        ))),
+    }
+}
+
+/// Concatenate the rendered release notes of the given releases into a single digest.
+///
+/// Each release is introduced by its tag, see [`render`].
+pub fn digest(releases: &[GitHubRelease]) -> String {
+    releases
+        .iter()
+        .map(|release| {
+            let notes = render(&release.body);
+            match notes.is_empty() {
+                true => format!("{}:\n    No release notes.", release.tag_name),
+                false => format!(
+                    "{}:\n{}",
+                    release.tag_name,
+                    notes
+                        .lines()
+                        .map(|line| format!("    {line}").trim_end().to_string())
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                ),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let markdown = "## What's Changed\r\n\r\n\r\n* **Fix** the [parser](https://example.com) in `lib.rs`\r\n  + nested\r\n<!-- hidden -->\r\n```sh\r\nsink install\r\n```\r\n![logo](logo.png)\r\n\r\n";
+
+        assert_eq!(
+            render(markdown),
+            "What's Changed\n\n- Fix the parser in lib.rs\n  - nested\n\nsink install\nlogo"
+        );
+        assert_eq!(render(""), "");
+    }
+
+    #[test]
+    fn test_between() {
+        use crate::test_utils::{MockRelease, MockReleaseServer};
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.3.0").body("# Three"))
+            .with_release("owner/repo", MockRelease::new("v1.2.0").body("* two"))
+            .with_release("owner/repo", MockRelease::new("v1.2.0-rc.1").prerelease())
+            .with_release("owner/repo", MockRelease::new("v1.1.0"))
+            .with_release("owner/repo", MockRelease::new("v1.0.0").body("one"));
+        let _guard = server.install();
+
+        let releases = between("owner/repo", "v1.0.0", "v1.2.0").unwrap();
+        assert_eq!(
+            digest(&releases),
+            "v1.2.0:\n    - two\n\nv1.1.0:\n    No release notes."
+        );
+
+        assert!(between("owner/repo", "v1.0.0", "v9.0.0").is_err());
+    }
+}
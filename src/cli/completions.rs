@@ -0,0 +1,179 @@
+use anyhow::Result;
+use clap::{CommandFactory, ValueEnum};
+use std::io::Write;
+use std::time::Duration;
+
+use super::SinkCLI;
+use crate::{cache, github, SinkTOML};
+
+/// How long fetched release tags are cached for completions.
+const TAGS_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The number of recent release tags offered as completions.
+const TAGS_LIMIT: usize = 30;
+
+/// Bash wrapper completing dependencies and versions dynamically, falling back to the static completions.
+const BASH_DYNAMIC: &str = r#"
+_sink_dynamic() {
+    local line="${COMP_LINE:0:COMP_POINT}" cur="" words
+    read -ra words <<< "$line"
+    if [[ "$line" != *" " ]]; then
+        cur="${words[-1]}"
+        unset 'words[-1]'
+    fi
+
+    local prev="${words[-1]}" subcommand="" dependency="" i
+    local file_args=()
+    for ((i = 1; i < ${#words[@]}; i++)); do
+        case "${words[i]}" in
+            -f|--file) file_args=(--file "${words[i+1]}"); ((i++)) ;;
+            -*) ;;
+            *)
+                if [[ -z "$subcommand" ]]; then
+                    subcommand="${words[i]}"
+                elif [[ -z "$dependency" ]]; then
+                    dependency="${words[i]}"
+                fi
+                ;;
+        esac
+    done
+
+    local candidates
+    if [[ "$subcommand" == "add" && -n "$dependency" && ( "$prev" == "--version" || "$prev" == "-t" ) ]]; then
+        candidates="$(sink "${file_args[@]}" complete-candidates versions "$dependency" 2>/dev/null)"
+    elif [[ ( "$subcommand" == "remove" || "$subcommand" == "clean" || "$subcommand" == "upgrade" ) && -z "$dependency" && "$cur" != -* ]]; then
+        candidates="$(sink "${file_args[@]}" complete-candidates dependencies 2>/dev/null)"
+    else
+        _sink "$@"
+        return
+    fi
+
+    COMPREPLY=($(compgen -W "$candidates" -- "$cur"))
+    if [[ "$cur" == *:* && "$COMP_WORDBREAKS" == *:* ]]; then
+        local prefix="${cur%"${cur##*:}"}"
+        for i in "${!COMPREPLY[@]}"; do
+            COMPREPLY[i]="${COMPREPLY[i]#"$prefix"}"
+        done
+    fi
+}
+complete -F _sink_dynamic -o nosort -o bashdefault -o default sink
+"#;
+
+/// Fish completions for dependencies and versions, added on top of the static completions.
+const FISH_DYNAMIC: &str = r#"
+function __sink_dependency
+    for word in (commandline -opc)[3..-1]
+        if not string match -q -- '-*' $word
+            echo $word
+            return
+        end
+    end
+end
+complete -c sink -n '__fish_seen_subcommand_from remove clean upgrade' -f -a '(sink complete-candidates dependencies 2>/dev/null)'
+complete -c sink -n '__fish_seen_subcommand_from add' -s t -l version -x -a '(sink complete-candidates versions (__sink_dependency) 2>/dev/null)'
+"#;
+
+/// The kinds of values that can be completed dynamically.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CompletionKind {
+    /// The pathspecs of the dependencies in the sink TOML
+    Dependencies,
+
+    /// The recent release tags of a repository
+    Versions,
+}
+
+/// Print the completions for the given shell to stdout.
+///
+/// For bash and fish, hooks completing dependencies and versions dynamically are appended.
+pub fn print_completions(shell: clap_complete::Shell) {
+    let mut stdout = std::io::stdout();
+    clap_complete::generate(
+        shell,
+        &mut SinkCLI::command(),
+        env!("CARGO_PKG_NAME"),
+        &mut stdout,
+    );
+
+    let dynamic = match shell {
+        clap_complete::Shell::Bash => BASH_DYNAMIC,
+        clap_complete::Shell::Fish => FISH_DYNAMIC,
+        _ => return,
+    };
+    // Ignore broken pipes, there is nothing sensible to do about them
+    let _ = stdout.write_all(dynamic.as_bytes());
+}
+
+/// Returns the pathspecs of all dependencies in the sink TOML, sorted.
+pub fn dependency_candidates(sink_toml: &SinkTOML) -> Vec<String> {
+    let mut candidates: Vec<String> = sink_toml
+        .dependencies
+        .keys()
+        .map(|pathspec| pathspec.to_string())
+        .collect();
+    candidates.sort();
+
+    candidates
+}
+
+/// Returns the 'owner/repository' part of a (partial) dependency specification.
+fn origin(dependency: &str, default_owner: &Option<String>) -> Option<String> {
+    let repository = dependency.split(':').next().unwrap_or_default();
+    if repository.is_empty() {
+        return None;
+    }
+
+    match (repository.contains('/'), default_owner) {
+        (true, _) => Some(repository.to_string()),
+        (false, Some(owner)) => Some(format!("{owner}/{repository}")),
+        (false, None) => None,
+    }
+}
+
+/// Returns the recent release tags of the repository of the given dependency.
+///
+/// The tags are cached to keep completions responsive.
+pub fn version_candidates(dependency: &str, default_owner: &Option<String>) -> Result<Vec<String>> {
+    let mut candidates = vec![String::from("latest"), String::from("prerelease")];
+    let Some(origin) = origin(dependency, default_owner) else {
+        return Ok(candidates);
+    };
+
+    let cache_key = format!("tags-{}/{origin}", github::api::host());
+    let tags = match cache::read(&cache_key, TAGS_TTL) {
+        Some(cached) => cached.lines().map(String::from).collect(),
+        None => {
+            let tags = github::list_tags(&origin, TAGS_LIMIT)?;
+            // A failing cache must never break completions
+            let _ = cache::write(&cache_key, &tags.join("\n"));
+            tags
+        }
+    };
+    candidates.extend(tags);
+
+    Ok(candidates)
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            origin("owner/repo:pattern", &None),
+            Some(String::from("owner/repo"))
+        );
+        assert_eq!(
+            origin("owner/repo", &None),
+            Some(String::from("owner/repo"))
+        );
+        assert_eq!(
+            origin("repo:pattern", &Some(String::from("owner"))),
+            Some(String::from("owner/repo"))
+        );
+        assert_eq!(origin("repo:pattern", &None), None);
+        assert_eq!(origin("", &Some(String::from("owner"))), None);
+    }
+}
@@ -0,0 +1,50 @@
+use anyhow::Result;
+use clap::CommandFactory;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tracing::info;
+
+use super::SinkCLI;
+
+/// Render the man page of the given command into a buffer.
+fn render(command: clap::Command) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    clap_mangen::Man::new(command).render(&mut buffer)?;
+
+    Ok(buffer)
+}
+
+fn _generate(dir: Option<&Path>) -> Result<()> {
+    let command = SinkCLI::command();
+    let name = command.get_name().to_string();
+
+    let Some(dir) = dir else {
+        std::io::stdout().write_all(&render(command)?)?;
+        return Ok(());
+    };
+
+    fs::create_dir_all(dir)?;
+    for subcommand in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        let page_name = format!("{name}-{}", subcommand.get_name());
+        let path = dir.join(format!("{page_name}.1"));
+        fs::write(&path, render(subcommand.clone().name(page_name))?)?;
+        info!("Generated '{}'!", path.display());
+    }
+
+    let path = dir.join(format!("{name}.1"));
+    fs::write(&path, render(command)?)?;
+    info!("Generated '{}'!", path.display());
+
+    Ok(())
+}
+/// Generate the roff man pages of sink.
+///
+/// Without a directory, only the page of the main command is printed to stdout.
+/// Otherwise, the pages of the main command and every subcommand are written into the directory.
+pub fn generate(dir: Option<&Path>) -> Result<()> {
+    match _generate(dir) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.context("Failed to generate man pages!")),
+    }
+}
@@ -1,6 +1,21 @@
-use clap::{Args, Parser, Subcommand};
+pub mod completions;
+pub mod man;
+#[cfg(feature = "tui")]
+pub mod ui;
 
+use clap::builder::BoolishValueParser;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+
+use std::io::{IsTerminal, Write};
+use tracing::level_filters::LevelFilter;
+
+use crate::env;
 use crate::github;
+use crate::import::ImportSource;
+use crate::list::ListFormat;
+use crate::lock::export::ExportFormat;
+use crate::output::{LogFormat, OutputFormat};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None )]
@@ -9,18 +24,165 @@ pub struct SinkCLI {
     #[command(subcommand)]
     pub command: SinkSubcommands,
 
-    /// Enable verbose (debug) output.
+    /// Increase the verbosity, can be repeated.
+    ///
+    /// ``-v`` sets the default log level from ``info`` to ``debug``, ``-vv`` to ``trace``,
+    /// which includes a summary of every request to GitHub.
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "SINK_VERBOSE",
+        action = ArgAction::Count,
+        value_parser = _parse_verbosity
+    )]
+    pub verbose: u8,
+
+    /// Only output warnings and errors.
+    ///
+    /// This flag will set the default log level from ``info`` to ``warn``.
+    #[arg(short, long, global = true, env = "SINK_QUIET", value_parser = BoolishValueParser::new())]
+    pub quiet: bool,
+
+    /// Explicitly set the log level.
+    ///
+    /// Possible values: ['off', 'error', 'warn', 'info', 'debug', 'trace']
+    #[arg(long, global = true, env = "SINK_LOG_LEVEL")]
+    pub log_level: Option<LevelFilter>,
+
+    /// The format of the logs written to stderr.
     ///
-    /// This flag will set the default log level from ``info`` to ``debug``.
-    /// TODO: Don't allow passing solely this flag
-    #[arg(long, global = true)]
-    pub verbose: bool,
+    /// ``json`` writes an object per line including the spans of the dependency and download being worked on.
+    #[arg(long, global = true, env = "SINK_LOG_FORMAT", value_enum, default_value_t = LogFormat::Human)]
+    pub log_format: LogFormat,
 
     /// Path to the sink TOML file to use.
     ///
     /// This is relative to the current working directory.
-    #[arg(short, long, global = true, default_value = "sink.toml")]
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "SINK_FILE",
+        default_value = "sink.toml"
+    )]
     pub file: String,
+
+    /// Use the user-wide sink TOML instead of the one of the current project.
+    ///
+    /// This is ``~/.config/sink/sink.toml``, created on first use, and takes precedence over ``--file``.
+    /// Added dependencies are installed to ``~/.local/share/sink`` unless a destination is given.
+    #[arg(long, global = true, env = "SINK_GLOBAL", value_parser = BoolishValueParser::new())]
+    pub global: bool,
+
+    /// The format of the results.
+    ///
+    /// With ``json``, a single JSON object containing the result, warnings and errors is printed to stdout.
+    /// Human readable logs are still written to stderr.
+    #[arg(
+        long,
+        global = true,
+        env = "SINK_OUTPUT",
+        value_enum,
+        default_value_t = OutputFormat::Human
+    )]
+    pub output: OutputFormat,
+
+    /// Turn all warnings about the sink TOML into errors.
+    ///
+    /// This includes unresolvable includes, unknown fields and missing destinations.
+    /// Can also be enabled by setting ``strict = true`` in the sink TOML.
+    #[arg(long, global = true, env = "SINK_STRICT", value_parser = BoolishValueParser::new())]
+    pub strict: bool,
+
+    /// Never contact GitHub.
+    ///
+    /// Commands that need to resolve or download a dependency will fail instead.
+    #[arg(long, global = true, env = "SINK_OFFLINE", value_parser = BoolishValueParser::new())]
+    pub offline: bool,
+
+    /// Allow resolving dependencies pinned to draft releases, e.g. 'draft:v1.2.0'.
+    ///
+    /// Drafts are only visible with push access to the repository.
+    #[arg(long, global = true, env = "SINK_ALLOW_DRAFT", value_parser = BoolishValueParser::new())]
+    pub allow_draft: bool,
+
+    /// Log every HTTP request to GitHub and its response to stderr, regardless of the log level.
+    ///
+    /// Includes the method, URL, headers, status and timing, but never credentials or bodies.
+    /// Use this to diagnose failures against GitHub Enterprise Server or proxies.
+    #[arg(long, global = true, env = "SINK_TRACE_HTTP", value_parser = BoolishValueParser::new())]
+    pub trace_http: bool,
+
+    /// Authenticate as an installation of the GitHub App with this ID instead of the user of the GitHub CLI.
+    ///
+    /// Installation tokens are created and refreshed as needed. Requires ``--app-private-key``.
+    #[arg(
+        long,
+        global = true,
+        env = "SINK_APP_ID",
+        requires = "app_private_key",
+        value_name = "ID"
+    )]
+    pub app_id: Option<String>,
+
+    /// The private key of the GitHub App, either as the path of a PEM file or the PEM itself.
+    #[arg(
+        long,
+        global = true,
+        env = "SINK_APP_PRIVATE_KEY",
+        requires = "app_id",
+        hide_env_values = true,
+        value_name = "KEY"
+    )]
+    pub app_private_key: Option<String>,
+
+    /// The installation of the GitHub App to authenticate as.
+    ///
+    /// Only needed if the app is installed more than once.
+    #[arg(
+        long,
+        global = true,
+        env = "SINK_APP_INSTALLATION_ID",
+        requires = "app_id",
+        value_name = "ID"
+    )]
+    pub app_installation_id: Option<u64>,
+
+    /// How many seconds the latest releases of repositories are cached on disk.
+    ///
+    /// Repeated commands then do not ask GitHub for the latest release of every dependency again. ``0`` disables the cache.
+    #[arg(
+        long,
+        global = true,
+        env = "SINK_METADATA_TTL",
+        default_value_t = 300,
+        value_name = "SECONDS"
+    )]
+    pub metadata_ttl: u64,
+
+    /// Always ask GitHub for the latest releases, ignoring and not filling the cache.
+    #[arg(long, global = true, env = "SINK_NO_METADATA_CACHE", value_parser = BoolishValueParser::new())]
+    pub no_metadata_cache: bool,
+
+    /// Never run the ``pre-install`` and ``post-install`` hooks of dependencies.
+    ///
+    /// Use this when installing from sink TOMLs you do not trust.
+    #[arg(long, global = true, env = "SINK_NO_HOOKS", value_parser = BoolishValueParser::new())]
+    pub no_hooks: bool,
+
+    /// Never check whether a newer release of sink is available.
+    ///
+    /// Otherwise, GitHub is asked at most once a day and a hint is logged at the end of the command.
+    /// The check is also skipped with ``--offline``, if stderr is not a terminal or the ``CI`` environment variable is set.
+    #[arg(long, global = true, env = "SINK_NO_UPDATE_CHECK", value_parser = BoolishValueParser::new())]
+    pub no_update_check: bool,
+
+    /// Do not ask for confirmation before deleting installed files.
+    ///
+    /// Prompts are also skipped if stdin is not a terminal or the ``CI`` environment variable is set.
+    #[arg(short, long, global = true, env = "SINK_YES", value_parser = BoolishValueParser::new())]
+    pub yes: bool,
 }
 
 #[derive(Subcommand)]
@@ -32,11 +194,147 @@ pub enum SinkSubcommands {
     /// Install dependencies
     Install(SubcommandInstall),
 
+    /// Resolve dependencies and write the lockfile without installing them
+    Lock(SubcommandLock),
+
     /// Add dependencies
     Add(SubcommandAdd),
 
     /// Remove dependencies
     Remove(SubcommandRemove),
+
+    /// Bump pinned versions to the newest release
+    Upgrade(SubcommandUpgrade),
+
+    /// Import dependencies from other tools
+    Import(SubcommandImport),
+
+    /// Check the dependencies against the published security advisories of their repositories
+    Audit(SubcommandAudit),
+
+    /// Verify installed files against the lockfile
+    Verify(SubcommandVerify),
+
+    /// Delete installed files
+    Clean(SubcommandClean),
+
+    /// Remove leftovers of dependencies that are no longer in the sink TOML
+    Prune(SubcommandPrune),
+
+    /// List dependencies
+    List(SubcommandList),
+
+    /// Compare the dependencies of two sink TOMLs or lockfiles
+    Diff(SubcommandDiff),
+
+    /// Print the pinned dependencies for update bots like Renovate
+    Metadata(SubcommandMetadata),
+
+    /// Print the shell command putting the managed bin directory on the PATH
+    Env(SubcommandEnv),
+
+    /// Run the executable of a dependency, installing it first if needed
+    Run(SubcommandRun),
+
+    /// Print the path of the installed executable of a dependency
+    Which(SubcommandWhich),
+
+    /// Switch a versioned dependency to another version
+    Use(SubcommandUse),
+
+    /// Browse, install, update and remove dependencies in an interactive dashboard
+    #[cfg(feature = "tui")]
+    Ui(SubcommandUi),
+
+    /// Update sink itself to its latest release
+    #[command(name = "self-update")]
+    SelfUpdate(SubcommandSelfUpdate),
+
+    /// Show the GitHub user, API host and remaining rate limits
+    #[command(name = "api-status")]
+    ApiStatus(SubcommandApiStatus),
+
+    /// Explain an error code printed along with an error, e.g. 'SINK-E010'
+    Explain(SubcommandExplain),
+
+    /// Generate shell completions
+    Completions(SubcommandCompletions),
+
+    /// Generate man pages
+    #[command(hide = true)]
+    Man(SubcommandMan),
+
+    /// Print dynamic completion candidates, used by the shell completions
+    #[command(name = "complete-candidates", hide = true)]
+    Complete(SubcommandComplete),
+}
+
+impl SinkSubcommands {
+    /// Returns the name of the subcommand as used on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SinkSubcommands::Config(_) => "config",
+            SinkSubcommands::Install(_) => "install",
+            SinkSubcommands::Lock(_) => "lock",
+            SinkSubcommands::Add(_) => "add",
+            SinkSubcommands::Remove(_) => "remove",
+            SinkSubcommands::Upgrade(_) => "upgrade",
+            SinkSubcommands::Import(_) => "import",
+            SinkSubcommands::Audit(_) => "audit",
+            SinkSubcommands::Verify(_) => "verify",
+            SinkSubcommands::Clean(_) => "clean",
+            SinkSubcommands::Prune(_) => "prune",
+            SinkSubcommands::List(_) => "list",
+            SinkSubcommands::Diff(_) => "diff",
+            SinkSubcommands::Metadata(_) => "metadata",
+            SinkSubcommands::Env(_) => "env",
+            SinkSubcommands::Run(_) => "run",
+            SinkSubcommands::Which(_) => "which",
+            SinkSubcommands::Use(_) => "use",
+            #[cfg(feature = "tui")]
+            SinkSubcommands::Ui(_) => "ui",
+            SinkSubcommands::SelfUpdate(_) => "self-update",
+            SinkSubcommands::ApiStatus(_) => "api-status",
+            SinkSubcommands::Explain(_) => "explain",
+            SinkSubcommands::Completions(_) => "completions",
+            SinkSubcommands::Man(_) => "man",
+            SinkSubcommands::Complete(_) => "complete-candidates",
+        }
+    }
+
+    /// Whether the subcommand modifies the sink TOML, the lockfile or the destinations.
+    pub fn is_mutating(&self) -> bool {
+        match self {
+            SinkSubcommands::Config(params) => {
+                params.unset.is_some() || params.update.is_some() || params.restore
+            }
+            SinkSubcommands::Upgrade(params) => !params.dry_run && !params.preview,
+            SinkSubcommands::Lock(params) => params.action.is_none(),
+            SinkSubcommands::Import(params) => !params.dry_run,
+            SinkSubcommands::Install(_)
+            | SinkSubcommands::Add(_)
+            | SinkSubcommands::Remove(_)
+            | SinkSubcommands::Clean(_)
+            | SinkSubcommands::Prune(_)
+            | SinkSubcommands::Use(_) => true,
+            #[cfg(feature = "tui")]
+            SinkSubcommands::Ui(_) => true,
+            SinkSubcommands::Verify(_)
+            | SinkSubcommands::Audit(_)
+            | SinkSubcommands::List(_)
+            | SinkSubcommands::Diff(_)
+            | SinkSubcommands::Metadata(_)
+            | SinkSubcommands::Env(_)
+            | SinkSubcommands::Run(_)
+            | SinkSubcommands::Which(_)
+            | SinkSubcommands::SelfUpdate(_)
+            | SinkSubcommands::ApiStatus(_)
+            | SinkSubcommands::Explain(_)
+            | SinkSubcommands::Completions(_)
+            | SinkSubcommands::Man(_)
+            | SinkSubcommands::Complete(_) => false,
+        }
+    }
 }
 
 #[derive(Args)]
@@ -58,6 +356,27 @@ pub struct SubcommandConfig {
     #[arg(short, long)]
     pub list: bool,
 
+    /// Print the value of a config field.
+    ///
+    /// Supports dotted keys, e.g. ``dependencies."owner/repo:pattern".version``.
+    /// Strings are printed without quotes, everything else in its TOML representation.
+    #[arg(short, long)]
+    pub get: Option<String>,
+
+    /// Remove an optional top-level config field.
+    ///
+    /// Expects the key, e.g. ``default-owner``.
+    /// A single include can be removed with ``includes=path/to/sink.toml``.
+    #[arg(long)]
+    pub unset: Option<String>,
+
+    /// Roll back the last change made by 'add', 'remove' or 'upgrade'.
+    ///
+    /// The sink TOML is restored from the latest backup in ``.sink/backups``, which is consumed.
+    /// Restoring repeatedly rolls back one change at a time.
+    #[arg(long)]
+    pub restore: bool,
+
     /// Update the value of a config field.
     ///
     /// Expects a ``key=value`` pairing.
@@ -74,6 +393,62 @@ pub struct SubcommandInstall {
     /// Recommended to be used for reproducible builds.
     #[arg(short, long)]
     pub sink: bool,
+
+    /// Refuse to install if ``sink.lock`` is missing or out of sync with the sink TOML.
+    ///
+    /// Dependencies are installed at their locked versions and must match the locked digests.
+    /// Nothing is ever re-resolved, which makes this the mode of choice for CI.
+    #[arg(long)]
+    pub frozen: bool,
+
+    /// Install the members of the workspace as well.
+    ///
+    /// The sink TOML must have a ``[workspace]`` table listing the members.
+    /// All members are locked in the lockfile of the root and shared assets are only downloaded once.
+    #[arg(long)]
+    pub workspace: bool,
+
+    /// Print how long the install took, what was downloaded and which dependencies were the slowest.
+    ///
+    /// Dependencies that were installed already count as cached.
+    /// With ``--output json``, the timings are added to the output as ``timings``.
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Stop at the first dependency that fails instead of installing the remaining ones.
+    ///
+    /// Failing optional dependencies never stop the install.
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Check that every repository can be read before installing anything.
+    ///
+    /// All inaccessible dependencies are reported at once instead of failing one by one halfway through.
+    /// Inaccessible optional dependencies are only warned about.
+    #[arg(long)]
+    pub preflight: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandLock {
+    /// Work with the existing lockfile instead of resolving all dependencies.
+    #[command(subcommand)]
+    pub action: Option<LockAction>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LockAction {
+    /// Print a flat listing of the resolved dependencies, one row per installed file
+    Export(SubcommandLockExport),
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandLockExport {
+    /// The format to export the lockfile in.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+    pub format: ExportFormat,
 }
 
 #[derive(Args, Debug)]
@@ -82,6 +457,9 @@ pub struct SubcommandAdd {
     /// The dependency to add.
     ///
     /// Supposed to be in the form of 'owner/repository:dependency'.
+    /// Without the ':dependency' part, all assets of the release are added.
+    /// GitHub URLs of a repository, release or release asset are accepted as well,
+    /// e.g. 'https://github.com/owner/repo/releases/tag/v1.2.3'.
     /// The 'owner/repository' part will default to the default owner and repository, if set.
     /// TODO: Use an enum for this
     pub dependency: String,
@@ -96,8 +474,9 @@ pub struct SubcommandAdd {
     ///
     /// Defaults to 'latest'.
     ///
-    /// Possible values: ['latest', 'prerelease', specific tag (e.g. 'v1.0.0')]
-    #[arg(short, long, value_parser = github::GitHubVersion::parse_cli)]
+    /// Possible values: ['latest', 'prerelease', specific tag (e.g. 'v1.0.0'), 'draft:<tag>', 'asof:YYYY-MM-DD']
+    /// The short form is ``-t`` (tag), as ``-v`` increases the verbosity.
+    #[arg(short = 't', long, value_parser = github::GitHubVersion::parse_cli)]
     pub version: Option<github::GitHubVersion>,
 
     /// Whether to skip adding the downloaded asset(s) to the gitignore.
@@ -120,9 +499,352 @@ pub struct SubcommandAdd {
 pub struct SubcommandRemove {
     /// The dependency to remove.
     ///
-    /// **Must** to be in the form of 'owner/repository:dependency'.
-    /// TODO: Use an enum for this
-    dependency: String,
+    /// Supposed to be in the form of 'owner/repository:dependency'.
+    /// The 'owner/' part will default to the default owner, if set.
+    /// Glob patterns such as 'owner/*' or 'owner/repository:*' remove all matching dependencies.
+    pub dependency: String,
+
+    /// Keep the installed files, only remove the dependency from the sink TOML, lockfile and gitignore
+    #[arg(long)]
+    pub keep_files: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandUpgrade {
+    /// The dependencies to upgrade, defaults to all dependencies pinned to a tag.
+    ///
+    /// Supports the same glob patterns as 'remove', e.g. 'owner/*'.
+    pub dependencies: Vec<String>,
+
+    /// Only show the available upgrades, do not modify the sink TOML
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Show the release notes of the new versions
+    #[arg(long)]
+    pub notes: bool,
+
+    /// Show the release notes of every release between the locked and the new version, implies '--dry-run'
+    #[arg(long)]
+    pub preview: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandImport {
+    /// The format of the file to import from.
+    #[arg(long, value_enum, default_value_t = ImportSource::ToolVersions)]
+    pub from: ImportSource,
+
+    /// The file to import from, defaults to e.g. '.tool-versions' next to the sink TOML.
+    pub file: Option<std::path::PathBuf>,
+
+    /// Only show the dependencies that would be imported, do not modify the sink TOML
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandAudit {
+    /// Only report advisories of at least this severity.
+    #[arg(long, value_enum, default_value_t = github::Severity::Unknown)]
+    pub severity: github::Severity,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandVerify {}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandClean {
+    /// Only delete the files of this dependency.
+    ///
+    /// Supposed to be in the form of 'owner/repository:dependency'.
+    /// The 'owner' part will default to the default owner, if set.
+    pub dependency: Option<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandPrune {}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandList {
+    /// The format to print the dependencies in.
+    #[arg(long, value_enum, default_value_t = ListFormat::Table)]
+    pub format: ListFormat,
+
+    /// Only list dependencies of this owner.
+    #[arg(long)]
+    pub owner: Option<String>,
+
+    /// Only list dependencies of this repository.
+    #[arg(long, alias = "repo")]
+    pub repository: Option<String>,
+
+    /// Show the locked version next to the requested one.
+    #[arg(short, long)]
+    pub resolved: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true)]
+pub struct SubcommandDiff {
+    /// The old sink TOML or lockfile, '-' reads it from stdin.
+    ///
+    /// E.g. ``git show main:sink.toml | sink diff -``.
+    pub old: String,
+
+    /// The new sink TOML or lockfile, defaults to the current one.
+    pub new: Option<std::path::PathBuf>,
+
+    /// Compare lockfiles instead of sink TOMLs.
+    ///
+    /// This is the default if the old file ends with '.lock'.
+    #[arg(long)]
+    pub lock: bool,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandEnv {
+    /// The shell to print the export for.
+    #[arg(long, value_enum, default_value_t = env::Shell::Sh)]
+    pub shell: env::Shell,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true)]
+pub struct SubcommandRun {
+    /// The dependency to run.
+    ///
+    /// Either its pathspec, the name of its executable or the name of its repository.
+    pub dependency: String,
+
+    /// The arguments to pass to the executable, e.g. ``sink run golangci-lint -- run ./...``.
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true)]
+pub struct SubcommandWhich {
+    /// The dependency to locate.
+    ///
+    /// Either its pathspec, the name of its executable or the name of its repository.
+    pub dependency: String,
+}
+
+#[cfg(feature = "tui")]
+#[derive(Args, Debug)]
+pub struct SubcommandUi {}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true)]
+pub struct SubcommandUse {
+    /// The dependency to switch.
+    ///
+    /// Either its pathspec, the name of its executable or the name of its repository.
+    /// It must be versioned and have a link, see ``versioned`` in the sink TOML.
+    pub dependency: String,
+
+    /// The version to switch to.
+    ///
+    /// Possible values: ['latest', 'prerelease', specific tag (e.g. 'v1.0.0'), 'draft:<tag>', 'asof:YYYY-MM-DD']
+    #[arg(value_parser = github::GitHubVersion::parse_cli)]
+    pub version: github::GitHubVersion,
+}
+
+#[derive(Args, Debug)]
+pub struct SubcommandSelfUpdate {
+    /// Only report whether a newer release is available, without downloading it.
+    #[arg(long)]
+    pub check: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SubcommandApiStatus {}
+
+#[derive(Args, Debug)]
+pub struct SubcommandMetadata {
+    /// Print the ``customManagers`` of a Renovate config bumping the versions in sink TOMLs instead.
+    ///
+    /// Merge it into ``renovate.json`` to have Renovate open pull requests for new releases.
+    #[arg(long)]
+    pub renovate_config: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SubcommandExplain {
+    /// The error code to explain, e.g. ``SINK-E010`` or ``E010``.
+    ///
+    /// Lists all error codes if omitted.
+    pub code: Option<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true)]
+pub struct SubcommandCompletions {
+    /// The shell to generate the completions for.
+    ///
+    /// The completions are printed to stdout, e.g. ``sink completions bash > /etc/bash_completion.d/sink``.
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = false)]
+pub struct SubcommandMan {
+    /// The directory to write the man pages of the main command and every subcommand into.
+    ///
+    /// If not set, only the man page of the main command is printed to stdout.
+    #[arg(short, long)]
+    pub dir: Option<std::path::PathBuf>,
+}
+
+#[derive(Args, Debug)]
+#[command(arg_required_else_help = true)]
+pub struct SubcommandComplete {
+    /// The kind of values to complete.
+    #[arg(value_enum)]
+    pub kind: completions::CompletionKind,
+
+    /// The dependency to complete versions for.
+    pub dependency: Option<String>,
+}
+
+impl SinkCLI {
+    /// Parse the command line, layered on top of the ``SINK_*`` environment variables.
+    ///
+    /// Exits on errors, just like [`Parser::parse`].
+    pub fn parse_layered() -> Self {
+        SinkCLI::try_parse_layered_from(std::env::args_os()).unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse the given arguments, layered on top of the ``SINK_*`` environment variables.
+    ///
+    /// ``--verbose``, ``--quiet`` and ``--log-level`` conflict with each other on the command line.
+    /// If any of them is passed on the command line, the others are ignored when set via the environment.
+    pub fn try_parse_layered_from<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let args: Vec<std::ffi::OsString> = args.into_iter().map(Into::into).collect();
+        let mut command = SinkCLI::command();
+        let matches = match command.try_get_matches_from_mut(args.iter().cloned()) {
+            Ok(matches) => matches,
+            // Global options alone are no command, so show the help just like for a bare 'sink'
+            Err(e) if e.kind() == clap::error::ErrorKind::MissingSubcommand => {
+                return Err(SinkCLI::command()
+                    .try_get_matches_from(args.iter().take(1))
+                    .err()
+                    .unwrap_or(e));
+            }
+            Err(e) => return Err(e),
+        };
+        let mut cli = SinkCLI::from_arg_matches(&matches)?;
+
+        let log_args = ["verbose", "quiet", "log_level"];
+        let from_command_line: Vec<&str> = log_args
+            .into_iter()
+            .filter(|id| Self::_is_from_command_line(&matches, id))
+            .collect();
+        if from_command_line.len() > 1 {
+            return Err(command.error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "'--verbose', '--quiet' and '--log-level' cannot be used with each other",
+            ));
+        }
+        if !from_command_line.is_empty() {
+            if !from_command_line.contains(&"verbose") {
+                cli.verbose = 0;
+            }
+            cli.quiet &= from_command_line.contains(&"quiet");
+            if !from_command_line.contains(&"log_level") {
+                cli.log_level = None;
+            }
+        }
+
+        Ok(cli)
+    }
+
+    /// Whether the argument was passed on the command line of any (sub)command.
+    fn _is_from_command_line(matches: &ArgMatches, id: &str) -> bool {
+        if matches.value_source(id) == Some(ValueSource::CommandLine) {
+            return true;
+        }
+        matches
+            .subcommand()
+            .is_some_and(|(_, sub_matches)| Self::_is_from_command_line(sub_matches, id))
+    }
+
+    /// Returns the default log level derived from ``--log-level``, ``--quiet`` and ``--verbose``.
+    ///
+    /// The ``RUST_LOG`` environment variable still takes precedence over this.
+    pub fn log_level(&self) -> LevelFilter {
+        if let Some(log_level) = self.log_level {
+            log_level
+        } else if self.quiet {
+            LevelFilter::WARN
+        } else if self.verbose > 1 {
+            LevelFilter::TRACE
+        } else if self.verbose == 1 {
+            LevelFilter::DEBUG
+        } else {
+            LevelFilter::INFO
+        }
+    }
+}
+
+/// Ask the user a yes/no question on stderr and read the answer from stdin.
+///
+/// Fails if stdin is not interactive, as there is nobody to answer.
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Err(anyhow::anyhow!(
+            "Cannot ask for confirmation, stdin is not interactive! Pass '--yes' to skip it."
+        ));
+    }
+
+    eprint!("{question} [y/N] ");
+    std::io::stderr().flush()?;
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Whether sink runs in a CI pipeline, where nobody can answer prompts.
+///
+/// CI services set the ``CI`` environment variable, e.g. GitHub Actions and GitLab CI set it to 'true'.
+pub fn is_ci() -> bool {
+    std::env::var("CI").is_ok_and(|ci| _is_truthy(&ci))
+}
+
+/// Whether the value of an environment variable enables something, i.e. is neither empty nor false.
+fn _is_truthy(value: &str) -> bool {
+    !matches!(
+        value.trim().to_lowercase().as_str(),
+        "" | "0" | "false" | "no" | "off"
+    )
+}
+
+/// Parse the verbosity set via ``SINK_VERBOSE``, either a count or a boolean for a single ``-v``.
+fn _parse_verbosity(value: &str) -> Result<u8, String> {
+    match value.trim().parse::<u8>() {
+        Ok(count) => Ok(count),
+        Err(_) => match value.trim().to_lowercase().as_str() {
+            "true" | "yes" | "on" => Ok(1),
+            "false" | "no" | "off" | "" => Ok(0),
+            _ => Err(format!("'{value}' is neither a number nor a boolean")),
+        },
+    }
 }
 
 /* ---------- [ Tests ] ---------- */
@@ -132,7 +854,74 @@ mod tests {
 
     #[test]
     fn verify_cli() {
-        use clap::CommandFactory;
         SinkCLI::command().debug_assert();
     }
+
+    #[test]
+    fn test_log_level() {
+        let parse = |args: &[&str]| SinkCLI::try_parse_layered_from(args).unwrap();
+
+        let cli = parse(&["sink", "list"]);
+        assert_eq!(cli.log_level(), LevelFilter::INFO);
+
+        let cli = parse(&["sink", "list", "--quiet"]);
+        assert_eq!(cli.log_level(), LevelFilter::WARN);
+
+        let cli = parse(&["sink", "list", "--verbose"]);
+        assert_eq!(cli.log_level(), LevelFilter::DEBUG);
+
+        let cli = parse(&["sink", "-vv", "list"]);
+        assert_eq!(cli.log_level(), LevelFilter::TRACE);
+        assert_eq!(parse(&["sink", "list", "-vvv"]).verbose, 3);
+
+        let cli = parse(&["sink", "list", "--log-level", "error"]);
+        assert_eq!(cli.log_level(), LevelFilter::ERROR);
+        assert_eq!(cli.log_format, LogFormat::Human);
+
+        let cli = parse(&["sink", "list", "--log-format", "json"]);
+        assert_eq!(cli.log_format, LogFormat::Json);
+
+        assert!(SinkCLI::try_parse_layered_from(["sink", "list", "--quiet", "--verbose"]).is_err());
+
+        // Without a subcommand, the help is shown instead of running anything
+        let kind = SinkCLI::try_parse_layered_from(["sink", "-v"]).map_err(|e| e.kind());
+        assert!(matches!(
+            kind,
+            Err(clap::error::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand)
+        ));
+
+        assert_eq!(_parse_verbosity("2"), Ok(2));
+        assert_eq!(_parse_verbosity("True"), Ok(1));
+        assert_eq!(_parse_verbosity("off"), Ok(0));
+        assert!(_parse_verbosity("loud").is_err());
+    }
+
+    #[test]
+    fn test_env() {
+        let command = SinkCLI::command();
+        let env_of = |id: &str| {
+            command
+                .get_arguments()
+                .find(|arg| arg.get_id() == id)
+                .and_then(|arg| arg.get_env())
+                .map(|env| env.to_string_lossy().to_string())
+        };
+
+        assert_eq!(env_of("file").as_deref(), Some("SINK_FILE"));
+        assert_eq!(env_of("verbose").as_deref(), Some("SINK_VERBOSE"));
+        assert_eq!(env_of("offline").as_deref(), Some("SINK_OFFLINE"));
+        assert_eq!(env_of("no_hooks").as_deref(), Some("SINK_NO_HOOKS"));
+        assert_eq!(env_of("yes").as_deref(), Some("SINK_YES"));
+        assert_eq!(env_of("log_format").as_deref(), Some("SINK_LOG_FORMAT"));
+    }
+
+    #[test]
+    fn test_is_truthy() {
+        assert!(_is_truthy("true"));
+        assert!(_is_truthy("1"));
+        assert!(_is_truthy("woodpecker"));
+        assert!(!_is_truthy(""));
+        assert!(!_is_truthy("False"));
+        assert!(!_is_truthy("0"));
+    }
 }
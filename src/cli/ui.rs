@@ -0,0 +1,464 @@
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::Frame;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+use crate::client::SinkClient;
+use crate::github::{self, GitHubPathspec};
+use crate::list::{self, ListFilter};
+use crate::output;
+
+/// How long to wait for input before checking for fetched releases again.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The keys of the dashboard, shown below the table.
+const HELP: &str =
+    "↑/↓ move  space select  a select all  i install  u update  d remove  r refresh  q quit";
+
+/// The latest release of a repository, as far as it is known.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Latest {
+    Loading,
+    Tag(String),
+    Failed,
+}
+
+/// A row of the dashboard.
+#[derive(Debug)]
+struct Entry {
+    pathspec: GitHubPathspec,
+    origin: String,
+    version: String,
+    locked: Option<String>,
+}
+
+/// The state of `sink ui`, independent of the terminal it is drawn on.
+pub struct Dashboard {
+    client: SinkClient,
+    entries: Vec<Entry>,
+    latest: BTreeMap<String, Latest>,
+    selected: BTreeSet<GitHubPathspec>,
+    table: TableState,
+    status: String,
+    confirm_remove: bool,
+    quit: bool,
+}
+impl Dashboard {
+    /// Create the dashboard of the dependencies of the client, without fetching their latest releases yet.
+    pub fn new(client: SinkClient) -> Self {
+        let mut dashboard = Dashboard {
+            client,
+            entries: Vec::new(),
+            latest: BTreeMap::new(),
+            selected: BTreeSet::new(),
+            table: TableState::default(),
+            status: String::new(),
+            confirm_remove: false,
+            quit: false,
+        };
+        dashboard._reload();
+        dashboard
+    }
+
+    /// Read the dependencies from the client again, e.g. after they were changed.
+    fn _reload(&mut self) {
+        let entries = list::entries(
+            self.client.sink_toml(),
+            Some(self.client.sink_lock()),
+            &ListFilter::default(),
+        );
+        self.entries = entries
+            .into_iter()
+            .filter_map(|entry| {
+                Some(Entry {
+                    pathspec: GitHubPathspec::try_from(entry.pathspec).ok()?,
+                    origin: format!("{}/{}", entry.owner, entry.repository),
+                    version: entry.version,
+                    locked: entry.locked,
+                })
+            })
+            .collect();
+        self.selected
+            .retain(|pathspec| self.entries.iter().any(|e| e.pathspec == *pathspec));
+
+        let selected = match self.entries.len() {
+            0 => None,
+            len => Some(self.table.selected().unwrap_or_default().min(len - 1)),
+        };
+        self.table.select(selected);
+    }
+
+    /// Returns the repositories whose latest release is not known yet.
+    pub fn origins(&self) -> BTreeSet<String> {
+        self.entries
+            .iter()
+            .map(|entry| entry.origin.clone())
+            .filter(|origin| !matches!(self.latest.get(origin), Some(Latest::Tag(_))))
+            .collect()
+    }
+
+    /// Record the latest release of the repository.
+    pub fn set_latest(&mut self, origin: &str, latest: Latest) {
+        self.latest.insert(String::from(origin), latest);
+    }
+
+    /// Whether the user asked to quit.
+    pub fn should_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// Returns the message shown above the keys, e.g. the result of the last action.
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    /// The dependencies to act on: the selected ones, or the highlighted one if none are selected.
+    fn _targets(&self) -> Vec<GitHubPathspec> {
+        if !self.selected.is_empty() {
+            return self.selected.iter().cloned().collect();
+        }
+
+        self.table
+            .selected()
+            .and_then(|index| self.entries.get(index))
+            .map(|entry| vec![entry.pathspec.clone()])
+            .unwrap_or_default()
+    }
+
+    /// Install the dependencies one after another, returning the status.
+    fn _install(&mut self, targets: &[GitHubPathspec]) -> String {
+        let mut failed = Vec::new();
+        for pathspec in targets {
+            if let Err(e) = self.client.install_dependency(pathspec) {
+                failed.push(format!("{pathspec}: {e:#}"));
+            }
+        }
+
+        match failed.is_empty() {
+            true => format!("Installed {} dependencies!", targets.len()),
+            false => format!("Failed to install {}", failed.join(", ")),
+        }
+    }
+
+    /// Bump the dependencies to their newest release and install them, returning the status.
+    fn _update(&mut self, targets: &[GitHubPathspec]) -> String {
+        let upgrades = match self.client.update(targets, false) {
+            Ok(upgrades) => upgrades,
+            Err(e) => return format!("{e:#}"),
+        };
+        if upgrades.is_empty() {
+            return String::from("All pinned dependencies are up to date!");
+        }
+
+        let upgraded: Vec<GitHubPathspec> = upgrades.into_iter().map(|u| u.pathspec).collect();
+        match self._install(&upgraded) {
+            status if status.starts_with("Installed") => {
+                format!("Updated {} dependencies!", upgraded.len())
+            }
+            status => status,
+        }
+    }
+
+    /// Handle a key press.
+    pub fn on_key(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+
+        if self.confirm_remove {
+            self.confirm_remove = false;
+            self.status = match key.code {
+                KeyCode::Char('y') => {
+                    let targets = self._targets();
+                    match self.client.remove(&targets, false) {
+                        Ok(report) => format!("Removed {} dependencies!", report.removed.len()),
+                        Err(e) => format!("{e:#}"),
+                    }
+                }
+                _ => String::from("Cancelled!"),
+            };
+            self._reload();
+            return;
+        }
+
+        let len = self.entries.len();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Down | KeyCode::Char('j') if len > 0 => {
+                let next = self.table.selected().map_or(0, |index| (index + 1) % len);
+                self.table.select(Some(next));
+            }
+            KeyCode::Up | KeyCode::Char('k') if len > 0 => {
+                let previous = self
+                    .table
+                    .selected()
+                    .map_or(0, |index| (index + len - 1) % len);
+                self.table.select(Some(previous));
+            }
+            KeyCode::Char(' ') => {
+                if let Some(entry) = self.table.selected().and_then(|i| self.entries.get(i)) {
+                    if !self.selected.remove(&entry.pathspec) {
+                        self.selected.insert(entry.pathspec.clone());
+                    }
+                }
+            }
+            KeyCode::Char('a') => {
+                self.selected = match self.selected.len() == len {
+                    true => BTreeSet::new(),
+                    false => self.entries.iter().map(|e| e.pathspec.clone()).collect(),
+                };
+            }
+            KeyCode::Char('i') => {
+                let targets = self._targets();
+                self.status = self._install(&targets);
+                self._reload();
+            }
+            KeyCode::Char('u') => {
+                let targets = self._targets();
+                self.status = self._update(&targets);
+                self._reload();
+            }
+            KeyCode::Char('d') => {
+                let targets = self._targets();
+                if !targets.is_empty() {
+                    self.confirm_remove = true;
+                    self.status = format!("Remove {} dependencies? (y/n)", targets.len());
+                }
+            }
+            KeyCode::Char('r') => {
+                self.latest.clear();
+                self.status = String::from("Refreshing the latest releases...");
+            }
+            _ => {}
+        }
+    }
+
+    /// Draw the dashboard.
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, status_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(frame.area());
+
+        let header = Row::new(["", "Dependency", "Version", "Locked", "Latest"])
+            .style(Style::default().add_modifier(Modifier::BOLD));
+        let rows = self.entries.iter().map(|entry| {
+            let latest = match self.latest.get(&entry.origin) {
+                Some(Latest::Tag(tag)) => tag.clone(),
+                Some(Latest::Failed) => String::from("?"),
+                Some(Latest::Loading) | None => String::from("..."),
+            };
+            let locked = entry.locked.clone().unwrap_or_else(|| String::from("-"));
+            let outdated = matches!(
+                self.latest.get(&entry.origin),
+                Some(Latest::Tag(tag)) if entry.locked.as_ref() != Some(tag)
+            );
+            let style = match outdated {
+                true => Style::default().fg(Color::Yellow),
+                false => Style::default(),
+            };
+
+            let mark = match self.selected.contains(&entry.pathspec) {
+                true => "*",
+                false => " ",
+            };
+            Row::new([
+                String::from(mark),
+                entry.pathspec.to_string(),
+                entry.version.clone(),
+                locked,
+                latest,
+            ])
+            .style(style)
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(1),
+                Constraint::Fill(1),
+                Constraint::Length(14),
+                Constraint::Length(14),
+                Constraint::Length(14),
+            ],
+        )
+        .header(header)
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(format!(" {} ", self.client.sink_toml().path.display())));
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        let status = Paragraph::new(vec![Line::from(self.status.as_str()), Line::from(HELP)])
+            .block(Block::bordered());
+        frame.render_widget(status, status_area);
+    }
+}
+
+/// Fetch the latest releases of the repositories in the background.
+fn _fetch_latest(origins: BTreeSet<String>) -> Receiver<(String, Latest)> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let origins: Vec<String> = origins.into_iter().collect();
+        let mut tags = github::latest_tags(&origins).unwrap_or_default();
+        for origin in origins {
+            let latest = match tags.remove(&origin) {
+                Some(tag) => Latest::Tag(tag),
+                None => Latest::Failed,
+            };
+            if sender.send((origin, latest)).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn _run(client: SinkClient) -> Result<()> {
+    let mut dashboard = Dashboard::new(client);
+    let mut terminal = ratatui::try_init()?;
+    // Logs would be drawn over the dashboard, results are shown in its status line instead
+    output::set_muted(true);
+
+    let mut fetching = None;
+    let result = loop {
+        if fetching.is_none() {
+            let origins = dashboard.origins();
+            for origin in origins.iter() {
+                dashboard.set_latest(origin, Latest::Loading);
+            }
+            fetching = Some(_fetch_latest(origins));
+        }
+        if let Some(receiver) = &fetching {
+            while let Ok((origin, latest)) = receiver.try_recv() {
+                dashboard.set_latest(&origin, latest);
+            }
+        }
+
+        if let Err(e) = terminal.draw(|frame| dashboard.draw(frame)) {
+            break Err(e.into());
+        }
+        match event::poll(POLL_INTERVAL) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) => {
+                    let refresh = key.code == KeyCode::Char('r');
+                    dashboard.on_key(key);
+                    if refresh {
+                        fetching = None;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => break Err(e.into()),
+            },
+            Ok(false) => {}
+            Err(e) => break Err(e.into()),
+        }
+        if dashboard.should_quit() {
+            break Ok(());
+        }
+    };
+
+    ratatui::restore();
+    output::set_muted(false);
+    result
+}
+/// Show the interactive dashboard of the dependencies until the user quits.
+///
+/// Lists the version of every dependency in the sink TOML, the lockfile and its latest release,
+/// and installs, updates or removes the selected ones.
+pub fn run(client: SinkClient) -> Result<()> {
+    match _run(client) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(e.context("Failed to run the dashboard!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::SinkLock;
+    use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+    use ratatui::backend::TestBackend;
+    use ratatui::crossterm::event::KeyModifiers;
+    use ratatui::Terminal;
+
+    fn press(dashboard: &mut Dashboard, code: KeyCode) {
+        dashboard.on_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn screen(dashboard: &mut Dashboard) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+        terminal.draw(|frame| dashboard.draw(frame)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(100)
+            .map(|line| line.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_dashboard() {
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v2.0.0").asset("tool", "2"))
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("tool", "1"))
+            .with_release("owner/other", MockRelease::new("v1.0.0").asset("file", "1"));
+        let _guard = server.install();
+
+        let project = TempProject::new("ui").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:tool", "v1.0.0")
+            .dependency("owner/other:file", "v1.0.0")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut dashboard = Dashboard::new(SinkClient::new(sink_toml, sink_lock));
+        assert_eq!(
+            dashboard.origins().into_iter().collect::<Vec<_>>(),
+            vec!["owner/other", "owner/repo"]
+        );
+        dashboard.set_latest("owner/repo", Latest::Tag(String::from("v2.0.0")));
+        dashboard.set_latest("owner/other", Latest::Failed);
+        assert_eq!(dashboard.origins().len(), 1);
+
+        let content = screen(&mut dashboard);
+        assert!(content.contains("owner/repo:tool"));
+        assert!(content.contains("v2.0.0"));
+        assert!(content.contains("?"));
+
+        // Install everything that is selected
+        press(&mut dashboard, KeyCode::Char('a'));
+        press(&mut dashboard, KeyCode::Char('i'));
+        assert_eq!(dashboard.status(), "Installed 2 dependencies!");
+        assert_eq!(dashboard.client.sink_lock().dependencies.len(), 2);
+
+        // Update the highlighted dependency only
+        press(&mut dashboard, KeyCode::Char('a'));
+        press(&mut dashboard, KeyCode::Down);
+        press(&mut dashboard, KeyCode::Char('u'));
+        assert_eq!(dashboard.status(), "Updated 1 dependencies!");
+        let pathspec = GitHubPathspec::try_from(String::from("owner/repo:tool")).unwrap();
+        assert_eq!(
+            dashboard.client.sink_lock().dependencies[&pathspec].version,
+            "v2.0.0"
+        );
+
+        // Removing needs to be confirmed
+        press(&mut dashboard, KeyCode::Char('d'));
+        press(&mut dashboard, KeyCode::Char('n'));
+        assert_eq!(dashboard.entries.len(), 2);
+        press(&mut dashboard, KeyCode::Char('d'));
+        press(&mut dashboard, KeyCode::Char('y'));
+        assert_eq!(dashboard.status(), "Removed 1 dependencies!");
+        assert_eq!(dashboard.entries.len(), 1);
+
+        press(&mut dashboard, KeyCode::Char('q'));
+        assert!(dashboard.should_quit());
+    }
+}
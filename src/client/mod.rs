@@ -0,0 +1,1747 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{error, info_span, warn};
+
+use crate::backup;
+use crate::detect;
+use crate::env;
+use crate::github::{self, GitHubAsset, GitHubDependency, GitHubPathspec, GitHubVersion};
+use crate::gitignore;
+use crate::hooks;
+use crate::interrupt;
+use crate::list;
+use crate::lock::{self, LockedDependency, SinkLock};
+use crate::output;
+use crate::timings::{MeteredApi, Timing, Timings};
+use crate::upgrade::{self, Upgrade};
+use crate::{SinkError, SinkTOML};
+
+/// The outcome of installing a single dependency, see [`SinkClient::install`].
+#[derive(Debug)]
+pub enum InstallOutcome {
+    /// The dependency was downloaded and locked.
+    Installed(LockedDependency),
+
+    /// The entry of the dependency in the sink TOML is invalid.
+    Invalid(anyhow::Error),
+
+    /// Resolving or downloading the dependency failed.
+    Failed(anyhow::Error),
+
+    /// Resolving or downloading an optional dependency failed, which does not fail the install.
+    ///
+    /// See [`GitHubDependency::required`].
+    Optional(anyhow::Error),
+
+    /// The downloaded files do not match the lockfile. Only happens in frozen mode.
+    Mismatch,
+}
+impl InstallOutcome {
+    /// Why the dependency could not be installed, or `None` if it was.
+    pub fn reason(&self) -> Option<String> {
+        match self {
+            InstallOutcome::Installed(_) => None,
+            InstallOutcome::Invalid(e)
+            | InstallOutcome::Failed(e)
+            | InstallOutcome::Optional(e) => Some(crate::errors::describe(e)),
+            InstallOutcome::Mismatch => Some(String::from(
+                "The downloaded files do not match their digests in the lockfile!",
+            )),
+        }
+    }
+}
+
+/// What `sink install` did with a dependency, see [`InstallSummary`].
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InstallAction {
+    /// The dependency was not locked before.
+    Installed,
+    /// The dependency was locked at another version before.
+    Updated,
+    /// Nothing had to be downloaded, e.g. because the version was installed already.
+    Skipped,
+    /// The dependency could not be installed, see [`InstallOutcome`].
+    Failed,
+}
+impl Display for InstallAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InstallAction::Installed => write!(f, "installed"),
+            InstallAction::Updated => write!(f, "updated"),
+            InstallAction::Skipped => write!(f, "skipped"),
+            InstallAction::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A row of the table printed once `sink install` is done, see [`InstallReport::summary`].
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct InstallSummary {
+    pub pathspec: GitHubPathspec,
+    pub action: InstallAction,
+
+    /// The version the dependency was resolved to, or '-' if it failed.
+    pub version: String,
+
+    /// The directory the dependency was installed into, relative to the sink TOML.
+    pub destination: String,
+}
+
+/// The result of [`SinkClient::install`].
+#[derive(Debug, Default)]
+pub struct InstallReport {
+    /// The outcome of every dependency of the sink TOML.
+    pub outcomes: BTreeMap<GitHubPathspec, InstallOutcome>,
+
+    /// The patterns of the dependencies whose assets were guessed, see [`detect::guessed_pattern`].
+    pub guessed: BTreeMap<GitHubPathspec, String>,
+
+    /// How long the install took and what was downloaded, see `sink install --timings`.
+    pub timings: Timings,
+
+    /// What was done with every dependency, sorted by pathspec.
+    pub summary: Vec<InstallSummary>,
+}
+impl InstallReport {
+    /// Returns the dependencies that were installed, along with their lockfile entries.
+    pub fn installed(&self) -> impl Iterator<Item = (&GitHubPathspec, &LockedDependency)> {
+        self.outcomes
+            .iter()
+            .filter_map(|(pathspec, outcome)| match outcome {
+                InstallOutcome::Installed(locked) => Some((pathspec, locked)),
+                _ => None,
+            })
+    }
+
+    /// Whether all dependencies were installed, except for optional ones that failed.
+    pub fn is_ok(&self) -> bool {
+        self.outcomes.values().all(|outcome| {
+            matches!(
+                outcome,
+                InstallOutcome::Installed(_) | InstallOutcome::Optional(_)
+            )
+        })
+    }
+
+    /// Returns the dependencies that could not be installed, along with why.
+    pub fn failures(&self) -> impl Iterator<Item = (&GitHubPathspec, String)> {
+        self.outcomes
+            .iter()
+            .filter_map(|(pathspec, outcome)| Some((pathspec, outcome.reason()?)))
+    }
+
+    /// Render the failures with a line per dependency, or `None` if nothing failed.
+    ///
+    /// Optional dependencies are marked as such, see [`GitHubDependency::required`].
+    pub fn failure_report(&self) -> Option<String> {
+        let lines: Vec<String> = self
+            .failures()
+            .map(|(pathspec, reason)| match self.outcomes[pathspec] {
+                InstallOutcome::Optional(_) => format!("    {pathspec} (optional): {reason}"),
+                _ => format!("    {pathspec}: {reason}"),
+            })
+            .collect();
+        if lines.is_empty() {
+            return None;
+        }
+
+        Some(format!(
+            "{} of {} dependencies could not be installed:\n{}",
+            lines.len(),
+            self.outcomes.len(),
+            lines.join("\n")
+        ))
+    }
+
+    /// Render the summary as a table with a row per dependency.
+    pub fn summary_table(&self) -> String {
+        let mut rows = vec![vec!["DEPENDENCY", "ACTION", "VERSION", "DESTINATION"]];
+        let cells: Vec<[String; 4]> = self
+            .summary
+            .iter()
+            .map(|row| {
+                [
+                    row.pathspec.to_string(),
+                    row.action.to_string(),
+                    row.version.clone(),
+                    row.destination.clone(),
+                ]
+            })
+            .collect();
+        rows.extend(
+            cells
+                .iter()
+                .map(|row| row.iter().map(String::as_str).collect()),
+        );
+        list::table(&rows)
+    }
+}
+
+/// The result of [`SinkClient::remove`].
+#[derive(Debug, Default)]
+pub struct RemoveReport {
+    /// The dependencies removed from the sink TOML.
+    pub removed: Vec<GitHubPathspec>,
+
+    /// The installed files that were deleted.
+    pub files: Vec<PathBuf>,
+}
+
+/// Follows the progress of [`SinkClient::install`], e.g. to render it in a GUI instead of parsing the logs.
+///
+/// Every method does nothing by default, so only the events of interest have to be implemented.
+pub trait InstallObserver: Send + Sync {
+    /// Called before the dependency is resolved, with the version requested by the sink TOML.
+    fn on_resolve(&self, _pathspec: &GitHubPathspec, _version: &GitHubVersion) {}
+
+    /// Called before and after an asset of the dependency is downloaded, with the bytes downloaded so far.
+    ///
+    /// Assets are downloaded as a whole, so `bytes` is either 0 or the size of the downloaded asset.
+    fn on_download_progress(&self, _pathspec: &GitHubPathspec, _asset: &GitHubAsset, _bytes: u64) {}
+
+    /// Called once the dependency was installed and locked.
+    fn on_complete(&self, _pathspec: &GitHubPathspec, _locked: &LockedDependency) {}
+
+    /// Called if the dependency could not be installed, see [`InstallOutcome`].
+    fn on_error(&self, _pathspec: &GitHubPathspec, _error: &anyhow::Error) {}
+}
+
+/// Drives sink programmatically, like the CLI does.
+///
+/// The client owns a sink TOML and its lockfile and keeps both in sync with the files on disk.
+/// Modifying operations back up the sink TOML first, see [`backup::create`].
+pub struct SinkClient {
+    sink_toml: SinkTOML,
+    sink_lock: SinkLock,
+    observer: Option<Arc<dyn InstallObserver>>,
+    fail_fast: bool,
+}
+impl std::fmt::Debug for SinkClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SinkClient")
+            .field("sink_toml", &self.sink_toml)
+            .field("sink_lock", &self.sink_lock)
+            .finish_non_exhaustive()
+    }
+}
+impl SinkClient {
+    /// Create a client from an already loaded sink TOML and lockfile.
+    pub fn new(sink_toml: SinkTOML, sink_lock: SinkLock) -> Self {
+        SinkClient {
+            sink_toml,
+            sink_lock,
+            observer: None,
+            fail_fast: false,
+        }
+    }
+
+    /// Report the progress of the following installs to the observer, or stop reporting it.
+    pub fn set_observer(&mut self, observer: Option<Arc<dyn InstallObserver>>) {
+        self.observer = observer;
+    }
+
+    /// Stop installing at the first dependency that fails, or install the remaining ones anyway (the default).
+    ///
+    /// Failing optional dependencies never stop the install, see [`GitHubDependency::required`].
+    pub fn set_fail_fast(&mut self, fail_fast: bool) {
+        self.fail_fast = fail_fast;
+    }
+
+    /// Load the sink TOML at the given path and its lockfile.
+    pub fn load(path: &Path, strict: bool) -> Result<Self> {
+        let sink_toml = match SinkTOML::from_file_with(&PathBuf::from(path), strict) {
+            Ok(sink_toml) => sink_toml,
+            Err(crate::SinkError::Any(e)) => return Err(e),
+        };
+        let sink_lock = SinkLock::load(&sink_toml)?;
+
+        Ok(SinkClient::new(sink_toml, sink_lock))
+    }
+
+    pub fn sink_toml(&self) -> &SinkTOML {
+        &self.sink_toml
+    }
+
+    pub fn sink_lock(&self) -> &SinkLock {
+        &self.sink_lock
+    }
+
+    /// Returns the sink TOML and the lockfile, consuming the client.
+    pub fn into_parts(self) -> (SinkTOML, SinkLock) {
+        (self.sink_toml, self.sink_lock)
+    }
+
+    /// Take the sink TOML for an operation consuming it, see [`SinkClient::_put_back`].
+    fn _take(&mut self) -> SinkTOML {
+        let empty = SinkTOML::new(&self.sink_toml.path);
+        std::mem::replace(&mut self.sink_toml, empty)
+    }
+
+    /// Put back the sink TOML returned by an operation.
+    ///
+    /// If the operation failed, the sink TOML is reloaded from disk instead, as it was consumed.
+    fn _put_back<T>(&mut self, result: Result<(SinkTOML, T)>) -> Result<T> {
+        match result {
+            Ok((sink_toml, value)) => {
+                self.sink_toml = sink_toml;
+                Ok(value)
+            }
+            Err(e) => {
+                match SinkTOML::from_file(&self.sink_toml.path) {
+                    Ok(sink_toml) => self.sink_toml = sink_toml,
+                    Err(reload_err) => warn!("{reload_err}"),
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn _preflight(&self) -> Result<()> {
+        if github::api::is_offline() {
+            warn!("Offline, not checking access to the repositories!");
+            return Ok(());
+        }
+
+        // Many dependencies share a repository, which only needs to be checked once
+        let api = github::api::api();
+        let mut checked: BTreeMap<String, Option<String>> = BTreeMap::new();
+        let mut inaccessible = 0;
+        for (pathspec, dependency) in self.sink_toml.dependencies.iter() {
+            let origin = pathspec.get_full_origin();
+            let reason = checked
+                .entry(origin.clone())
+                .or_insert_with(|| api.check_access(&origin).err().map(|e| format!("{e:#}")));
+            let Some(reason) = reason else {
+                continue;
+            };
+
+            let _span = info_span!("dependency", %pathspec).entered();
+            let required = dependency
+                .to_github_dependency(pathspec)
+                .map_or(true, |dependency| dependency.required);
+            if required {
+                inaccessible += 1;
+                output::error(&anyhow::anyhow!("Cannot access '{origin}': {reason}"));
+            } else {
+                warn!("Cannot access '{origin}', which {pathspec} (optional) needs: {reason}");
+            }
+        }
+
+        match inaccessible {
+            0 => Ok(()),
+            _ => Err(crate::errors::GitHubError(format!(
+                "{inaccessible} of {} dependencies cannot be accessed, not installing anything!",
+                self.sink_toml.dependencies.len()
+            ))
+            .into()),
+        }
+    }
+    /// Check that every repository the dependencies are installed from can be read, before installing any of them.
+    ///
+    /// Every inaccessible dependency is reported at once, instead of failing one by one while installing.
+    /// Fails if a required dependency cannot be accessed, optional ones are only warned about.
+    /// Nothing is checked while offline, see [`github::api::set_offline`].
+    pub fn preflight(&self) -> Result<()> {
+        match self._preflight() {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.context("Preflight check failed!")),
+        }
+    }
+
+    /// Install the dependencies, or only the given one.
+    fn _install(&mut self, frozen: bool, only: Option<&GitHubPathspec>) -> Result<InstallReport> {
+        if frozen {
+            if !self.sink_lock.path.exists() {
+                return Err(anyhow::anyhow!(
+                    "'{}' does not exist, refusing to install in frozen mode!",
+                    self.sink_lock.path.display()
+                ));
+            }
+
+            let reasons = lock::out_of_sync(&self.sink_toml, &self.sink_lock);
+            if !reasons.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "'{}' is out of sync with the sink TOML, refusing to install in frozen mode: {}",
+                    self.sink_lock.path.display(),
+                    reasons.join(" ")
+                ));
+            }
+        }
+
+        let selected = |pathspec: &GitHubPathspec| only.is_none_or(|only| only == pathspec);
+
+        // Refuse to install anything if a single dependency is not allowed
+        for pathspec in self.sink_toml.dependencies.keys().filter(|p| selected(p)) {
+            self.sink_toml.policy.check(pathspec)?;
+        }
+
+        let root = self.sink_toml.root();
+        let mut report = InstallReport::default();
+        let started = Instant::now();
+        // Only this thread downloads through the metered API, so parallel clients are counted apart
+        let metered = Arc::new(MeteredApi::new(github::api::api()));
+        let scope = github::api::scoped_api(metered.clone());
+        // The size of the assets downloaded so far, counted against the total size limit
+        let mut spent = 0;
+        for (pathspec, dependency) in self.sink_toml.dependencies.iter() {
+            if !selected(pathspec) {
+                continue;
+            }
+            if interrupt::is_interrupted() {
+                warn!("Interrupted, not installing the remaining dependencies!");
+                break;
+            }
+            if self.fail_fast && !report.is_ok() {
+                warn!("Failing fast, not installing the remaining dependencies!");
+                break;
+            }
+            let _span = info_span!("dependency", %pathspec).entered();
+            let github_dependency = match dependency.to_github_dependency(pathspec) {
+                Ok(github_dependency) => github_dependency,
+                Err(e) => {
+                    output::error(&e);
+                    if let Some(observer) = &self.observer {
+                        observer.on_error(pathspec, &e);
+                    }
+                    report
+                        .outcomes
+                        .insert(pathspec.clone(), InstallOutcome::Invalid(e));
+                    report.summary.push(InstallSummary {
+                        pathspec: pathspec.clone(),
+                        action: InstallAction::Failed,
+                        version: String::from("-"),
+                        destination: String::from("-"),
+                    });
+                    continue;
+                }
+            };
+
+            // In frozen mode, install exactly what is locked
+            let expected = frozen
+                .then(|| self.sink_lock.dependencies.get(pathspec).cloned())
+                .flatten();
+            let policy = self.sink_toml.policy.remaining(spent);
+            let dependency_started = Instant::now();
+            let (bytes, downloads) = metered.totals();
+            if let Some(observer) = &self.observer {
+                observer.on_resolve(pathspec, &github_dependency.version);
+                metered.observe(Some((pathspec.clone(), observer.clone())));
+            }
+            let downloaded = match &expected {
+                Some(expected) => {
+                    github::download_locked(&github_dependency, expected, &root, &policy)
+                }
+                None => github::download(
+                    &github_dependency,
+                    &root,
+                    &policy,
+                    self.sink_lock.dependencies.get(pathspec),
+                ),
+            };
+            metered.observe(None);
+            let (total_bytes, total_downloads) = metered.totals();
+            let timing = Timing {
+                duration: dependency_started.elapsed(),
+                bytes: total_bytes - bytes,
+                downloads: total_downloads - downloads,
+            };
+            // Only what was actually downloaded counts, installs that were skipped cost nothing
+            spent += timing.bytes;
+            let previous_version = self
+                .sink_lock
+                .dependencies
+                .get(pathspec)
+                .map(|previous| previous.version.clone());
+
+            let outcome = match downloaded {
+                Ok(locked)
+                    if expected
+                        .as_ref()
+                        .is_some_and(|expected| !expected.same_files(&locked)) =>
+                {
+                    error!("{pathspec} does not match its digests in the lockfile!");
+                    InstallOutcome::Mismatch
+                }
+                Ok(locked) => {
+                    // Do not leave the link of the previous install behind
+                    let previous_link = self
+                        .sink_lock
+                        .dependencies
+                        .get(pathspec)
+                        .and_then(|previous| previous.link.clone());
+                    if let Some(previous_link) = previous_link {
+                        if locked.link.as_ref() != Some(&previous_link) {
+                            if let Err(e) = lock::remove_link(&root, &previous_link) {
+                                warn!("{e}");
+                            }
+                        }
+                    }
+                    if let Some(pattern) = detect::guessed_pattern(&github_dependency, &locked) {
+                        report.guessed.insert(pathspec.clone(), pattern);
+                    }
+                    self.sink_lock
+                        .dependencies
+                        .insert(pathspec.clone(), locked.clone());
+                    report.timings.dependencies.insert(pathspec.clone(), timing);
+                    InstallOutcome::Installed(locked)
+                }
+                Err(e) if github_dependency.required => {
+                    output::error(&e);
+                    InstallOutcome::Failed(e)
+                }
+                Err(e) => {
+                    let outcome = InstallOutcome::Optional(e);
+                    warn!(
+                        "Skipping optional dependency: {}",
+                        outcome.reason().unwrap_or_default()
+                    );
+                    outcome
+                }
+            };
+            if let Some(observer) = &self.observer {
+                match &outcome {
+                    InstallOutcome::Installed(locked) => observer.on_complete(pathspec, locked),
+                    InstallOutcome::Failed(e)
+                    | InstallOutcome::Optional(e)
+                    | InstallOutcome::Invalid(e) => observer.on_error(pathspec, e),
+                    InstallOutcome::Mismatch => observer.on_error(
+                        pathspec,
+                        &anyhow::anyhow!("{pathspec} does not match its digests in the lockfile!"),
+                    ),
+                }
+            }
+            report.summary.push(match &outcome {
+                InstallOutcome::Installed(locked) => InstallSummary {
+                    pathspec: pathspec.clone(),
+                    action: match previous_version {
+                        _ if report.timings.dependencies[pathspec].is_cached() => {
+                            InstallAction::Skipped
+                        }
+                        None => InstallAction::Installed,
+                        Some(previous) if previous != locked.version => InstallAction::Updated,
+                        Some(_) => InstallAction::Installed,
+                    },
+                    version: locked.version.clone(),
+                    destination: github_dependency
+                        .install_dir(&locked.version)
+                        .display()
+                        .to_string(),
+                },
+                _ => InstallSummary {
+                    pathspec: pathspec.clone(),
+                    action: InstallAction::Failed,
+                    version: String::from("-"),
+                    destination: github_dependency.destination.display().to_string(),
+                },
+            });
+            report.outcomes.insert(pathspec.clone(), outcome);
+        }
+        drop(scope);
+        report.summary.sort_by(|a, b| a.pathspec.cmp(&b.pathspec));
+
+        self.sink_lock.save()?;
+        gitignore::sync(&self.sink_toml, &self.sink_lock)?;
+        env::sync(&self.sink_toml, &self.sink_lock)?;
+        // What was installed so far is locked, but the project is incomplete
+        interrupt::check()?;
+
+        // Installing a single dependency is no install of the project
+        if only.is_none() && report.is_ok() {
+            let installed: Vec<&GitHubPathspec> =
+                report.installed().map(|(pathspec, _)| pathspec).collect();
+            hooks::run_project(&self.sink_toml, &installed)?;
+        }
+        report.timings.total = started.elapsed();
+
+        Ok(report)
+    }
+    /// Install all dependencies and update the lockfile.
+    ///
+    /// In frozen mode, the lockfile must exist and be in sync with the sink TOML,
+    /// and exactly the locked assets are installed.
+    /// Failing dependencies do not stop the others from being installed, unless failing fast, see [`SinkClient::set_fail_fast`].
+    /// Once sink is interrupted, no further dependencies are installed and an [`crate::errors::InterruptedError`] is returned.
+    /// Once all dependencies are installed, the post-install hook of the sink TOML is run, see [`hooks::run_project`].
+    pub fn install(&mut self, frozen: bool) -> Result<InstallReport> {
+        match self._install(frozen, None) {
+            Ok(report) => Ok(report),
+            Err(e) => Err(e.context("Failed to install dependencies!")),
+        }
+    }
+
+    /// Install a single dependency of the sink TOML and update the lockfile.
+    ///
+    /// Returns the lockfile entry of the installed dependency.
+    pub fn install_dependency(&mut self, pathspec: &GitHubPathspec) -> Result<LockedDependency> {
+        if !self.sink_toml.dependencies.contains_key(pathspec) {
+            return Err(anyhow::anyhow!("Dependency '{pathspec}' does not exist!"));
+        }
+
+        let mut report = match self._install(false, Some(pathspec)) {
+            Ok(report) => report,
+            Err(e) => return Err(e.context(format!("Failed to install '{pathspec}'!"))),
+        };
+        match report.outcomes.remove(pathspec) {
+            Some(InstallOutcome::Installed(locked)) => Ok(locked),
+            Some(
+                InstallOutcome::Invalid(e)
+                | InstallOutcome::Failed(e)
+                | InstallOutcome::Optional(e),
+            ) => Err(e.context(format!("Failed to install '{pathspec}'!"))),
+            _ => Err(anyhow::anyhow!("Failed to install '{pathspec}'!")),
+        }
+    }
+
+    fn _add(&mut self, dependency: GitHubDependency, short_form: bool) -> Result<LockedDependency> {
+        backup::create(&self.sink_toml.path)?;
+
+        let pathspec = dependency.pathspec.clone();
+        let sink_toml = self._take();
+        let result = github::add(sink_toml, &mut self.sink_lock, dependency, short_form)
+            .map(|sink_toml| (sink_toml, ()));
+        self._put_back(result)?;
+
+        self.sink_lock
+            .dependencies
+            .get(&pathspec)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Dependency '{pathspec}' has not been locked!"))
+    }
+    /// Add and install a dependency.
+    ///
+    /// Returns the lockfile entry of the installed dependency.
+    pub fn add(
+        &mut self,
+        dependency: GitHubDependency,
+        short_form: bool,
+    ) -> Result<LockedDependency> {
+        match self._add(dependency, short_form) {
+            Ok(locked) => Ok(locked),
+            Err(e) => Err(e.context("Failed to add dependency!")),
+        }
+    }
+
+    fn _remove(&mut self, pathspecs: &[GitHubPathspec], keep_files: bool) -> Result<RemoveReport> {
+        backup::create(&self.sink_toml.path)?;
+
+        let mut report = RemoveReport::default();
+        for pathspec in pathspecs {
+            let sink_toml = self._take();
+            let result = github::remove(sink_toml, &mut self.sink_lock, pathspec, keep_files);
+            report.files.extend(self._put_back(result)?);
+            report.removed.push(pathspec.clone());
+        }
+
+        Ok(report)
+    }
+    /// Remove the given dependencies, see [`github::remove`].
+    ///
+    /// Use [`SinkTOML::matching_dependencies`] to resolve glob patterns.
+    /// Stops at the first failure, the dependencies removed until then stay removed.
+    pub fn remove(
+        &mut self,
+        pathspecs: &[GitHubPathspec],
+        keep_files: bool,
+    ) -> Result<RemoveReport> {
+        match self._remove(pathspecs, keep_files) {
+            Ok(report) => Ok(report),
+            Err(e) => Err(e.context("Failed to remove dependencies!")),
+        }
+    }
+
+    fn _persist_pattern(
+        &mut self,
+        pathspec: &GitHubPathspec,
+        pattern: &str,
+    ) -> Result<GitHubPathspec> {
+        let renamed = GitHubPathspec::new(pathspec.owner(), pathspec.repository(), pattern)?;
+        backup::create(&self.sink_toml.path)?;
+
+        let sink_toml = self._take();
+        let result = sink_toml
+            .rename_dependency(pathspec, renamed.clone())
+            .map(|sink_toml| (sink_toml, ()));
+        self._put_back(result)?;
+
+        if let Some(locked) = self.sink_lock.dependencies.remove(pathspec) {
+            self.sink_lock.dependencies.insert(renamed.clone(), locked);
+            self.sink_lock.save()?;
+        }
+
+        Ok(renamed)
+    }
+    /// Replace the pattern of a dependency, e.g. by the one of a guessed asset.
+    ///
+    /// The lockfile entry is kept, as the same assets match. Returns the new pathspec.
+    pub fn persist_pattern(
+        &mut self,
+        pathspec: &GitHubPathspec,
+        pattern: &str,
+    ) -> Result<GitHubPathspec> {
+        match self._persist_pattern(pathspec, pattern) {
+            Ok(renamed) => Ok(renamed),
+            Err(e) => Err(e.context(format!("Failed to persist the pattern of '{pathspec}'!"))),
+        }
+    }
+
+    fn _use_version(
+        &mut self,
+        pathspec: &GitHubPathspec,
+        version: &GitHubVersion,
+    ) -> Result<LockedDependency> {
+        let Some(dependency) = self.sink_toml.dependencies.get(pathspec) else {
+            return Err(anyhow::anyhow!("Dependency '{pathspec}' does not exist!"));
+        };
+        let dependency = dependency.to_github_dependency(pathspec)?;
+        if !dependency.versioned || dependency.link.is_none() {
+            return Err(anyhow::anyhow!(
+                "'{pathspec}' needs 'versioned = true' and a 'link' to switch between versions!"
+            ));
+        }
+
+        backup::create(&self.sink_toml.path)?;
+
+        let sink_toml = self._take();
+        let result = sink_toml
+            .set_version(pathspec, version)
+            .map(|sink_toml| (sink_toml, ()));
+        self._put_back(result)?;
+
+        self.install_dependency(pathspec)
+    }
+    /// Switch a versioned dependency to the given version, see [`GitHubDependency::versioned`].
+    ///
+    /// The version is pinned in the sink TOML and installed next to the others, unless it already is.
+    /// Afterwards, the link of the dependency points to it. Returns the lockfile entry of the dependency.
+    pub fn use_version(
+        &mut self,
+        pathspec: &GitHubPathspec,
+        version: &GitHubVersion,
+    ) -> Result<LockedDependency> {
+        match self._use_version(pathspec, version) {
+            Ok(locked) => Ok(locked),
+            Err(e) => Err(e.context(format!("Failed to switch the version of '{pathspec}'!"))),
+        }
+    }
+
+    fn _lock(&mut self) -> Result<()> {
+        // Keep the version, members and unknown fields of the lockfile
+        let mut locked = self.sink_lock.clone();
+        locked.dependencies.clear();
+
+        let mut failure = None;
+        for (pathspec, dependency) in self.sink_toml.dependencies.iter() {
+            let dependency = dependency.to_github_dependency(pathspec);
+            let required = dependency.as_ref().map_or(true, |d| d.required);
+            match dependency
+                .and_then(|dependency| github::lock(&dependency, &self.sink_toml.policy))
+            {
+                Ok(locked_dependency) => {
+                    locked
+                        .dependencies
+                        .insert(pathspec.clone(), locked_dependency);
+                }
+                // Optional dependencies keep what was locked before
+                Err(e) if !required => {
+                    warn!("Skipping optional dependency: {}", SinkError::Any(e));
+                    if let Some(previous) = self.sink_lock.dependencies.get(pathspec) {
+                        locked
+                            .dependencies
+                            .insert(pathspec.clone(), previous.clone());
+                    }
+                }
+                // The first failure is returned, so only log the others
+                Err(e) if failure.is_some() => output::error(&e),
+                Err(e) => failure = Some(e),
+            }
+        }
+
+        // Never write a partial lockfile
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        locked.save()?;
+        self.sink_lock = locked;
+
+        Ok(())
+    }
+    /// Resolve and lock all dependencies without installing them, see [`github::lock`].
+    ///
+    /// Nothing is written to the destinations. Optional dependencies that fail keep their previous entry.
+    /// The lockfile is only saved if all required dependencies were locked.
+    pub fn lock(&mut self) -> Result<()> {
+        match self._lock() {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.context("Failed to lock dependencies!")),
+        }
+    }
+
+    fn _update(&mut self, selection: &[GitHubPathspec], dry_run: bool) -> Result<Vec<Upgrade>> {
+        let upgrades = upgrade::plan(&self.sink_toml, selection)?;
+        if upgrades.is_empty() || dry_run {
+            return Ok(upgrades);
+        }
+
+        backup::create(&self.sink_toml.path)?;
+
+        let sink_toml = self._take();
+        let result = upgrade::apply(sink_toml, &upgrades).map(|sink_toml| (sink_toml, ()));
+        self._put_back(result)?;
+
+        Ok(upgrades)
+    }
+    /// Bump the dependencies pinned to a tag to their newest allowed release, see [`upgrade::plan`].
+    ///
+    /// If `selection` is not empty, only those dependencies are checked.
+    /// Only the sink TOML is modified, unless `dry_run` is set. Returns the found upgrades.
+    pub fn update(&mut self, selection: &[GitHubPathspec], dry_run: bool) -> Result<Vec<Upgrade>> {
+        match self._update(selection, dry_run) {
+            Ok(upgrades) => Ok(upgrades),
+            Err(e) => Err(e.context("Failed to update dependencies!")),
+        }
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_remove() {
+        let root = std::env::temp_dir().join(format!("sink-test-client-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("sink.toml");
+        fs::write(
+            &path,
+            "[dependencies]\n\"owner/repo:a\" = \"v1.0.0\"\n\"owner/repo:b\" = \"v1.0.0\"\n",
+        )
+        .unwrap();
+
+        let mut client = SinkClient::load(&path, false).unwrap();
+        let a = GitHubPathspec::new("owner", "repo", "a").unwrap();
+        let b = GitHubPathspec::new("owner", "repo", "b").unwrap();
+
+        let report = client.remove(std::slice::from_ref(&a), true).unwrap();
+        assert_eq!(report.removed, vec![a.clone()]);
+        assert!(!fs::read_to_string(&path).unwrap().contains("owner/repo:a"));
+
+        // The consumed sink TOML is reloaded after a failure
+        assert!(client.remove(&[a], true).is_err());
+        assert!(client.sink_toml().dependencies.contains_key(&b));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_install_total_size() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "5678"),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-total-size").unwrap();
+        let mut sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/repo:b", "v1.0.0")
+            .write()
+            .unwrap();
+        sink_toml.policy.max_total_size = Some(crate::policy::ByteSize(6));
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+
+        // Each asset fits on its own, but not both of them
+        let report = SinkClient::new(sink_toml, sink_lock)
+            .install(false)
+            .unwrap();
+        assert_eq!(report.installed().count(), 1);
+        assert!(report.outcomes.values().any(|outcome| matches!(
+            outcome,
+            InstallOutcome::Failed(e) if e.chain().any(|cause| cause.is::<crate::errors::PolicyError>())
+        )));
+    }
+
+    #[test]
+    fn test_install_sizes() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "5678"),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-sizes").unwrap();
+        project
+            .sink_toml()
+            .dependency_table("owner/repo:a", "{ version = \"v1.0.0\", versioned = true }")
+            .dependency("owner/repo:b", "v1.0.0")
+            .write()
+            .unwrap();
+        let client = |max_asset_size: Option<u64>, max_total_size: Option<u64>| {
+            let mut sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+            sink_toml.policy.max_asset_size = max_asset_size.map(crate::policy::ByteSize);
+            sink_toml.policy.max_total_size = max_total_size.map(crate::policy::ByteSize);
+            let sink_lock = SinkLock::load(&sink_toml).unwrap();
+            SinkClient::new(sink_toml, sink_lock)
+        };
+        let b = GitHubPathspec::try_from(String::from("owner/repo:b")).unwrap();
+        let is_policy_error = |report: &InstallReport| {
+            matches!(
+                &report.outcomes[&b],
+                InstallOutcome::Failed(e) if e.chain().any(|cause| cause.is::<crate::errors::PolicyError>())
+            )
+        };
+        assert!(client(None, None).install(false).unwrap().is_ok());
+
+        // The versioned install is reused, so it does not count against the total size
+        assert!(client(None, Some(6)).install(false).unwrap().is_ok());
+
+        // Frozen installs obey the size limits as well, for the files they have to download
+        assert!(client(Some(3), None).install(true).unwrap().is_ok());
+        std::fs::write(project.root.join("b"), "changed").unwrap();
+        assert!(is_policy_error(
+            &client(Some(3), None).install(true).unwrap()
+        ));
+        assert!(client(Some(4), None).install(true).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_lock() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+        let project = TempProject::new("client-lock").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency_table(
+                "owner/repo:a",
+                "{ version = \"v1.0.0\", destination = \"tools\" }",
+            )
+            .dependency_table("owner/gone:b", "{ version = \"v1.0.0\", required = false }")
+            .write()
+            .unwrap();
+        let optional = GitHubPathspec::new("owner", "gone", "b").unwrap();
+        let previous = LockedDependency {
+            version: String::from("v0.9.0"),
+            ..Default::default()
+        };
+        let mut sink_lock = SinkLock::load(&sink_toml).unwrap();
+        sink_lock
+            .dependencies
+            .insert(optional.clone(), previous.clone());
+        sink_lock
+            .unknown_fields
+            .insert(String::from("future"), toml::Value::Boolean(true));
+
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+        client.lock().unwrap();
+        assert!(!project.root.join("tools").exists());
+
+        let saved = SinkLock::load(client.sink_toml()).unwrap();
+        assert_eq!(saved.dependencies[&optional], previous);
+        assert_eq!(
+            saved.dependencies[&GitHubPathspec::new("owner", "repo", "a").unwrap()].files[0]
+                .checksum,
+            lock::digest_bytes(b"1234")
+        );
+        assert!(saved.unknown_fields.contains_key("future"));
+
+        // A failing required dependency leaves the lockfile untouched
+        let lockfile = project.root.join(lock::LOCKFILE_NAME);
+        let contents = fs::read_to_string(&lockfile).unwrap();
+        project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/gone:c", "v1.0.0")
+            .write()
+            .unwrap();
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        assert!(client.lock().is_err());
+        assert_eq!(fs::read_to_string(&lockfile).unwrap(), contents);
+    }
+
+    #[test]
+    fn test_install_unchanged_signature() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        // The example of the minisign-verify crate, signing 'test'
+        let public_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        let signature = "untrusted comment: signature from minisign secret key\n\
+            RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+            trusted comment: timestamp:1633700835\tfile:test\tprehashed\n\
+            wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==\n";
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/signed",
+            MockRelease::new("v1.0.0")
+                .asset("tool", "tampered")
+                .asset("tool.minisig", signature)
+                .digests(),
+        );
+        let _guard = server.install();
+        let project = TempProject::new("client-unchanged-signature").unwrap();
+        project
+            .sink_toml()
+            .dependency("owner/signed:tool", "v1.0.0")
+            .write()
+            .unwrap();
+        assert!(SinkClient::load(&project.sink_toml_path(), false)
+            .unwrap()
+            .install(false)
+            .unwrap()
+            .is_ok());
+
+        // The installed file matches its published digest, but must still match the signature
+        project
+            .sink_toml()
+            .dependency_table(
+                "owner/signed:tool",
+                &format!("{{ version = \"v1.0.0\", minisign-key = \"{public_key}\" }}"),
+            )
+            .write()
+            .unwrap();
+        let report = SinkClient::load(&project.sink_toml_path(), false)
+            .unwrap()
+            .install(false)
+            .unwrap();
+        assert!(report.outcomes.values().any(|outcome| matches!(
+            outcome,
+            InstallOutcome::Failed(e) if crate::errors::ExitCode::from_error(e) == crate::errors::ExitCode::Verification
+        )));
+    }
+
+    #[test]
+    fn test_install_versioned_tampered() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+        let project = TempProject::new("client-versioned-tampered").unwrap();
+        project
+            .sink_toml()
+            .dependency_table(
+                "owner/repo:a",
+                "{ version = \"v1.0.0\", destination = \"tools\", versioned = true }",
+            )
+            .write()
+            .unwrap();
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        assert!(client.install(false).unwrap().is_ok());
+
+        // Without a published digest, the file must match the lockfile to be kept
+        let asset = project.root.join("tools/v1.0.0/a");
+        fs::write(&asset, "tampered").unwrap();
+        let report = client.install(false).unwrap();
+        assert_eq!(report.timings.bytes(), 4);
+        assert_eq!(fs::read_to_string(&asset).unwrap(), "1234");
+        assert_eq!(
+            client
+                .sink_lock()
+                .dependencies
+                .values()
+                .next()
+                .unwrap()
+                .files[0]
+                .checksum,
+            lock::digest_bytes(b"1234")
+        );
+    }
+
+    #[test]
+    fn test_install_optional() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+        let project = TempProject::new("client-install-optional").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency_table("owner/gone:b", "{ version = \"v1.0.0\", required = false }")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+
+        // The missing dependency is skipped without failing the install
+        let report = SinkClient::new(sink_toml, sink_lock)
+            .install(false)
+            .unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.installed().count(), 1);
+        let gone = GitHubPathspec::try_from(String::from("owner/gone:b")).unwrap();
+        assert!(matches!(
+            report.outcomes[&gone],
+            InstallOutcome::Optional(_)
+        ));
+
+        // It is still reported along with why it failed
+        assert_eq!(report.failures().count(), 1);
+        assert!(report.failure_report().unwrap().starts_with(
+            "1 of 2 dependencies could not be installed:\n    owner/gone:b (optional): "
+        ));
+    }
+
+    #[test]
+    fn test_install_fail_fast() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+        let project = TempProject::new("client-install-fail-fast").unwrap();
+        project
+            .sink_toml()
+            .dependency("owner/gone:a", "v1.0.0")
+            .dependency("owner/gone:b", "v1.0.0")
+            .dependency_table("owner/gone:c", "{ version = \"v1.0.0\", required = false }")
+            .write()
+            .unwrap();
+
+        // Nothing is installed after the first required dependency failed
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        client.set_fail_fast(true);
+        let report = client.install(false).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(
+            report
+                .outcomes
+                .values()
+                .filter(|outcome| matches!(outcome, InstallOutcome::Failed(_)))
+                .count(),
+            1
+        );
+
+        // Otherwise, all dependencies are tried
+        client.set_fail_fast(false);
+        let report = client.install(false).unwrap();
+        assert_eq!(report.outcomes.len(), 3);
+    }
+
+    #[test]
+    fn test_preflight() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"))
+            .with_private("owner/secret")
+            .with_private("owner/extra");
+        let _guard = server.install();
+        let project = TempProject::new("client-preflight").unwrap();
+        project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency_table(
+                "owner/extra:a",
+                "{ version = \"v1.0.0\", required = false }",
+            )
+            .write()
+            .unwrap();
+
+        // Inaccessible optional dependencies do not fail the check
+        let client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        client.preflight().unwrap();
+
+        project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/secret:a", "v1.0.0")
+            .dependency("owner/secret:b", "v1.0.0")
+            .write()
+            .unwrap();
+        let client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        let e = client.preflight().unwrap_err();
+        assert!(e
+            .chain()
+            .any(|cause| cause.is::<crate::errors::GitHubError>()));
+        assert!(format!("{e:#}").contains("2 of 3 dependencies cannot be accessed"));
+    }
+
+    #[test]
+    fn test_install_dependency() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "5678"),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-dependency").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/repo:b", "v1.0.0")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+
+        // Only the given dependency is installed
+        let pathspec = GitHubPathspec::try_from(String::from("owner/repo:a")).unwrap();
+        let locked = client.install_dependency(&pathspec).unwrap();
+        assert_eq!(locked.files.len(), 1);
+        assert_eq!(
+            client.sink_lock().dependencies.keys().collect::<Vec<_>>(),
+            vec![&pathspec]
+        );
+
+        let missing = GitHubPathspec::try_from(String::from("owner/missing")).unwrap();
+        assert!(client.install_dependency(&missing).is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_install_hooks() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "5678"),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-hooks").unwrap();
+        fs::write(
+            project.sink_toml_path(),
+            r#"[hooks]
+post-install = ["printf '%s' \"$SINK_INSTALLED\" > installed.txt"]
+
+[dependencies]
+"owner/repo:a" = { version = "v1.0.0", destination = "bin", post-install = ["cat a > hooked"] }
+"owner/repo:b" = { version = "v1.0.0", destination = "bin" }
+"#,
+        )
+        .unwrap();
+        let sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+
+        // A single dependency only runs its own hooks
+        let pathspec = GitHubPathspec::try_from(String::from("owner/repo:a")).unwrap();
+        client.install_dependency(&pathspec).unwrap();
+        assert_eq!(
+            fs::read_to_string(project.root.join("bin/hooked")).unwrap(),
+            "1234"
+        );
+        assert!(!project.root.join("installed.txt").exists());
+
+        assert!(client.install(false).unwrap().is_ok());
+        assert_eq!(
+            fs::read_to_string(project.root.join("installed.txt")).unwrap(),
+            "owner/repo:a\nowner/repo:b"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_included_hooks() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+
+        let project = TempProject::new("client-included-hooks").unwrap();
+        fs::write(
+            project.root.join("shared.toml"),
+            r#"[dependencies]
+"owner/repo:a" = { version = "v1.0.0", destination = "bin", pre-install = ["touch pre"], post-install = ["touch post"] }
+"#,
+        )
+        .unwrap();
+        fs::write(
+            project.sink_toml_path(),
+            format!(
+                "includes = [{:?}]\n\n[dependencies]\n",
+                project.root.join("shared.toml").display().to_string()
+            ),
+        )
+        .unwrap();
+        let sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+
+        assert!(client.install(false).unwrap().is_ok());
+        assert_eq!(
+            fs::read_to_string(project.root.join("bin/a")).unwrap(),
+            "1234"
+        );
+        assert!(!project.root.join("bin/pre").exists());
+        assert!(!project.root.join("bin/post").exists());
+    }
+
+    #[test]
+    fn test_install_timings() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "567890"),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-timings").unwrap();
+        fs::write(
+            project.sink_toml_path(),
+            r#"[dependencies]
+"owner/repo:a" = { version = "v1.0.0", destination = "tools", versioned = true }
+"owner/repo:b" = { version = "v1.0.0", destination = "bin" }
+"#,
+        )
+        .unwrap();
+        let sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+        let a = GitHubPathspec::try_from(String::from("owner/repo:a")).unwrap();
+        let b = GitHubPathspec::try_from(String::from("owner/repo:b")).unwrap();
+
+        let timings = client.install(false).unwrap().timings;
+        assert_eq!(timings.bytes(), 10);
+        assert_eq!(timings.cache_hits(), 0);
+        assert_eq!(timings.dependencies[&a].downloads, 1);
+        assert_eq!(timings.dependencies[&b].bytes, 6);
+        assert!(timings.total >= timings.dependencies[&a].duration);
+        assert_eq!(timings.slowest(1).len(), 1);
+
+        // Versioned installs are reused, everything else is downloaded again
+        let timings = client.install(false).unwrap().timings;
+        assert_eq!(timings.bytes(), 6);
+        assert_eq!(timings.cache_hits(), 1);
+        assert!(timings.dependencies[&a].is_cached());
+        assert!(timings.to_string().contains("1 downloaded, 1 cached"));
+    }
+
+    #[test]
+    fn test_install_unchanged_assets() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "567890")
+                .digests(),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-unchanged").unwrap();
+        project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/repo:b", "v1.0.0")
+            .write()
+            .unwrap();
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        let a = GitHubPathspec::try_from(String::from("owner/repo:a")).unwrap();
+        let b = GitHubPathspec::try_from(String::from("owner/repo:b")).unwrap();
+        let report = client.install(false).unwrap();
+        assert_eq!(report.timings.cache_hits(), 0);
+        let file = project.root.join(
+            &report
+                .installed()
+                .find(|(pathspec, _)| **pathspec == b)
+                .unwrap()
+                .1
+                .files[0]
+                .path,
+        );
+
+        // Files matching the digests of their assets are not downloaded again, even without a lockfile
+        fs::remove_file(&client.sink_lock().path).unwrap();
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        fs::write(&file, "tampered").unwrap();
+        let report = client.install(false).unwrap();
+        assert!(report.timings.dependencies[&a].is_cached());
+        assert!(!report.timings.dependencies[&b].is_cached());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "567890");
+        assert!(report
+            .installed()
+            .all(|(_, locked)| locked.files.len() == 1));
+    }
+
+    #[test]
+    fn test_install_observer() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::sync::Mutex;
+
+        // Dependencies are installed in no particular order, so the events are recorded per dependency
+        #[derive(Default)]
+        struct Recorder(Mutex<BTreeMap<String, Vec<String>>>);
+        impl Recorder {
+            fn record(&self, pathspec: &GitHubPathspec, event: String) {
+                let mut events = self.0.lock().unwrap();
+                events.entry(pathspec.to_string()).or_default().push(event);
+            }
+        }
+        impl InstallObserver for Recorder {
+            fn on_resolve(&self, pathspec: &GitHubPathspec, version: &GitHubVersion) {
+                self.record(pathspec, format!("resolve {version}"));
+            }
+
+            fn on_download_progress(
+                &self,
+                pathspec: &GitHubPathspec,
+                asset: &GitHubAsset,
+                bytes: u64,
+            ) {
+                self.record(
+                    pathspec,
+                    format!("download {} {bytes}/{}", asset.name, asset.size),
+                );
+            }
+
+            fn on_complete(&self, pathspec: &GitHubPathspec, locked: &LockedDependency) {
+                self.record(pathspec, format!("complete {}", locked.version));
+            }
+
+            fn on_error(&self, pathspec: &GitHubPathspec, _error: &anyhow::Error) {
+                self.record(pathspec, String::from("error"));
+            }
+        }
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-observer").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/gone:b", "v1.0.0")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+        let recorder = Arc::new(Recorder::default());
+        client.set_observer(Some(recorder.clone()));
+
+        client.install(false).unwrap();
+        let events = recorder.0.lock().unwrap().clone();
+        assert_eq!(events["owner/gone:b"], vec!["resolve v1.0.0", "error"]);
+        assert_eq!(
+            events["owner/repo:a"],
+            vec![
+                "resolve v1.0.0",
+                "download a 0/4",
+                "download a 4/4",
+                "complete v1.0.0",
+            ]
+        );
+
+        // Nothing is reported once the observer is removed
+        client.set_observer(None);
+        recorder.0.lock().unwrap().clear();
+        client.install(false).unwrap();
+        assert!(recorder.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_install_interrupted() {
+        use crate::errors::ExitCode;
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        struct Interrupter;
+        impl InstallObserver for Interrupter {
+            fn on_complete(&self, _pathspec: &GitHubPathspec, _locked: &LockedDependency) {
+                interrupt::set_interrupted(true);
+            }
+        }
+
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/repo",
+            MockRelease::new("v1.0.0")
+                .asset("a", "1234")
+                .asset("b", "5678"),
+        );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-interrupted").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .dependency("owner/repo:b", "v1.0.0")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+
+        // The dependency installed before the interrupt is kept and locked
+        client.set_observer(Some(Arc::new(Interrupter)));
+        let result = client.install(false);
+        client.set_observer(None);
+        let interrupted = client.install(false);
+        interrupt::set_interrupted(false);
+        assert_eq!(
+            ExitCode::from_error(&result.unwrap_err()),
+            ExitCode::Interrupted
+        );
+        assert!(interrupted.is_err());
+        let locked = SinkLock::load(client.sink_toml()).unwrap();
+        assert_eq!(locked.dependencies.len(), 1);
+
+        assert!(client.install(false).unwrap().is_ok());
+        assert_eq!(client.sink_lock().dependencies.len(), 2);
+    }
+
+    #[test]
+    fn test_install_frozen_mismatch() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+        let project = TempProject::new("client-frozen-mismatch").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:a", "v1.0.0")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        SinkClient::new(sink_toml, sink_lock)
+            .install(false)
+            .unwrap();
+
+        // The lockfile expects other contents than the release offers
+        let lock_path = project.root.join("sink.lock");
+        let digest = lock::digest_bytes(b"1234");
+        let lockfile = fs::read_to_string(&lock_path).unwrap();
+        fs::write(
+            &lock_path,
+            lockfile.replace(&digest, &lock::digest_bytes(b"5678")),
+        )
+        .unwrap();
+        let asset = project.root.join("a");
+        fs::write(&asset, "0000").unwrap();
+
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        let report = client.install(true).unwrap();
+        assert!(matches!(
+            report.outcomes.values().next(),
+            Some(InstallOutcome::Mismatch)
+        ));
+
+        // The unverified asset was never placed
+        assert_eq!(fs::read_to_string(&asset).unwrap(), "0000");
+        let leftovers = fs::read_dir(&project.root)
+            .unwrap()
+            .filter(|entry| {
+                let name = entry.as_ref().unwrap().file_name();
+                name.to_string_lossy().ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_install_frozen_unchanged() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1234"));
+        let _guard = server.install();
+        let project = TempProject::new("client-frozen-unchanged").unwrap();
+        project
+            .sink_toml()
+            .dependency_table(
+                "owner/repo:a",
+                "{ version = \"v1.0.0\", pre-install = [\"echo pre >> hooks\"] }",
+            )
+            .write()
+            .unwrap();
+        let install = || {
+            let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+            let report = client.install(true).unwrap();
+            assert!(report.is_ok());
+            report.timings.bytes()
+        };
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        assert!(client.install(false).unwrap().is_ok());
+        let hooks = project.root.join("hooks");
+        fs::remove_file(&hooks).unwrap();
+
+        // Files matching the lockfile are neither downloaded again nor hooked
+        assert_eq!(install(), 0);
+        assert!(!hooks.exists());
+
+        fs::write(project.root.join("a"), "tampered").unwrap();
+        assert_eq!(install(), 4);
+        assert_eq!(fs::read_to_string(project.root.join("a")).unwrap(), "1234");
+        assert_eq!(fs::read_to_string(&hooks).unwrap(), "pre\n");
+    }
+
+    #[test]
+    fn test_install_summary() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+        use std::fs;
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("a", "1"))
+            .with_release("owner/repo", MockRelease::new("v2.0.0").asset("a", "2"))
+            .with_release("owner/other", MockRelease::new("v1.0.0").asset("b", "3"));
+        let _guard = server.install();
+
+        let project = TempProject::new("client-install-summary").unwrap();
+        let write = |version: &str| {
+            fs::write(
+                project.sink_toml_path(),
+                format!(
+                    r#"[dependencies]
+"owner/repo:a" = {{ version = "{version}", destination = "bin" }}
+"owner/other:b" = {{ version = "v1.0.0", destination = "tools", versioned = true }}
+"owner/gone:missing" = {{ version = "v1.0.0", destination = "bin" }}
+"#
+                ),
+            )
+            .unwrap();
+        };
+        let summary = |client: &mut SinkClient| -> Vec<(String, InstallAction, String, String)> {
+            client
+                .install(false)
+                .unwrap()
+                .summary
+                .into_iter()
+                .map(|row| {
+                    (
+                        row.pathspec.to_string(),
+                        row.action,
+                        row.version,
+                        row.destination,
+                    )
+                })
+                .collect()
+        };
+        let row = |pathspec: &str, action, version: &str, destination: &str| {
+            (
+                String::from(pathspec),
+                action,
+                String::from(version),
+                String::from(destination),
+            )
+        };
+
+        write("v1.0.0");
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        let tools = PathBuf::from("tools").join("v1.0.0").display().to_string();
+        assert_eq!(
+            summary(&mut client),
+            vec![
+                row("owner/gone:missing", InstallAction::Failed, "-", "bin"),
+                row("owner/other:b", InstallAction::Installed, "v1.0.0", &tools),
+                row("owner/repo:a", InstallAction::Installed, "v1.0.0", "bin"),
+            ]
+        );
+
+        write("v2.0.0");
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        assert_eq!(
+            summary(&mut client),
+            vec![
+                row("owner/gone:missing", InstallAction::Failed, "-", "bin"),
+                row("owner/other:b", InstallAction::Skipped, "v1.0.0", &tools),
+                row("owner/repo:a", InstallAction::Updated, "v2.0.0", "bin"),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_use_version() {
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v2.0.0").asset("tool", "2").digests(),
+            )
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v1.0.0").asset("tool", "1").digests(),
+            );
+        let _guard = server.install();
+
+        let project = TempProject::new("client-use-version").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency_table(
+                "owner/repo:tool",
+                r#"{ version = "v1.0.0", destination = "tools", versioned = true, link = "bin/tool" }"#,
+            )
+            .dependency("owner/plain", "v1.0.0")
+            .write()
+            .unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let mut client = SinkClient::new(sink_toml, sink_lock);
+        let pathspec = GitHubPathspec::try_from(String::from("owner/repo:tool")).unwrap();
+        let link = project.root.join("bin/tool");
+
+        client.install_dependency(&pathspec).unwrap();
+        assert_eq!(fs::read_to_string(&link).unwrap(), "1");
+
+        // Both versions stay installed side by side
+        let v2 = GitHubVersion::Tag(String::from("v2.0.0"));
+        let locked = client.use_version(&pathspec, &v2).unwrap();
+        assert_eq!(locked.files[0].path, PathBuf::from("tools/v2.0.0/tool"));
+        assert_eq!(fs::read_to_string(&link).unwrap(), "2");
+        assert!(project.root.join("tools/v1.0.0/tool").is_file());
+        assert!(fs::read_to_string(project.sink_toml_path())
+            .unwrap()
+            .contains(r#"version = "v2.0.0""#));
+
+        // Switching back reuses the installed version, unless it was modified
+        let v1 = GitHubVersion::Tag(String::from("v1.0.0"));
+        client.use_version(&pathspec, &v1).unwrap();
+        assert_eq!(fs::read_to_string(&link).unwrap(), "1");
+        client.use_version(&pathspec, &v2).unwrap();
+        fs::write(project.root.join("tools/v1.0.0/tool"), "1 (tampered)").unwrap();
+        client.use_version(&pathspec, &v1).unwrap();
+        assert_eq!(fs::read_to_string(&link).unwrap(), "1");
+
+        // Only versioned dependencies with a link can be switched
+        let plain = GitHubPathspec::try_from(String::from("owner/plain")).unwrap();
+        assert!(client.use_version(&plain, &v1).is_err());
+    }
+}
@@ -0,0 +1,210 @@
+use tracing::debug;
+
+use crate::github::{GitHubAsset, GitHubDependency};
+use crate::lock::LockedDependency;
+use crate::template;
+
+/// Extensions of common archive formats, preferred over bare binaries.
+const ARCHIVE_EXTENSIONS: [&str; 8] = [
+    ".tar.gz", ".tgz", ".tar.xz", ".txz", ".tar.bz2", ".tbz", ".tar.zst", ".zip",
+];
+
+/// Extensions of assets that never contain the tool itself, e.g. checksums, signatures or installers.
+const IGNORED_EXTENSIONS: [&str; 20] = [
+    ".sha256",
+    ".sha256sum",
+    ".sha512",
+    ".sha512sum",
+    ".md5",
+    ".sig",
+    ".asc",
+    ".pem",
+    ".crt",
+    ".pub",
+    ".sbom",
+    ".spdx",
+    ".json",
+    ".jsonl",
+    ".txt",
+    ".deb",
+    ".rpm",
+    ".apk",
+    ".msi",
+    ".dmg",
+];
+
+/// Whether the asset name has the extension of a common archive format, e.g. '.tar.gz' or '.zip'.
+pub fn is_archive(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ARCHIVE_EXTENSIONS
+        .iter()
+        .any(|extension| name.ends_with(extension))
+}
+
+/// Returns the names the given operating system goes by in asset names, see [`template::os`].
+fn _os_aliases(os: &str) -> Vec<&str> {
+    match os {
+        "darwin" => vec!["darwin", "macos", "apple", "osx"],
+        "windows" => vec!["windows", "win64", "win32"],
+        os => vec![os],
+    }
+}
+
+/// Returns the names the given architecture goes by in asset names, see [`template::arch`].
+fn _arch_aliases(arch: &str) -> Vec<&str> {
+    match arch {
+        "amd64" => vec!["x86_64", "amd64", "x64"],
+        "arm64" => vec!["aarch64", "arm64"],
+        "386" => vec!["i386", "i686", "386"],
+        "arm" => vec!["armv7", "armv6", "armhf"],
+        arch => vec![arch],
+    }
+}
+
+/// Rate how well the asset suits the running platform, `None` if it does not suit it at all.
+fn _score(repository: &str, name: &str, os: &str, arch: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    if IGNORED_EXTENSIONS
+        .iter()
+        .any(|extension| name.ends_with(extension))
+    {
+        return None;
+    }
+
+    let matches_os = _os_aliases(os).iter().any(|alias| name.contains(alias));
+    // Universal binaries run on every architecture of macOS
+    let matches_arch = _arch_aliases(arch).iter().any(|alias| name.contains(alias))
+        || (os == "darwin" && name.contains("universal"));
+    if !matches_os || !matches_arch {
+        return None;
+    }
+
+    let mut score = 0;
+    if name.contains(&repository.to_lowercase()) {
+        score += 4;
+    }
+    if is_archive(&name) {
+        score += 2;
+    }
+    // Statically linked binaries work on every distribution
+    if os == "linux" && name.contains("musl") {
+        score += 1;
+    }
+
+    Some(score)
+}
+
+/// Guess the asset of a release built for the running platform.
+///
+/// Similar to cargo-binstall, the asset names are searched for the operating system and architecture
+/// of the target triple. The name of the repository and common archive extensions are preferred.
+/// Checksums, signatures and installers are never guessed.
+pub fn guess<'a>(repository: &str, assets: &'a [GitHubAsset]) -> Option<&'a GitHubAsset> {
+    let (os, arch) = (template::os(), template::arch());
+
+    let mut best: Option<(&GitHubAsset, u32)> = None;
+    for asset in assets {
+        let Some(score) = _score(repository, &asset.name, os, arch) else {
+            continue;
+        };
+        debug!("'{}' scored {score} for {os}/{arch}!", asset.name);
+        if best.is_none_or(|(_, best)| score > best) {
+            best = Some((asset, score));
+        }
+    }
+
+    best.map(|(asset, _)| asset)
+}
+
+/// Turn the name of a guessed asset into a pattern that also matches future releases.
+///
+/// The version, with or without a leading 'v', is replaced by a wildcard.
+pub fn generalize(name: &str, tag: &str) -> String {
+    let semver = tag.trim_start_matches('v');
+    let version = [tag, semver]
+        .into_iter()
+        .find(|version| !version.is_empty() && name.contains(version));
+
+    match version {
+        Some(version) => name
+            .split(version)
+            .map(glob::Pattern::escape)
+            .collect::<Vec<String>>()
+            .join("*"),
+        None => glob::Pattern::escape(name),
+    }
+}
+
+/// Returns the pattern to persist if the assets of the installed dependency were guessed.
+///
+/// The assets were guessed if the pattern of the dependency matches none of the locked files.
+pub fn guessed_pattern(dependency: &GitHubDependency, locked: &LockedDependency) -> Option<String> {
+    let pattern = glob::Pattern::new(&dependency.asset_pattern(&locked.version).ok()?).ok()?;
+    let names: Vec<String> = locked
+        .files
+        .iter()
+        .filter_map(|file| file.path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+
+    match names.as_slice() {
+        [name] if !pattern.matches(name) => Some(generalize(name, &locked.version)),
+        _ => None,
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        let assets = [
+            "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz",
+            "ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz.sha256",
+            "ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz",
+            "ripgrep-14.1.0-x86_64-apple-darwin.tar.gz",
+            "ripgrep-14.1.0-x86_64-pc-windows-msvc.zip",
+            "ripgrep_14.1.0-1_amd64.deb",
+        ];
+        let best = |os: &str, arch: &str| {
+            assets
+                .iter()
+                .filter_map(|name| Some((*name, _score("ripgrep", name, os, arch)?)))
+                .max_by_key(|(_, score)| *score)
+                .map(|(name, _)| name)
+        };
+
+        assert_eq!(
+            best("linux", "amd64"),
+            Some("ripgrep-14.1.0-x86_64-unknown-linux-musl.tar.gz")
+        );
+        assert_eq!(
+            best("linux", "arm64"),
+            Some("ripgrep-14.1.0-aarch64-unknown-linux-gnu.tar.gz")
+        );
+        assert_eq!(
+            best("darwin", "amd64"),
+            Some("ripgrep-14.1.0-x86_64-apple-darwin.tar.gz")
+        );
+        assert_eq!(
+            best("windows", "amd64"),
+            Some("ripgrep-14.1.0-x86_64-pc-windows-msvc.zip")
+        );
+        assert_eq!(best("freebsd", "amd64"), None);
+    }
+
+    #[test]
+    fn test_generalize() {
+        assert_eq!(
+            generalize("tool-v1.2.3-linux-amd64.tar.gz", "v1.2.3"),
+            "tool-*-linux-amd64.tar.gz"
+        );
+        assert_eq!(
+            generalize("tool_1.2.3_linux_amd64[static].zip", "v1.2.3"),
+            "tool_*_linux_amd64[[]static[]].zip"
+        );
+        assert_eq!(generalize("tool-linux", "v1.2.3"), "tool-linux");
+    }
+}
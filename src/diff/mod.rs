@@ -0,0 +1,178 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::github::GitHubPathspec;
+use crate::lock::SinkLock;
+use crate::toml::DependencyType;
+use crate::SinkTOML;
+
+/// The versions of all dependencies of a sink TOML or lockfile.
+pub type Versions = BTreeMap<GitHubPathspec, String>;
+
+/// A dependency that only exists on one side of a [`Diff`].
+#[derive(Serialize, Debug, PartialEq)]
+pub struct DiffEntry {
+    pub pathspec: GitHubPathspec,
+    pub version: String,
+}
+
+/// A dependency whose version differs between both sides of a [`Diff`].
+#[derive(Serialize, Debug, PartialEq)]
+pub struct VersionChange {
+    pub pathspec: GitHubPathspec,
+    pub from: String,
+    pub to: String,
+}
+
+/// The differences between two sink TOMLs or lockfiles.
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct Diff {
+    pub added: Vec<DiffEntry>,
+    pub removed: Vec<DiffEntry>,
+    pub changed: Vec<VersionChange>,
+}
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+fn _sink_toml_versions(contents: &str) -> Result<Versions> {
+    let sink_toml: SinkTOML = toml::from_str(contents)?;
+
+    Ok(sink_toml
+        .dependencies
+        .into_iter()
+        .map(|(pathspec, dependency)| {
+            let version = match dependency {
+                DependencyType::Version(version) => version.to_string(),
+                DependencyType::Full(dependency) => dependency.version.to_string(),
+                DependencyType::Invalid(_) => String::from("invalid"),
+            };
+            (pathspec, version)
+        })
+        .collect())
+}
+/// Returns the requested versions of the dependencies of a sink TOML given by its contents.
+///
+/// Includes are not resolved.
+pub fn sink_toml_versions(contents: &str) -> Result<Versions> {
+    match _sink_toml_versions(contents) {
+        Ok(versions) => Ok(versions),
+        Err(e) => Err(e.context("Failed to parse sink TOML!")),
+    }
+}
+
+/// Returns the locked versions of the dependencies of a lockfile given by its contents.
+pub fn sink_lock_versions(contents: &str) -> Result<Versions> {
+    Ok(SinkLock::parse(contents)?
+        .dependencies
+        .into_iter()
+        .map(|(pathspec, locked)| (pathspec, locked.version))
+        .collect())
+}
+
+/// Compare the versions of two sink TOMLs or lockfiles.
+pub fn diff(old: &Versions, new: &Versions) -> Diff {
+    let mut diff = Diff::default();
+    for (pathspec, version) in new.iter() {
+        match old.get(pathspec) {
+            None => diff.added.push(DiffEntry {
+                pathspec: pathspec.clone(),
+                version: version.clone(),
+            }),
+            Some(old_version) if old_version != version => diff.changed.push(VersionChange {
+                pathspec: pathspec.clone(),
+                from: old_version.clone(),
+                to: version.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+    for (pathspec, version) in old.iter() {
+        if !new.contains_key(pathspec) {
+            diff.removed.push(DiffEntry {
+                pathspec: pathspec.clone(),
+                version: version.clone(),
+            });
+        }
+    }
+
+    diff
+}
+
+/// Format the diff for humans, one dependency per line.
+///
+/// Added dependencies are prefixed by '+', removed ones by '-' and changed ones by '~'.
+pub fn format(diff: &Diff) -> String {
+    let mut lines = Vec::new();
+    for entry in diff.added.iter() {
+        lines.push(format!("+ {} {}", entry.pathspec, entry.version));
+    }
+    for entry in diff.removed.iter() {
+        lines.push(format!("- {} {}", entry.pathspec, entry.version));
+    }
+    for change in diff.changed.iter() {
+        lines.push(format!(
+            "~ {} {} -> {}",
+            change.pathspec, change.from, change.to
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let old = sink_toml_versions(
+            r#"
+            [dependencies]
+            "owner/repo:a" = "v1.0.0"
+            "owner/repo:b" = { version = "v1.0.0", destination = "bin" }
+            "owner/repo:c" = "latest"
+            "#,
+        )
+        .unwrap();
+        let new = sink_toml_versions(
+            r#"
+            [dependencies]
+            "owner/repo:a" = "v1.0.0"
+            "owner/repo:b" = { version = "v2.0.0" }
+            "owner/repo:d" = "v1.0.0"
+            "#,
+        )
+        .unwrap();
+
+        let diff = diff(&old, &new);
+        assert_eq!(
+            format(&diff),
+            "+ owner/repo:d v1.0.0\n- owner/repo:c latest\n~ owner/repo:b v1.0.0 -> v2.0.0"
+        );
+        assert!(super::diff(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn test_sink_lock_versions() {
+        let versions = sink_lock_versions(
+            r#"
+            version = 2
+
+            [dependencies."owner/repo:a"]
+            version = "v1.2.3"
+            files = []
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            versions.get(&GitHubPathspec::new("owner", "repo", "a").unwrap()),
+            Some(&String::from("v1.2.3"))
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+use crate::atomic;
+use crate::lock::SinkLock;
+use crate::SinkTOML;
+
+/// Magic numbers at the start of executables: ELF, Mach-O (both byte orders, 32 and 64 bit, universal), PE and scripts.
+const EXECUTABLE_MAGIC: [&[u8]; 8] = [
+    b"\x7fELF",
+    b"\xfe\xed\xfa\xce",
+    b"\xfe\xed\xfa\xcf",
+    b"\xce\xfa\xed\xfe",
+    b"\xcf\xfa\xed\xfe",
+    b"\xca\xfe\xba\xbe",
+    b"MZ",
+    b"#!",
+];
+
+/// The shells `sink env` can print the export for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum Shell {
+    /// POSIX shells like sh, bash or zsh
+    #[default]
+    Sh,
+    Fish,
+    Powershell,
+}
+
+/// Returns the managed bin directory of the given sink TOML.
+///
+/// This is `.sink/bin` next to the sink TOML, see [`SinkTOML::link_executables`].
+pub fn bin_dir(sink_toml: &SinkTOML) -> PathBuf {
+    sink_toml.root().join(".sink").join("bin")
+}
+
+/// Whether the file at the given path is an executable, judging by its first bytes.
+pub fn is_executable(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    let read = fs::File::open(path)
+        .and_then(|mut file| file.read(&mut magic))
+        .unwrap_or(0);
+
+    EXECUTABLE_MAGIC
+        .iter()
+        .any(|expected| read >= expected.len() && magic.starts_with(expected))
+}
+
+/// Returns the executables of all locked dependencies by the name they are linked as, relative to the sink TOML.
+///
+/// Executables are named after their file, or after the [`crate::github::GitHubDependency::link`] of the dependency.
+/// If several dependencies provide the same name, the first one by pathspec wins.
+pub fn executables(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> BTreeMap<String, PathBuf> {
+    let root = sink_toml.root();
+    let mut executables = BTreeMap::new();
+
+    let mut locked: Vec<_> = sink_lock.dependencies.iter().collect();
+    locked.sort_by_key(|(pathspec, _)| *pathspec);
+    for (pathspec, locked) in locked {
+        let files: Vec<&PathBuf> = locked
+            .files
+            .iter()
+            .map(|file| &file.path)
+            .filter(|path| is_executable(&root.join(path)))
+            .collect();
+        let link_name = locked
+            .link
+            .as_ref()
+            .and_then(|link| link.file_name())
+            .filter(|_| files.len() == 1);
+
+        for path in files {
+            let Some(name) = link_name.or(path.file_name()) else {
+                continue;
+            };
+            let name = name.to_string_lossy().to_string();
+            if executables.contains_key(&name) {
+                warn!("{pathspec}: '{name}' is already provided by another dependency, skipping!");
+                continue;
+            }
+            executables.insert(name, path.clone());
+        }
+    }
+
+    executables
+}
+
+fn _sync(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Result<BTreeMap<String, PathBuf>> {
+    let dir = bin_dir(sink_toml);
+    let executables = match sink_toml.link_executables {
+        true => executables(sink_toml, sink_lock),
+        false => BTreeMap::new(),
+    };
+    if !dir.exists() && executables.is_empty() {
+        return Ok(executables);
+    }
+
+    fs::create_dir_all(&dir)?;
+    let gitignore = dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(gitignore, "*\n")?;
+    }
+
+    // Remove links of executables that are gone
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if path.is_symlink() && !executables.contains_key(&name) {
+            debug!("Removing stale link '{}'...", path.display());
+            fs::remove_file(&path)?;
+        }
+    }
+
+    let root = sink_toml.root();
+    for (name, path) in executables.iter() {
+        let target = std::path::absolute(root.join(path))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut permissions = fs::metadata(&target)?.permissions();
+            if permissions.mode() & 0o111 != 0o111 {
+                permissions.set_mode(permissions.mode() | 0o111);
+                fs::set_permissions(&target, permissions)?;
+            }
+        }
+        atomic::symlink(&target, &dir.join(name))?;
+    }
+    info!(
+        "Linked {} executable(s) into '{}'!",
+        executables.len(),
+        dir.display()
+    );
+
+    Ok(executables)
+}
+/// Link the executables of all locked dependencies into the managed bin directory, see [`bin_dir`].
+///
+/// Only done if [`SinkTOML::link_executables`] is set, links of executables that are gone are removed either way.
+/// Returns the linked executables by name.
+pub fn sync(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Result<BTreeMap<String, PathBuf>> {
+    match _sync(sink_toml, sink_lock) {
+        Ok(executables) => Ok(executables),
+        Err(e) => Err(e.context("Failed to link executables!")),
+    }
+}
+
+/// Returns the shell command putting the given directory in front of the PATH.
+pub fn export(dir: &Path, shell: Shell) -> String {
+    let dir = dir.display().to_string();
+    match shell {
+        Shell::Sh => format!("export PATH='{}':\"$PATH\"", dir.replace('\'', "'\\''")),
+        Shell::Fish => format!("set -gx PATH '{}' $PATH", dir.replace('\'', "\\'")),
+        Shell::Powershell => format!(
+            "$env:PATH = '{}' + [IO.Path]::PathSeparator + $env:PATH",
+            dir.replace('\'', "''")
+        ),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let dir = Path::new("/home/o'neil/project/.sink/bin");
+        assert_eq!(
+            export(dir, Shell::Sh),
+            "export PATH='/home/o'\\''neil/project/.sink/bin':\"$PATH\""
+        );
+        assert_eq!(
+            export(dir, Shell::Fish),
+            "set -gx PATH '/home/o\\'neil/project/.sink/bin' $PATH"
+        );
+        assert_eq!(
+            export(dir, Shell::Powershell),
+            "$env:PATH = '/home/o''neil/project/.sink/bin' + [IO.Path]::PathSeparator + $env:PATH"
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_sync() {
+        use crate::lock::{LockedDependency, LockedFile};
+        use crate::test_utils::TempProject;
+
+        let project = TempProject::new("env").unwrap();
+        let mut sink_toml = project
+            .sink_toml()
+            .dependency("owner/tool", "v1.0.0")
+            .dependency("owner/other", "v1.0.0")
+            .write()
+            .unwrap();
+        fs::create_dir_all(project.root.join("vendor")).unwrap();
+        fs::write(project.root.join("vendor/tool-linux"), b"\x7fELF...").unwrap();
+        fs::write(project.root.join("vendor/script"), "#!/bin/sh\n").unwrap();
+        fs::write(project.root.join("vendor/README.md"), "# Tool").unwrap();
+
+        let locked = |link: Option<&str>, paths: &[&str]| LockedDependency {
+            version: String::from("v1.0.0"),
+            link: link.map(PathBuf::from),
+            files: paths
+                .iter()
+                .map(|path| LockedFile {
+                    path: PathBuf::from(path),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let mut sink_lock = SinkLock::load(&sink_toml).unwrap();
+        sink_lock.dependencies.insert(
+            crate::github::GitHubPathspec::new("owner", "tool", "").unwrap(),
+            locked(Some("bin/tool"), &["vendor/tool-linux", "vendor/README.md"]),
+        );
+        sink_lock.dependencies.insert(
+            crate::github::GitHubPathspec::new("owner", "other", "").unwrap(),
+            locked(None, &["vendor/script"]),
+        );
+
+        // Nothing is linked unless opted in
+        assert!(sync(&sink_toml, &sink_lock).unwrap().is_empty());
+        assert!(!bin_dir(&sink_toml).exists());
+
+        sink_toml.link_executables = true;
+        let linked = sync(&sink_toml, &sink_lock).unwrap();
+        assert_eq!(linked.keys().collect::<Vec<_>>(), vec!["script", "tool"]);
+        let dir = bin_dir(&sink_toml);
+        assert_eq!(
+            fs::read(dir.join("tool")).unwrap(),
+            fs::read(project.root.join("vendor/tool-linux")).unwrap()
+        );
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.join("tool")).unwrap().permissions().mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        // Links of removed dependencies are cleaned up
+        sink_lock
+            .dependencies
+            .retain(|pathspec, _| pathspec.repository() == "tool");
+        sync(&sink_toml, &sink_lock).unwrap();
+        assert!(!dir.join("script").is_symlink());
+        assert!(dir.join("tool").is_symlink());
+    }
+}
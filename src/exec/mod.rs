@@ -0,0 +1,270 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::debug;
+
+use crate::env;
+use crate::github::GitHubPathspec;
+use crate::lock::{LockedDependency, SinkLock};
+use crate::SinkTOML;
+
+fn _find(sink_toml: &SinkTOML, sink_lock: &SinkLock, name: &str) -> Result<GitHubPathspec> {
+    // An exact pathspec
+    if let Ok(pathspec) = GitHubPathspec::parse(String::from(name), &sink_toml.default_owner) {
+        if sink_toml.dependencies.contains_key(&pathspec) {
+            return Ok(pathspec);
+        }
+    }
+
+    let mut pathspecs: Vec<&GitHubPathspec> = sink_toml.dependencies.keys().collect();
+    pathspecs.sort();
+
+    // The name of an installed executable, e.g. the link of the dependency
+    let root = sink_toml.root();
+    if let Some(pathspec) = pathspecs.iter().find(|pathspec| {
+        sink_lock
+            .dependencies
+            .get(pathspec)
+            .is_some_and(|locked| names(&root, locked).iter().any(|n| n == name))
+    }) {
+        return Ok((*pathspec).clone());
+    }
+
+    // The name of the repository
+    let matches: Vec<&GitHubPathspec> = pathspecs
+        .into_iter()
+        .filter(|pathspec| pathspec.repository().eq_ignore_ascii_case(name))
+        .collect();
+    match matches.as_slice() {
+        [pathspec] => Ok((*pathspec).clone()),
+        [] => Err(anyhow::anyhow!("No dependency matches '{name}'!")),
+        _ => Err(anyhow::anyhow!(
+            "'{name}' is ambiguous, use one of {}!",
+            matches
+                .iter()
+                .map(|pathspec| format!("'{pathspec}'"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+/// Find the dependency of the sink TOML the given name refers to.
+///
+/// The name is either a pathspec, the name of an installed executable of a dependency or the name of a repository.
+pub fn find(sink_toml: &SinkTOML, sink_lock: &SinkLock, name: &str) -> Result<GitHubPathspec> {
+    match _find(sink_toml, sink_lock, name) {
+        Ok(pathspec) => Ok(pathspec),
+        Err(e) => Err(e.context(format!("Failed to find dependency '{name}'!"))),
+    }
+}
+
+/// Returns the names of the executables of the locked dependency, including its link.
+fn names(root: &Path, locked: &LockedDependency) -> Vec<String> {
+    let files = executable_files(root, locked);
+    let mut names: Vec<String> = files
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+    if let Some(link) = locked.link.as_ref().and_then(|link| link.file_name()) {
+        if files.len() == 1 {
+            names.push(link.to_string_lossy().to_string());
+        }
+    }
+    names
+}
+
+/// Returns the files of the locked dependency that are executables, relative to the sink TOML.
+fn executable_files<'a>(root: &Path, locked: &'a LockedDependency) -> Vec<&'a PathBuf> {
+    locked
+        .files
+        .iter()
+        .map(|file| &file.path)
+        .filter(|path| env::is_executable(&root.join(path)))
+        .collect()
+}
+
+/// Returns the executable of the locked dependency to run, relative to the sink TOML.
+///
+/// This is the only executable of the dependency, or the one named like `name`, ignoring its extension.
+pub fn executable(root: &Path, locked: &LockedDependency, name: &str) -> Result<PathBuf> {
+    let files = executable_files(root, locked);
+    if let [path] = files.as_slice() {
+        return Ok((*path).clone());
+    }
+
+    let named = |path: &&&PathBuf| {
+        path.file_name().is_some_and(|file| file == name)
+            || path.file_stem().is_some_and(|stem| stem == name)
+    };
+    match files.iter().find(named) {
+        Some(path) => Ok((*path).clone()),
+        None if files.is_empty() => Err(anyhow::anyhow!("The dependency has no executables!")),
+        None => Err(anyhow::anyhow!(
+            "The dependency has several executables, none of them named '{name}': {}!",
+            files
+                .iter()
+                .map(|path| format!("'{}'", path.display()))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Returns the installed file of the locked dependency to locate, relative to the sink TOML.
+///
+/// Like [`executable`], but falls back to the only file of dependencies without executables.
+pub fn artifact(root: &Path, locked: &LockedDependency, name: &str) -> Result<PathBuf> {
+    match (executable(root, locked, name), locked.files.as_slice()) {
+        (Ok(path), _) => Ok(path),
+        (Err(_), [file]) if executable_files(root, locked).is_empty() => Ok(file.path.clone()),
+        (Err(e), _) => Err(e),
+    }
+}
+
+fn _run(path: &Path, args: &[String]) -> Result<i32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut permissions = std::fs::metadata(path)?.permissions();
+        if permissions.mode() & 0o111 != 0o111 {
+            permissions.set_mode(permissions.mode() | 0o111);
+            std::fs::set_permissions(path, permissions)?;
+        }
+    }
+
+    debug!("Running '{}' with {args:?}...", path.display());
+    let status = Command::new(path).args(args).status()?;
+
+    // Processes killed by a signal have no exit code, report them like shells do
+    #[cfg(unix)]
+    if let Some(signal) = std::os::unix::process::ExitStatusExt::signal(&status) {
+        return Ok(128 + signal);
+    }
+    Ok(status.code().unwrap_or(1))
+}
+/// Run the executable at the given path with the given arguments, inheriting stdin, stdout and stderr.
+///
+/// Returns the exit code of the process.
+pub fn run(path: &Path, args: &[String]) -> Result<i32> {
+    match _run(path, args) {
+        Ok(code) => Ok(code),
+        Err(e) => Err(e.context(format!("Failed to run '{}'!", path.display()))),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lock::LockedFile;
+    use crate::test_utils::TempProject;
+    use std::fs;
+
+    fn locked(link: Option<&str>, paths: &[&str]) -> LockedDependency {
+        LockedDependency {
+            version: String::from("v1.0.0"),
+            link: link.map(PathBuf::from),
+            files: paths
+                .iter()
+                .map(|path| LockedFile {
+                    path: PathBuf::from(path),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find() {
+        let project = TempProject::new("exec_find").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("golangci/golangci-lint", "v1.0.0")
+            .dependency("owner/tool", "v1.0.0")
+            .dependency("other/tool", "v1.0.0")
+            .write()
+            .unwrap();
+        let pathspec = |s: &str| GitHubPathspec::try_from(String::from(s)).unwrap();
+
+        fs::create_dir_all(project.root.join("vendor")).unwrap();
+        fs::write(project.root.join("vendor/tool"), b"\x7fELF...").unwrap();
+        let mut sink_lock = SinkLock::load(&sink_toml).unwrap();
+        sink_lock.dependencies.insert(
+            pathspec("owner/tool"),
+            locked(Some("bin/owner-tool"), &["vendor/tool"]),
+        );
+
+        assert_eq!(
+            find(&sink_toml, &sink_lock, "owner/tool").unwrap(),
+            pathspec("owner/tool")
+        );
+        assert_eq!(
+            find(&sink_toml, &sink_lock, "owner-tool").unwrap(),
+            pathspec("owner/tool")
+        );
+        assert_eq!(
+            find(&sink_toml, &sink_lock, "GolangCI-Lint").unwrap(),
+            pathspec("golangci/golangci-lint")
+        );
+        // Installed executables take precedence over ambiguous repository names
+        assert_eq!(
+            find(&sink_toml, &sink_lock, "tool").unwrap(),
+            pathspec("owner/tool")
+        );
+        sink_lock.dependencies.clear();
+        assert!(find(&sink_toml, &sink_lock, "tool").is_err());
+        assert!(find(&sink_toml, &sink_lock, "missing").is_err());
+    }
+
+    #[test]
+    fn test_executable() {
+        let project = TempProject::new("exec_executable").unwrap();
+        let root = project.root.clone();
+        fs::create_dir_all(root.join("vendor")).unwrap();
+        fs::write(root.join("vendor/tool.exe"), b"MZ...").unwrap();
+        fs::write(root.join("vendor/helper"), "#!/bin/sh\n").unwrap();
+        fs::write(root.join("vendor/README.md"), "# Tool").unwrap();
+
+        let single = locked(None, &["vendor/tool.exe", "vendor/README.md"]);
+        assert_eq!(
+            executable(&root, &single, "anything").unwrap(),
+            PathBuf::from("vendor/tool.exe")
+        );
+
+        let several = locked(None, &["vendor/tool.exe", "vendor/helper"]);
+        assert_eq!(
+            executable(&root, &several, "tool").unwrap(),
+            PathBuf::from("vendor/tool.exe")
+        );
+        assert_eq!(
+            executable(&root, &several, "helper").unwrap(),
+            PathBuf::from("vendor/helper")
+        );
+        assert!(executable(&root, &several, "other").is_err());
+        assert!(executable(&root, &locked(None, &["vendor/README.md"]), "tool").is_err());
+
+        // Without executables, the only file is located instead
+        assert_eq!(
+            artifact(&root, &locked(None, &["vendor/README.md"]), "tool").unwrap(),
+            PathBuf::from("vendor/README.md")
+        );
+        assert!(artifact(&root, &several, "other").is_err());
+
+        let linked = locked(Some("bin/tl"), &["vendor/helper"]);
+        assert_eq!(names(&root, &linked), vec!["helper", "tl"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run() {
+        let project = TempProject::new("exec_run").unwrap();
+        let script = project.root.join("script");
+        fs::write(&script, "#!/bin/sh\nexit \"$1\"\n").unwrap();
+
+        assert_eq!(run(&script, &[String::from("3")]).unwrap(), 3);
+        assert_eq!(run(&script, &[String::from("0")]).unwrap(), 0);
+        assert!(run(&project.root.join("missing"), &[]).is_err());
+    }
+}
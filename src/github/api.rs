@@ -0,0 +1,659 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    ffi::{OsStr, OsString},
+    io::Read,
+    process::{Command, Output, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use tracing::{debug, info, trace};
+
+use super::{
+    GitHubAdvisory, GitHubAsset, GitHubRateLimit, GitHubRelease, GitHubRepository, GitHubStatus,
+};
+use crate::errors::{GitHubError, InterruptedError};
+use crate::interrupt;
+
+/// The most items the REST API of GitHub returns per page.
+const PER_PAGE: usize = 100;
+
+/// The number of pages of security advisories fetched at most.
+const ADVISORY_PAGES: usize = 10;
+
+/// The number of repositories asked for in a single GraphQL query, see [`GitHubApi::latest_tags`].
+const GRAPHQL_BATCH: usize = 50;
+
+/// How often a running GitHub CLI is checked for having finished, or sink for being interrupted.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Access to the releases of GitHub repositories.
+///
+/// All of sink's communication with GitHub goes through this trait, see [`set_api`].
+/// Repositories are given as 'owner/repository'.
+pub trait GitHubApi: Send + Sync {
+    /// List the most recent releases of the repository, newest first.
+    ///
+    /// Only the tag and whether it is a prerelease need to be filled in, assets may be omitted.
+    fn list_releases(&self, origin: &str, limit: usize) -> Result<Vec<GitHubRelease>>;
+
+    /// Returns the release with the given tag, or the latest release if no tag is given.
+    fn view_release(&self, origin: &str, tag: Option<&str>) -> Result<GitHubRelease>;
+
+    /// Download a single asset of the release with the given tag and return its contents.
+    fn download_asset(&self, origin: &str, tag: &str, asset: &GitHubAsset) -> Result<Vec<u8>>;
+
+    /// List all published security advisories of the repository.
+    fn list_advisories(&self, origin: &str) -> Result<Vec<GitHubAdvisory>>;
+
+    /// Returns the SPDX identifier of the repository's license, e.g. 'MIT'.
+    ///
+    /// Returns `None` if the repository has no license or GitHub cannot identify it.
+    fn view_license(&self, origin: &str) -> Result<Option<String>>;
+
+    /// Returns the contents of the file at the given path of the repository, at the given git reference.
+    fn view_file(&self, origin: &str, path: &str, reference: &str) -> Result<Vec<u8>>;
+
+    /// Returns the host of the API, the authenticated user and the remaining rate limits.
+    fn view_status(&self) -> Result<GitHubStatus>;
+
+    /// Returns the tags of the latest releases of many repositories at once, by 'owner/repository'.
+    ///
+    /// Repositories without releases, or whose latest release cannot be viewed, are missing from the result.
+    /// Defaults to viewing the latest release of every repository on its own.
+    fn latest_tags(&self, origins: &[String]) -> Result<BTreeMap<String, String>> {
+        Ok(_latest_tags_one_by_one(self, origins))
+    }
+
+    /// Fails if the repository does not exist or cannot be read with the configured credential.
+    ///
+    /// Defaults to viewing the license of the repository, which only needs read access to its metadata.
+    fn check_access(&self, origin: &str) -> Result<()> {
+        self.view_license(origin).map(|_| ())
+    }
+}
+
+/// View the latest release of every repository on its own, see [`GitHubApi::latest_tags`].
+fn _latest_tags_one_by_one<A: GitHubApi + ?Sized>(
+    api: &A,
+    origins: &[String],
+) -> BTreeMap<String, String> {
+    let mut tags = BTreeMap::new();
+    for origin in origins {
+        match api.view_release(origin, None) {
+            Ok(release) => {
+                tags.insert(origin.clone(), release.tag_name);
+            }
+            Err(e) => debug!("Failed to view the latest release of '{origin}': {e:#}"),
+        }
+    }
+    tags
+}
+
+/// Returns a GraphQL query for the latest release of each of the repositories, aliased by their index.
+fn _latest_tags_query(origins: &[String]) -> String {
+    let mut query = String::from("query {");
+    for (index, origin) in origins.iter().enumerate() {
+        let (owner, name) = origin.split_once('/').unwrap_or((origin, ""));
+        // JSON strings are valid GraphQL strings, which escapes any quotes
+        query.push_str(&format!(
+            " r{index}: repository(owner: {}, name: {}) {{ latestRelease {{ tagName }} }}",
+            Value::from(owner),
+            Value::from(name)
+        ));
+    }
+    query.push_str(" }");
+    query
+}
+
+/// Collect the tags of the latest releases from the response to a [`_latest_tags_query`].
+///
+/// Repositories that do not exist or have no release are skipped.
+fn _parse_latest_tags(origins: &[String], response: &Value) -> BTreeMap<String, String> {
+    origins
+        .iter()
+        .enumerate()
+        .filter_map(|(index, origin)| {
+            let tag = response["data"][format!("r{index}")]["latestRelease"]["tagName"].as_str()?;
+            Some((origin.clone(), String::from(tag)))
+        })
+        .collect()
+}
+
+/// The [`GitHubApi`] used by default, which invokes the GitHub CLI.
+#[derive(Debug, Default)]
+pub struct GhCli;
+impl GitHubApi for GhCli {
+    fn list_releases(&self, origin: &str, limit: usize) -> Result<Vec<GitHubRelease>> {
+        Ok(serde_json::from_str(&gh([
+            "release",
+            "list",
+            "--repo",
+            origin,
+            "--json",
+            "tagName,isPrerelease,isDraft,publishedAt",
+            "--limit",
+            &limit.to_string(),
+        ])?)?)
+    }
+
+    fn view_release(&self, origin: &str, tag: Option<&str>) -> Result<GitHubRelease> {
+        let mut args = vec!["release", "view"];
+        if let Some(tag) = tag {
+            args.push(tag);
+        }
+        args.extend([
+            "--repo",
+            origin,
+            "--json",
+            "tagName,databaseId,url,isPrerelease,isDraft,publishedAt,body,assets",
+        ]);
+
+        Ok(serde_json::from_str(&gh(args)?)?)
+    }
+
+    fn download_asset(&self, origin: &str, tag: &str, asset: &GitHubAsset) -> Result<Vec<u8>> {
+        // The REST API serves the asset directly, without resolving the release again
+        if !asset.api_url.is_empty() {
+            return gh_raw([
+                "api",
+                "--header",
+                "Accept: application/octet-stream",
+                &asset.api_url,
+            ]);
+        }
+
+        gh_raw([
+            "release",
+            "download",
+            tag,
+            "--repo",
+            origin,
+            "--pattern",
+            &glob::Pattern::escape(&asset.name),
+            "--output",
+            "-",
+        ])
+    }
+
+    fn list_advisories(&self, origin: &str) -> Result<Vec<GitHubAdvisory>> {
+        let mut advisories = Vec::new();
+        for page in 1..=ADVISORY_PAGES {
+            let listed: Vec<GitHubAdvisory> = serde_json::from_str(&gh([
+                "api",
+                &format!(
+                    "repos/{origin}/security-advisories?state=published&per_page={PER_PAGE}&page={page}"
+                ),
+            ])?)?;
+            let last = listed.len() < PER_PAGE;
+            advisories.extend(listed);
+            if last {
+                break;
+            }
+        }
+
+        Ok(advisories)
+    }
+
+    fn view_license(&self, origin: &str) -> Result<Option<String>> {
+        let repository: GitHubRepository =
+            serde_json::from_str(&gh(["api", &format!("repos/{origin}")])?)?;
+        Ok(repository.spdx_id())
+    }
+
+    fn view_file(&self, origin: &str, path: &str, reference: &str) -> Result<Vec<u8>> {
+        gh_raw([
+            "api",
+            "--header",
+            "Accept: application/vnd.github.raw",
+            &format!("repos/{origin}/contents/{path}?ref={reference}"),
+        ])
+    }
+
+    fn view_status(&self) -> Result<GitHubStatus> {
+        // Tokens of apps, e.g. of GitHub Actions runs, are not allowed to view any user
+        let user = match gh(["api", "user", "--jq", ".login"]) {
+            Ok(login) => Some(login).filter(|login| !login.is_empty()),
+            Err(e) => {
+                debug!("Not authenticated as a user: {e:#}");
+                None
+            }
+        };
+        let rate_limit: Value = serde_json::from_str(&gh(["api", "rate_limit"])?)?;
+        let rate_limits: BTreeMap<String, GitHubRateLimit> =
+            serde_json::from_value(rate_limit["resources"].clone())?;
+
+        Ok(GitHubStatus {
+            host: host(),
+            user,
+            rate_limits,
+        })
+    }
+
+    fn latest_tags(&self, origins: &[String]) -> Result<BTreeMap<String, String>> {
+        let mut tags = BTreeMap::new();
+        for batch in origins.chunks(GRAPHQL_BATCH) {
+            let query = format!("query={}", _latest_tags_query(batch));
+            // The GraphQL API needs authentication, the REST API does not
+            match gh(["api", "graphql", "--field", &query]) {
+                Ok(response) => {
+                    tags.extend(_parse_latest_tags(batch, &serde_json::from_str(&response)?))
+                }
+                Err(e) => {
+                    debug!("Failed to query the latest releases in bulk, viewing them one by one: {e:#}");
+                    tags.extend(_latest_tags_one_by_one(self, batch));
+                }
+            }
+        }
+
+        Ok(tags)
+    }
+}
+
+/// The API replacing the GitHub CLI, see [`set_api`].
+static API: RwLock<Option<Arc<dyn GitHubApi>>> = RwLock::new(None);
+
+/// Replace the API used to communicate with GitHub, e.g. with a stub in tests.
+///
+/// Passing `None` restores the default, see [`GhCli`].
+pub fn set_api(api: Option<Arc<dyn GitHubApi>>) {
+    if let Ok(mut current) = API.write() {
+        *current = api;
+    }
+}
+
+thread_local! {
+    /// The API of the current thread, which takes precedence over [`API`], see [`scoped_api`].
+    static SCOPED_API: RefCell<Option<Arc<dyn GitHubApi>>> = const { RefCell::new(None) };
+}
+
+/// Restores the previous API of the current thread once dropped, see [`scoped_api`].
+pub struct ScopedApi(Option<Arc<dyn GitHubApi>>);
+impl Drop for ScopedApi {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        SCOPED_API.with(|scoped| *scoped.borrow_mut() = previous);
+    }
+}
+
+/// Use the given API on the current thread until the returned guard is dropped, e.g. to meter an install.
+///
+/// Unlike [`set_api`], other threads are not affected. The previous API is restored even on panic.
+#[must_use]
+pub fn scoped_api(api: Arc<dyn GitHubApi>) -> ScopedApi {
+    ScopedApi(SCOPED_API.with(|scoped| scoped.borrow_mut().replace(api)))
+}
+
+/// Returns the API used to communicate with GitHub.
+///
+/// This is the API of the current thread, if any, see [`scoped_api`].
+pub fn api() -> Arc<dyn GitHubApi> {
+    if let Some(api) = SCOPED_API.with(|scoped| scoped.borrow().clone()) {
+        return api;
+    }
+    match API.read().ok().and_then(|api| api.clone()) {
+        Some(api) => api,
+        None => Arc::new(GhCli),
+    }
+}
+
+/// Returns the host the GitHub CLI talks to, i.e. `GH_HOST` for GitHub Enterprise Server or 'github.com'.
+pub fn host() -> String {
+    std::env::var("GH_HOST")
+        .ok()
+        .filter(|host| !host.is_empty())
+        .unwrap_or_else(|| String::from("github.com"))
+}
+
+/// Whether contacting GitHub is forbidden.
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Forbid (or allow) contacting GitHub.
+///
+/// While offline, every operation that would invoke the GitHub CLI fails instead.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+/// Whether contacting GitHub is forbidden, see [`set_offline`].
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// The target of the logs of the HTTP requests, see [`set_trace_http`].
+pub const HTTP_TARGET: &str = "sink::http";
+
+/// Whether the HTTP requests of the GitHub CLI are logged.
+static TRACE_HTTP: AtomicBool = AtomicBool::new(false);
+
+/// Log every HTTP request of the GitHub CLI and its response (or stop doing so), with the target [`HTTP_TARGET`].
+///
+/// Relies on the debug output of the GitHub CLI (`GH_DEBUG=api`). Credentials are redacted and bodies are left out.
+pub fn set_trace_http(enabled: bool) {
+    TRACE_HTTP.store(enabled, Ordering::Relaxed);
+}
+
+/// Headers whose values are never logged, in lowercase.
+const SENSITIVE_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+/// Split the stderr of the GitHub CLI with `GH_DEBUG=api` into the HTTP trace and the remaining lines.
+///
+/// The trace consists of the lines prefixed with '* ' (e.g. the URL and timing), '> ' (the request) and '< ' (the response).
+/// Values of sensitive headers are redacted. The remaining lines contain the bodies and the actual error messages.
+fn _split_http_trace(stderr: &str) -> (Vec<String>, Vec<&str>) {
+    let mut trace = Vec::new();
+    let mut rest = Vec::new();
+    for line in stderr.lines() {
+        if !["* ", "> ", "< "]
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+        {
+            rest.push(line);
+            continue;
+        }
+
+        let redacted = line[2..].split_once(':').and_then(|(name, _)| {
+            SENSITIVE_HEADERS
+                .contains(&name.trim().to_lowercase().as_str())
+                .then(|| format!("{}{name}: [redacted]", &line[..2]))
+        });
+        trace.push(redacted.unwrap_or_else(|| line.to_string()));
+    }
+
+    (trace, rest)
+}
+
+/// Read the pipe to its end on another thread, so the child process never blocks on a full pipe.
+fn _read_all<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut contents = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut contents);
+        }
+        contents
+    })
+}
+
+/// Run the command like [`Command::output`], but kill it once sink is interrupted, see [`interrupt`].
+///
+/// Returns `None` if the command was killed.
+fn _output(command: &mut Command) -> std::io::Result<Option<Output>> {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = _read_all(child.stdout.take());
+    let stderr = _read_all(child.stderr.take());
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if interrupt::is_interrupted() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// Returns the argument as logged, with the values of sensitive headers redacted.
+fn _redacted(arg: &OsStr) -> String {
+    let arg = arg.to_string_lossy();
+    match arg.split_once(':') {
+        Some((name, _)) if SENSITIVE_HEADERS.contains(&name.trim().to_lowercase().as_str()) => {
+            format!("{name}: [redacted]")
+        }
+        _ => arg.to_string(),
+    }
+}
+
+/// Invoke the GitHub CLI with the given arguments and return its raw stdout.
+///
+/// Authenticates as the GitHub App installation, if one is configured, see `github::app::set_app` (feature `app-auth`).
+/// Fails with an [`InterruptedError`] if sink is interrupted before or while the GitHub CLI runs.
+fn gh_raw<I, S>(args: I) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    #[cfg(feature = "app-auth")]
+    let token = match OFFLINE.load(Ordering::Relaxed) {
+        true => None,
+        false => super::app::token()?,
+    };
+    #[cfg(not(feature = "app-auth"))]
+    let token: Option<String> = None;
+    _gh_raw(args, token.as_deref())
+}
+
+/// Invoke the GitHub API as the GitHub App itself, e.g. to create an installation token, see [`super::app`].
+///
+/// Returns the trimmed response.
+#[cfg(feature = "app-auth")]
+pub(super) fn gh_as_app(jwt: &str, args: &[&str]) -> Result<String> {
+    let header = format!("Authorization: Bearer {jwt}");
+    let mut all = vec!["api", "--header", &header];
+    all.extend(args);
+    // The GitHub CLI refuses to run without a token, even though the header takes precedence
+    let stdout = String::from_utf8(_gh_raw(all, Some(jwt))?)?;
+
+    Ok(stdout.trim().to_string())
+}
+
+/// Invoke the GitHub CLI with the given arguments and token, or the one it is logged in with.
+fn _gh_raw<I, S>(args: I, token: Option<&str>) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    if OFFLINE.load(Ordering::Relaxed) {
+        return Err(anyhow::Error::new(GitHubError(String::from(
+            "Refusing to contact GitHub in offline mode!",
+        ))));
+    }
+
+    let args: Vec<OsString> = args
+        .into_iter()
+        .map(|arg| arg.as_ref().to_owned())
+        .collect();
+    let command_line = args
+        .iter()
+        .map(|arg| _redacted(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    trace!("Request: gh {command_line}");
+    let started = Instant::now();
+    let trace_http = TRACE_HTTP.load(Ordering::Relaxed);
+    let mut command = Command::new("gh");
+    command.args(&args);
+    if trace_http {
+        command.env("GH_DEBUG", "api");
+    }
+    if let Some(token) = token {
+        command.env("GH_TOKEN", token);
+        command.env("GH_ENTERPRISE_TOKEN", token);
+    }
+    interrupt::check()?;
+    let output = match _output(&mut command) {
+        Ok(Some(output)) => output,
+        Ok(None) => return Err(anyhow::Error::new(InterruptedError)),
+        Err(e) => {
+            return Err(anyhow::Error::new(GitHubError(format!(
+                "Failed to invoke GitHub CLI: {e}. Is it installed?"
+            ))))
+        }
+    };
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let stderr = match trace_http {
+        true => {
+            let (trace, rest) = _split_http_trace(&stderr);
+            for line in trace {
+                info!(target: HTTP_TARGET, "{line}");
+            }
+            // The bodies are part of the rest, the error message of the GitHub CLI comes last
+            rest.iter()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .map(|line| line.to_string())
+                .unwrap_or_default()
+        }
+        false => stderr.to_string(),
+    };
+    let stderr = stderr.trim();
+
+    trace!(
+        "Response: {}, {} byte(s) in {:.2?}",
+        output.status,
+        output.stdout.len(),
+        started.elapsed()
+    );
+    if !stderr.is_empty() {
+        trace!("Stderr: {stderr}");
+    }
+    // The GitHub CLI receives the Ctrl-C of the terminal as well, and may have exited before noticing it here
+    if !output.status.success() && interrupt::is_interrupted() {
+        return Err(anyhow::Error::new(InterruptedError));
+    }
+    if !output.status.success() {
+        return Err(anyhow::Error::new(GitHubError(format!(
+            "GitHub CLI invocation failed: '{stderr}'"
+        ))));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Invoke the GitHub CLI with the given arguments and return its trimmed stdout.
+fn gh<I, S>(args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let stdout = String::from_utf8(gh_raw(args)?)?;
+    trace!("Stdout: {}", stdout.trim());
+
+    Ok(stdout.trim().to_string())
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scoped_api() {
+        let scoped: Arc<dyn GitHubApi> = Arc::new(GhCli);
+        let address = |api: Arc<dyn GitHubApi>| Arc::as_ptr(&api) as *const () as usize;
+        let expected = address(scoped.clone());
+
+        let scope = scoped_api(scoped.clone());
+        assert_eq!(address(api()), expected);
+        // Other threads are not affected
+        assert_ne!(
+            thread::spawn(move || address(api())).join().unwrap(),
+            expected
+        );
+        drop(scope);
+        assert_ne!(address(api()), expected);
+    }
+
+    #[test]
+    fn test_redacted() {
+        assert_eq!(
+            _redacted(OsStr::new("Authorization: Bearer secret")),
+            "Authorization: [redacted]"
+        );
+        assert_eq!(
+            _redacted(OsStr::new("Accept: application/octet-stream")),
+            "Accept: application/octet-stream"
+        );
+        assert_eq!(
+            _redacted(OsStr::new("repos/owner/repo")),
+            "repos/owner/repo"
+        );
+    }
+
+    #[test]
+    fn test_split_http_trace() {
+        let stderr = "* Request at 2026-10-17 12:00:00\n\
+                      * Request to https://ghe.example.com/api/v3/repos/owner/repo/releases/latest\n\
+                      > GET /api/v3/repos/owner/repo/releases/latest HTTP/1.1\n\
+                      > Authorization: token ghp_secret\n\
+                      > User-Agent: GitHub CLI 2.62.0\n\
+                      \n\
+                      < HTTP/2.0 404 Not Found\n\
+                      < Set-Cookie: session=secret\n\
+                      \n\
+                      {\"message\": \"Not Found\"}\n\
+                      * Request took 312ms\n\
+                      gh: Not Found (HTTP 404)";
+        let (trace, rest) = _split_http_trace(stderr);
+
+        assert_eq!(
+            trace,
+            vec![
+                "* Request at 2026-10-17 12:00:00",
+                "* Request to https://ghe.example.com/api/v3/repos/owner/repo/releases/latest",
+                "> GET /api/v3/repos/owner/repo/releases/latest HTTP/1.1",
+                "> Authorization: [redacted]",
+                "> User-Agent: GitHub CLI 2.62.0",
+                "< HTTP/2.0 404 Not Found",
+                "< Set-Cookie: [redacted]",
+                "* Request took 312ms",
+            ]
+        );
+        assert_eq!(
+            rest,
+            vec![
+                "",
+                "",
+                "{\"message\": \"Not Found\"}",
+                "gh: Not Found (HTTP 404)"
+            ]
+        );
+        assert!(!trace.iter().any(|line| line.contains("secret")));
+    }
+
+    #[test]
+    fn test_latest_tags_query() {
+        let origins = vec![String::from("owner/repo"), String::from("other/\"tool\"")];
+        assert_eq!(
+            _latest_tags_query(&origins),
+            "query { r0: repository(owner: \"owner\", name: \"repo\") { latestRelease { tagName } } \
+             r1: repository(owner: \"other\", name: \"\\\"tool\\\"\") { latestRelease { tagName } } }"
+        );
+
+        let response = json!({
+            "data": {
+                "r0": { "latestRelease": { "tagName": "v1.2.0" } },
+                "r1": { "latestRelease": null },
+            }
+        });
+        assert_eq!(
+            _parse_latest_tags(&origins, &response),
+            BTreeMap::from([(String::from("owner/repo"), String::from("v1.2.0"))])
+        );
+        assert!(_parse_latest_tags(&origins, &json!({ "data": { "r0": null } })).is_empty());
+    }
+}
@@ -1,11 +1,38 @@
 use anyhow::Result;
-use log::{debug, info};
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, path::PathBuf, process::Command};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+use tracing::{debug, debug_span, info, warn};
 
 extern crate toml as ex_toml;
 
+pub mod api;
+#[cfg(feature = "app-auth")]
+pub mod app;
+
+pub use api::{set_api, set_offline, set_trace_http, GhCli, GitHubApi};
+
+use crate::atomic;
+use crate::cache;
+use crate::detect;
+use crate::env;
+use crate::errors::{CodedError, ErrorCode};
+use crate::gitignore;
+use crate::hooks::{self, Stage};
+use crate::integrity;
+use crate::interrupt;
+use crate::lock::{self, LockedDependency, LockedFile, SinkLock};
+use crate::policy::Policy;
+use crate::template::{self, TemplateValues};
 use crate::{toml::DependencyType, SinkTOML};
 
 /// Provides a default value of `true` for [`serde`].
@@ -13,6 +40,11 @@ fn _default_true() -> bool {
     true
 }
 
+/// Provides the default destination (the directory of the sink TOML) for [`serde`].
+fn _default_destination() -> PathBuf {
+    PathBuf::from(".")
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all(deserialize = "kebab-case", serialize = "snake_case"))]
 pub struct GitHubDependency {
@@ -25,6 +57,8 @@ pub struct GitHubDependency {
     /// The local destination to download the file(s) into.
     ///
     /// Either an absolute path or a relative path starting from the directory of the sink TOML.
+    /// This defaults to the directory of the sink TOML.
+    #[serde(default = "_default_destination")]
     pub destination: PathBuf,
 
     /// The version to download.
@@ -39,8 +73,105 @@ pub struct GitHubDependency {
     /// This defaults to true.
     #[serde(default = "_default_true")]
     pub gitignore: bool,
+
+    /// Whether failing to install the dependency fails the install, e.g. of `sink install`.
+    ///
+    /// Failures of optional dependencies are logged as warnings instead. This defaults to true.
+    #[serde(default = "_default_true")]
+    pub required: bool,
+
+    /// What `sink upgrade` may change about the version.
+    ///
+    /// This defaults to [`UpdatePolicy::Latest`].
+    #[serde(default)]
+    pub update_policy: UpdatePolicy,
+
+    /// Tags of known-bad releases that are never resolved, e.g. 'v1.4.0'.
+    ///
+    /// `latest`, `prerelease` and `asof:` skip them, and `sink upgrade` never suggests them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked_versions: Vec<String>,
+
+    /// The archive format filled into '{{.Format}}' of an asset template, e.g. 'tar.gz'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    /// The formats to use instead of `format` on specific operating systems, e.g. `{ windows = "zip" }`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub format_overrides: BTreeMap<String, String>,
+
+    /// Replacements of the operating system and architecture in an asset template, e.g. `{ amd64 = "x86_64" }`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub replacements: BTreeMap<String, String>,
+
+    /// A stable path to link to the installed asset, e.g. 'bin/rg', relative to the directory of the sink TOML.
+    ///
+    /// The symlink is created or updated after every install, so it survives upgrades.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<PathBuf>,
+
+    /// Whether to install every version into its own directory below the destination, e.g. 'tools/v1.2.3'.
+    ///
+    /// Other installed versions are kept, `sink use` switches between them by pointing the `link` elsewhere.
+    /// This defaults to false.
+    #[serde(default)]
+    pub versioned: bool,
+
+    /// The glob pattern of an asset listing the checksums of the other assets, e.g. 'checksums.txt' or 'SHA256SUMS'.
+    ///
+    /// Every downloaded asset must be listed in it with a matching digest, see [`integrity::verify_listed`].
+    /// Asset templates are supported, like in the pattern of the pathspec.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksums_asset: Option<String>,
+
+    /// The minisign public key the assets are signed with, e.g. 'RWQf6LRC...'.
+    ///
+    /// Every downloaded asset must come with a valid signature in '<asset>.minisig', see [`integrity::verify_signatures`].
+    /// Takes precedence over the key of the owner in the policy, see [`Policy::minisign_keys`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minisign_key: Option<String>,
+
+    /// Commands to run before the assets are downloaded, see [`hooks::run`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_install: Vec<String>,
+
+    /// Commands to run after the assets are downloaded, e.g. to make them executable or register them.
+    ///
+    /// See [`hooks::run`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_install: Vec<String>,
+
+    /// Fields that are not known to sink.
+    ///
+    /// These are collected instead of rejected to allow for helpful warnings.
+    #[serde(flatten, skip_serializing)]
+    pub unknown_fields: BTreeMap<String, ex_toml::Value>,
 }
 impl GitHubDependency {
+    /// The keys of a dependency table in the sink TOML, used to suggest the intended one for unknown fields.
+    pub const FIELDS: &'static [&'static str] = &[
+        "destination",
+        "version",
+        "gitignore",
+        "required",
+        "update-policy",
+        "blocked-versions",
+        "format",
+        "format-overrides",
+        "replacements",
+        "link",
+        "versioned",
+        "checksums-asset",
+        "minisign-key",
+        "pre-install",
+        "post-install",
+    ];
+
+    /// Create a dependency from a pathspec or a GitHub URL.
+    ///
+    /// URLs of a repository, release or release asset are supported,
+    /// e.g. 'https://github.com/owner/repo/releases/tag/v1.2.3'.
+    /// An explicitly given version takes precedence over the version of the URL.
     pub fn new(
         dependency: String,
         destination: Option<String>,
@@ -48,219 +179,1579 @@ impl GitHubDependency {
         gitignore: bool,
         default_owner: &Option<String>,
     ) -> Result<Self> {
-        let pathspec = match GitHubPathspec::try_from(dependency.clone()) {
-            Ok(pathspec) => pathspec,
-            Err(e) => {
-                if default_owner.is_none() {
-                    return Err(e);
-                }
-                match GitHubPathspec::try_from(format!(
-                    "{}/{}",
-                    default_owner.as_ref().unwrap(),
-                    dependency
-                )) {
-                    Ok(pathspec) => pathspec,
-                    Err(e) => return Err(e),
-                }
+        let (pathspec, version) = match _parse_url(&dependency) {
+            Some(parsed) => {
+                let (pathspec, url_version) = parsed?;
+                (pathspec, version.or(url_version))
             }
+            None => (GitHubPathspec::parse(dependency, default_owner)?, version),
         };
 
         Ok(GitHubDependency {
             pathspec,
-            destination: PathBuf::from(destination.unwrap_or(String::from("."))),
+            destination: destination.map_or_else(_default_destination, PathBuf::from),
             version: version.unwrap_or(GitHubVersion::Latest),
             gitignore,
+            required: true,
+            update_policy: UpdatePolicy::default(),
+            blocked_versions: Vec::new(),
+            format: None,
+            format_overrides: BTreeMap::new(),
+            replacements: BTreeMap::new(),
+            link: None,
+            versioned: false,
+            checksums_asset: None,
+            minisign_key: None,
+            pre_install: Vec::new(),
+            post_install: Vec::new(),
+            unknown_fields: BTreeMap::new(),
         })
     }
+
+    /// Whether the release with the given tag must never be resolved, see [`GitHubDependency::blocked_versions`].
+    ///
+    /// A leading 'v' is ignored, so blocking '1.4.0' blocks 'v1.4.0' as well.
+    pub fn is_blocked(&self, tag: &str) -> bool {
+        self.blocked_versions
+            .iter()
+            .any(|blocked| blocked.trim_start_matches('v') == tag.trim_start_matches('v'))
+    }
+
+    /// Whether the dependency runs any `pre-install` or `post-install` commands.
+    pub fn has_hooks(&self) -> bool {
+        !self.pre_install.is_empty() || !self.post_install.is_empty()
+    }
+
+    /// Returns the directory the given version is installed into, relative to the directory of the sink TOML.
+    ///
+    /// This is the destination, or a directory named after the version below it if [`GitHubDependency::versioned`] is set.
+    pub fn install_dir(&self, version: &str) -> PathBuf {
+        match self.versioned {
+            true => self.destination.join(version.replace(['/', '\\'], "-")),
+            false => self.destination.clone(),
+        }
+    }
+
+    /// Returns the glob pattern of the assets to download from the release with the given tag.
+    ///
+    /// Asset templates are rendered for the running platform, see [`template::render`].
+    pub fn asset_pattern(&self, tag: &str) -> Result<String> {
+        self._render(self.pathspec.asset_pattern(), tag)
+    }
+
+    /// Returns the glob pattern of the checksums asset of the release with the given tag, if one is set.
+    ///
+    /// See [`GitHubDependency::checksums_asset`] and [`GitHubDependency::asset_pattern`].
+    pub fn checksums_pattern(&self, tag: &str) -> Option<Result<String>> {
+        let pattern = self.checksums_asset.as_deref()?;
+        Some(self._render(pattern, tag))
+    }
+
+    /// Render the pattern for the running platform if it is an asset template.
+    fn _render(&self, pattern: &str, tag: &str) -> Result<String> {
+        if !template::is_template(pattern) {
+            return Ok(pattern.to_string());
+        }
+
+        let values = TemplateValues::current(
+            tag,
+            self.format.as_deref(),
+            &self.format_overrides,
+            &self.replacements,
+        );
+        template::render(pattern, &values)
+    }
+}
+
+/// Parse a GitHub URL of a repository, release or release asset into a pathspec and version.
+///
+/// Returns `None` if the value is not a GitHub URL.
+fn _parse_url(value: &str) -> Option<Result<(GitHubPathspec, Option<GitHubVersion>)>> {
+    let re = Regex::new(
+        r"^(?:https?://)?(?:www\.)?github\.com/(?<owner>[^/]+)/(?<repo>[^/?#]+?)(?:\.git)?(?<rest>/[^?#]*)?(?:[?#].*)?$",
+    )
+    .unwrap();
+    if !(value.starts_with("https://")
+        || value.starts_with("http://")
+        || value.starts_with("github.com/")
+        || value.starts_with("www.github.com/"))
+    {
+        return None;
+    }
+
+    let Some(captures) = re.captures(value) else {
+        return Some(Err(anyhow::anyhow!("Invalid GitHub URL: '{value}'!")));
+    };
+    let segments: Vec<&str> = captures
+        .name("rest")
+        .map(|rest| rest.as_str().split('/').filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+
+    let (pattern, version) = match segments.as_slice() {
+        [] | ["releases"] => ("", None),
+        ["releases", "latest"] => ("", Some(GitHubVersion::Latest)),
+        ["releases", "latest", "download", asset] => (*asset, Some(GitHubVersion::Latest)),
+        ["releases", "tag", tag @ ..] if !tag.is_empty() => {
+            ("", Some(GitHubVersion::Tag(tag.join("/"))))
+        }
+        ["releases", "download", tag @ .., asset] if !tag.is_empty() => {
+            (*asset, Some(GitHubVersion::Tag(tag.join("/"))))
+        }
+        _ => {
+            return Some(Err(anyhow::anyhow!(
+                "Unsupported GitHub URL: '{value}'! Please use the URL of a repository, release or release asset!"
+            )))
+        }
+    };
+
+    Some(
+        GitHubPathspec::new(&captures["owner"], &captures["repo"], pattern)
+            .map(|pathspec| (pathspec, version)),
+    )
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
-#[serde(rename_all = "lowercase")]
+#[serde(from = "String", into = "String")]
 pub enum GitHubVersion {
     Latest,
     Prerelease,
-
-    #[serde(untagged)]
     Tag(String),
+
+    /// The draft release of the given tag, written as 'draft:tag'. Only resolved if drafts are allowed, see [`set_allow_draft`].
+    Draft(String),
+
+    /// The newest release published on or before the given date, written as 'asof:YYYY-MM-DD'.
+    AsOf(String),
+}
+impl GitHubVersion {
+    pub fn parse_cli(s: &str) -> Result<Self, String> {
+        Ok(Self::from(s))
+    }
+}
+impl Display for GitHubVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitHubVersion::Latest => write!(f, "latest"),
+            GitHubVersion::Prerelease => write!(f, "prerelease"),
+            GitHubVersion::Tag(tag) => write!(f, "{}", tag),
+            GitHubVersion::Draft(tag) => write!(f, "draft:{}", tag),
+            GitHubVersion::AsOf(date) => write!(f, "asof:{}", date),
+        }
+    }
+}
+impl From<&str> for GitHubVersion {
+    fn from(s: &str) -> Self {
+        match s {
+            "latest" => GitHubVersion::Latest,
+            "prerelease" => GitHubVersion::Prerelease,
+            _ => match (s.strip_prefix("draft:"), s.strip_prefix("asof:")) {
+                (Some(tag), _) => GitHubVersion::Draft(tag.to_string()),
+                (_, Some(date)) => GitHubVersion::AsOf(date.to_string()),
+                _ => GitHubVersion::Tag(s.to_string()),
+            },
+        }
+    }
+}
+impl From<String> for GitHubVersion {
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+impl From<GitHubVersion> for String {
+    fn from(value: GitHubVersion) -> Self {
+        value.to_string()
+    }
+}
+
+/// Controls which releases a pinned dependency may be upgraded to.
+///
+/// Versions are compared by the numeric 'major.minor.patch' parts of their tags, e.g. 'v1.2.3'.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdatePolicy {
+    /// Never change the version
+    Pinned,
+
+    /// Only upgrade to releases with the same major and minor version
+    Patch,
+
+    /// Only upgrade to releases with the same major version
+    Minor,
+
+    /// Upgrade to any newer release
+    #[default]
+    Latest,
+}
+impl UpdatePolicy {
+    /// Whether the policy allows upgrading from the `current` to the `candidate` tag.
+    ///
+    /// Tags without a numeric version are only allowed by [`UpdatePolicy::Latest`].
+    pub fn allows(&self, current: &str, candidate: &str) -> bool {
+        let parts = |tag: &str| -> Option<(u64, u64)> {
+            let mut numbers = tag
+                .trim_start_matches(|c: char| !c.is_ascii_digit())
+                .split(['.', '-', '+'])
+                .map(|part| part.parse::<u64>().ok());
+            Some((numbers.next()??, numbers.next().flatten().unwrap_or(0)))
+        };
+
+        match self {
+            UpdatePolicy::Pinned => false,
+            UpdatePolicy::Latest => true,
+            UpdatePolicy::Minor | UpdatePolicy::Patch => {
+                let (Some(current), Some(candidate)) = (parts(current), parts(candidate)) else {
+                    return false;
+                };
+                match self {
+                    UpdatePolicy::Minor => current.0 == candidate.0,
+                    _ => current == candidate,
+                }
+            }
+        }
+    }
+}
+impl Display for UpdatePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UpdatePolicy::Pinned => write!(f, "pinned"),
+            UpdatePolicy::Patch => write!(f, "patch"),
+            UpdatePolicy::Minor => write!(f, "minor"),
+            UpdatePolicy::Latest => write!(f, "latest"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash, Default, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct GitHubPathspec {
+    pub(crate) owner: String,
+    pub(crate) repository: String,
+    pub(crate) pattern: String,
+}
+impl GitHubPathspec {
+    /// Create a pathspec from its parts.
+    ///
+    /// An empty pattern includes all assets of the release.
+    /// Fails if the owner or repository are empty or contain '/' or ':',
+    /// or the parts would not survive a round-trip through 'owner/repo:pattern'.
+    pub fn new(owner: &str, repository: &str, pattern: &str) -> Result<Self> {
+        let pathspec = GitHubPathspec {
+            owner: String::from(owner),
+            repository: String::from(repository),
+            pattern: String::from(pattern),
+        };
+
+        let is_name = |name: &str| !name.contains(['/', ':']);
+        if !pathspec.is_valid()
+            || !is_name(owner)
+            || !is_name(repository)
+            || GitHubPathspec::try_from(String::from(pathspec.clone())).ok()
+                != Some(pathspec.clone())
+        {
+            return Err(anyhow::anyhow!(
+                "Invalid dependency path specification: owner '{owner}', repository '{repository}', pattern '{pattern}'!"
+            ));
+        }
+
+        Ok(pathspec)
+    }
+
+    /// Returns the owner of the repository.
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Returns the name of the repository.
+    pub fn repository(&self) -> &str {
+        &self.repository
+    }
+
+    /// Returns the glob pattern matching the release assets.
+    ///
+    /// This is empty if all assets are included, see [`GitHubPathspec::asset_pattern`].
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns the glob pattern to match the release assets against.
+    ///
+    /// Unlike [`GitHubPathspec::pattern`], this is '*' if all assets are included.
+    pub fn asset_pattern(&self) -> &str {
+        match self.pattern.is_empty() {
+            true => "*",
+            false => &self.pattern,
+        }
+    }
+
+    /// Parse a pathspec, falling back to the default owner if it is missing.
+    pub fn parse(value: String, default_owner: &Option<String>) -> Result<Self> {
+        match GitHubPathspec::try_from(value.clone()) {
+            Ok(pathspec) => Ok(pathspec),
+            Err(e) => match default_owner {
+                Some(owner) => GitHubPathspec::try_from(format!("{owner}/{value}")),
+                None => Err(e),
+            },
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        !self.owner.is_empty() && !self.repository.is_empty()
+    }
+
+    pub fn get_full_origin(&self) -> String {
+        assert!(self.is_valid());
+        format!("{}/{}", self.owner, self.repository)
+    }
+}
+impl Display for GitHubPathspec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from(self.clone()))
+    }
+}
+impl From<GitHubPathspec> for String {
+    fn from(value: GitHubPathspec) -> Self {
+        match value.pattern.is_empty() {
+            true => format!("{}/{}", value.owner, value.repository),
+            false => format!("{}/{}:{}", value.owner, value.repository, value.pattern),
+        }
+    }
+}
+impl TryFrom<String> for GitHubPathspec {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        // The pattern is optional, without it all assets of the release are included
+        let re = Regex::new(r"^(?<owner>[^/:]+)/(?<repo>[^/:]+)(?::(?<pattern>.+))?$").unwrap();
+        match re.captures(&value) {
+            Some(captures) => Ok(GitHubPathspec {
+                owner: String::from(&captures["owner"]),
+                repository: String::from(&captures["repo"]),
+                pattern: captures
+                    .name("pattern")
+                    .map(|pattern| String::from(pattern.as_str()))
+                    .unwrap_or_default(),
+            }),
+            None => Err(anyhow::anyhow!("Invalid dependency path specification: '{value}'! Please ensure it's in the form of 'owner/repo:pattern' or 'owner/repo'!")),
+        }
+    }
+}
+
+/// A GitHub release as returned by the GitHub CLI.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubRelease {
+    pub tag_name: String,
+
+    #[serde(default)]
+    pub database_id: u64,
+
+    #[serde(default)]
+    pub url: String,
+
+    #[serde(default)]
+    pub is_prerelease: bool,
+
+    /// Whether the release is a draft, which only users with push access can see.
+    #[serde(default)]
+    pub is_draft: bool,
+
+    /// When the release was published, e.g. '2024-06-01T12:00:00Z'. Empty for drafts.
+    #[serde(default)]
+    pub published_at: String,
+
+    /// The release notes in markdown.
+    #[serde(default)]
+    pub body: String,
+
+    #[serde(default)]
+    pub assets: Vec<GitHubAsset>,
+}
+impl GitHubRelease {
+    /// Returns all assets matching the given glob pattern.
+    pub fn matching_assets(&self, pattern: &str) -> Result<Vec<&GitHubAsset>> {
+        let pattern = glob::Pattern::new(pattern)?;
+        Ok(self
+            .assets
+            .iter()
+            .filter(|asset| pattern.matches(&asset.name))
+            .collect())
+    }
+}
+
+/// A single asset of a [`GitHubRelease`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubAsset {
+    pub name: String,
+
+    #[serde(default)]
+    pub id: String,
+
+    #[serde(default)]
+    pub size: u64,
+
+    /// The browser download URL.
+    #[serde(default)]
+    pub url: String,
+
+    /// The REST API URL, which serves the raw asset.
+    #[serde(default)]
+    pub api_url: String,
+
+    /// The digest GitHub computed for the asset, e.g. 'sha256:hex'. Only known for newer releases.
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+/// The severity of a [`GitHubAdvisory`], ordered from least to most severe.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The severity has not been assessed
+    #[default]
+    Unknown,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Unknown => write!(f, "unknown"),
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+/// A published security advisory of a repository, as returned by the REST API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GitHubAdvisory {
+    pub ghsa_id: String,
+
+    #[serde(default)]
+    pub cve_id: Option<String>,
+
+    #[serde(default)]
+    pub summary: String,
+
+    /// Missing or unknown severities are deserialized as [`Severity::Unknown`].
+    #[serde(default, deserialize_with = "_deserialize_severity")]
+    pub severity: Severity,
+
+    #[serde(default)]
+    pub html_url: String,
+
+    #[serde(default)]
+    pub vulnerabilities: Vec<GitHubAdvisoryVulnerability>,
+}
+
+/// The versions affected by a [`GitHubAdvisory`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GitHubAdvisoryVulnerability {
+    /// Comma separated constraints, e.g. '>= 1.0.0, < 1.2.3'.
+    #[serde(default)]
+    pub vulnerable_version_range: Option<String>,
+
+    #[serde(default)]
+    pub patched_versions: Option<String>,
+}
+
+/// The license of a [`GitHubRepository`].
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GitHubLicense {
+    /// The SPDX identifier, 'NOASSERTION' if GitHub cannot identify the license.
+    #[serde(default)]
+    pub spdx_id: Option<String>,
+}
+
+/// The metadata of a repository, as returned by the REST API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct GitHubRepository {
+    #[serde(default)]
+    pub license: Option<GitHubLicense>,
+}
+impl GitHubRepository {
+    /// Returns the SPDX identifier of the license, if GitHub could identify it.
+    pub fn spdx_id(&self) -> Option<String> {
+        self.license
+            .as_ref()
+            .and_then(|license| license.spdx_id.clone())
+            .filter(|id| !id.is_empty() && id != "NOASSERTION")
+    }
+}
+
+/// The rate limit of a single resource of the GitHub API, as returned by the REST API.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct GitHubRateLimit {
+    pub limit: u64,
+
+    #[serde(default)]
+    pub used: u64,
+
+    pub remaining: u64,
+
+    /// When the rate limit is reset, in seconds since the Unix epoch.
+    pub reset: u64,
+}
+
+/// Who sink talks to GitHub as and how much of its rate limits is left, see [`GitHubApi::view_status`].
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct GitHubStatus {
+    /// The host of the API, e.g. 'github.com'.
+    pub host: String,
+
+    /// The login of the authenticated user.
+    ///
+    /// `None` if unauthenticated, or authenticated as an app, e.g. with the token of a GitHub Actions run.
+    pub user: Option<String>,
+
+    /// The rate limits by resource, e.g. 'core' for the REST API or 'graphql'.
+    pub rate_limits: BTreeMap<String, GitHubRateLimit>,
+}
+
+/// Deserialize a severity, which is `null` if it has not been assessed.
+fn _deserialize_severity<'de, D>(deserializer: D) -> Result<Severity, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let severity = Option::<String>::deserialize(deserializer)?;
+    Ok(match severity.as_deref() {
+        Some("low") => Severity::Low,
+        Some("medium") => Severity::Medium,
+        Some("high") => Severity::High,
+        Some("critical") => Severity::Critical,
+        _ => Severity::Unknown,
+    })
+}
+
+/* ---------- [ Functions ] ---------- */
+/// How many seconds the latest releases of repositories are cached, zero if they are never cached.
+static METADATA_TTL: AtomicU64 = AtomicU64::new(0);
+
+/// Cache the latest releases of repositories on disk for the given time, or never with `None`.
+///
+/// Repeated commands, e.g. `sink upgrade --dry-run` followed by `sink install`, then do not ask GitHub again.
+/// Only releases looked up by [`resolve`] are cached, see [`cache::dir`].
+pub fn set_metadata_ttl(ttl: Option<Duration>) {
+    METADATA_TTL.store(ttl.map_or(0, |ttl| ttl.as_secs()), Ordering::Relaxed);
+}
+
+/// Whether draft releases may be resolved, see [`GitHubVersion::Draft`].
+static ALLOW_DRAFT: AtomicBool = AtomicBool::new(false);
+
+/// Allow (or forbid) resolving draft releases.
+///
+/// Drafts are not published yet, so they are refused unless explicitly allowed, e.g. by maintainers testing their assets.
+pub fn set_allow_draft(allow: bool) {
+    ALLOW_DRAFT.store(allow, Ordering::Relaxed);
+}
+
+/// Returns the cache key of the latest release of the repository.
+///
+/// The key contains the host, so releases of GitHub Enterprise Server never mix with those of github.com.
+fn _metadata_key(origin: &str) -> String {
+    format!("release-{}/{origin}", api::host())
+}
+
+/// Returns the cached latest release of the repository, if it is recent enough.
+fn _cached_latest(origin: &str) -> Option<GitHubRelease> {
+    let ttl = METADATA_TTL.load(Ordering::Relaxed);
+    if ttl == 0 {
+        return None;
+    }
+
+    let cached = cache::read(&_metadata_key(origin), Duration::from_secs(ttl))?;
+    match serde_json::from_str(&cached) {
+        Ok(release) => {
+            debug!("Using the cached latest release of '{origin}'!");
+            Some(release)
+        }
+        Err(e) => {
+            debug!("Ignoring the broken cached latest release of '{origin}': {e}");
+            None
+        }
+    }
+}
+
+/// Returns the latest release of the repository, from the cache if it is recent enough.
+fn _latest_release(origin: &str) -> Result<GitHubRelease> {
+    if let Some(release) = _cached_latest(origin) {
+        return Ok(release);
+    }
+
+    let release = api::api().view_release(origin, None)?;
+    if METADATA_TTL.load(Ordering::Relaxed) > 0 {
+        // A failing cache must never fail the lookup
+        let written = serde_json::to_string(&release)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| cache::write(&_metadata_key(origin), &json));
+        if let Err(e) = written {
+            debug!("Failed to cache the latest release of '{origin}': {e:#}");
+        }
+    }
+
+    Ok(release)
+}
+
+/// Returns the tags of the latest releases of the given repositories, by 'owner/repository'.
+///
+/// Cached releases are used if they are recent enough, the others are looked up in bulk, see [`GitHubApi::latest_tags`].
+pub fn latest_tags(origins: &[String]) -> Result<BTreeMap<String, String>> {
+    let mut tags = BTreeMap::new();
+    let mut missing = Vec::new();
+    for origin in origins {
+        match _cached_latest(origin) {
+            Some(release) => {
+                tags.insert(origin.clone(), release.tag_name);
+            }
+            None => missing.push(origin.clone()),
+        }
+    }
+    if !missing.is_empty() {
+        tags.extend(api::api().latest_tags(&missing)?);
+    }
+
+    Ok(tags)
+}
+
+/// The number of releases listed first when searching the releases of a repository, see [`search_releases`].
+const RELEASES_PAGE: usize = 30;
+
+/// The number of most recent releases searched at most, see [`search_releases`].
+pub const RELEASES_MAX: usize = 1000;
+
+/// Whether the string is a valid date in the form of 'YYYY-MM-DD', e.g. '2024-02-29'.
+fn _is_date(date: &str) -> bool {
+    let re = Regex::new(r"^(?<year>\d{4})-(?<month>\d{2})-(?<day>\d{2})$").unwrap();
+    let Some(captures) = re.captures(date) else {
+        return false;
+    };
+    let [year, month, day] = ["year", "month", "day"].map(|name| {
+        captures[name]
+            .parse::<u32>()
+            .expect("The regex only matches digits")
+    });
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+
+    (1..=days).contains(&day)
+}
+
+/// Visit the releases of the given 'owner/repository', newest first, until `visit` breaks with a result.
+///
+/// Only the tag, whether it is a prerelease or a draft and when it was published are filled in. The releases are listed in pages of growing size,
+/// so the search ends early once the result is found, but still reaches old releases of repositories with hundreds of them.
+/// Returns `None` if all releases, or the [`RELEASES_MAX`] most recent ones, were visited without a result.
+pub fn search_releases<T>(
+    origin: &str,
+    mut visit: impl FnMut(&GitHubRelease) -> Result<ControlFlow<T>>,
+) -> Result<Option<T>> {
+    let mut visited = 0;
+    let mut limit = RELEASES_PAGE;
+    loop {
+        let releases = api::api().list_releases(origin, limit)?;
+        for release in releases.iter().skip(visited) {
+            if let ControlFlow::Break(result) = visit(release)? {
+                return Ok(Some(result));
+            }
+        }
+        visited = releases.len();
+
+        if visited < limit {
+            return Ok(None);
+        }
+        if limit >= RELEASES_MAX {
+            debug!("Searched the {RELEASES_MAX} most recent releases of '{origin}', giving up!");
+            return Ok(None);
+        }
+        debug!("Searched {visited} releases of '{origin}', listing more...");
+        limit = (limit * 2).min(RELEASES_MAX);
+    }
+}
+
+fn _resolve(dependency: &GitHubDependency) -> Result<GitHubRelease> {
+    let origin = dependency.pathspec.get_full_origin();
+    debug!(
+        "Resolving {}@{}...",
+        dependency.pathspec, dependency.version
+    );
+
+    if let GitHubVersion::Tag(tag) | GitHubVersion::Draft(tag) = &dependency.version {
+        if dependency.is_blocked(tag) {
+            return Err(anyhow::anyhow!(
+                "{}@{tag} is blocked, pin another version!",
+                dependency.pathspec
+            ));
+        }
+    }
+
+    let release = match &dependency.version {
+        GitHubVersion::Latest => match _latest_release(&origin)? {
+            latest if dependency.is_blocked(&latest.tag_name) => {
+                debug!(
+                    "'{}' is blocked, looking for an older release...",
+                    latest.tag_name
+                );
+                let older = search_releases(&origin, |release| {
+                    Ok(
+                        match !release.is_prerelease
+                            && !release.is_draft
+                            && !dependency.is_blocked(&release.tag_name)
+                        {
+                            true => ControlFlow::Break(release.tag_name.clone()),
+                            false => ControlFlow::Continue(()),
+                        },
+                    )
+                })?;
+                match older {
+                    Some(tag) => api::api().view_release(&origin, Some(&tag))?,
+                    None => {
+                        return Err(anyhow::anyhow!(
+                            "'{origin}' has no release that is not blocked!"
+                        ))
+                    }
+                }
+            }
+            latest => latest,
+        },
+        GitHubVersion::Tag(tag) => api::api().view_release(&origin, Some(tag))?,
+        GitHubVersion::Prerelease => {
+            let prerelease = search_releases(&origin, |release| {
+                Ok(
+                    match release.is_prerelease
+                        && !release.is_draft
+                        && !dependency.is_blocked(&release.tag_name)
+                    {
+                        true => ControlFlow::Break(release.tag_name.clone()),
+                        false => ControlFlow::Continue(()),
+                    },
+                )
+            })?;
+            match prerelease {
+                Some(tag) => api::api().view_release(&origin, Some(&tag))?,
+                None => return Err(anyhow::anyhow!("'{origin}' has no prerelease!")),
+            }
+        }
+        GitHubVersion::AsOf(date) => {
+            if !_is_date(date) {
+                return Err(anyhow::anyhow!(
+                    "'{date}' is not a date in the form of 'YYYY-MM-DD'!"
+                ));
+            }
+            // Timestamps start with the date, so they can be compared as strings
+            let as_of = search_releases(&origin, |release| {
+                let published = release.published_at.get(..10).unwrap_or_default();
+                Ok(
+                    match !release.is_prerelease
+                        && !release.is_draft
+                        && !dependency.is_blocked(&release.tag_name)
+                        && !published.is_empty()
+                        && published <= date.as_str()
+                    {
+                        true => ControlFlow::Break(release.tag_name.clone()),
+                        false => ControlFlow::Continue(()),
+                    },
+                )
+            })?;
+            match as_of {
+                Some(tag) => api::api().view_release(&origin, Some(&tag))?,
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "'{origin}' has no release published on or before {date}!"
+                    ))
+                }
+            }
+        }
+        GitHubVersion::Draft(tag) => {
+            if !ALLOW_DRAFT.load(Ordering::Relaxed) {
+                return Err(anyhow::anyhow!(
+                    "{} is a draft release, which must be allowed with '--allow-draft'!",
+                    dependency.version
+                ));
+            }
+            // Drafts can only be found by listing the releases, and only with push access to the repository
+            let draft = search_releases(&origin, |release| {
+                Ok(match release.is_draft && release.tag_name == *tag {
+                    true => ControlFlow::Break(()),
+                    false => ControlFlow::Continue(()),
+                })
+            })?;
+            match draft {
+                Some(()) => api::api().view_release(&origin, Some(tag))?,
+                None => {
+                    return Err(anyhow::anyhow!(
+                        "'{origin}' has no draft release '{tag}', or it cannot be seen without push access!"
+                    ))
+                }
+            }
+        }
+    };
+
+    debug!(
+        "Resolved {}@{} to '{}'!",
+        dependency.pathspec, dependency.version, release.tag_name
+    );
+
+    Ok(release)
+}
+/// Resolve the release the given dependency refers to.
+pub fn resolve(dependency: &GitHubDependency) -> Result<GitHubRelease> {
+    match _resolve(dependency) {
+        Ok(release) => Ok(release),
+        Err(e) => Err(e.context("Failed to resolve dependency!")),
+    }
+}
+
+fn _list_tags(origin: &str, limit: usize) -> Result<Vec<String>> {
+    Ok(api::api()
+        .list_releases(origin, limit)?
+        .into_iter()
+        .map(|release| release.tag_name)
+        .collect())
+}
+/// List the tags of the most recent releases of the given 'owner/repository'.
+pub fn list_tags(origin: &str, limit: usize) -> Result<Vec<String>> {
+    match _list_tags(origin, limit) {
+        Ok(tags) => Ok(tags),
+        Err(e) => Err(e.context(format!("Failed to list releases of '{origin}'!"))),
+    }
+}
+
+fn _newest_release(dependency: &GitHubDependency) -> Result<Option<GitHubRelease>> {
+    let origin = dependency.pathspec.get_full_origin();
+    let GitHubVersion::Tag(current) = &dependency.version else {
+        return Err(anyhow::anyhow!(
+            "'{}' is not pinned to a tag!",
+            dependency.pathspec
+        ));
+    };
+
+    let newest = search_releases(&origin, |release| {
+        // Releases are listed newest first, so everything after the current tag is older
+        if release.tag_name == *current {
+            return Ok(ControlFlow::Break(None));
+        }
+        if release.is_prerelease || release.is_draft || dependency.is_blocked(&release.tag_name) {
+            return Ok(ControlFlow::Continue(()));
+        }
+        if !dependency.update_policy.allows(current, &release.tag_name) {
+            debug!(
+                "'{}' is not allowed by the update policy '{}', skipping!",
+                release.tag_name, dependency.update_policy
+            );
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        let release = api::api().view_release(&origin, Some(&release.tag_name))?;
+        if _matching_or_guessed(dependency, &release)?.is_empty() {
+            debug!(
+                "'{}' has no asset matching '{}', skipping!",
+                release.tag_name,
+                dependency.pathspec.asset_pattern()
+            );
+            return Ok(ControlFlow::Continue(()));
+        }
+
+        Ok(ControlFlow::Break(Some(release)))
+    })?;
+
+    Ok(newest.flatten())
+}
+/// Returns the newest stable release that is newer than the pinned tag of the dependency.
+///
+/// Only releases allowed by the update policy and providing assets matching the pattern of the dependency are considered.
+/// At most the [`RELEASES_MAX`] most recent releases are checked.
+pub fn newest_release(dependency: &GitHubDependency) -> Result<Option<GitHubRelease>> {
+    match _newest_release(dependency) {
+        Ok(release) => Ok(release),
+        Err(e) => Err(e.context(format!(
+            "Failed to find a newer release of '{}'!",
+            dependency.pathspec
+        ))),
+    }
+}
+
+fn _add(
+    sink_toml: SinkTOML,
+    sink_lock: &mut SinkLock,
+    dependency: GitHubDependency,
+    short_form: bool,
+) -> Result<SinkTOML> {
+    if !dependency.pathspec.is_valid() {
+        return Err(anyhow::anyhow!(
+            "Invalid dependency: '{}'!",
+            dependency.pathspec
+        ));
+    }
+
+    let _pathspec = dependency.pathspec.to_string();
+    info!("Adding {_pathspec}@{}...", dependency.version);
+
+    // Fail if the dependency already exists
+    if sink_toml.dependencies.contains_key(&dependency.pathspec) {
+        return Err(anyhow::anyhow!("Dependency '{_pathspec}' already exists!"));
+    }
+
+    sink_toml.check_destination(&dependency.pathspec, &dependency.destination)?;
+
+    // Check if it can be installed
+    let locked = download(&dependency, &sink_toml.root(), &sink_toml.policy, None)?;
+
+    // Add the dependency to sink TOML
+    let dependency_type;
+    let formatted_value;
+    if short_form {
+        dependency_type = DependencyType::Version(dependency.version.clone());
+        formatted_value = toml_edit::value(dependency.version.to_string())
+    } else {
+        let dep_clone = dependency.clone();
+        let mut table = toml_edit::table();
+        table["version"] = toml_edit::value(dep_clone.version.to_string());
+        table["destination"] = toml_edit::value(dep_clone.destination.display().to_string());
+        table["gitignore"] = toml_edit::value(dep_clone.gitignore);
+
+        dependency_type = DependencyType::Full(Box::new(dep_clone));
+        formatted_value = table;
+    };
+
+    let pathspec = dependency.pathspec.clone();
+    match sink_toml.add_dependency(dependency, dependency_type, formatted_value) {
+        Ok(sink_toml) => {
+            sink_lock.dependencies.insert(pathspec, locked);
+            sink_lock.save()?;
+            gitignore::sync(&sink_toml, sink_lock)?;
+            env::sync(&sink_toml, sink_lock)?;
+
+            info!("Added {_pathspec}!");
+            Ok(sink_toml)
+        }
+        Err(e) => Err(e),
+    }
+}
+/// Add a dependency.
+///
+/// The dependency is installed and its resolved state is written to the lockfile.
+pub fn add(
+    sink_toml: SinkTOML,
+    sink_lock: &mut SinkLock,
+    dependency: GitHubDependency,
+    short_form: bool,
+) -> Result<SinkTOML> {
+    match _add(sink_toml, sink_lock, dependency, short_form) {
+        Ok(sink_toml) => Ok(sink_toml),
+        Err(e) => Err(e.context("Failed to add dependency!")),
+    }
+}
+
+fn _remove(
+    sink_toml: SinkTOML,
+    sink_lock: &mut SinkLock,
+    pathspec: &GitHubPathspec,
+    keep_files: bool,
+) -> Result<(SinkTOML, Vec<PathBuf>)> {
+    info!("Removing {pathspec}...");
+
+    if !sink_toml.dependencies.contains_key(pathspec) {
+        return Err(anyhow::anyhow!("Dependency '{pathspec}' does not exist!"));
+    }
+
+    // Delete the installed files before touching the sink TOML, so a failure leaves everything in place
+    let mut removed = Vec::new();
+    if keep_files {
+        debug!("Keeping the installed files of {pathspec}!");
+    } else if sink_lock.dependencies.contains_key(pathspec) {
+        removed = lock::clean(
+            &sink_toml.root(),
+            sink_lock,
+            Some(pathspec),
+            sink_toml.allow_external_destinations,
+        )?;
+    }
+
+    let sink_toml = sink_toml.remove_dependency(pathspec)?;
+    sink_lock.dependencies.remove(pathspec);
+    sink_lock.save()?;
+    gitignore::sync(&sink_toml, sink_lock)?;
+    env::sync(&sink_toml, sink_lock)?;
+
+    info!("Removed {pathspec}!");
+    Ok((sink_toml, removed))
+}
+/// Remove a dependency.
+///
+/// The dependency is removed from the sink TOML and the lockfile.
+/// Unless `keep_files` is set, its installed files are deleted as well. Returns the deleted paths.
+pub fn remove(
+    sink_toml: SinkTOML,
+    sink_lock: &mut SinkLock,
+    pathspec: &GitHubPathspec,
+    keep_files: bool,
+) -> Result<(SinkTOML, Vec<PathBuf>)> {
+    match _remove(sink_toml, sink_lock, pathspec, keep_files) {
+        Ok(result) => Ok(result),
+        Err(e) => Err(e.context("Failed to remove dependency!")),
+    }
+}
+
+/// Returns the assets of the release matching the pattern of the dependency.
+///
+/// If none match, the asset for the running platform is guessed instead, see [`detect::guess`].
+fn _matching_or_guessed<'a>(
+    dependency: &GitHubDependency,
+    release: &'a GitHubRelease,
+) -> Result<Vec<&'a GitHubAsset>> {
+    let pattern = dependency.asset_pattern(&release.tag_name)?;
+    let assets = release.matching_assets(&pattern)?;
+    if !assets.is_empty() {
+        return Ok(assets);
+    }
+
+    match detect::guess(dependency.pathspec.repository(), &release.assets) {
+        Some(asset) => {
+            warn!(
+                "No asset of '{}' matches '{pattern}', guessed '{}'!",
+                release.tag_name, asset.name
+            );
+            Ok(vec![asset])
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Returns the path the asset with the given name is written to inside `dir`.
+///
+/// Names are controlled by whoever publishes the release, so they must be plain file names
+/// that cannot escape `dir`, e.g. '../tool' or '/usr/bin/tool'.
+/// Existing symlinks are never written through, as they could point anywhere.
+fn _asset_path(dir: &Path, name: &str) -> Result<PathBuf> {
+    let mut components = Path::new(name).components();
+    let is_plain = matches!(
+        (components.next(), components.next()),
+        (Some(std::path::Component::Normal(_)), None)
+    );
+    if !is_plain || name.contains(['/', '\\']) {
+        return Err(anyhow::anyhow!(
+            "Refusing to write asset '{name}', which would escape '{}'!",
+            dir.display()
+        ));
+    }
+
+    let path = dir.join(name);
+    if path.is_symlink() {
+        return Err(anyhow::anyhow!(
+            "Refusing to write asset '{name}' through the symlink '{}'!",
+            path.display()
+        ));
+    }
+
+    Ok(path)
+}
+
+/// Whether the file at `path` matches the digest GitHub computed for the asset, so it need not be downloaded again.
+///
+/// Always `false` for assets of older releases, which have no digest.
+fn _is_unchanged(path: &Path, asset: &GitHubAsset) -> bool {
+    let Some(expected) = &asset.digest else {
+        return false;
+    };
+    let expected = expected.to_ascii_lowercase();
+    lock::digest_like(path, &expected).is_ok_and(|actual| actual == expected)
+}
+
+/// Verify the contents of the given assets of the release, see [`_download_assets`].
+///
+/// Fails if the policy refuses any of them, if they do not match the checksums asset of the dependency
+/// or if they are not signed with its minisign key.
+fn _verify_assets(
+    dependency: &GitHubDependency,
+    release: &GitHubRelease,
+    assets: &[&GitHubAsset],
+    contents: &[Vec<u8>],
+    policy: &Policy,
+) -> Result<()> {
+    let origin = dependency.pathspec.get_full_origin();
+    let verified: Vec<(&GitHubAsset, &[u8])> = assets
+        .iter()
+        .copied()
+        .zip(contents.iter().map(Vec::as_slice))
+        .collect();
+    for (asset, contents) in verified.iter() {
+        policy.check_asset(&origin, release, asset, contents)?;
+    }
+    if let Some(pattern) = dependency.checksums_pattern(&release.tag_name) {
+        integrity::verify_listed(&origin, release, &pattern?, &verified)?;
+    }
+    let minisign_key = dependency
+        .minisign_key
+        .as_deref()
+        .or_else(|| policy.minisign_key(&dependency.pathspec));
+    if let Some(public_key) = minisign_key {
+        integrity::verify_signatures(&origin, release, public_key, &verified)?;
+    }
+
+    Ok(())
+}
+
+/// Download the given assets of the release into `dir`, replacing existing files.
+///
+/// Nothing is written if the policy refuses any of the assets, e.g. because their published checksums do not match,
+/// or if they do not match the checksums asset of the dependency, see [`GitHubDependency::checksums_asset`],
+/// or if they are not signed with its minisign key, see [`GitHubDependency::minisign_key`].
+/// Nothing is downloaded if their declared sizes exceed the limits of the policy.
+/// Every file is written atomically, see [`atomic::write`].
+fn _download_assets(
+    dependency: &GitHubDependency,
+    release: &GitHubRelease,
+    assets: &[&GitHubAsset],
+    dir: &Path,
+    policy: &Policy,
+) -> Result<()> {
+    policy.check_sizes(&dependency.pathspec, release, assets)?;
+    let paths = assets
+        .iter()
+        .map(|asset| _asset_path(dir, &asset.name))
+        .collect::<Result<Vec<PathBuf>>>()?;
+
+    let origin = dependency.pathspec.get_full_origin();
+    let mut downloaded = Vec::new();
+    for asset in assets {
+        interrupt::check()?;
+        let _span = debug_span!("download", asset = %asset.name).entered();
+        let contents = api::api().download_asset(&origin, &release.tag_name, asset)?;
+        debug!(bytes = contents.len(), "Downloaded '{}'!", asset.name);
+        downloaded.push(contents);
+    }
+    _verify_assets(dependency, release, assets, &downloaded, policy)?;
+
+    std::fs::create_dir_all(dir)?;
+    for (path, contents) in paths.iter().zip(downloaded) {
+        atomic::write(path, contents)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the license of the repository of the dependency, unless the policy refuses it.
+fn _checked_license(dependency: &GitHubDependency, policy: &Policy) -> Result<Option<String>> {
+    let license = api::api().view_license(&dependency.pathspec.get_full_origin())?;
+    policy.check_license(&dependency.pathspec, license.as_deref())?;
+
+    Ok(license)
 }
-impl GitHubVersion {
-    pub fn parse_cli(s: &str) -> Result<Self, String> {
-        Ok(Self::from(s))
+
+/// Returns the lockfile entry of the given assets, which have been downloaded into `dir`.
+fn _locked(
+    dependency: &GitHubDependency,
+    release: &GitHubRelease,
+    assets: &[&GitHubAsset],
+    dir: &Path,
+    license: Option<String>,
+) -> Result<LockedDependency> {
+    let mut files = Vec::new();
+    for asset in assets {
+        files.push(LockedFile {
+            path: dependency.install_dir(&release.tag_name).join(&asset.name),
+            checksum: lock::digest(&dir.join(&asset.name))?,
+            asset_id: Some(asset.id.clone()).filter(|id| !id.is_empty()),
+            url: Some(asset.url.clone()).filter(|url| !url.is_empty()),
+            api_url: Some(asset.api_url.clone()).filter(|url| !url.is_empty()),
+            ..Default::default()
+        });
     }
+
+    Ok(LockedDependency {
+        version: release.tag_name.clone(),
+        release_id: Some(release.database_id).filter(|id| *id != 0),
+        release_url: Some(release.url.clone()).filter(|url| !url.is_empty()),
+        license,
+        link: dependency.link.clone(),
+        files,
+        ..Default::default()
+    })
 }
-impl Display for GitHubVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            GitHubVersion::Latest => write!(f, "latest"),
-            GitHubVersion::Prerelease => write!(f, "prerelease"),
-            GitHubVersion::Tag(tag) => write!(f, "{}", tag),
+
+/// Returns the target of the symlink at `link` pointing to `file`, both given relative to `root`.
+///
+/// The target is relative to the directory of the link if possible, so the project can be moved around.
+fn _link_target(root: &Path, link: &Path, file: &Path) -> Result<PathBuf> {
+    // Only plain relative paths can be related to each other
+    let parent = link.parent().unwrap_or(Path::new(""));
+    match (lock::normalized(parent), lock::normalized(file)) {
+        (Some(parent), Some(file)) => {
+            let parent: Vec<_> = parent.components().collect();
+            let file: Vec<_> = file.components().collect();
+            let common = parent
+                .iter()
+                .zip(file.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            let mut target = PathBuf::new();
+            for _ in common..parent.len() {
+                target.push("..");
+            }
+            target.extend(&file[common..]);
+            Ok(target)
         }
+        _ => Ok(std::path::absolute(root.join(file))?),
     }
 }
-impl From<&str> for GitHubVersion {
-    fn from(s: &str) -> Self {
-        match s {
-            "latest" => GitHubVersion::Latest,
-            "prerelease" => GitHubVersion::Prerelease,
-            _ => GitHubVersion::Tag(s.to_string()),
-        }
+
+/// Link the installed asset to [`GitHubDependency::link`], if set.
+fn _create_link(
+    dependency: &GitHubDependency,
+    root: &Path,
+    locked: &LockedDependency,
+) -> Result<()> {
+    let Some(link) = &dependency.link else {
+        return Ok(());
+    };
+    let [file] = locked.files.as_slice() else {
+        return Err(anyhow::anyhow!(
+            "'{}' can only link a single asset, but {} were installed!",
+            link.display(),
+            locked.files.len()
+        ));
+    };
+
+    let full_link = root.join(link);
+    if let Some(parent) = full_link.parent() {
+        std::fs::create_dir_all(parent)?;
     }
+    atomic::symlink(&_link_target(root, link, &file.path)?, &full_link)?;
+    info!("Linked '{}' to '{}'!", link.display(), file.path.display());
+
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq, Hash, Default)]
-#[serde(try_from = "String", into = "String")]
-pub struct GitHubPathspec {
-    owner: String,
-    repository: String,
-    pattern: String,
+/// Whether the file at `path` is still the one recorded at `relative` in the previous lockfile entry.
+fn _matches_lock(path: &Path, relative: &Path, previous: Option<&LockedDependency>) -> bool {
+    previous
+        .and_then(|previous| previous.files.iter().find(|file| file.path == relative))
+        .is_some_and(|file| {
+            lock::digest_like(path, &file.checksum).is_ok_and(|actual| actual == file.checksum)
+        })
 }
-impl GitHubPathspec {
-    pub fn is_valid(&self) -> bool {
-        !self.owner.is_empty() && !self.repository.is_empty() && !self.pattern.is_empty()
+
+fn _download(
+    dependency: &GitHubDependency,
+    root: &Path,
+    policy: &Policy,
+    previous: Option<&LockedDependency>,
+) -> Result<LockedDependency> {
+    policy.check(&dependency.pathspec)?;
+    let release = resolve(dependency)?;
+    policy.check_release(&dependency.pathspec, &release)?;
+    let license = _checked_license(dependency, policy)?;
+    let assets = _matching_or_guessed(dependency, &release)?;
+    if assets.is_empty() {
+        return Err(CodedError(
+            ErrorCode::NoMatchingAsset,
+            format!(
+                "No asset of '{}' matches '{}'!",
+                release.tag_name,
+                dependency.pathspec.asset_pattern()
+            ),
+        )
+        .into());
     }
 
-    pub fn get_full_origin(&self) -> String {
-        assert!(self.is_valid());
-        format!("{}/{}", self.owner, self.repository)
+    let dir = dependency.install_dir(&release.tag_name);
+    // Files matching the digests GitHub computed for their assets are not downloaded again, even if nothing is locked yet.
+    // Without a digest, versioned installs are kept as long as they match the lockfile, since they never change
+    let installed = assets.iter().all(|asset| {
+        _asset_path(&root.join(&dir), &asset.name).is_ok_and(|path| {
+            path.is_file()
+                && match asset.digest {
+                    Some(_) => _is_unchanged(&path, asset),
+                    None => {
+                        dependency.versioned
+                            && _matches_lock(&path, &dir.join(&asset.name), previous)
+                    }
+                }
+        })
+    });
+    if installed {
+        // Kept files must pass the same verifications as downloaded ones
+        let contents = assets
+            .iter()
+            .map(|asset| Ok(std::fs::read(_asset_path(&root.join(&dir), &asset.name)?)?))
+            .collect::<Result<Vec<Vec<u8>>>>()?;
+        _verify_assets(dependency, &release, &assets, &contents, policy)?;
+        info!(
+            "{}@{} is already installed in '{}'!",
+            dependency.pathspec,
+            release.tag_name,
+            dir.display()
+        );
+    } else {
+        info!(
+            "Downloading {}@{} into '{}' ...",
+            dependency.pathspec,
+            release.tag_name,
+            dir.display()
+        );
+
+        hooks::run(
+            dependency,
+            Stage::PreInstall,
+            &root.join(&dir),
+            &release.tag_name,
+        )?;
+        _download_assets(dependency, &release, &assets, &root.join(&dir), policy)?;
+
+        info!(
+            "Downloaded {}@{} into '{}'!",
+            dependency.pathspec,
+            release.tag_name,
+            dir.display()
+        );
     }
-}
-impl Display for GitHubPathspec {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", String::from(self.clone()))
+
+    let locked = _locked(dependency, &release, &assets, &root.join(&dir), license)?;
+    _create_link(dependency, root, &locked)?;
+    if !installed {
+        hooks::run(
+            dependency,
+            Stage::PostInstall,
+            &root.join(&dir),
+            &release.tag_name,
+        )?;
     }
+
+    Ok(locked)
 }
-impl From<GitHubPathspec> for String {
-    fn from(value: GitHubPathspec) -> Self {
-        format!("{}/{}:{}", value.owner, value.repository, value.pattern)
+/// Download the given dependency, unless the policy refuses it.
+///
+/// Relative destinations are resolved from `root`, which should be the directory of the sink TOML.
+/// `previous` is the lockfile entry of the dependency, if any, which existing versioned installs are checked against.
+/// Returns the resolved state of the dependency for the lockfile.
+pub fn download(
+    dependency: &GitHubDependency,
+    root: &Path,
+    policy: &Policy,
+    previous: Option<&LockedDependency>,
+) -> Result<LockedDependency> {
+    match _download(dependency, root, policy, previous) {
+        Ok(locked) => Ok(locked),
+        Err(e) => Err(e.context("Failed to download dependency!")),
     }
 }
-impl TryFrom<String> for GitHubPathspec {
-    type Error = anyhow::Error;
 
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        let re = Regex::new(r"^(?<owner>.+)/(?<repo>.+):(?<pattern>.+)$").unwrap();
-        match re.captures(&value) {
-            Some(captures) => Ok(GitHubPathspec {
-                owner: String::from(&captures["owner"]),
-                repository: String::from(&captures["repo"]),
-                pattern: String::from(&captures["pattern"]),
-            }),
-            None => Err(anyhow::anyhow!("Invalid dependency path specification: '{value}'! Please ensure it's in the form of 'owner/repo:pattern'!")),
-        }
+fn _lock(dependency: &GitHubDependency, policy: &Policy) -> Result<LockedDependency> {
+    policy.check(&dependency.pathspec)?;
+    let release = resolve(dependency)?;
+    policy.check_release(&dependency.pathspec, &release)?;
+    let license = _checked_license(dependency, policy)?;
+    let assets = _matching_or_guessed(dependency, &release)?;
+    if assets.is_empty() {
+        return Err(CodedError(
+            ErrorCode::NoMatchingAsset,
+            format!(
+                "No asset of '{}' matches '{}'!",
+                release.tag_name,
+                dependency.pathspec.asset_pattern()
+            ),
+        )
+        .into());
+    }
+
+    info!("Locking {}@{}...", dependency.pathspec, release.tag_name);
+
+    // The assets are only needed for their digests, so keep them away from the destination
+    let temp_dir = std::env::temp_dir().join(format!(
+        "sink-lock-{}-{}",
+        std::process::id(),
+        dependency
+            .pathspec
+            .to_string()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "-")
+    ));
+    let locked = _download_assets(dependency, &release, &assets, &temp_dir, policy)
+        .and_then(|_| _locked(dependency, &release, &assets, &temp_dir, license));
+    if temp_dir.exists() {
+        std::fs::remove_dir_all(&temp_dir)?;
+    }
+
+    locked
+}
+/// Resolve the given dependency to its state in the lockfile without installing it.
+///
+/// The matching assets are downloaded into a temporary directory to compute their digests.
+/// Fails if the policy refuses the dependency.
+pub fn lock(dependency: &GitHubDependency, policy: &Policy) -> Result<LockedDependency> {
+    match _lock(dependency, policy) {
+        Ok(locked) => Ok(locked),
+        Err(e) => Err(e.context(format!("Failed to lock '{}'!", dependency.pathspec))),
     }
 }
 
-/* ---------- [ Functions ] ---------- */
-fn _add(sink_toml: SinkTOML, dependency: GitHubDependency, short_form: bool) -> Result<SinkTOML> {
-    if !dependency.pathspec.is_valid() {
-        return Err(anyhow::anyhow!(
-            "Invalid dependency: '{}'!",
+fn _download_locked(
+    dependency: &GitHubDependency,
+    locked: &LockedDependency,
+    root: &Path,
+    policy: &Policy,
+) -> Result<LockedDependency> {
+    // Older lockfiles do not know where the assets came from, so fall back to the locked tag
+    if locked.files.iter().any(|file| file.api_url.is_none()) {
+        debug!(
+            "{} has no asset URLs in the lockfile, downloading by tag!",
             dependency.pathspec
-        ));
+        );
+        let mut dependency = dependency.clone();
+        dependency.version = GitHubVersion::Tag(locked.version.clone());
+        return download(&dependency, root, policy, Some(locked));
     }
 
-    let _pathspec = dependency.pathspec.to_string();
-    info!("Adding {_pathspec}@{}...", dependency.version);
+    policy.check(&dependency.pathspec)?;
 
-    // Fail if the dependency already exists
-    if sink_toml.dependencies.contains_key(&dependency.pathspec) {
-        return Err(anyhow::anyhow!("Dependency '{_pathspec}' already exists!"));
+    // Older lockfiles do not record the license
+    let origin = dependency.pathspec.get_full_origin();
+    let mut downloaded = locked.clone();
+    if !policy.licenses.is_empty() {
+        if downloaded.license.is_none() {
+            downloaded.license = api::api().view_license(&origin)?;
+        }
+        policy.check_license(&dependency.pathspec, downloaded.license.as_deref())?;
     }
 
-    // Check if it can be installed
-    download(&dependency)?;
-
-    // Add the dependency to sink TOML
-    let dependency_type;
-    let formatted_value;
-    if short_form {
-        dependency_type = DependencyType::Version(dependency.version.clone());
-        formatted_value = toml_edit::value(dependency.version.to_string())
-    } else {
-        let dep_clone = dependency.clone();
-        let mut table = toml_edit::table();
-        table["version"] = toml_edit::value(dep_clone.version.to_string());
-        table["destination"] = toml_edit::value(dep_clone.destination.display().to_string());
-        table["gitignore"] = toml_edit::value(dep_clone.gitignore);
+    // Files matching their digests in the lockfile are kept, only the others are downloaded
+    let install_dir = dependency.install_dir(&locked.version);
+    let dir = root.join(&install_dir);
+    let mut pending = Vec::new();
+    for (index, file) in locked.files.iter().enumerate() {
+        let Some(api_url) = &file.api_url else {
+            continue;
+        };
+        let name = file
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // A tampered lockfile must not redirect the asset outside of the destination
+        let expected = install_dir.join(&name);
+        let contained = match lock::normalized(&expected) {
+            Some(expected) => lock::normalized(&file.path) == Some(expected),
+            None => file.path == expected,
+        };
+        if !contained {
+            return Err(anyhow::anyhow!(
+                "'{}' is not inside the destination '{}'!",
+                file.path.display(),
+                install_dir.display()
+            ));
+        }
+        let full_path = _asset_path(&dir, &name)?;
+        if full_path.is_file()
+            && lock::digest_like(&full_path, &file.checksum).is_ok_and(|d| d == file.checksum)
+        {
+            debug!("'{}' matches the lockfile, skipping!", file.path.display());
+            continue;
+        }
 
-        dependency_type = DependencyType::Full(dep_clone);
-        formatted_value = table;
-    };
+        let asset = GitHubAsset {
+            name,
+            id: file.asset_id.clone().unwrap_or_default(),
+            api_url: api_url.clone(),
+            ..Default::default()
+        };
+        pending.push((index, full_path, asset));
+    }
 
-    match sink_toml.add_dependency(dependency, dependency_type, formatted_value) {
-        Ok(sink_toml) => {
-            info!("Added {_pathspec}!");
-            Ok(sink_toml)
+    // The lockfile does not know whether the locked tag is a prerelease, nor how large the assets are
+    let limits_sizes = policy.max_asset_size.is_some() || policy.max_total_size.is_some();
+    if !policy.allow_prerelease || (limits_sizes && !pending.is_empty()) {
+        let release = api::api().view_release(&origin, Some(&locked.version))?;
+        if !policy.allow_prerelease {
+            policy.check_release(&dependency.pathspec, &release)?;
         }
-        Err(e) => Err(e),
+        let assets: Vec<&GitHubAsset> = release
+            .assets
+            .iter()
+            .filter(|asset| {
+                pending
+                    .iter()
+                    .any(|(_, _, pending)| pending.api_url == asset.api_url)
+            })
+            .collect();
+        policy.check_sizes(&dependency.pathspec, &release, &assets)?;
     }
-}
-/// Add a dependency.
-pub fn add(
-    sink_toml: SinkTOML,
-    dependency: GitHubDependency,
-    short_form: bool,
-) -> Result<SinkTOML> {
-    match _add(sink_toml, dependency, short_form) {
-        Ok(sink_toml) => Ok(sink_toml),
-        Err(e) => Err(e.context("Failed to add dependency!")),
+
+    downloaded.link = dependency.link.clone();
+    if pending.is_empty() {
+        info!(
+            "{}@{} is installed already!",
+            dependency.pathspec, locked.version
+        );
+        _create_link(dependency, root, &downloaded)?;
+        return Ok(downloaded);
     }
-}
 
-fn _download(dependency: &GitHubDependency) -> Result<()> {
     info!(
         "Downloading {}@{} into '{}' ...",
         dependency.pathspec,
-        dependency.version,
-        dependency.destination.display()
+        locked.version,
+        install_dir.display()
     );
+    hooks::run(dependency, Stage::PreInstall, &dir, &locked.version)?;
 
-    // Use the GH CLI to download the asset
-    let output = match Command::new("gh")
-        .arg("release")
-        .arg("download")
-        .arg("--repo")
-        .arg(dependency.pathspec.get_full_origin())
-        .arg("--pattern")
-        .arg(dependency.pathspec.pattern.clone())
-        .arg("--dir")
-        .arg(dependency.destination.clone())
-        .output()
-    {
-        Ok(output) => output,
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to invoke GitHub CLI: {e}. Is it installed?"
-            ))
-        }
-    };
-
-    let stdout = String::from_utf8(output.stdout)?;
-    let stdout = stdout.trim();
-    let stderr = String::from_utf8(output.stderr)?;
-    let stderr = stderr.trim();
+    // Nothing is placed until all assets were downloaded and match the lockfile
+    let mut contents = Vec::new();
+    for (index, full_path, asset) in pending {
+        interrupt::check()?;
+        let _span = debug_span!("download", asset = %asset.name).entered();
+        let asset_contents = api::api().download_asset(&origin, &locked.version, &asset)?;
+        debug!(bytes = asset_contents.len(), "Downloaded '{}'!", asset.name);
+        // Keep the algorithm of the lockfile, so the digests can be compared
+        let file = &mut downloaded.files[index];
+        file.checksum = lock::digest_bytes_like(&asset_contents, &file.checksum)?;
+        contents.push((full_path, asset_contents));
+    }
+    if !locked.same_files(&downloaded) {
+        return Ok(downloaded);
+    }
 
-    debug!("Status: {}", output.status);
-    debug!("Stdout: {stdout}");
-    debug!("Stderr: {stderr}");
-    if !output.status.success() {
-        return Err(anyhow::anyhow!("GitHub CLI invocation failed: '{stderr}'"));
+    std::fs::create_dir_all(&dir)?;
+    for (path, asset_contents) in contents {
+        atomic::write(&path, asset_contents)?;
     }
+    _create_link(dependency, root, &downloaded)?;
+    hooks::run(dependency, Stage::PostInstall, &dir, &locked.version)?;
 
     info!(
         "Downloaded {}@{} into '{}'!",
         dependency.pathspec,
-        dependency.version,
-        dependency.destination.display()
+        locked.version,
+        install_dir.display()
     );
 
-    Ok(())
+    Ok(downloaded)
 }
-/// Download the given dependency.
-pub fn download(dependency: &GitHubDependency) -> Result<()> {
-    match _download(dependency) {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e.context("Failed to download dependency!")),
+/// Download exactly the assets recorded in the lockfile, without resolving the dependency again.
+///
+/// Returns the state of the downloaded files, which should be compared against `locked`.
+/// Local files matching their digests in the lockfile are kept, hooks only run if anything else is downloaded.
+/// Files are only placed, and hooks only run, if all downloads match their digests in the lockfile.
+/// The digests of the lockfile take the place of published checksums, see [`Policy::require_checksum`].
+pub fn download_locked(
+    dependency: &GitHubDependency,
+    locked: &LockedDependency,
+    root: &Path,
+    policy: &Policy,
+) -> Result<LockedDependency> {
+    match _download_locked(dependency, locked, root, policy) {
+        Ok(downloaded) => Ok(downloaded),
+        Err(e) => Err(e.context("Failed to download locked dependency!")),
     }
 }
 
@@ -302,7 +1793,7 @@ mod tests {
             assert!(dependency.is_err());
 
             let dependency = GitHubDependency::new(
-                String::from("repo/pattern"),
+                String::from("owner/repo:"),
                 Some(String::from("destination")),
                 Some(GitHubVersion::Tag(String::from("v1.0.0"))),
                 false,
@@ -322,6 +1813,62 @@ mod tests {
             assert!(dependency.is_err());
         }
 
+        #[test]
+        fn test_new_url() {
+            let new = |url: &str, version: Option<GitHubVersion>| {
+                GitHubDependency::new(String::from(url), None, version, true, &None).map(
+                    |dependency| {
+                        (
+                            dependency.pathspec.to_string(),
+                            dependency.version.to_string(),
+                        )
+                    },
+                )
+            };
+
+            assert_eq!(
+                new("https://github.com/owner/repo/releases/tag/v1.2.3", None).unwrap(),
+                (String::from("owner/repo"), String::from("v1.2.3"))
+            );
+            assert_eq!(
+                new(
+                    "https://github.com/owner/repo/releases/download/v1.2.3/tool.tar.gz",
+                    None
+                )
+                .unwrap(),
+                (
+                    String::from("owner/repo:tool.tar.gz"),
+                    String::from("v1.2.3")
+                )
+            );
+            assert_eq!(
+                new(
+                    "https://github.com/owner/repo/releases/latest/download/tool.tar.gz",
+                    None
+                )
+                .unwrap(),
+                (
+                    String::from("owner/repo:tool.tar.gz"),
+                    String::from("latest")
+                )
+            );
+            assert_eq!(
+                new("github.com/owner/repo.git", None).unwrap(),
+                (String::from("owner/repo"), String::from("latest"))
+            );
+            assert_eq!(
+                new(
+                    "https://github.com/owner/repo/releases/tag/v1.2.3",
+                    Some(GitHubVersion::Tag(String::from("v2.0.0")))
+                )
+                .unwrap(),
+                (String::from("owner/repo"), String::from("v2.0.0"))
+            );
+
+            assert!(new("https://github.com/owner/repo/issues/1", None).is_err());
+            assert!(new("https://github.com/owner", None).is_err());
+        }
+
         #[test]
         fn test_new_default() {
             let dependency = GitHubDependency::new(
@@ -340,6 +1887,455 @@ mod tests {
         }
     }
 
+    mod test_update_policy {
+        use super::*;
+
+        #[test]
+        fn test_allows() {
+            assert!(!UpdatePolicy::Pinned.allows("v1.0.0", "v1.0.1"));
+
+            assert!(UpdatePolicy::Patch.allows("v1.0.0", "v1.0.1"));
+            assert!(!UpdatePolicy::Patch.allows("v1.0.0", "v1.1.0"));
+
+            assert!(UpdatePolicy::Minor.allows("v1.0.0", "v1.1.0"));
+            assert!(UpdatePolicy::Minor.allows("1.0", "v1.2.3-rc.1"));
+            assert!(!UpdatePolicy::Minor.allows("v1.0.0", "v2.0.0"));
+            assert!(!UpdatePolicy::Minor.allows("nightly", "v1.0.0"));
+
+            assert!(UpdatePolicy::Latest.allows("v1.0.0", "v2.0.0"));
+            assert!(UpdatePolicy::Latest.allows("nightly", "v1.0.0"));
+        }
+    }
+
+    mod test_api {
+        use super::*;
+        use crate::test_utils::{use_api, use_cache, TempProject};
+        use std::sync::Arc;
+
+        /// Serves the releases of a single repository from memory.
+        struct StubApi {
+            releases: Vec<GitHubRelease>,
+        }
+        impl GitHubApi for StubApi {
+            fn list_releases(&self, _origin: &str, limit: usize) -> Result<Vec<GitHubRelease>> {
+                Ok(self.releases.iter().take(limit).cloned().collect())
+            }
+
+            fn view_release(&self, _origin: &str, tag: Option<&str>) -> Result<GitHubRelease> {
+                self.releases
+                    .iter()
+                    .find(|release| tag.is_none_or(|tag| release.tag_name == tag))
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("release not found"))
+            }
+
+            fn download_asset(
+                &self,
+                _origin: &str,
+                tag: &str,
+                asset: &GitHubAsset,
+            ) -> Result<Vec<u8>> {
+                Ok(format!("{tag}/{}", asset.name).into_bytes())
+            }
+
+            fn list_advisories(&self, _origin: &str) -> Result<Vec<GitHubAdvisory>> {
+                Ok(Vec::new())
+            }
+
+            fn view_license(&self, _origin: &str) -> Result<Option<String>> {
+                Ok(None)
+            }
+
+            fn view_file(&self, _origin: &str, path: &str, reference: &str) -> Result<Vec<u8>> {
+                Ok(format!("{reference}/{path}").into_bytes())
+            }
+
+            fn view_status(&self) -> Result<GitHubStatus> {
+                Ok(GitHubStatus::default())
+            }
+        }
+
+        fn release(tag_name: &str, assets: &[&str]) -> GitHubRelease {
+            GitHubRelease {
+                tag_name: String::from(tag_name),
+                assets: assets
+                    .iter()
+                    .map(|name| GitHubAsset {
+                        name: String::from(*name),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }
+        }
+
+        fn dependency(pathspec: &str, version: &str) -> GitHubDependency {
+            GitHubDependency::new(
+                String::from(pathspec),
+                Some(String::from("vendor")),
+                Some(GitHubVersion::from(version)),
+                true,
+                &None,
+            )
+            .unwrap()
+        }
+
+        #[test]
+        fn test_download() {
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![
+                    release("v2.0.0", &["tool.tar.gz", "tool.zip"]),
+                    release("v1.0.0", &["tool.tar.gz"]),
+                ],
+            }));
+
+            let root = std::env::temp_dir().join(format!("sink-test-api-{}", std::process::id()));
+            let locked = download(
+                &dependency("owner/repo:*.tar.gz", "v1.0.0"),
+                &root,
+                &Policy::default(),
+                None,
+            );
+            let contents = std::fs::read_to_string(root.join("vendor/tool.tar.gz"));
+            let all = download(
+                &dependency("owner/repo", "latest"),
+                &root,
+                &Policy::default(),
+                None,
+            );
+            drop(guard);
+
+            let locked = locked.unwrap();
+            assert_eq!(locked.version, "v1.0.0");
+            assert_eq!(locked.files.len(), 1);
+            assert_eq!(contents.unwrap(), "v1.0.0/tool.tar.gz");
+
+            let all = all.unwrap();
+            assert_eq!(all.version, "v2.0.0");
+            assert_eq!(all.files.len(), 2);
+
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[test]
+        fn test_download_escaping_assets() {
+            let root =
+                std::env::temp_dir().join(format!("sink-test-escape-{}", std::process::id()));
+            let outside = root.join("outside");
+            std::fs::create_dir_all(root.join("vendor")).unwrap();
+            std::fs::write(&outside, "untouched").unwrap();
+
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![
+                    release("v2.0.0", &["tool.tar.gz", "../outside"]),
+                    release("v1.0.0", &["tool.tar.gz"]),
+                ],
+            }));
+            let escaping = download(
+                &dependency("owner/repo", "v2.0.0"),
+                &root,
+                &Policy::default(),
+                None,
+            );
+            let written = root.join("vendor/tool.tar.gz").exists();
+            let tampered = download_locked(
+                &dependency("owner/repo:tool.tar.gz", "v1.0.0"),
+                &LockedDependency {
+                    version: String::from("v1.0.0"),
+                    files: vec![LockedFile {
+                        path: PathBuf::from("vendor/../outside"),
+                        api_url: Some(String::from("http://localhost/asset")),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                },
+                &root,
+                &Policy::default(),
+            );
+            #[cfg(unix)]
+            let symlinked = {
+                std::os::unix::fs::symlink(&outside, root.join("vendor/tool.tar.gz")).unwrap();
+                download(
+                    &dependency("owner/repo:tool.tar.gz", "v1.0.0"),
+                    &root,
+                    &Policy::default(),
+                    None,
+                )
+            };
+            drop(guard);
+
+            assert!(escaping.is_err());
+            // Nothing is written if a single asset would escape
+            assert!(!written);
+            assert!(tampered.is_err());
+            #[cfg(unix)]
+            assert!(symlinked.is_err());
+            assert_eq!(std::fs::read_to_string(&outside).unwrap(), "untouched");
+
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[test]
+        #[cfg(unix)]
+        fn test_download_link() {
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![
+                    release("v2.0.0", &["tool.tar.gz", "tool.zip"]),
+                    release("v1.0.0", &["tool.tar.gz"]),
+                ],
+            }));
+
+            let root = std::env::temp_dir().join(format!("sink-test-link-{}", std::process::id()));
+            let mut linked = dependency("owner/repo:tool.tar.gz", "v1.0.0");
+            linked.link = Some(PathBuf::from("bin/tool"));
+            let first = download(&linked, &root, &Policy::default(), None);
+            let first_contents = std::fs::read_to_string(root.join("bin/tool"));
+            linked.version = GitHubVersion::Tag(String::from("v2.0.0"));
+            let second = download(&linked, &root, &Policy::default(), None);
+            let second_contents = std::fs::read_to_string(root.join("bin/tool"));
+
+            let mut ambiguous = dependency("owner/repo", "v2.0.0");
+            ambiguous.link = Some(PathBuf::from("bin/ambiguous"));
+            let ambiguous = download(&ambiguous, &root, &Policy::default(), None);
+            drop(guard);
+
+            assert_eq!(first.unwrap().link, Some(PathBuf::from("bin/tool")));
+            assert_eq!(first_contents.unwrap(), "v1.0.0/tool.tar.gz");
+            assert!(second.is_ok());
+            assert_eq!(second_contents.unwrap(), "v2.0.0/tool.tar.gz");
+            assert_eq!(
+                std::fs::read_link(root.join("bin/tool")).unwrap(),
+                PathBuf::from("../vendor/tool.tar.gz")
+            );
+            assert!(ambiguous.is_err());
+
+            std::fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[test]
+        fn test_newest_release() {
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![
+                    release("v2.0.0", &["other.zip"]),
+                    release("v1.2.0", &["tool.tar.gz"]),
+                    release("v1.1.0", &["tool.tar.gz"]),
+                    release("v1.0.0", &["tool.tar.gz"]),
+                ],
+            }));
+
+            let newest = newest_release(&dependency("owner/repo:tool.tar.gz", "v1.0.0"));
+            let up_to_date = newest_release(&dependency("owner/repo:tool.tar.gz", "v1.2.0"));
+            drop(guard);
+
+            assert_eq!(newest.unwrap().unwrap().tag_name, "v1.2.0");
+            assert!(up_to_date.unwrap().is_none());
+        }
+
+        #[test]
+        fn test_draft_release() {
+            let mut draft = release("v2.0.0", &["tool.tar.gz"]);
+            draft.is_draft = true;
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![draft, release("v1.0.0", &["tool.tar.gz"])],
+            }));
+
+            let version = GitHubVersion::from("draft:v2.0.0");
+            assert!(matches!(&version, GitHubVersion::Draft(tag) if tag == "v2.0.0"));
+            assert_eq!(serde_json::to_string(&version).unwrap(), "\"draft:v2.0.0\"");
+
+            let refused = resolve(&dependency("owner/repo:tool.tar.gz", "draft:v2.0.0"));
+            set_allow_draft(true);
+            let resolved = resolve(&dependency("owner/repo:tool.tar.gz", "draft:v2.0.0"));
+            let published = resolve(&dependency("owner/repo:tool.tar.gz", "draft:v1.0.0"));
+            set_allow_draft(false);
+            let newest = newest_release(&dependency("owner/repo:tool.tar.gz", "v1.0.0"));
+            drop(guard);
+
+            assert!(refused.is_err());
+            assert_eq!(resolved.unwrap().tag_name, "v2.0.0");
+            assert!(published.is_err());
+            // Drafts are never offered as upgrades
+            assert!(newest.unwrap().is_none());
+        }
+
+        #[test]
+        fn test_blocked_versions() {
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![
+                    release("v1.5.0", &["tool.tar.gz"]),
+                    release("v1.4.0", &["tool.tar.gz"]),
+                    release("v1.3.0", &["tool.tar.gz"]),
+                ],
+            }));
+
+            let blocked = |version: &str| {
+                let mut dependency = dependency("owner/repo:tool.tar.gz", version);
+                dependency.blocked_versions = vec![String::from("v1.5.0"), String::from("1.4.0")];
+                dependency
+            };
+            let latest = resolve(&blocked("latest"));
+            let pinned = resolve(&blocked("v1.4.0"));
+            let newest = newest_release(&blocked("v1.3.0"));
+            drop(guard);
+
+            assert!(blocked("latest").is_blocked("1.5.0"));
+            assert!(!blocked("latest").is_blocked("v1.3.0"));
+            assert_eq!(latest.unwrap().tag_name, "v1.3.0");
+            assert!(pinned.is_err());
+            assert!(newest.unwrap().is_none());
+        }
+
+        #[test]
+        fn test_as_of_release() {
+            let published = |tag_name: &str, published_at: &str| GitHubRelease {
+                published_at: String::from(published_at),
+                ..release(tag_name, &["tool.tar.gz"])
+            };
+            let mut prerelease = published("v2.1.0-rc.1", "2024-06-20T08:00:00Z");
+            prerelease.is_prerelease = true;
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![
+                    prerelease,
+                    published("v2.0.0", "2024-06-01T23:59:59Z"),
+                    published("v1.0.0", "2023-01-15T10:00:00Z"),
+                ],
+            }));
+
+            let as_of = |date: &str| {
+                resolve(&dependency(
+                    "owner/repo:tool.tar.gz",
+                    &format!("asof:{date}"),
+                ))
+                .map(|release| release.tag_name)
+            };
+            let same_day = as_of("2024-06-01");
+            let day_before = as_of("2024-05-31");
+            let today = as_of("2024-07-01");
+            let too_early = as_of("2022-12-31");
+            let invalid = as_of("June 2024");
+            let out_of_range = as_of("2024-13-45");
+            drop(guard);
+
+            assert_eq!(
+                GitHubVersion::from("asof:2024-06-01").to_string(),
+                "asof:2024-06-01"
+            );
+            assert_eq!(same_day.unwrap(), "v2.0.0");
+            assert_eq!(day_before.unwrap(), "v1.0.0");
+            assert_eq!(today.unwrap(), "v2.0.0");
+            assert!(too_early.is_err());
+            assert!(invalid.is_err());
+            assert!(out_of_range.is_err());
+            assert!(_is_date("2024-02-29"));
+            assert!(!_is_date("2023-02-29"));
+            assert!(!_is_date("2024-04-31"));
+            assert!(!_is_date("2024-00-01"));
+        }
+
+        #[test]
+        fn test_metadata_cache() {
+            let origin = String::from("metadata-cache/repo");
+            let project = TempProject::new("metadata-cache").unwrap();
+            let _cache = use_cache(&project.root);
+            assert!(cache::path_for(&_metadata_key(&origin))
+                .unwrap()
+                .starts_with(&project.root));
+            let latest = || {
+                resolve(&dependency("metadata-cache/repo:tool.tar.gz", "latest"))
+                    .unwrap()
+                    .tag_name
+            };
+
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![release("v1.0.0", &["tool.tar.gz"])],
+            }));
+            set_metadata_ttl(Some(Duration::from_secs(60)));
+            assert_eq!(latest(), "v1.0.0");
+            set_metadata_ttl(None);
+            drop(guard);
+
+            // The cached release is used until the cache is bypassed
+            let guard = use_api(Arc::new(StubApi {
+                releases: vec![release("v2.0.0", &["tool.tar.gz"])],
+            }));
+            set_metadata_ttl(Some(Duration::from_secs(60)));
+            let cached_latest = latest();
+            let cached_tags = latest_tags(std::slice::from_ref(&origin)).unwrap();
+            set_metadata_ttl(None);
+            let bypassed = latest();
+            drop(guard);
+
+            assert_eq!(cached_latest, "v1.0.0");
+            assert_eq!(cached_tags[&origin], "v1.0.0");
+            assert_eq!(bypassed, "v2.0.0");
+        }
+
+        #[test]
+        fn test_search_releases() {
+            // Only an old release provides the asset, so the first pages hold no match
+            let mut releases: Vec<GitHubRelease> = (0..RELEASES_MAX + 10)
+                .rev()
+                .map(|i| release(&format!("v0.{i}.0"), &["other.zip"]))
+                .collect();
+            releases[400] = release("v0.609.0", &["tool.tar.gz"]);
+            releases[150].is_prerelease = true;
+            let guard = use_api(Arc::new(StubApi { releases }));
+
+            let old = newest_release(&dependency("owner/repo:tool.tar.gz", "v0.500.0"));
+            let prerelease = resolve(&dependency("owner/repo:tool.tar.gz", "prerelease"));
+            let mut visited = 0;
+            let exhausted = search_releases(&String::from("owner/repo"), |_| {
+                visited += 1;
+                Ok(ControlFlow::<()>::Continue(()))
+            });
+            drop(guard);
+
+            assert_eq!(old.unwrap().unwrap().tag_name, "v0.609.0");
+            assert_eq!(prerelease.unwrap().tag_name, "v0.859.0");
+            assert_eq!(exhausted.unwrap(), None);
+            assert_eq!(visited, RELEASES_MAX);
+        }
+    }
+
+    #[test]
+    fn test_link_target() {
+        let root = Path::new("/project");
+        let target =
+            |link: &str, file: &str| _link_target(root, Path::new(link), Path::new(file)).unwrap();
+
+        assert_eq!(target("bin/rg", "vendor/rg"), PathBuf::from("../vendor/rg"));
+        assert_eq!(target("./rg", "vendor/rg"), PathBuf::from("vendor/rg"));
+        assert_eq!(target("tools/bin/rg", "tools/rg"), PathBuf::from("../rg"));
+        assert_eq!(
+            target("/usr/local/bin/rg", "vendor/rg"),
+            PathBuf::from("/project/vendor/rg")
+        );
+        assert_eq!(
+            target("bin/rg", "../shared/rg"),
+            PathBuf::from("/project/../shared/rg")
+        );
+    }
+
+    #[test]
+    fn test_asset_path() {
+        let dir = Path::new("vendor");
+        assert_eq!(
+            _asset_path(dir, "tool.tar.gz").unwrap(),
+            PathBuf::from("vendor/tool.tar.gz")
+        );
+        for name in [
+            "../tool",
+            "/usr/bin/tool",
+            "sub/tool",
+            "..\\tool",
+            "..",
+            ".",
+            "",
+        ] {
+            assert!(_asset_path(dir, name).is_err(), "'{name}' was accepted");
+        }
+    }
+
     mod test_pathspec {
         use super::*;
 
@@ -363,12 +2359,26 @@ mod tests {
 
         #[test]
         fn test_from_string_invalid() {
-            assert!(GitHubPathspec::try_from(String::from("owner/repo")).is_err());
             assert!(GitHubPathspec::try_from(String::from("repo:pattern")).is_err());
             assert!(GitHubPathspec::try_from(String::from("/:")).is_err());
             assert!(GitHubPathspec::try_from(String::from("owner/:pattern")).is_err());
         }
 
+        #[test]
+        fn test_without_pattern() {
+            let path_spec = GitHubPathspec::try_from(String::from("owner/repo")).unwrap();
+
+            assert_eq!(path_spec.owner(), "owner");
+            assert_eq!(path_spec.repository(), "repo");
+            assert_eq!(path_spec.pattern(), "");
+            assert_eq!(path_spec.asset_pattern(), "*");
+            assert_eq!(path_spec.to_string(), "owner/repo");
+            assert_eq!(GitHubPathspec::new("owner", "repo", "").unwrap(), path_spec);
+
+            assert!(GitHubPathspec::try_from(String::from("owner/repo:")).is_err());
+            assert!(GitHubPathspec::try_from(String::from("owner/")).is_err());
+        }
+
         #[test]
         fn test_into_string() {
             let path_spec = GitHubPathspec {
@@ -379,5 +2389,23 @@ mod tests {
 
             assert_eq!(path_spec.to_string(), "owner/repo:pattern");
         }
+
+        #[test]
+        fn test_new() {
+            let path_spec = GitHubPathspec::new("owner", "repo", "*.tar.gz").unwrap();
+
+            assert_eq!(path_spec.owner(), "owner");
+            assert_eq!(path_spec.repository(), "repo");
+            assert_eq!(path_spec.pattern(), "*.tar.gz");
+            assert_eq!(
+                path_spec,
+                GitHubPathspec::try_from(String::from("owner/repo:*.tar.gz")).unwrap()
+            );
+
+            assert!(GitHubPathspec::new("", "repo", "pattern").is_err());
+            assert!(GitHubPathspec::new("owner", "", "pattern").is_err());
+            assert!(GitHubPathspec::new("owner", "re:po", "pattern").is_err());
+            assert!(GitHubPathspec::new("own/er", "repo", "pattern").is_err());
+        }
     }
 }
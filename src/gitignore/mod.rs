@@ -0,0 +1,147 @@
+use anyhow::Result;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+use crate::atomic;
+use crate::lock::SinkLock;
+use crate::SinkTOML;
+
+/// Marks the beginning of the section of the gitignore managed by sink.
+const BEGIN_MARKER: &str = "# >>> sink >>>";
+
+/// Marks the end of the section of the gitignore managed by sink.
+const END_MARKER: &str = "# <<< sink <<<";
+
+/// Returns the path of the gitignore belonging to the given sink TOML.
+pub fn path_for(sink_toml: &SinkTOML) -> PathBuf {
+    sink_toml.root().join(".gitignore")
+}
+
+/// Converts a path relative to the sink TOML into an anchored gitignore entry.
+fn to_entry(path: &Path) -> String {
+    let parts: Vec<String> = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    format!("/{}", parts.join("/"))
+}
+
+/// Returns the gitignore entries for all locked files of dependencies that want to be ignored.
+pub fn entries(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Vec<String> {
+    let mut entries = Vec::new();
+
+    for (pathspec, locked) in sink_lock.dependencies.iter() {
+        let gitignore = match sink_toml.dependencies.get(pathspec) {
+            Some(dependency) => dependency
+                .to_github_dependency(pathspec)
+                .map(|dependency| dependency.gitignore)
+                .unwrap_or(false),
+            None => false,
+        };
+        if !gitignore {
+            continue;
+        }
+
+        entries.extend(locked.files.iter().map(|file| to_entry(&file.path)));
+        entries.extend(locked.link.iter().map(|link| to_entry(link)));
+    }
+    entries.sort();
+    entries.dedup();
+
+    entries
+}
+
+/// Replaces the section managed by sink in the given gitignore contents.
+///
+/// Everything outside of the markers is left untouched.
+/// The section is removed entirely if there are no entries.
+fn render(contents: &str, entries: &[String]) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    let mut in_section = false;
+    for line in contents.lines() {
+        match line.trim() {
+            BEGIN_MARKER => in_section = true,
+            END_MARKER => in_section = false,
+            _ if !in_section => lines.push(line),
+            _ => {}
+        }
+    }
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let mut rendered = lines.join("\n");
+    if !entries.is_empty() {
+        if !rendered.is_empty() {
+            rendered.push_str("\n\n");
+        }
+        rendered.push_str(BEGIN_MARKER);
+        for entry in entries {
+            rendered.push('\n');
+            rendered.push_str(entry);
+        }
+        rendered.push('\n');
+        rendered.push_str(END_MARKER);
+    }
+    if !rendered.is_empty() {
+        rendered.push('\n');
+    }
+
+    rendered
+}
+
+fn _sync(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Result<()> {
+    let path = path_for(sink_toml);
+    debug!("Syncing '{}'...", path.display());
+
+    let contents = if path.exists() {
+        fs::read_to_string(&path)?
+    } else {
+        String::new()
+    };
+    let rendered = render(&contents, &entries(sink_toml, sink_lock));
+
+    if rendered != contents {
+        atomic::write(&path, rendered)?;
+    }
+
+    debug!("Syncing done!");
+
+    Ok(())
+}
+/// Sync the section managed by sink in the gitignore next to the sink TOML with the lockfile.
+pub fn sync(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Result<()> {
+    match _sync(sink_toml, sink_lock) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.context("Failed to update gitignore!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_entry() {
+        assert_eq!(to_entry(Path::new("./file.txt")), "/file.txt");
+        assert_eq!(
+            to_entry(Path::new("imported/file.txt")),
+            "/imported/file.txt"
+        );
+    }
+
+    #[test]
+    fn test_render_preserves_user_entries() {
+        let contents = "target/\n\n# >>> sink >>>\n/old\n# <<< sink <<<\n*.log\n";
+
+        assert_eq!(
+            render(contents, &[String::from("/new")]),
+            "target/\n\n*.log\n\n# >>> sink >>>\n/new\n# <<< sink <<<\n"
+        );
+        assert_eq!(render(contents, &[]), "target/\n\n*.log\n");
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::atomic;
+
+/// The contents of a new global sink TOML.
+///
+/// Global dependencies are installed to the shared [`data_dir`], so external destinations are allowed.
+const MANIFEST_TEMPLATE: &str = "\
+# User-wide dependencies, managed with 'sink --global'
+allow-external-destinations = true
+link-executables = true
+
+[dependencies]
+";
+
+/// Returns the home directory of the user.
+fn _home() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Returns the user-level config directory of sink, holding the global sink TOML.
+///
+/// This is `$XDG_CONFIG_HOME/sink`, falling back to `~/.config/sink`.
+pub fn config_dir() -> Option<PathBuf> {
+    if let Some(config_home) = env::var_os("XDG_CONFIG_HOME").filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(config_home).join("sink"));
+    }
+
+    _home().map(|home| home.join(".config").join("sink"))
+}
+
+/// Returns the shared user directory global dependencies are installed to by default.
+///
+/// This is `$XDG_DATA_HOME/sink`, falling back to `~/.local/share/sink`.
+pub fn data_dir() -> Option<PathBuf> {
+    if let Some(data_home) = env::var_os("XDG_DATA_HOME").filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(data_home).join("sink"));
+    }
+
+    _home().map(|home| home.join(".local").join("share").join("sink"))
+}
+
+fn _manifest() -> Result<PathBuf> {
+    let dir =
+        config_dir().ok_or_else(|| anyhow::anyhow!("Neither HOME nor XDG_CONFIG_HOME are set!"))?;
+    let path = dir.join("sink.toml");
+    if !path.exists() {
+        fs::create_dir_all(&dir)?;
+        atomic::write(&path, MANIFEST_TEMPLATE)?;
+        info!("Created the global sink TOML '{}'!", path.display());
+    }
+
+    Ok(path)
+}
+/// Returns the path of the global sink TOML in the [`config_dir`], creating it if it does not exist yet.
+pub fn manifest() -> Result<PathBuf> {
+    match _manifest() {
+        Ok(path) => Ok(path),
+        Err(e) => Err(e.context("Failed to locate the global sink TOML!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use crate::SinkTOML;
+
+    #[test]
+    fn test_manifest() {
+        let project = TempProject::new("global").unwrap();
+        let config_home = project.root.join("config");
+        // Other tests do not read XDG_CONFIG_HOME
+        env::set_var("XDG_CONFIG_HOME", &config_home);
+
+        let path = manifest().unwrap();
+        assert_eq!(path, config_home.join("sink").join("sink.toml"));
+        let sink_toml = SinkTOML::from_file(&path).unwrap();
+        assert!(sink_toml.allow_external_destinations);
+        assert!(sink_toml.link_executables);
+        assert!(sink_toml.dependencies.is_empty());
+
+        // An existing manifest is left untouched
+        fs::write(&path, "[dependencies]\n").unwrap();
+        assert_eq!(manifest().unwrap(), path);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[dependencies]\n");
+
+        env::remove_var("XDG_CONFIG_HOME");
+    }
+}
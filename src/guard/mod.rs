@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+use crate::cache;
+use crate::errors::{CodedError, ErrorCode};
+
+/// Holds the advisory lock of a sink TOML until it is dropped.
+///
+/// Only one sink process at a time may modify a sink TOML, its lockfile and the destinations.
+#[derive(Debug)]
+pub struct ProcessGuard {
+    /// Contains the locked file, the lock is released when it is closed
+    _file: File,
+
+    /// Contains the path of the locked file
+    pub path: PathBuf,
+}
+
+/// Returns the path of the file locked for the given sink TOML.
+///
+/// It lives in the cache directory, so it neither clutters the repository nor has to be deleted.
+/// If the cache directory is unknown, it is placed next to the sink TOML instead.
+fn path_for(sink_toml_path: &Path) -> PathBuf {
+    let absolute = fs::canonicalize(sink_toml_path).unwrap_or_else(|_| sink_toml_path.into());
+    let file_name = cache::file_name(&absolute.to_string_lossy());
+
+    match cache::dir() {
+        Some(dir) => dir.join("locks").join(format!("{file_name}.lock")),
+        None => absolute.with_file_name(".sink.process.lock"),
+    }
+}
+
+fn _acquire(sink_toml_path: &Path) -> Result<ProcessGuard> {
+    let path = path_for(sink_toml_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&path)?;
+    match file.try_lock() {
+        Ok(_) => {}
+        Err(TryLockError::WouldBlock) => {
+            return Err(CodedError(
+                ErrorCode::Locked,
+                format!(
+                    "Another sink process is running on '{}'! Please wait for it to finish.",
+                    sink_toml_path.display()
+                ),
+            )
+            .into())
+        }
+        Err(TryLockError::Error(e)) => return Err(e.into()),
+    }
+
+    debug!("Acquired '{}'!", path.display());
+
+    Ok(ProcessGuard { _file: file, path })
+}
+/// Acquire the advisory lock of the given sink TOML.
+///
+/// Fails immediately if another sink process holds it.
+pub fn acquire(sink_toml_path: &Path) -> Result<ProcessGuard> {
+    match _acquire(sink_toml_path) {
+        Ok(guard) => Ok(guard),
+        Err(e) => Err(e.context("Failed to lock the sink TOML!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_acquire() {
+        let path =
+            std::env::temp_dir().join(format!("sink-test-guard-{}.toml", std::process::id()));
+        fs::write(&path, "").unwrap();
+
+        let guard = acquire(&path).unwrap();
+        let second = acquire(&path);
+        assert!(second.is_err_and(|e| e
+            .chain()
+            .any(|cause| cause.to_string().contains("Another sink process"))));
+
+        drop(guard);
+        assert!(acquire(&path).is_ok());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+
+use crate::github::{GitHubDependency, GitHubPathspec};
+use crate::SinkTOML;
+
+/// Whether hooks of dependencies are skipped.
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Skip (or run) the hooks of all dependencies, e.g. for untrusted sink TOMLs.
+pub fn set_disabled(disabled: bool) {
+    DISABLED.store(disabled, Ordering::Relaxed);
+}
+
+/// When the commands of a hook are run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// Before the assets are downloaded, see [`GitHubDependency::pre_install`].
+    PreInstall,
+    /// After the assets are downloaded and linked, see [`GitHubDependency::post_install`].
+    PostInstall,
+}
+impl Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::PreInstall => write!(f, "pre-install"),
+            Stage::PostInstall => write!(f, "post-install"),
+        }
+    }
+}
+
+/// The `[hooks]` table of a sink TOML, running commands once for the whole project.
+///
+/// Hooks of included sink TOMLs are ignored, as are the hooks of their dependencies.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(
+    rename_all(deserialize = "kebab-case", serialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct Hooks {
+    /// Commands to run after all dependencies were installed successfully, e.g. to regenerate a checksum manifest.
+    #[serde(default)]
+    pub post_install: Vec<String>,
+}
+
+/// Returns the shell command running the given command line.
+fn _shell(command: &str) -> Command {
+    let (program, flag) = match cfg!(windows) {
+        true => ("cmd", "/C"),
+        false => ("sh", "-c"),
+    };
+    let mut shell = Command::new(program);
+    shell.args([flag, command]);
+    shell
+}
+
+fn _run(dependency: &GitHubDependency, stage: Stage, dir: &Path, version: &str) -> Result<()> {
+    let commands = match stage {
+        Stage::PreInstall => &dependency.pre_install,
+        Stage::PostInstall => &dependency.post_install,
+    };
+    if commands.is_empty() {
+        return Ok(());
+    }
+    if DISABLED.load(Ordering::Relaxed) {
+        warn!(
+            "Skipping the {stage} hook of '{}', hooks are disabled!",
+            dependency.pathspec
+        );
+        return Ok(());
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let dir = std::path::absolute(dir)?;
+    let destination = dir.to_string_lossy();
+    _run_commands(
+        commands,
+        &dir,
+        &[
+            ("SINK_HOOK", &stage.to_string()),
+            ("SINK_DEPENDENCY", &dependency.pathspec.to_string()),
+            ("SINK_VERSION", version),
+            ("SINK_DESTINATION", &destination),
+        ],
+    )
+}
+/// Run the commands of the given hook of the dependency in the directory it is installed into.
+///
+/// Every command is run by the shell with `SINK_HOOK`, `SINK_DEPENDENCY`, `SINK_VERSION` and `SINK_DESTINATION` set.
+/// Fails on the first command that fails. Does nothing while hooks are disabled, see [`set_disabled`].
+pub fn run(dependency: &GitHubDependency, stage: Stage, dir: &Path, version: &str) -> Result<()> {
+    match _run(dependency, stage, dir, version) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(e.context(format!(
+            "Failed to run the {stage} hook of '{}'!",
+            dependency.pathspec
+        ))),
+    }
+}
+
+/// Run the commands one after another in the given directory, failing on the first one that fails.
+fn _run_commands(commands: &[String], dir: &Path, envs: &[(&str, &str)]) -> Result<()> {
+    for command in commands {
+        info!("Running hook '{command}'...");
+        let status = _shell(command)
+            .current_dir(dir)
+            .envs(envs.iter().copied())
+            .status()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!("'{command}' failed with {status}!"));
+        }
+    }
+
+    Ok(())
+}
+
+fn _run_project(sink_toml: &SinkTOML, installed: &[&GitHubPathspec]) -> Result<()> {
+    let commands = &sink_toml.hooks.post_install;
+    if commands.is_empty() {
+        return Ok(());
+    }
+    if DISABLED.load(Ordering::Relaxed) {
+        warn!("Skipping the post-install hook of the project, hooks are disabled!");
+        return Ok(());
+    }
+
+    let root = std::path::absolute(sink_toml.root())?;
+    let path = std::path::absolute(&sink_toml.path)?;
+    let installed: Vec<String> = installed.iter().map(|p| p.to_string()).collect();
+    _run_commands(
+        commands,
+        &root,
+        &[
+            ("SINK_HOOK", "post-install"),
+            ("SINK_TOML", &path.to_string_lossy()),
+            ("SINK_INSTALLED", &installed.join("\n")),
+        ],
+    )
+}
+/// Run the post-install hook of the sink TOML in its directory, see [`Hooks`].
+///
+/// `SINK_TOML` is set to the path of the sink TOML and `SINK_INSTALLED` to the installed pathspecs, one per line.
+/// Does nothing while hooks are disabled, see [`set_disabled`].
+pub fn run_project(sink_toml: &SinkTOML, installed: &[&GitHubPathspec]) -> Result<()> {
+    match _run_project(sink_toml, installed) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(e.context("Failed to run the post-install hook of the project!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::GitHubVersion;
+    use crate::test_utils::TempProject;
+    use std::fs;
+
+    #[test]
+    #[cfg(unix)]
+    fn test_run() {
+        let project = TempProject::new("hooks").unwrap();
+        let mut dependency = GitHubDependency::new(
+            String::from("owner/repo:tool"),
+            Some(String::from("bin")),
+            Some(GitHubVersion::Tag(String::from("v1.0.0"))),
+            true,
+            &None,
+        )
+        .unwrap();
+        dependency.post_install = vec![
+            String::from("echo \"$SINK_HOOK $SINK_DEPENDENCY $SINK_VERSION\" > hook.txt"),
+            String::from("pwd >> hook.txt"),
+        ];
+        let dir = project.root.join("bin");
+
+        run(&dependency, Stage::PreInstall, &dir, "v1.0.0").unwrap();
+        assert!(!dir.exists());
+        run(&dependency, Stage::PostInstall, &dir, "v1.0.0").unwrap();
+        let output = fs::read_to_string(dir.join("hook.txt")).unwrap();
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some("post-install owner/repo:tool v1.0.0"));
+        assert_eq!(
+            fs::canonicalize(lines.next().unwrap()).unwrap(),
+            fs::canonicalize(&dir).unwrap()
+        );
+
+        dependency.pre_install = vec![String::from("exit 3")];
+        assert!(run(&dependency, Stage::PreInstall, &dir, "v1.0.0").is_err());
+    }
+}
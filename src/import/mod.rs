@@ -0,0 +1,196 @@
+use anyhow::Result;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::github::{GitHubDependency, GitHubPathspec, GitHubVersion};
+use crate::toml::DependencyType;
+use crate::SinkTOML;
+
+/// The formats `sink import` can read dependencies from.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum ImportSource {
+    /// An asdf-style '.tool-versions' file
+    #[default]
+    ToolVersions,
+}
+impl ImportSource {
+    /// Returns the default file name of the source.
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ImportSource::ToolVersions => ".tool-versions",
+        }
+    }
+}
+
+/// Tools with releases on GitHub, mapped to their repository and the format of their release tags.
+///
+/// In the tag format, '{version}' is replaced by the version of the tool.
+const KNOWN_TOOLS: &[(&str, &str, &str)] = &[
+    ("age", "FiloSottile/age", "v{version}"),
+    ("bat", "sharkdp/bat", "v{version}"),
+    ("delta", "dandavison/delta", "{version}"),
+    ("direnv", "direnv/direnv", "v{version}"),
+    ("fd", "sharkdp/fd", "v{version}"),
+    ("fzf", "junegunn/fzf", "v{version}"),
+    ("gh", "cli/cli", "v{version}"),
+    ("github-cli", "cli/cli", "v{version}"),
+    ("golangci-lint", "golangci/golangci-lint", "v{version}"),
+    ("hadolint", "hadolint/hadolint", "v{version}"),
+    ("hugo", "gohugoio/hugo", "v{version}"),
+    ("jq", "jqlang/jq", "jq-{version}"),
+    ("just", "casey/just", "{version}"),
+    ("k9s", "derailed/k9s", "v{version}"),
+    ("lazygit", "jesseduffield/lazygit", "v{version}"),
+    ("ripgrep", "BurntSushi/ripgrep", "{version}"),
+    ("shellcheck", "koalaman/shellcheck", "v{version}"),
+    ("sops", "getsops/sops", "v{version}"),
+    ("starship", "starship/starship", "v{version}"),
+    ("yq", "mikefarah/yq", "v{version}"),
+];
+
+/// A dependency to be added by `sink import`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Import {
+    /// The name of the tool in the source.
+    pub tool: String,
+
+    /// All assets of the repository, narrow the pattern down after importing.
+    pub pathspec: GitHubPathspec,
+
+    pub version: String,
+}
+
+/// Parse an asdf-style '.tool-versions' file into tools and their versions.
+///
+/// Only the first version of a tool is used, comments and empty lines are skipped.
+pub fn parse_tool_versions(contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            match (fields.next(), fields.next()) {
+                (Some(tool), Some(version)) => Some((tool.to_string(), version.to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn _plan(sink_toml: &SinkTOML, tools: &[(String, String)]) -> Result<Vec<Import>> {
+    let mut imports: Vec<Import> = Vec::new();
+    for (tool, version) in tools {
+        let Some((_, repository, tag_format)) =
+            KNOWN_TOOLS.iter().find(|(name, _, _)| name == tool)
+        else {
+            warn!("'{tool}' is not a known tool, skipping! Please add it manually.");
+            continue;
+        };
+
+        // asdf specific versions cannot be mapped to a release
+        let version = match version.as_str() {
+            "latest" => GitHubVersion::Latest.to_string(),
+            "system" => {
+                debug!("'{tool}' uses the system version, skipping!");
+                continue;
+            }
+            version if version.starts_with("ref:") || version.starts_with("path:") => {
+                warn!("'{tool}' is not installed from a release, skipping!");
+                continue;
+            }
+            version => tag_format.replace("{version}", version),
+        };
+
+        let pathspec = GitHubPathspec::try_from(String::from(*repository))?;
+        let exists = sink_toml.dependencies.keys().any(|existing| {
+            existing.owner() == pathspec.owner() && existing.repository() == pathspec.repository()
+        });
+        if exists || imports.iter().any(|import| import.pathspec == pathspec) {
+            info!("'{tool}' is already a dependency, skipping!");
+            continue;
+        }
+
+        imports.push(Import {
+            tool: tool.clone(),
+            pathspec,
+            version,
+        });
+    }
+
+    Ok(imports)
+}
+/// Map the given tools to dependencies that are not yet part of the sink TOML.
+///
+/// Unknown tools and versions that do not refer to a release are skipped with a warning.
+pub fn plan(sink_toml: &SinkTOML, tools: &[(String, String)]) -> Result<Vec<Import>> {
+    match _plan(sink_toml, tools) {
+        Ok(imports) => Ok(imports),
+        Err(e) => Err(e.context("Failed to map the tools to dependencies!")),
+    }
+}
+
+/// Write the imported dependencies to the sink TOML in the short form.
+///
+/// Nothing is downloaded, run `sink install` afterwards.
+pub fn apply(sink_toml: SinkTOML, imports: &[Import]) -> Result<SinkTOML> {
+    let mut sink_toml = sink_toml;
+    for import in imports {
+        let version = GitHubVersion::from(import.version.as_str());
+        let dependency = GitHubDependency::new(
+            import.pathspec.to_string(),
+            None,
+            Some(version.clone()),
+            true,
+            &None,
+        )?;
+        sink_toml = sink_toml.add_dependency(
+            dependency,
+            DependencyType::Version(version),
+            toml_edit::value(import.version.as_str()),
+        )?;
+        info!("Imported '{}' as {}!", import.tool, import.pathspec);
+    }
+
+    Ok(sink_toml)
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        let tools = parse_tool_versions(
+            "# Tools\nripgrep 14.1.0\nfd 10.1.0 9.0.0 # pinned\nnodejs 20.0.0\njq system\nbat latest\n\n",
+        );
+        assert_eq!(tools.len(), 5);
+
+        let path =
+            std::env::temp_dir().join(format!("sink-test-import-{}.toml", std::process::id()));
+        let sink_toml = SinkTOML::new(&path);
+        let imports = plan(&sink_toml, &tools).unwrap();
+
+        let imported: Vec<(String, String)> = imports
+            .iter()
+            .map(|import| (import.pathspec.to_string(), import.version.clone()))
+            .collect();
+        assert_eq!(
+            imported,
+            vec![
+                (String::from("BurntSushi/ripgrep"), String::from("14.1.0")),
+                (String::from("sharkdp/fd"), String::from("v10.1.0")),
+                (String::from("sharkdp/bat"), String::from("latest")),
+            ]
+        );
+
+        let sink_toml = apply(sink_toml, &imports).unwrap();
+        assert_eq!(sink_toml.dependencies.len(), 3);
+        assert!(plan(&sink_toml, &tools).unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
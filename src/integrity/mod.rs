@@ -0,0 +1,453 @@
+use anyhow::Result;
+use minisign_verify::{PublicKey, Signature};
+use tracing::debug;
+
+use crate::errors::{CodedError, ErrorCode};
+use crate::github::{api, GitHubAsset, GitHubRelease};
+use crate::lock::{self, Algorithm};
+
+/// Whether the asset lists the checksums of all assets of its release, e.g. 'checksums.txt' or 'SHA256SUMS'.
+fn _is_checksum_list(name: &str) -> bool {
+    let name = name.to_lowercase();
+    name.contains("checksums")
+        || name.contains("sha256sums")
+        || name.contains("sha512sums")
+        || name.contains("b3sums")
+        || name == "sha256sum.txt"
+}
+
+/// Returns the algorithm the checksum file is named after, e.g. 'B3SUMS' or 'tool.tar.gz.sha512'.
+fn _algorithm_of_file(name: &str) -> Option<Algorithm> {
+    let name = name.to_lowercase();
+    if name.contains("blake3") || name.contains("b3sum") || name.ends_with(".b3") {
+        Some(Algorithm::Blake3)
+    } else if name.contains("sha512") {
+        Some(Algorithm::Sha512)
+    } else if name.contains("sha256") {
+        Some(Algorithm::Sha256)
+    } else {
+        None
+    }
+}
+
+/// Returns the algorithm and hex digest of a line of a checksum file, and the name listed in it, if any.
+fn _parse_line(line: &str, fallback: Option<Algorithm>) -> Option<(Algorithm, &str, Option<&str>)> {
+    // BSD style, e.g. 'BLAKE3 (tool.tar.gz) = hex' as written by 'shasum --tag' or 'b3sum --tag'
+    let tagged = line.trim().split_once(" (").and_then(|(algorithm, rest)| {
+        let (listed, hex) = rest.rsplit_once(") = ")?;
+        let algorithm = Algorithm::of(&format!("{algorithm}:")).ok()?;
+        Some((algorithm, hex.trim(), Some(listed)))
+    });
+    if tagged.is_some() {
+        return tagged;
+    }
+
+    let mut fields = line.split_whitespace();
+    let digest = fields.next()?;
+    let (algorithm, hex) = match digest.split_once(':') {
+        Some((_, hex)) => (Algorithm::of(digest).ok()?, hex),
+        None => match (fallback, digest.len()) {
+            (Some(algorithm), _) => (algorithm, digest),
+            (None, 64) => (Algorithm::Sha256, digest),
+            (None, 128) => (Algorithm::Sha512, digest),
+            _ => return None,
+        },
+    };
+    Some((algorithm, hex, fields.next()))
+}
+
+/// Find the digest of the asset in the contents of the checksum file named `file`.
+///
+/// Lines are expected in the format of `sha256sum`, `sha512sum` or `b3sum`, i.e. '<hex>  <name>', in the BSD style
+/// of `--tag`, i.e. 'BLAKE3 (<name>) = <hex>', or with the algorithm spelled out, i.e. 'blake3:<hex>  <name>'.
+/// Bare digests are read with the algorithm the file is named after, e.g. 'B3SUMS', or else told apart by their length
+/// as SHA-256 or SHA-512. If `single` is set, the file belongs to the asset alone (e.g. 'tool.tar.gz.sha256')
+/// and may contain the bare digest.
+/// Returns the digest in the form of 'algorithm:hex', e.g. 'sha256:hex'.
+pub fn find_checksum(contents: &str, name: &str, file: &str, single: bool) -> Option<String> {
+    let fallback = _algorithm_of_file(file);
+    for line in contents.lines() {
+        let Some((algorithm, hex, listed)) = _parse_line(line, fallback) else {
+            continue;
+        };
+        let length = match algorithm {
+            Algorithm::Sha256 | Algorithm::Blake3 => 64,
+            Algorithm::Sha512 => 128,
+        };
+        if hex.len() != length || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            continue;
+        }
+
+        // Binary mode prefixes the name with '*'
+        let listed = listed.map(|listed| listed.trim_start_matches('*'));
+        let listed = listed.map(|listed| listed.trim_start_matches("./"));
+        if listed.is_none_or(|listed| listed == name) && (single || listed.is_some()) {
+            return Some(format!("{algorithm}:{}", hex.to_lowercase()));
+        }
+    }
+
+    None
+}
+
+fn _published_digest(
+    origin: &str,
+    release: &GitHubRelease,
+    asset: &GitHubAsset,
+) -> Result<Option<String>> {
+    if let Some(digest) = asset.digest.as_ref().filter(|d| Algorithm::of(d).is_ok()) {
+        return Ok(Some(digest.clone()));
+    }
+
+    // Checksum files of the asset itself take precedence over lists of all assets
+    let single = [
+        ".sha256",
+        ".sha256sum",
+        ".sha512",
+        ".sha512sum",
+        ".b3",
+        ".blake3",
+    ]
+    .map(|extension| format!("{}{extension}", asset.name));
+    let mut candidates: Vec<(&GitHubAsset, bool)> = release
+        .assets
+        .iter()
+        .filter(|candidate| single.contains(&candidate.name))
+        .map(|candidate| (candidate, true))
+        .collect();
+    candidates.extend(
+        release
+            .assets
+            .iter()
+            .filter(|candidate| _is_checksum_list(&candidate.name))
+            .map(|candidate| (candidate, false)),
+    );
+
+    for (candidate, single) in candidates {
+        debug!(
+            "Looking up the checksum of '{}' in '{}'...",
+            asset.name, candidate.name
+        );
+        let contents = api::api().download_asset(origin, &release.tag_name, candidate)?;
+        let contents = String::from_utf8_lossy(&contents);
+        if let Some(digest) = find_checksum(&contents, &asset.name, &candidate.name, single) {
+            return Ok(Some(digest));
+        }
+    }
+
+    Ok(None)
+}
+/// Returns the digest published for the asset of the release, if any, in the form of 'algorithm:hex'.
+///
+/// Uses the digest GitHub computed for the asset, or else checksum files among the assets of the release.
+pub fn published_digest(
+    origin: &str,
+    release: &GitHubRelease,
+    asset: &GitHubAsset,
+) -> Result<Option<String>> {
+    match _published_digest(origin, release, asset) {
+        Ok(digest) => Ok(digest),
+        Err(e) => Err(e.context(format!(
+            "Failed to look up the checksum of '{}'!",
+            asset.name
+        ))),
+    }
+}
+
+fn _verify_listed(
+    origin: &str,
+    release: &GitHubRelease,
+    pattern: &str,
+    downloaded: &[(&GitHubAsset, &[u8])],
+) -> Result<()> {
+    let listing = match release.matching_assets(pattern)?.as_slice() {
+        [listing] => *listing,
+        [] => {
+            return Err(CodedError(
+                ErrorCode::NoMatchingAsset,
+                format!("No asset of {} matches '{pattern}'!", release.tag_name),
+            )
+            .into())
+        }
+        listings => {
+            return Err(anyhow::anyhow!(
+                "{} assets of {} match '{pattern}', expected a single one!",
+                listings.len(),
+                release.tag_name
+            ))
+        }
+    };
+
+    debug!("Verifying the assets against '{}'...", listing.name);
+    let contents = api::api().download_asset(origin, &release.tag_name, listing)?;
+    let contents = String::from_utf8_lossy(&contents);
+    // The listing cannot contain its own checksum
+    for (asset, asset_contents) in downloaded.iter().filter(|(a, _)| a.name != listing.name) {
+        let Some(expected) = find_checksum(&contents, &asset.name, &listing.name, false) else {
+            return Err(anyhow::anyhow!(
+                "'{}' is not listed in '{}'!",
+                asset.name,
+                listing.name
+            ));
+        };
+        let actual = lock::digest_bytes_like(asset_contents, &expected)?;
+        if actual != expected {
+            return Err(CodedError(
+                ErrorCode::ChecksumMismatch,
+                format!(
+                    "'{}' does not match its checksum in '{}'! Expected '{expected}', got '{actual}'",
+                    asset.name,
+                    listing.name
+                ),
+            )
+            .into());
+        }
+        debug!(
+            "'{}' matches its checksum in '{}'!",
+            asset.name, listing.name
+        );
+    }
+
+    Ok(())
+}
+/// Verify the downloaded assets against the checksums listed in the asset of the release matching the pattern.
+///
+/// The pattern must match exactly one asset, e.g. 'checksums.txt'. Every downloaded asset must be listed in it.
+pub fn verify_listed(
+    origin: &str,
+    release: &GitHubRelease,
+    pattern: &str,
+    downloaded: &[(&GitHubAsset, &[u8])],
+) -> Result<()> {
+    match _verify_listed(origin, release, pattern, downloaded) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(e.context(format!(
+            "Failed to verify the assets of {} against '{pattern}'!",
+            release.tag_name
+        ))),
+    }
+}
+
+/// The extension of minisign signatures, which are published next to the signed asset, e.g. 'tool.tar.gz.minisig'.
+const MINISIG_EXTENSION: &str = ".minisig";
+
+fn _verify_signatures(
+    origin: &str,
+    release: &GitHubRelease,
+    public_key: &str,
+    downloaded: &[(&GitHubAsset, &[u8])],
+) -> Result<()> {
+    let public_key = PublicKey::from_base64(public_key)
+        .map_err(|e| anyhow::anyhow!("Invalid minisign public key '{public_key}': {e}"))?;
+
+    // Signatures are not signed themselves
+    let signed = downloaded
+        .iter()
+        .filter(|(asset, _)| !asset.name.ends_with(MINISIG_EXTENSION));
+    for (asset, contents) in signed {
+        let name = format!("{}{MINISIG_EXTENSION}", asset.name);
+        let Some(signature) = release.assets.iter().find(|asset| asset.name == name) else {
+            return Err(CodedError(
+                ErrorCode::InvalidSignature,
+                format!("'{}' is not signed, '{name}' is missing!", asset.name),
+            )
+            .into());
+        };
+
+        debug!("Verifying '{}' against '{name}'...", asset.name);
+        let signature = api::api().download_asset(origin, &release.tag_name, signature)?;
+        let signature = Signature::decode(&String::from_utf8_lossy(&signature))
+            .map_err(|e| anyhow::anyhow!("'{name}' is not a minisign signature: {e}"))?;
+        public_key.verify(contents, &signature, true).map_err(|e| {
+            CodedError(
+                ErrorCode::InvalidSignature,
+                format!("'{}' does not match '{name}': {e}", asset.name),
+            )
+        })?;
+        debug!("'{}' is signed by the minisign key!", asset.name);
+    }
+
+    Ok(())
+}
+/// Verify the minisign signatures of the downloaded assets, published as '<asset>.minisig' in the release.
+///
+/// Every downloaded asset must be signed with the given public key, e.g. 'RWQf6LRC...'.
+pub fn verify_signatures(
+    origin: &str,
+    release: &GitHubRelease,
+    public_key: &str,
+    downloaded: &[(&GitHubAsset, &[u8])],
+) -> Result<()> {
+    match _verify_signatures(origin, release, public_key, downloaded) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(e.context(format!(
+            "Failed to verify the signatures of {}!",
+            release.tag_name
+        ))),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockRelease, MockReleaseServer};
+
+    const HEX: &str = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    #[test]
+    fn test_find_checksum() {
+        let list = format!("{}  other.zip\n{HEX} *./tool.tar.gz\n", "0".repeat(64));
+        assert_eq!(
+            find_checksum(&list, "tool.tar.gz", "checksums.txt", false),
+            Some(format!("sha256:{HEX}"))
+        );
+        assert_eq!(
+            find_checksum(&list, "missing.zip", "checksums.txt", false),
+            None
+        );
+
+        let single = format!("{}\n", HEX.to_uppercase());
+        assert_eq!(
+            find_checksum(&single, "tool.tar.gz", "tool.tar.gz.sha256", true),
+            Some(format!("sha256:{HEX}"))
+        );
+        assert_eq!(
+            find_checksum(HEX, "tool.tar.gz", "checksums.txt", false),
+            None
+        );
+
+        // SHA-512 digests are twice as long
+        let sha512 = HEX.repeat(2);
+        assert_eq!(
+            find_checksum(
+                &format!("{sha512}  tool.tar.gz\n"),
+                "tool.tar.gz",
+                "checksums.txt",
+                false
+            ),
+            Some(format!("sha512:{sha512}"))
+        );
+        assert_eq!(
+            find_checksum("not a checksum", "tool.tar.gz", "checksums.txt", true),
+            None
+        );
+
+        // BLAKE3 digests are as long as SHA-256 ones, so they need to be told apart
+        let line = format!("{HEX}  tool.tar.gz\n");
+        assert_eq!(
+            find_checksum(&line, "tool.tar.gz", "B3SUMS", false),
+            Some(format!("blake3:{HEX}"))
+        );
+        assert_eq!(
+            find_checksum(
+                &format!("blake3:{HEX}  tool.tar.gz\n"),
+                "tool.tar.gz",
+                "checksums.txt",
+                false
+            ),
+            Some(format!("blake3:{HEX}"))
+        );
+        assert_eq!(
+            find_checksum(
+                &format!("BLAKE3 (tool.tar.gz) = {HEX}\n"),
+                "tool.tar.gz",
+                "checksums.txt",
+                false
+            ),
+            Some(format!("blake3:{HEX}"))
+        );
+        assert_eq!(
+            find_checksum(
+                &format!("SHA512 (tool.tar.gz) = {HEX}\n"),
+                "tool.tar.gz",
+                "checksums.txt",
+                false
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_listed() {
+        let listing = format!(
+            "{}  tool.tar.gz\n",
+            lock::digest_bytes(b"tool").trim_start_matches("sha256:")
+        );
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release(
+                "owner/listed",
+                MockRelease::new("v1.0.0")
+                    .asset("tool.tar.gz", "tool")
+                    .asset("tool.zip", "zip")
+                    .asset("checksums.txt", listing.clone()),
+            )
+            .with_release(
+                "owner/tampered",
+                MockRelease::new("v1.0.0")
+                    .asset("tool.tar.gz", "tampered")
+                    .asset("checksums.txt", listing),
+            );
+        let _guard = server.install();
+        let api = api::api();
+        let verify = |origin: &str, pattern: &str, names: &[&str]| {
+            let release = api.view_release(origin, Some("v1.0.0")).unwrap();
+            let assets: Vec<GitHubAsset> = release
+                .assets
+                .iter()
+                .filter(|asset| names.contains(&asset.name.as_str()))
+                .cloned()
+                .collect();
+            let contents: Vec<Vec<u8>> = assets
+                .iter()
+                .map(|asset| api.download_asset(origin, "v1.0.0", asset).unwrap())
+                .collect();
+            let downloaded: Vec<(&GitHubAsset, &[u8])> = assets
+                .iter()
+                .zip(contents.iter().map(Vec::as_slice))
+                .collect();
+            verify_listed(origin, &release, pattern, &downloaded)
+        };
+
+        // The listing itself may be among the downloaded assets
+        assert!(verify("owner/listed", "checksums.txt", &["tool.tar.gz"]).is_ok());
+        assert!(verify("owner/listed", "*.txt", &["tool.tar.gz", "checksums.txt"]).is_ok());
+        assert!(verify("owner/listed", "checksums.txt", &["tool.zip"]).is_err());
+        assert!(verify("owner/listed", "SHA256SUMS", &["tool.tar.gz"]).is_err());
+        assert!(verify("owner/tampered", "checksums.txt", &["tool.tar.gz"]).is_err());
+    }
+
+    #[test]
+    fn test_verify_signatures() {
+        // The example of the minisign-verify crate, signing 'test'
+        let public_key = "RWQf6LRCGA9i53mlYecO4IzT51TGPpvWucNSCh1CBM0QTaLn73Y7GFO3";
+        let signature = "untrusted comment: signature from minisign secret key\n\
+            RUQf6LRCGA9i559r3g7V1qNyJDApGip8MfqcadIgT9CuhV3EMhHoN1mGTkUidF/z7SrlQgXdy8ofjb7bNJJylDOocrCo8KLzZwo=\n\
+            trusted comment: timestamp:1633700835\tfile:test\tprehashed\n\
+            wLMDjy9FLAuxZ3q4NlEvkgtyhrr0gtTu6KC4KBJdITbbOeAi1zBIYo0v4iTgt8jJpIidRJnp94ABQkJAgAooBQ==\n";
+        let server = MockReleaseServer::start().unwrap().with_release(
+            "owner/signed",
+            MockRelease::new("v1.0.0")
+                .asset("tool", "test")
+                .asset("tool.minisig", signature)
+                .asset("tampered", "tampered")
+                .asset("tampered.minisig", signature)
+                .asset("unsigned", "test"),
+        );
+        let _guard = server.install();
+        let api = api::api();
+        let release = api.view_release("owner/signed", Some("v1.0.0")).unwrap();
+        let verify = |name: &str, public_key: &str| {
+            let asset = release.assets.iter().find(|a| a.name == name).unwrap();
+            let contents = api.download_asset("owner/signed", "v1.0.0", asset).unwrap();
+            verify_signatures("owner/signed", &release, public_key, &[(asset, &contents)])
+        };
+
+        assert!(verify("tool", public_key).is_ok());
+        // Signatures themselves are skipped
+        assert!(verify("tool.minisig", public_key).is_ok());
+        assert!(verify("tampered", public_key).is_err());
+        assert!(verify("unsigned", public_key).is_err());
+        assert!(verify("tool", "not a key").is_err());
+    }
+}
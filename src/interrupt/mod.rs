@@ -0,0 +1,33 @@
+use anyhow::Result;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
+
+use crate::errors::InterruptedError;
+
+/// Whether sink was asked to stop, e.g. by Ctrl-C.
+static INTERRUPTED: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+
+/// Returns the flag marking sink as interrupted, e.g. to set it from a signal handler.
+pub fn flag() -> Arc<AtomicBool> {
+    INTERRUPTED.clone()
+}
+
+/// Mark sink as interrupted (or not), see [`check`].
+pub fn set_interrupted(interrupted: bool) {
+    INTERRUPTED.store(interrupted, Ordering::Relaxed);
+}
+
+/// Whether sink was asked to stop.
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Fail with an [`InterruptedError`] if sink was asked to stop.
+///
+/// Called before starting work that should not be started anymore, e.g. the next download.
+pub fn check() -> Result<()> {
+    match is_interrupted() {
+        true => Err(anyhow::Error::new(InterruptedError)),
+        false => Ok(()),
+    }
+}
@@ -1,5 +1,39 @@
+pub mod actions;
+pub mod atomic;
+pub mod audit;
+pub mod backup;
+pub mod cache;
+pub mod changelog;
+#[cfg(feature = "cli")]
 pub mod cli;
+pub mod client;
+pub mod detect;
+pub mod diff;
+pub mod env;
+pub mod exec;
 pub mod github;
+pub mod gitignore;
+pub mod global;
+pub mod guard;
+pub mod hooks;
+pub mod import;
+pub mod integrity;
+pub mod interrupt;
+pub mod list;
+pub mod lock;
+pub mod metadata;
+pub mod output;
+pub mod policy;
+pub mod remote;
+pub mod self_update;
+pub mod status;
+pub mod suggest;
+pub mod template;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod timings;
+pub mod upgrade;
+pub mod workspace;
 
 pub use errors::SinkError;
 pub use toml::SinkTOML;
@@ -7,21 +41,409 @@ pub use toml::SinkTOML;
 /* ---------- [ Errors ] ---------- */
 pub mod errors {
     use std::fmt::Display;
+    use std::ops::Range;
+    use std::path::{Path, PathBuf};
 
     /// Wrapper around anyhow::Error to allow for custom Display trait
     #[derive(Debug)]
     pub enum SinkError {
         Any(anyhow::Error),
     }
+    impl SinkError {
+        /// Returns the category of the error, see [`ErrorCode::of`].
+        pub fn code(&self) -> Option<ErrorCode> {
+            let Self::Any(as_error) = self;
+            ErrorCode::of(as_error)
+        }
+    }
     impl Display for SinkError {
         fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
             let Self::Any(as_error) = self;
-            let mut error_string = as_error.to_string();
-            as_error
-                .chain()
-                .skip(1)
-                .for_each(|cause| error_string.push_str(&format!(" Caused by: {}", cause)));
-            write!(f, "{error_string}")
+            write!(f, "{}", describe(as_error))
+        }
+    }
+
+    /// Describe the error like [`SinkError`] does, without taking ownership of it.
+    ///
+    /// The error is prefixed by its code, if it has one, and followed by its causes.
+    pub fn describe(error: &anyhow::Error) -> String {
+        let mut error_string = match ErrorCode::of(error) {
+            Some(code) => format!("[{code}] {error}"),
+            None => error.to_string(),
+        };
+        error
+            .chain()
+            .skip(1)
+            .for_each(|cause| error_string.push_str(&format!(" Caused by: {}", cause)));
+        error_string
+    }
+
+    /// Error raised when communicating with GitHub fails.
+    #[derive(Debug)]
+    pub struct GitHubError(pub String);
+    impl Display for GitHubError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+    impl std::error::Error for GitHubError {}
+
+    /// Error raised when a dependency violates the policy of the sink TOML.
+    #[derive(Debug)]
+    pub struct PolicyError(pub String);
+    impl Display for PolicyError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+    impl std::error::Error for PolicyError {}
+
+    /// Error raised when sink stops early because it was interrupted, see [`crate::interrupt`].
+    #[derive(Debug)]
+    pub struct InterruptedError;
+    impl Display for InterruptedError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "Interrupted!")
+        }
+    }
+    impl std::error::Error for InterruptedError {}
+
+    /// Error of a category without an error type of its own, see [`ErrorCode`].
+    #[derive(Debug)]
+    pub struct CodedError(pub ErrorCode, pub String);
+    impl Display for CodedError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.1)
+        }
+    }
+    impl std::error::Error for CodedError {}
+
+    /// The categories of errors, each identified by a stable code like 'SINK-E010'.
+    ///
+    /// The codes are printed along with the errors and explained by `sink explain`.
+    /// These are part of the public interface and must not change.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ErrorCode {
+        /// The sink TOML or lockfile is invalid, see [`SourceError`].
+        Config,
+
+        /// Communicating with GitHub failed, see [`GitHubError`].
+        GitHub,
+
+        /// A dependency violates the policy of the sink TOML, see [`PolicyError`].
+        Policy,
+
+        /// sink was interrupted, see [`InterruptedError`].
+        Interrupted,
+
+        /// The asset pattern of a dependency matches no asset of the release.
+        NoMatchingAsset,
+
+        /// Downloaded contents do not match the checksum they were expected to have.
+        ChecksumMismatch,
+
+        /// A downloaded asset is not signed by the minisign key of its dependency.
+        InvalidSignature,
+
+        /// Another sink process is working on the same sink TOML.
+        Locked,
+    }
+    impl ErrorCode {
+        /// All error codes, in the order of their identifiers.
+        pub const ALL: [ErrorCode; 8] = [
+            ErrorCode::Config,
+            ErrorCode::GitHub,
+            ErrorCode::Policy,
+            ErrorCode::Interrupted,
+            ErrorCode::NoMatchingAsset,
+            ErrorCode::ChecksumMismatch,
+            ErrorCode::InvalidSignature,
+            ErrorCode::Locked,
+        ];
+
+        /// Returns the stable identifier of the code, e.g. 'SINK-E010'.
+        pub fn id(&self) -> &'static str {
+            match self {
+                ErrorCode::Config => "SINK-E001",
+                ErrorCode::GitHub => "SINK-E002",
+                ErrorCode::Policy => "SINK-E003",
+                ErrorCode::Interrupted => "SINK-E004",
+                ErrorCode::NoMatchingAsset => "SINK-E010",
+                ErrorCode::ChecksumMismatch => "SINK-E011",
+                ErrorCode::InvalidSignature => "SINK-E012",
+                ErrorCode::Locked => "SINK-E020",
+            }
+        }
+
+        /// Parse an identifier like 'SINK-E010', ignoring its case. The 'SINK-' prefix may be omitted.
+        pub fn parse(id: &str) -> Option<Self> {
+            ErrorCode::ALL.into_iter().find(|code| {
+                code.id().eq_ignore_ascii_case(id)
+                    || code
+                        .id()
+                        .trim_start_matches("SINK-")
+                        .eq_ignore_ascii_case(id)
+            })
+        }
+
+        /// Returns a one-line summary of the category.
+        pub fn summary(&self) -> &'static str {
+            match self {
+                ErrorCode::Config => "The sink TOML or lockfile is invalid",
+                ErrorCode::GitHub => "Communicating with GitHub failed",
+                ErrorCode::Policy => "A dependency violates the policy of the sink TOML",
+                ErrorCode::Interrupted => "sink was interrupted",
+                ErrorCode::NoMatchingAsset => "The asset pattern matched nothing",
+                ErrorCode::ChecksumMismatch => "Downloaded contents do not match their checksum",
+                ErrorCode::InvalidSignature => {
+                    "An asset is not signed by the expected minisign key"
+                }
+                ErrorCode::Locked => "Another sink process is running",
+            }
+        }
+
+        /// Returns what causes errors of the category and how to resolve them.
+        pub fn explanation(&self) -> &'static str {
+            match self {
+                ErrorCode::Config => {
+                    "The sink TOML or sink.lock could not be parsed, or contains an invalid entry.\n\
+                     The error points at the offending line. Fix the entry, or run 'sink config --restore' \
+                     to restore the sink TOML from its last backup."
+                }
+                ErrorCode::GitHub => {
+                    "A request to GitHub failed, e.g. because the repository or release does not exist, \
+                     the rate limit is exhausted or the network is down.\n\
+                     Check 'sink api-status' and whether 'gh auth status' is logged in. \
+                     '--trace-http' logs every request."
+                }
+                ErrorCode::Policy => {
+                    "A dependency is not allowed by the [policy] table of the sink TOML, \
+                     e.g. because of its owner, license or size.\n\
+                     Pick another dependency or version, or relax the policy."
+                }
+                ErrorCode::Interrupted => {
+                    "sink received Ctrl-C or SIGTERM and stopped early. \
+                     Dependencies installed until then are kept and locked.\n\
+                     Run the command again to finish it."
+                }
+                ErrorCode::NoMatchingAsset => {
+                    "The release exists, but none of its assets matches the pattern of the dependency, \
+                     and no asset could be guessed either.\n\
+                     Check the asset names of the release, e.g. with 'gh release view', and adjust the pattern. \
+                     Templates like '{{.OS}}' are filled in for the running platform."
+                }
+                ErrorCode::ChecksumMismatch => {
+                    "Downloaded contents differ from the checksum they were expected to have, \
+                     e.g. the one in sink.lock, in a checksums asset of the release or of an include.\n\
+                     The release may have been modified after it was published. \
+                     Make sure the new contents can be trusted before updating the checksum."
+                }
+                ErrorCode::InvalidSignature => {
+                    "A dependency with a minisign key has an asset without a valid '.minisig' signature.\n\
+                     Make sure the key is the one the maintainers sign their releases with."
+                }
+                ErrorCode::Locked => {
+                    "Another sink process is modifying the same sink TOML.\n\
+                     Wait for it to finish, or stop it if it hangs."
+                }
+            }
+        }
+
+        /// Determine the category of the error, or `None` if it has none.
+        pub fn of(error: &anyhow::Error) -> Option<Self> {
+            error.chain().find_map(|cause| {
+                if let Some(CodedError(code, _)) = cause.downcast_ref::<CodedError>() {
+                    Some(*code)
+                } else if cause.is::<InterruptedError>() {
+                    Some(ErrorCode::Interrupted)
+                } else if cause.is::<PolicyError>() {
+                    Some(ErrorCode::Policy)
+                } else if cause.is::<GitHubError>() {
+                    Some(ErrorCode::GitHub)
+                } else if cause.is::<SourceError>() {
+                    Some(ErrorCode::Config)
+                } else {
+                    None
+                }
+            })
+        }
+    }
+    impl Display for ErrorCode {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.id())
+        }
+    }
+
+    /// Error pointing at the offending part of a file, e.g. an invalid entry of a sink TOML.
+    ///
+    /// Rendered with the location and the source line, with the offending part underlined.
+    #[derive(Debug)]
+    pub struct SourceError {
+        pub message: String,
+        pub path: PathBuf,
+
+        /// The line of the offending part, starting at 1.
+        pub line: usize,
+
+        /// The column of the offending part in characters, starting at 1.
+        pub column: usize,
+
+        /// The line containing the offending part.
+        source_line: String,
+
+        /// The length of the offending part in characters, limited to the end of its first line.
+        length: usize,
+    }
+    impl SourceError {
+        /// Create the error for the given byte range of the contents of the file at `path`.
+        pub fn new(message: &str, path: &Path, contents: &str, span: Range<usize>) -> Self {
+            let start = span.start.min(contents.len());
+            let line_start = contents[..start].rfind('\n').map_or(0, |index| index + 1);
+            let line_end = contents[start..]
+                .find('\n')
+                .map_or(contents.len(), |index| start + index);
+            let end = span.end.clamp(start, line_end);
+
+            SourceError {
+                message: message.to_string(),
+                path: path.to_path_buf(),
+                line: contents[..start].matches('\n').count() + 1,
+                column: contents[line_start..start].chars().count() + 1,
+                source_line: contents[line_start..line_end].trim_end().to_string(),
+                length: contents[start..end].chars().count().max(1),
+            }
+        }
+    }
+    impl Display for SourceError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            let gutter = " ".repeat(self.line.to_string().len());
+            writeln!(f, "{}", self.message)?;
+            writeln!(
+                f,
+                "{gutter}--> {}:{}:{}",
+                self.path.display(),
+                self.line,
+                self.column
+            )?;
+            writeln!(f, "{gutter} |")?;
+            writeln!(f, "{} | {}", self.line, self.source_line)?;
+            write!(
+                f,
+                "{gutter} | {}{}",
+                " ".repeat(self.column - 1),
+                "^".repeat(self.length)
+            )
+        }
+    }
+    impl std::error::Error for SourceError {}
+
+    /// The exit codes of the sink process.
+    ///
+    /// These are part of the public interface and must not change.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum ExitCode {
+        /// The command finished successfully.
+        Success = 0,
+
+        /// Any error not covered by a more specific code.
+        Failure = 1,
+
+        /// The sink TOML or lockfile could not be loaded or is invalid.
+        Config = 2,
+
+        /// Communicating with GitHub failed.
+        Network = 3,
+
+        /// Files do not match their checksums or signatures, e.g. installed files and the lockfile.
+        Verification = 4,
+
+        /// Some, but not all dependencies could be installed.
+        PartialInstall = 5,
+
+        /// The command had nothing to do.
+        NothingToDo = 6,
+
+        /// A dependency violates the policy of the sink TOML.
+        Policy = 7,
+
+        /// Dependencies are affected by known vulnerabilities.
+        Vulnerable = 8,
+
+        /// sink was interrupted, e.g. by Ctrl-C, and stopped early.
+        Interrupted = 130,
+    }
+    impl ExitCode {
+        /// Determine the exit code for the given error.
+        pub fn from_error(error: &anyhow::Error) -> Self {
+            let is_verification = |cause: &(dyn std::error::Error + 'static)| {
+                matches!(
+                    cause.downcast_ref::<CodedError>(),
+                    Some(CodedError(
+                        ErrorCode::ChecksumMismatch | ErrorCode::InvalidSignature,
+                        _
+                    ))
+                )
+            };
+            if error.chain().any(|cause| cause.is::<InterruptedError>()) {
+                ExitCode::Interrupted
+            } else if error.chain().any(is_verification) {
+                ExitCode::Verification
+            } else if error.chain().any(|cause| cause.is::<PolicyError>()) {
+                ExitCode::Policy
+            } else if error.chain().any(|cause| cause.is::<GitHubError>()) {
+                ExitCode::Network
+            } else {
+                ExitCode::Failure
+            }
+        }
+    }
+    impl From<ExitCode> for std::process::ExitCode {
+        fn from(value: ExitCode) -> Self {
+            std::process::ExitCode::from(value as u8)
+        }
+    }
+
+    /* ---------- [ Tests ] ---------- */
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_error_code() {
+            for code in ErrorCode::ALL {
+                assert_eq!(ErrorCode::parse(code.id()), Some(code));
+            }
+            assert_eq!(
+                ErrorCode::parse("sink-e010"),
+                Some(ErrorCode::NoMatchingAsset)
+            );
+            assert_eq!(ErrorCode::parse("E020"), Some(ErrorCode::Locked));
+            assert_eq!(ErrorCode::parse("SINK-E999"), None);
+
+            let e = anyhow::Error::new(CodedError(
+                ErrorCode::NoMatchingAsset,
+                String::from("No asset matches!"),
+            ))
+            .context("Failed to download dependency!");
+            assert_eq!(ErrorCode::of(&e), Some(ErrorCode::NoMatchingAsset));
+            assert_eq!(
+                SinkError::Any(e).to_string(),
+                "[SINK-E010] Failed to download dependency! Caused by: No asset matches!"
+            );
+
+            let e = anyhow::Error::new(GitHubError(String::from("Not Found")));
+            assert_eq!(ErrorCode::of(&e), Some(ErrorCode::GitHub));
+            assert_eq!(ExitCode::from_error(&e), ExitCode::Network);
+            for code in [ErrorCode::ChecksumMismatch, ErrorCode::InvalidSignature] {
+                let e = anyhow::Error::new(CodedError(code, String::from("Mismatch!")))
+                    .context("Failed to download dependency!");
+                assert_eq!(ExitCode::from_error(&e), ExitCode::Verification);
+            }
+            assert_eq!(ErrorCode::of(&anyhow::anyhow!("Failed!")), None);
+            assert_eq!(
+                SinkError::Any(anyhow::anyhow!("Failed!")).to_string(),
+                "Failed!"
+            );
         }
     }
 }
@@ -29,15 +451,19 @@ pub mod errors {
 /* ---------- [ TOML ] ---------- */
 pub mod toml {
     use anyhow::Result;
-    use log::{debug, error, info, warn};
+    use regex::Regex;
     use serde::{Deserialize, Serialize};
     use std::collections::HashMap;
     use std::fs::{self};
-    use std::path::PathBuf;
+    use std::ops::Range;
+    use std::path::{Component, Path, PathBuf};
     use toml_edit::{self, DocumentMut};
+    use tracing::{debug, error, info, warn};
 
-    use super::errors::SinkError;
+    use super::errors::{SinkError, SourceError};
     use super::github;
+    use super::policy::Policy;
+    use super::suggest;
 
     #[derive(Serialize, Deserialize, Debug)]
     #[serde(
@@ -48,87 +474,313 @@ pub mod toml {
         /// Optional: The default owner to fall back to if not explicitly set
         pub default_owner: Option<String>,
 
-        /// Optional: Collection of paths to other sink TOMLs to include.
+        /// Optional: Collection of paths to other sink TOMLs to include, see [`Include`].
+        #[serde(default)]
+        pub includes: Vec<Include>,
+
+        /// Optional: Turn all warnings about the sink TOML into errors.
+        #[serde(default)]
+        pub strict: bool,
+
+        /// Optional: Allow destinations outside of the directory of the sink TOML, e.g. '/usr/local/bin'.
+        ///
+        /// This is only respected in the sink TOML sink was invoked with, never in included ones.
         #[serde(default)]
-        pub includes: Vec<PathBuf>,
+        pub allow_external_destinations: bool,
+
+        /// Optional: Link the executables of all dependencies into `.sink/bin`, see [`crate::env::sync`].
+        #[serde(default)]
+        pub link_executables: bool,
+
+        /// Optional: Restrict the repositories dependencies may come from.
+        #[serde(default)]
+        pub policy: Policy,
+
+        /// Optional: The member sink TOMLs of the workspace this sink TOML is the root of.
+        ///
+        /// See [`crate::workspace`].
+        #[serde(default)]
+        pub workspace: Option<super::workspace::Workspace>,
+
+        /// Optional: Commands to run around installing the dependencies, see [`crate::hooks::Hooks`].
+        #[serde(default)]
+        pub hooks: super::hooks::Hooks,
 
         /// The actual dependencies.
         pub dependencies: HashMap<github::GitHubPathspec, DependencyType>,
 
+        /// Optional: Changes to dependencies of the includes, see [`Override`].
+        #[serde(default)]
+        pub overrides: HashMap<github::GitHubPathspec, Override>,
+
         /// Contains the path to the this sink TOML
         #[serde(skip)]
         pub path: PathBuf,
 
+        /// The dependencies merged from the includes, by the path of the sink TOML they come from.
+        ///
+        /// These are not part of [`SinkTOML::formatted`], so they cannot be modified.
+        #[serde(skip)]
+        pub included: HashMap<github::GitHubPathspec, PathBuf>,
+
         /// Contains the formatted document for in-place manipulation and writing back to the file.
         #[serde(skip)]
         pub formatted: DocumentMut,
     }
     impl SinkTOML {
+        /// Rewrite the error of an unknown field to name its table and suggest the closest known field.
+        ///
+        /// Returns `None` if the error is not about an unknown field.
+        fn _unknown_field(message: &str, contents: &str, span: &Range<usize>) -> Option<String> {
+            let re =
+                Regex::new(r"^unknown field `(?<field>[^`]+)`, expected (?<expected>.*)$").unwrap();
+            let captures = re.captures(message.trim())?;
+            let field = &captures["field"];
+            let expected: Vec<&str> = captures["expected"].split('`').skip(1).step_by(2).collect();
+
+            // The closest table header above the field, inline tables are not told apart
+            let table = contents[..span.start.min(contents.len())]
+                .lines()
+                .rev()
+                .map(str::trim)
+                .find(|line| line.starts_with('['))
+                .map_or(String::from("at the top level"), |header| {
+                    format!("in `{header}`")
+                });
+            Some(match suggest::closest(field, &expected) {
+                Some(closest) => {
+                    format!("Unknown field `{field}` {table}, did you mean `{closest}`?")
+                }
+                None => format!(
+                    "Unknown field `{field}` {table}, expected {}",
+                    &captures["expected"]
+                ),
+            })
+        }
+
         /// Checks the TOML syntax.
         ///
         /// This fails, if any of the fields could not be parsed correctly.
-        fn _validate_toml_syntax(&self) -> Result<()> {
-            for (key, value) in self.dependencies.iter() {
-                if let DependencyType::Invalid(_) = value {
-                    return Err(anyhow::anyhow!("Invalid dependency entry for '{key}'!"));
+        /// `contents` are the contents of the sink TOML, used to point at the invalid entry, see [`SourceError`].
+        fn _validate_toml_syntax(&self, contents: &str) -> Result<()> {
+            let mut invalid: Vec<_> = self
+                .dependencies
+                .iter()
+                .filter_map(|(pathspec, dependency)| match dependency {
+                    DependencyType::Invalid(value) => Some((pathspec, value)),
+                    _ => None,
+                })
+                .collect();
+            invalid.sort_by_key(|(pathspec, _)| *pathspec);
+            let Some((pathspec, value)) = invalid.first() else {
+                return Ok(());
+            };
+
+            let message = format!(
+                "Invalid dependency entry for '{pathspec}': {}",
+                DependencyType::invalid_reason(value)
+            );
+            // Spans are only kept by the immutable document
+            let span = toml_edit::ImDocument::parse(contents)
+                .ok()
+                .and_then(|document| {
+                    document
+                        .get("dependencies")?
+                        .get(pathspec.to_string())?
+                        .span()
+                });
+            Err(match span {
+                Some(span) => SourceError::new(&message, &self.path, contents, span).into(),
+                None => anyhow::anyhow!("{message}!"),
+            })
+        }
+
+        /// Validates the TOML semantics.
+        ///
+        /// This checks for unknown fields, missing destinations, etc. and returns the found issues.
+        fn _validate_toml_semantics(&self) -> Vec<String> {
+            let mut issues = Vec::new();
+
+            for (pathspec, dependency) in self.dependencies.iter() {
+                let DependencyType::Full(dependency) = dependency else {
+                    continue;
+                };
+                // Issues of included dependencies are reported when loading the included sink TOML
+                if self.included.contains_key(pathspec) {
+                    continue;
+                }
+
+                for field in dependency.unknown_fields.keys() {
+                    issues.push(
+                        match suggest::closest(field, github::GitHubDependency::FIELDS) {
+                            Some(closest) => format!(
+                                "Unknown field '{field}' in '{pathspec}', did you mean '{closest}'?"
+                            ),
+                            None => format!("Unknown field '{field}' in '{pathspec}'!"),
+                        },
+                    );
+                }
+
+                let has_destination = self
+                    .formatted
+                    .get("dependencies")
+                    .and_then(|dependencies| dependencies.get(pathspec.to_string()))
+                    .is_some_and(|formatted| formatted.get("destination").is_some());
+                if !has_destination {
+                    issues.push(format!(
+                        "'{pathspec}' has no destination, defaulting to '{}'!",
+                        dependency.destination.display()
+                    ));
                 }
             }
 
-            Ok(())
+            issues
         }
 
-        /// Validates the TOML semantics.
+        /// Whether the destination stays inside of `root` once resolved from it.
         ///
-        /// This checks for missing owner specification, etc.
-        fn _validate_toml_semantics(&self) -> Result<()> {
+        /// This only looks at the path itself, e.g. absolute paths or '..' components leaving `root`.
+        fn _is_contained(destination: &Path) -> bool {
+            let mut depth = 0usize;
+            for component in destination.components() {
+                match component {
+                    Component::Normal(_) => depth += 1,
+                    Component::CurDir => {}
+                    Component::ParentDir if depth > 0 => depth -= 1,
+                    Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                        return false
+                    }
+                }
+            }
+
+            true
+        }
+
+        /// Check whether the destination may be written to, see [`SinkTOML::allow_external_destinations`].
+        ///
+        /// Relative destinations are resolved from the directory of the sink TOML.
+        pub fn check_destination(
+            &self,
+            pathspec: &github::GitHubPathspec,
+            destination: &Path,
+        ) -> Result<()> {
+            if self.allow_external_destinations || SinkTOML::_is_contained(destination) {
+                return Ok(());
+            }
+
+            Err(anyhow::anyhow!(
+                "The destination '{}' of '{pathspec}' is outside of '{}'! Set 'allow-external-destinations = true' to allow it",
+                destination.display(),
+                self.root().display()
+            ))
+        }
+
+        /// Check the destinations of all dependencies, see [`SinkTOML::check_destination`].
+        fn _check_destinations(&self) -> Result<()> {
+            for (pathspec, dependency) in self.dependencies.iter() {
+                if let DependencyType::Full(dependency) = dependency {
+                    self.check_destination(pathspec, &dependency.destination)?;
+                    if let Some(link) = &dependency.link {
+                        self.check_destination(pathspec, link)?;
+                    }
+                }
+            }
+
             Ok(())
         }
 
         /// Validate the sink TOML.
         ///
         /// This performs basic checks, such as checking for TOML errors, missing specification, etc.
-        fn _validate(&self) -> Result<()> {
-            if let Err(e) = self._validate_toml_syntax() {
+        /// Semantic issues are only logged as warnings, unless `strict` is set.
+        fn _validate(&self, contents: &str, strict: bool, mut issues: Vec<String>) -> Result<()> {
+            if let Err(e) = self._validate_toml_syntax(contents) {
                 return Err(e.context("Failed to parse TOML data!"));
             }
 
-            if let Err(e) = self._validate_toml_semantics() {
-                return Err(e.context("Failed to validate TOML data!"));
+            issues.extend(self._validate_toml_semantics());
+            for issue in issues.iter() {
+                if strict {
+                    error!("{issue}");
+                } else {
+                    warn!("{issue}");
+                }
+            }
+            if strict && !issues.is_empty() {
+                return Err(
+                    anyhow::anyhow!("Found {} issue(s) in strict mode!", issues.len())
+                        .context("Failed to validate TOML data!"),
+                );
             }
 
             Ok(())
         }
 
-        fn _from_file(path: &PathBuf) -> Result<SinkTOML> {
+        fn _from_file(path: &PathBuf, strict: bool) -> Result<SinkTOML> {
             debug!("Parsing sink TOML from '{}'...", path.display());
 
             let string_contents = fs::read_to_string(path.clone())?;
 
-            let mut sink_toml: SinkTOML = toml::from_str(&string_contents)?;
+            let mut sink_toml: SinkTOML = match toml::from_str(&string_contents) {
+                Ok(sink_toml) => sink_toml,
+                Err(e) => {
+                    return Err(match e.span() {
+                        Some(span) => {
+                            let message =
+                                SinkTOML::_unknown_field(e.message(), &string_contents, &span)
+                                    .unwrap_or_else(|| e.message().to_string());
+                            SourceError::new(&message, path, &string_contents, span).into()
+                        }
+                        None => e.into(),
+                    })
+                }
+            };
             sink_toml.path = PathBuf::from(path);
             sink_toml.formatted = string_contents.parse::<DocumentMut>()?;
+            let strict = strict || sink_toml.strict;
 
             // Extend with all files listed in include
-            for include_path in sink_toml.includes.iter() {
-                let included = SinkTOML::from_file(include_path);
+            let mut issues = Vec::new();
+            for include in sink_toml.includes.clone() {
+                let include_path = &include.path;
+                let resolved = match super::remote::resolve(&include) {
+                    Ok(resolved) => resolved,
+                    Err(e) => {
+                        issues.push(format!("{e:#}"));
+                        continue;
+                    }
+                };
+                let mut included = match SinkTOML::from_file_with(&resolved, strict) {
+                    Ok(included) => included,
+                    Err(e) => {
+                        issues.push(format!(
+                            "Failed to include '{}': {}",
+                            include_path.display(),
+                            e
+                        ));
+                        continue;
+                    }
+                };
 
-                if included.is_err() {
-                    warn!(
+                // Included sink TOMLs must not allow themselves to write anywhere
+                included.allow_external_destinations = sink_toml.allow_external_destinations;
+                if let Err(e) = included._check_destinations() {
+                    issues.push(format!(
                         "Failed to include '{}': {}",
                         include_path.display(),
-                        included.unwrap_err()
-                    );
+                        e
+                    ));
                     continue;
                 }
 
                 info!("Including {}...", include_path.display(),);
-
-                // TODO: Implement merge
-                error!("Including is not yet implemented!");
+                issues.extend(sink_toml._merge(&include, included)?);
             }
+            issues.extend(sink_toml._apply_overrides());
 
             // Check for invalid entries
-            sink_toml._validate()?;
+            sink_toml._validate(&string_contents, strict, issues)?;
+            sink_toml._check_destinations()?;
 
             // Fill the missing pathspec, as serde skips over it
             for (pathspec, dependency) in sink_toml.dependencies.iter_mut() {
@@ -142,23 +794,297 @@ pub mod toml {
 
             Ok(sink_toml)
         }
+        /// Merge the dependencies of an included sink TOML, applying the options of the include.
+        ///
+        /// Dependencies outside of the owner namespace are skipped, collisions are resolved by [`Include::on_conflict`].
+        /// Returns the issues found while merging.
+        fn _merge(&mut self, include: &Include, included: SinkTOML) -> Result<Vec<String>> {
+            let mut issues = Vec::new();
+            let mut pathspecs: Vec<_> = included.dependencies.keys().cloned().collect();
+            pathspecs.sort();
+
+            for pathspec in pathspecs {
+                let dependency = &included.dependencies[&pathspec];
+                if let Some(owner) = &include.owner {
+                    if pathspec.owner() != owner {
+                        issues.push(format!(
+                            "'{pathspec}' of '{}' is not owned by '{owner}', skipping it!",
+                            include.path.display()
+                        ));
+                        continue;
+                    }
+                }
+                if self.dependencies.contains_key(&pathspec) {
+                    let existing = match self.included.get(&pathspec) {
+                        Some(path) => format!("the one of '{}'", path.display()),
+                        None => String::from("a local one"),
+                    };
+                    match include.on_conflict {
+                        None => {
+                            issues.push(format!(
+                                "'{pathspec}' of '{}' collides with {existing}, skipping it!",
+                                include.path.display()
+                            ));
+                            continue;
+                        }
+                        Some(OnConflict::Ours) => {
+                            debug!(
+                                "'{pathspec}' of '{}' collides with {existing}, keeping it!",
+                                include.path.display()
+                            );
+                            continue;
+                        }
+                        Some(OnConflict::Theirs) => debug!(
+                            "'{pathspec}' of '{}' replaces {existing}!",
+                            include.path.display()
+                        ),
+                        Some(OnConflict::Error) => {
+                            return Err(anyhow::anyhow!(
+                                "'{pathspec}' of '{}' collides with {existing}!",
+                                include.path.display()
+                            ))
+                        }
+                    }
+                }
+
+                let checked =
+                    dependency
+                        .to_github_dependency(&pathspec)
+                        .and_then(|mut github_dependency| {
+                            if let Some(prefix) = &include.prefix {
+                                github_dependency.destination =
+                                    prefix.join(&github_dependency.destination);
+                            }
+                            self.check_destination(&pathspec, &github_dependency.destination)?;
+                            if let Some(link) = &github_dependency.link {
+                                self.check_destination(&pathspec, link)?;
+                            }
+                            Ok(github_dependency)
+                        });
+                let dependency = match (&include.prefix, checked) {
+                    (None, Ok(github_dependency)) if !github_dependency.has_hooks() => {
+                        dependency.clone()
+                    }
+                    (_, Ok(mut github_dependency)) => {
+                        // Includes are shared, they must not run commands on every machine including them
+                        if github_dependency.has_hooks() {
+                            issues.push(format!(
+                                "Ignoring the hooks of '{pathspec}' of '{}', included dependencies never run hooks!",
+                                include.path.display()
+                            ));
+                            github_dependency.pre_install.clear();
+                            github_dependency.post_install.clear();
+                        }
+                        DependencyType::Full(Box::new(github_dependency))
+                    }
+                    (_, Err(e)) => {
+                        issues.push(format!(
+                            "Failed to include '{pathspec}' of '{}': {e}",
+                            include.path.display()
+                        ));
+                        continue;
+                    }
+                };
+                self.included.insert(pathspec.clone(), include.path.clone());
+                self.dependencies.insert(pathspec, dependency);
+            }
+
+            Ok(issues)
+        }
+
+        /// Apply the overrides to the dependencies merged from the includes.
+        ///
+        /// Returns the issues found, e.g. overrides of local or missing dependencies.
+        fn _apply_overrides(&mut self) -> Vec<String> {
+            let mut issues = Vec::new();
+            let mut pathspecs: Vec<_> = self.overrides.keys().cloned().collect();
+            pathspecs.sort();
+
+            for pathspec in pathspecs {
+                let Some(dependency) = self.dependencies.get(&pathspec) else {
+                    issues.push(format!(
+                        "The override of '{pathspec}' matches no dependency, ignoring it!"
+                    ));
+                    continue;
+                };
+                if !self.included.contains_key(&pathspec) {
+                    issues.push(format!(
+                        "'{pathspec}' is not included, change it directly instead of overriding it!"
+                    ));
+                    continue;
+                }
+
+                let over = &self.overrides[&pathspec];
+                let overridden =
+                    dependency
+                        .to_github_dependency(&pathspec)
+                        .and_then(|mut github_dependency| {
+                            if let Some(version) = &over.version {
+                                github_dependency.version = version.clone();
+                            }
+                            if let Some(destination) = &over.destination {
+                                self.check_destination(&pathspec, destination)?;
+                                github_dependency.destination = destination.clone();
+                            }
+                            Ok(github_dependency)
+                        });
+                match overridden {
+                    Ok(github_dependency) => {
+                        debug!("Overriding '{pathspec}'...");
+                        self.dependencies
+                            .insert(pathspec, DependencyType::Full(Box::new(github_dependency)));
+                    }
+                    Err(e) => issues.push(format!("Failed to override '{pathspec}': {e}")),
+                }
+            }
+
+            issues
+        }
+
         /// Try loading a sink TOML from a file.
         pub fn from_file(path: &PathBuf) -> Result<SinkTOML, SinkError> {
-            match SinkTOML::_from_file(path) {
+            SinkTOML::from_file_with(path, false)
+        }
+
+        /// Try loading a sink TOML from a file, optionally turning all warnings into errors.
+        ///
+        /// Strict mode is also enabled by setting ``strict = true`` in the sink TOML itself.
+        pub fn from_file_with(path: &PathBuf, strict: bool) -> Result<SinkTOML, SinkError> {
+            match SinkTOML::_from_file(path, strict) {
                 Ok(sink_toml) => Ok(sink_toml),
                 Err(e) => Err(SinkError::Any(e.context("Failed to load Sink TOML!"))),
             }
         }
 
+        /// Create an empty sink TOML in memory.
+        ///
+        /// The document only contains the ``[dependencies]`` table. Nothing is written until it is saved,
+        /// e.g. via [`SinkTOML::save`] or by adding a dependency.
+        pub fn new(path: &Path) -> SinkTOML {
+            let mut formatted = DocumentMut::new();
+            formatted["dependencies"] = toml_edit::table();
+
+            SinkTOML {
+                default_owner: None,
+                includes: Vec::new(),
+                strict: false,
+                allow_external_destinations: false,
+                link_executables: false,
+                policy: Policy::default(),
+                workspace: None,
+                hooks: super::hooks::Hooks::default(),
+                dependencies: HashMap::new(),
+                overrides: HashMap::new(),
+                path: path.to_path_buf(),
+                included: HashMap::new(),
+                formatted,
+            }
+        }
+
+        /// Set the default owner, see [`SinkTOML::default_owner`].
+        ///
+        /// It is placed at the top of the document, above the dependencies.
+        pub fn with_default_owner(mut self, default_owner: &str) -> SinkTOML {
+            self.default_owner = Some(default_owner.to_string());
+
+            // Values must be placed before any table
+            let mut formatted = DocumentMut::new();
+            formatted["default-owner"] = toml_edit::value(default_owner);
+            for (key, item) in self.formatted.iter() {
+                if key != "default-owner" {
+                    formatted.insert(key, item.clone());
+                }
+            }
+            formatted.set_trailing(self.formatted.trailing().as_str().unwrap_or_default());
+            self.formatted = formatted;
+
+            self
+        }
+
+        /// Returns the directory the sink TOML is in.
+        ///
+        /// Relative destinations and the lockfile are resolved from here.
+        pub fn root(&self) -> PathBuf {
+            match self.path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+                _ => PathBuf::from("."),
+            }
+        }
+
         /// Returns the TOML representation of the parsed file.
         pub fn to_toml(&self) -> String {
             self.formatted.to_string()
         }
 
+        /// Returns the item at the given (dotted) key, e.g. ``dependencies."owner/repo:pattern".version``.
+        pub fn get_item(&self, key: &str) -> Result<Option<&toml_edit::Item>> {
+            let keys = toml_edit::Key::parse(key)
+                .map_err(|e| anyhow::anyhow!("Invalid key '{key}': {e}"))?;
+
+            let mut item = self.formatted.as_item();
+            for key in keys.iter() {
+                match item.get(key.get()) {
+                    Some(child) => item = child,
+                    None => return Ok(None),
+                }
+            }
+
+            Ok(Some(item))
+        }
+
+        /// Returns the value at the given (dotted) key as it should be printed.
+        ///
+        /// Strings are returned without quotes, everything else in its TOML representation.
+        pub fn get_value(&self, key: &str) -> Result<String> {
+            let Some(item) = self.get_item(key)? else {
+                return Err(anyhow::anyhow!("Key '{key}' does not exist!"));
+            };
+
+            let value = match item {
+                toml_edit::Item::Value(toml_edit::Value::String(string)) => string.value().clone(),
+                toml_edit::Item::Value(value) => value.clone().decorated("", "").to_string(),
+                toml_edit::Item::Table(table) => table.to_string(),
+                toml_edit::Item::ArrayOfTables(tables) => tables.to_string(),
+                toml_edit::Item::None => String::new(),
+            };
+
+            Ok(value.trim().to_string())
+        }
+
+        /// Returns the pathspecs of all dependencies matching the given glob pattern, sorted.
+        ///
+        /// A dependency listed verbatim is matched exactly, even though its pattern contains glob characters.
+        /// Patterns without an owner are prefixed with the default owner.
+        pub fn matching_dependencies(&self, pattern: &str) -> Result<Vec<github::GitHubPathspec>> {
+            let pattern = match (pattern.contains('/'), &self.default_owner) {
+                (false, Some(owner)) => format!("{owner}/{pattern}"),
+                _ => pattern.to_string(),
+            };
+
+            if let Some((pathspec, _)) = self
+                .dependencies
+                .iter()
+                .find(|(pathspec, _)| pathspec.to_string() == pattern)
+            {
+                return Ok(vec![pathspec.clone()]);
+            }
+
+            let glob = glob::Pattern::new(&pattern)?;
+            let mut matching: Vec<github::GitHubPathspec> = self
+                .dependencies
+                .keys()
+                .filter(|pathspec| glob.matches(&pathspec.to_string()))
+                .cloned()
+                .collect();
+            matching.sort();
+
+            Ok(matching)
+        }
+
         fn _save(&self) -> Result<()> {
             debug!("Saving sink TOML to '{}'...", self.path.display());
 
-            fs::write(&self.path, self.to_toml())?;
+            super::atomic::write(&self.path, self.to_toml())?;
 
             debug!("Saving done!");
 
@@ -168,7 +1094,7 @@ pub mod toml {
         /// Save the current sink TOML to the file.
         ///
         /// This writes the contents from [`SinkTOML::to_toml()`] back to the file at [`SinkTOML::path`].
-        fn save(&self) -> Result<()> {
+        pub fn save(&self) -> Result<()> {
             match self._save() {
                 Ok(_) => Ok(()),
                 Err(e) => Err(e.context("Failed to save Sink TOML!")),
@@ -194,18 +1120,1383 @@ pub mod toml {
 
             Ok(self)
         }
+
+        /// Returns the part of the decor prefix of a removed entry that does not belong to it.
+        ///
+        /// Comment lines directly above the entry are part of it, everything up to them (e.g. blank lines
+        /// or comments separated by a blank line) belongs to the surroundings.
+        fn _detached_prefix(prefix: &str) -> &str {
+            // The indentation of the entry itself follows the last newline
+            let Some(last_newline) = prefix.rfind('\n') else {
+                return "";
+            };
+            let lines = &prefix[..=last_newline];
+
+            let mut end = lines.len();
+            for line in lines.split_inclusive('\n').rev() {
+                if !line.trim_start().starts_with('#') {
+                    break;
+                }
+                end -= line.len();
+            }
+
+            &lines[..end]
+        }
+
+        /// Join a detached prefix with the prefix of its new neighbor, without piling up blank lines.
+        fn _join_prefix(detached: &str, current: &str) -> String {
+            if detached.trim().is_empty() && current.starts_with('\n') {
+                return current.to_string();
+            }
+            if detached.ends_with("\n\n") {
+                return format!("{detached}{}", current.trim_start_matches('\n'));
+            }
+
+            format!("{detached}{current}")
+        }
+
+        /// Prepend the given prefix to the first table after the given position.
+        ///
+        /// If there is no such table, the prefix is moved to the end of the document.
+        fn _prepend_to_next_table(&mut self, position: Option<usize>, prefix: &str) {
+            let mut tables: Vec<(usize, Vec<String>)> = Vec::new();
+            for (key, item) in self.formatted.iter() {
+                let Some(table) = item.as_table() else {
+                    continue;
+                };
+                tables.extend(table.position().map(|p| (p, vec![key.to_string()])));
+                for (sub_key, sub_item) in table.iter() {
+                    if let Some(position) = sub_item.as_table().and_then(|t| t.position()) {
+                        tables.push((position, vec![key.to_string(), sub_key.to_string()]));
+                    }
+                }
+            }
+            let next = tables
+                .into_iter()
+                .filter(|(table_position, _)| position.is_some_and(|p| *table_position > p))
+                .min_by_key(|(table_position, _)| *table_position);
+
+            let next_table = next.and_then(|(_, path)| {
+                path.iter()
+                    .try_fold(self.formatted.as_item_mut(), |item, key| item.get_mut(key))
+                    .and_then(|item| item.as_table_mut())
+            });
+            match next_table {
+                Some(table) => {
+                    let decor = table.decor_mut();
+                    let current = decor
+                        .prefix()
+                        .and_then(|prefix| prefix.as_str())
+                        .unwrap_or_default();
+                    decor.set_prefix(SinkTOML::_join_prefix(prefix, current));
+                }
+                None => {
+                    let trailing = self.formatted.trailing().as_str().unwrap_or_default();
+                    let trailing = format!("{}{trailing}", prefix.trim_end());
+                    self.formatted.set_trailing(trailing);
+                }
+            }
+        }
+
+        /// Remove the dependency from [`SinkTOML::formatted`], keeping the surrounding comments and whitespace.
+        fn _remove_formatted(&mut self, key: &str) {
+            let Some(dependencies) = self
+                .formatted
+                .get_mut("dependencies")
+                .and_then(|dependencies| dependencies.as_table_mut())
+            else {
+                // Inline dependency tables have no comments to keep
+                if let Some(dependencies) = self
+                    .formatted
+                    .get_mut("dependencies")
+                    .and_then(|dependencies| dependencies.as_table_like_mut())
+                {
+                    dependencies.remove(key);
+                }
+                return;
+            };
+
+            let Some(index) = dependencies.iter().position(|(k, _)| k == key) else {
+                return;
+            };
+            let subtable_position = dependencies
+                .get(key)
+                .and_then(|item| item.as_table())
+                .map(|table| table.position());
+            let prefix = match dependencies.get(key).and_then(|item| item.as_table()) {
+                Some(table) => table.decor().prefix(),
+                None => dependencies
+                    .key(key)
+                    .and_then(|key| key.leaf_decor().prefix()),
+            }
+            .and_then(|prefix| prefix.as_str())
+            .unwrap_or_default()
+            .to_string();
+
+            dependencies.remove(key);
+            let detached = SinkTOML::_detached_prefix(&prefix).to_string();
+            if detached.is_empty() {
+                return;
+            }
+
+            let position = match subtable_position {
+                Some(position) => position,
+                None => {
+                    // The next key-value pair of the dependencies is the closest neighbor
+                    let next = dependencies
+                        .iter()
+                        .skip(index)
+                        .find(|(_, item)| item.is_value())
+                        .map(|(k, _)| k.to_string());
+                    if let Some(mut next_key) = next.and_then(|next| dependencies.key_mut(&next)) {
+                        let decor = next_key.leaf_decor_mut();
+                        let current = decor
+                            .prefix()
+                            .and_then(|prefix| prefix.as_str())
+                            .unwrap_or_default();
+                        decor.set_prefix(SinkTOML::_join_prefix(&detached, current));
+                        return;
+                    }
+                    dependencies.position()
+                }
+            };
+            self._prepend_to_next_table(position, &detached);
+        }
+
+        /// Remove a dependency from the sink TOML.
+        ///
+        /// This will remove the dependency from the sink TOML (incl. [`SinkTOML::formatted`]) and save it to the file.
+        /// Comments directly above the dependency are removed with it, all other comments and whitespace are kept.
+        pub fn remove_dependency(mut self, pathspec: &github::GitHubPathspec) -> Result<Self> {
+            if self.dependencies.remove(pathspec).is_none() {
+                return Err(anyhow::anyhow!("Dependency '{pathspec}' does not exist!"));
+            }
+            self._remove_formatted(&pathspec.to_string());
+
+            self.save()?;
+
+            Ok(self)
+        }
+
+        /// Rename the dependency in [`SinkTOML::formatted`], keeping its position, comments and whitespace.
+        fn _rename_formatted(&mut self, from: &str, to: &str) {
+            let Some(dependencies) = self
+                .formatted
+                .get_mut("dependencies")
+                .and_then(|dependencies| dependencies.as_table_like_mut())
+            else {
+                return;
+            };
+
+            // Keys cannot be renamed in place, so re-insert all entries to keep their order
+            let keys: Vec<String> = dependencies
+                .iter()
+                .map(|(key, _)| key.to_string())
+                .collect();
+            let entries: Vec<(toml_edit::Key, toml_edit::Item)> = keys
+                .iter()
+                .filter_map(|key| {
+                    let formatted = dependencies.key(key)?.clone();
+                    Some((formatted, dependencies.remove(key)?))
+                })
+                .collect();
+            for (key, item) in entries {
+                let key = match key.get() == from {
+                    true => toml_edit::Key::new(to).with_leaf_decor(key.leaf_decor().clone()),
+                    false => key,
+                };
+                dependencies.entry_format(&key).or_insert(item);
+            }
+        }
+
+        /// Rename a dependency in the sink TOML, e.g. to change its pattern.
+        ///
+        /// The entry itself is kept as is, including the surrounding comments and whitespace.
+        pub fn rename_dependency(
+            mut self,
+            from: &github::GitHubPathspec,
+            to: github::GitHubPathspec,
+        ) -> Result<Self> {
+            if self.dependencies.contains_key(&to) {
+                return Err(anyhow::anyhow!("Dependency '{to}' already exists!"));
+            }
+            let Some(mut dependency) = self.dependencies.remove(from) else {
+                return Err(anyhow::anyhow!("Dependency '{from}' does not exist!"));
+            };
+            if let DependencyType::Full(full) = &mut dependency {
+                full.pathspec = to.clone();
+            }
+
+            self._rename_formatted(&from.to_string(), &to.to_string());
+            self.dependencies.insert(to, dependency);
+
+            self.save()?;
+
+            Ok(self)
+        }
+
+        /// Set the value of the key in the table, keeping the comments and whitespace around an existing value.
+        fn _set_formatted_value(
+            table: &mut dyn toml_edit::TableLike,
+            key: &str,
+            value: toml_edit::Value,
+        ) {
+            match table.get_mut(key).and_then(|item| item.as_value_mut()) {
+                Some(current) => {
+                    let decor = current.decor().clone();
+                    *current = value;
+                    *current.decor_mut() = decor;
+                }
+                None => {
+                    table.insert(key, toml_edit::Item::Value(value));
+                }
+            }
+        }
+
+        fn _update_dependency(
+            &mut self,
+            pathspec: &github::GitHubPathspec,
+            update: &DependencyUpdate,
+        ) -> Result<DependencyType> {
+            let Some(current) = self.dependencies.get(pathspec) else {
+                return Err(anyhow::anyhow!("Dependency '{pathspec}' does not exist!"));
+            };
+            let previous = current.clone();
+
+            // The short form can only hold a version
+            let updated = match (current, update.is_version_only()) {
+                (DependencyType::Version(version), true) => DependencyType::Version(
+                    update.version.clone().unwrap_or_else(|| version.clone()),
+                ),
+                (DependencyType::Invalid(_), _) => {
+                    return Err(anyhow::anyhow!(
+                        "Invalid dependency entry for '{pathspec}'!"
+                    ))
+                }
+                (_, _) => {
+                    let mut dependency = current.to_github_dependency(pathspec)?;
+                    if let Some(version) = &update.version {
+                        dependency.version = version.clone();
+                    }
+                    if let Some(destination) = &update.destination {
+                        self.check_destination(pathspec, destination)?;
+                        dependency.destination = destination.clone();
+                    }
+                    if let Some(gitignore) = update.gitignore {
+                        dependency.gitignore = gitignore;
+                    }
+                    DependencyType::Full(Box::new(dependency))
+                }
+            };
+
+            let Some(formatted) = self
+                .formatted
+                .get_mut("dependencies")
+                .and_then(|dependencies| dependencies.get_mut(pathspec.to_string()))
+            else {
+                return Err(anyhow::anyhow!("Dependency '{pathspec}' does not exist!"));
+            };
+
+            match (&updated, formatted.as_value_mut()) {
+                (DependencyType::Version(version), Some(value)) => {
+                    let decor = value.decor().clone();
+                    *value = toml_edit::Value::from(version.to_string());
+                    *value.decor_mut() = decor;
+                }
+                // Turn the short form into an inline table, keeping its trailing comment
+                (DependencyType::Full(dependency), Some(value)) if value.is_str() => {
+                    let mut table = toml_edit::InlineTable::new();
+                    table.insert("version", dependency.version.to_string().into());
+                    if update.destination.is_some() {
+                        table.insert(
+                            "destination",
+                            dependency.destination.display().to_string().into(),
+                        );
+                    }
+                    if update.gitignore.is_some() {
+                        table.insert("gitignore", dependency.gitignore.into());
+                    }
+
+                    let decor = value.decor().clone();
+                    *value = toml_edit::Value::InlineTable(table);
+                    *value.decor_mut() = decor;
+                }
+                (_, _) => {
+                    let Some(table) = formatted.as_table_like_mut() else {
+                        return Err(anyhow::anyhow!(
+                            "Invalid dependency entry for '{pathspec}'!"
+                        ));
+                    };
+                    if let Some(version) = &update.version {
+                        SinkTOML::_set_formatted_value(
+                            table,
+                            "version",
+                            version.to_string().into(),
+                        );
+                    }
+                    if let Some(destination) = &update.destination {
+                        SinkTOML::_set_formatted_value(
+                            table,
+                            "destination",
+                            destination.display().to_string().into(),
+                        );
+                    }
+                    if let Some(gitignore) = update.gitignore {
+                        SinkTOML::_set_formatted_value(table, "gitignore", gitignore.into());
+                    }
+                }
+            }
+            self.dependencies.insert(pathspec.clone(), updated);
+
+            Ok(previous)
+        }
+        /// Modify an existing dependency.
+        ///
+        /// Only the keys changed by the update are touched in [`SinkTOML::formatted`], all comments and
+        /// whitespace are kept. A dependency in the short form is turned into an inline table if needed.
+        /// Returns the updated sink TOML, which has been saved to the file, and the previous value of the dependency.
+        pub fn update_dependency(
+            mut self,
+            pathspec: &github::GitHubPathspec,
+            update: &DependencyUpdate,
+        ) -> Result<(Self, DependencyType)> {
+            let previous = match self._update_dependency(pathspec, update) {
+                Ok(previous) => previous,
+                Err(e) => return Err(e.context(format!("Failed to update '{pathspec}'!"))),
+            };
+
+            self.save()?;
+
+            Ok((self, previous))
+        }
+
+        /// Set the version of a dependency.
+        ///
+        /// This will update both the dependency and [`SinkTOML::formatted`] and save it to the file.
+        pub fn set_version(
+            self,
+            pathspec: &github::GitHubPathspec,
+            version: &github::GitHubVersion,
+        ) -> Result<Self> {
+            let update = DependencyUpdate {
+                version: Some(version.clone()),
+                ..Default::default()
+            };
+
+            Ok(self.update_dependency(pathspec, &update)?.0)
+        }
+
+        fn _unset(&mut self, key: &str) -> Result<()> {
+            // Remove a single entry of the includes
+            if let Some((key, value)) = key.split_once('=') {
+                if key.trim() != "includes" {
+                    return Err(anyhow::anyhow!(
+                        "Only entries of 'includes' can be unset individually!"
+                    ));
+                }
+
+                let value = value.trim();
+                let Some(position) = self
+                    .includes
+                    .iter()
+                    .position(|include| include.path.as_os_str() == value)
+                else {
+                    return Err(anyhow::anyhow!("'{value}' is not included!"));
+                };
+                self.includes.remove(position);
+
+                let includes = self
+                    .formatted
+                    .get_mut("includes")
+                    .and_then(|includes| includes.as_array_mut())
+                    .ok_or_else(|| anyhow::anyhow!("'includes' is not an array!"))?;
+                let index = includes.iter().position(|include| {
+                    include.as_str().or_else(|| {
+                        include
+                            .as_inline_table()
+                            .and_then(|table| table.get("path"))
+                            .and_then(|path| path.as_str())
+                    }) == Some(value)
+                });
+                if let Some(index) = index {
+                    // Comments trailing the previous entry are part of the prefix of the removed one
+                    let removed = includes.remove(index);
+                    let prefix = removed
+                        .decor()
+                        .prefix()
+                        .and_then(|prefix| prefix.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    if index < includes.len() {
+                        if let Some(next) = includes.get_mut(index) {
+                            next.decor_mut().set_prefix(prefix);
+                        }
+                    } else {
+                        let trailing = includes.trailing().as_str().unwrap_or_default();
+                        let indent = trailing.rsplit('\n').next().unwrap_or_default();
+                        let trailing = format!("{}{indent}", prefix.trim_end_matches([' ', '\t']));
+                        includes.set_trailing(trailing);
+                    }
+                }
+
+                return Ok(());
+            }
+
+            match key.trim() {
+                "default-owner" => self.default_owner = None,
+                "includes" => self.includes.clear(),
+                "strict" => self.strict = false,
+                "allow-external-destinations" => self.allow_external_destinations = false,
+                "link-executables" => self.link_executables = false,
+                "policy" => self.policy = Policy::default(),
+                "workspace" => self.workspace = None,
+                "hooks" => self.hooks = super::hooks::Hooks::default(),
+                "overrides" => self.overrides.clear(),
+                "dependencies" => {
+                    return Err(anyhow::anyhow!(
+                        "Dependencies can't be unset, use 'sink remove' instead!"
+                    ))
+                }
+                key => return Err(anyhow::anyhow!("'{key}' is not an optional key!")),
+            }
+            if self.formatted.remove(key.trim()).is_none() {
+                return Err(anyhow::anyhow!("Key '{}' is not set!", key.trim()));
+            }
+
+            Ok(())
+        }
+        /// Remove an optional top-level key from the sink TOML and save it to the file.
+        ///
+        /// A single entry of the includes can be removed with ``includes=path``.
+        /// The formatting of the rest of the document is preserved.
+        pub fn unset(mut self, key: &str) -> Result<Self> {
+            if let Err(e) = self._unset(key) {
+                return Err(e.context(format!("Failed to unset '{key}'!")));
+            }
+
+            self.save()?;
+
+            Ok(self)
+        }
     }
 
-    #[derive(Serialize, Deserialize, Debug)]
+    /// An entry of [`SinkTOML::includes`].
+    ///
+    /// Either just the path of the included sink TOML, or a table like `{ path = "shared.toml", prefix = "vendor/shared" }`.
+    /// Sink TOMLs in GitHub repositories are fetched, see [`crate::remote::RemoteInclude`].
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    #[serde(from = "_IncludeValue")]
+    pub struct Include {
+        /// The path to the included sink TOML, relative to the current working directory.
+        ///
+        /// Remote sink TOMLs are written as `owner/repo:path/sink.toml@tag` or as their URL on GitHub.
+        pub path: PathBuf,
+
+        /// Optional: Prefix the destinations of the included dependencies, e.g. 'vendor/shared'.
+        ///
+        /// Destinations of included dependencies are relative to the including sink TOML.
+        pub prefix: Option<PathBuf>,
+
+        /// Optional: Only accept dependencies of this owner from the included sink TOML.
+        pub owner: Option<String>,
+
+        /// Optional: The checksum the included sink TOML must match, e.g. to pin a remote one.
+        ///
+        /// Written as 'algorithm:hex', see [`crate::lock::Algorithm`]. A bare hex digest is taken as SHA-256.
+        pub checksum: Option<String>,
+
+        /// Optional: What to do with included dependencies that already exist, locally or in an earlier include.
+        ///
+        /// If not set, they are skipped with a warning.
+        pub on_conflict: Option<OnConflict>,
+    }
+    impl From<PathBuf> for Include {
+        fn from(path: PathBuf) -> Self {
+            Include {
+                path,
+                prefix: None,
+                owner: None,
+                checksum: None,
+                on_conflict: None,
+            }
+        }
+    }
+
+    /// The table form of an [`Include`].
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    struct _IncludeTable {
+        path: PathBuf,
+        #[serde(default)]
+        prefix: Option<PathBuf>,
+        #[serde(default)]
+        owner: Option<String>,
+        #[serde(default, alias = "sha256")]
+        checksum: Option<String>,
+        #[serde(default)]
+        on_conflict: Option<OnConflict>,
+    }
+
+    /// The forms an [`Include`] can be written in.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum _IncludeValue {
+        Path(PathBuf),
+        Table(_IncludeTable),
+    }
+    impl From<_IncludeValue> for Include {
+        fn from(value: _IncludeValue) -> Self {
+            match value {
+                _IncludeValue::Path(path) => Include::from(path),
+                _IncludeValue::Table(table) => Include {
+                    path: table.path,
+                    prefix: table.prefix,
+                    owner: table.owner,
+                    checksum: table.checksum,
+                    on_conflict: table.on_conflict,
+                },
+            }
+        }
+    }
+
+    /// An entry of [`SinkTOML::overrides`], changing a dependency merged from an include without editing the included file.
+    ///
+    /// Either just the version, or a table like `{ version = "v2.0.0", destination = "tools" }`.
+    #[derive(Serialize, Deserialize, Debug, Clone, Default)]
+    #[serde(from = "_OverrideValue")]
+    pub struct Override {
+        /// Optional: The version to install instead of the included one.
+        pub version: Option<github::GitHubVersion>,
+
+        /// Optional: The destination to install to instead of the included one, relative to the including sink TOML.
+        pub destination: Option<PathBuf>,
+    }
+
+    /// The table form of an [`Override`].
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case", deny_unknown_fields)]
+    struct _OverrideTable {
+        #[serde(default)]
+        version: Option<github::GitHubVersion>,
+        #[serde(default)]
+        destination: Option<PathBuf>,
+    }
+
+    /// The forms an [`Override`] can be written in.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum _OverrideValue {
+        Version(github::GitHubVersion),
+        Table(_OverrideTable),
+    }
+    impl From<_OverrideValue> for Override {
+        fn from(value: _OverrideValue) -> Self {
+            match value {
+                _OverrideValue::Version(version) => Override {
+                    version: Some(version),
+                    destination: None,
+                },
+                _OverrideValue::Table(table) => Override {
+                    version: table.version,
+                    destination: table.destination,
+                },
+            }
+        }
+    }
+
+    /// How an [`Include`] resolves dependencies that already exist in the including sink TOML.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+    #[serde(rename_all = "kebab-case")]
+    pub enum OnConflict {
+        /// Fail loading the sink TOML.
+        Error,
+        /// Keep the existing dependency, silently skipping the included one.
+        Ours,
+        /// Replace the existing dependency with the included one.
+        Theirs,
+    }
+
+    /// Changes to apply to an existing dependency, see [`SinkTOML::update_dependency`].
+    ///
+    /// Fields that are not set are kept as they are.
+    #[derive(Debug, Default, Clone)]
+    pub struct DependencyUpdate {
+        pub version: Option<github::GitHubVersion>,
+        pub destination: Option<PathBuf>,
+        pub gitignore: Option<bool>,
+    }
+    impl DependencyUpdate {
+        /// Whether only the version is changed, which the short form can hold.
+        pub fn is_version_only(&self) -> bool {
+            self.destination.is_none() && self.gitignore.is_none()
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone)]
     #[serde(untagged)]
     pub enum DependencyType {
         /// Single line declaration with only the version
         Version(github::GitHubVersion),
 
         /// Full declaration with all fields specified
-        Full(github::GitHubDependency),
+        Full(Box<github::GitHubDependency>),
 
         /// Catch all potential TOML mismatches to better pinpoint the problem
         Invalid(toml::Value),
     }
+    impl DependencyType {
+        /// Returns why the value of an invalid dependency entry could not be parsed.
+        fn invalid_reason(value: &toml::Value) -> String {
+            match value {
+                toml::Value::Table(_) => match value.clone().try_into::<github::GitHubDependency>()
+                {
+                    Ok(_) => String::from("the entry could not be parsed"),
+                    Err(e) => e.message().to_string(),
+                },
+                _ => format!("expected a version or a table, found {}", value.type_str()),
+            }
+        }
+
+        /// Converts the entry into a full [`github::GitHubDependency`].
+        ///
+        /// Fails for [`DependencyType::Invalid`] entries.
+        pub fn to_github_dependency(
+            &self,
+            pathspec: &github::GitHubPathspec,
+        ) -> Result<github::GitHubDependency> {
+            match self {
+                DependencyType::Full(github_dependency) => Ok(github_dependency.as_ref().clone()),
+                DependencyType::Version(version) => github::GitHubDependency::new(
+                    pathspec.to_string(),
+                    None,
+                    Some(version.to_owned()),
+                    true,
+                    &None,
+                ),
+                DependencyType::Invalid(_) => Err(anyhow::anyhow!(
+                    "Invalid dependency entry for '{}'!",
+                    pathspec
+                )),
+            }
+        }
+    }
+
+    /* ---------- [ Tests ] ---------- */
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// Returns a temporary path for a sink TOML used by a test.
+        fn _path(name: &str) -> PathBuf {
+            std::env::temp_dir().join(format!("sink-test-{name}-{}.toml", std::process::id()))
+        }
+
+        /// Write the contents to a temporary file and load it as sink TOML.
+        fn _load(name: &str, contents: &str) -> SinkTOML {
+            let path = _path(name);
+            fs::write(&path, contents).unwrap();
+            let sink_toml = SinkTOML::from_file(&path).unwrap();
+            fs::remove_file(&path).unwrap();
+
+            sink_toml
+        }
+
+        #[test]
+        fn test_get_value() {
+            let sink_toml = _load(
+                "get",
+                r#"
+default-owner = "owner"
+
+[dependencies]
+"owner/repo:short" = "v1.0.0"
+"owner/repo:full" = { version = "latest", destination = "dest", gitignore = false }
+"#,
+            );
+
+            assert_eq!(sink_toml.get_value("default-owner").unwrap(), "owner");
+            assert_eq!(
+                sink_toml
+                    .get_value(r#"dependencies."owner/repo:short""#)
+                    .unwrap(),
+                "v1.0.0"
+            );
+            assert_eq!(
+                sink_toml
+                    .get_value(r#"dependencies."owner/repo:full".gitignore"#)
+                    .unwrap(),
+                "false"
+            );
+            assert!(sink_toml.get_value("missing").is_err());
+            assert!(sink_toml.get_value("dependencies.").is_err());
+        }
+
+        #[test]
+        fn test_matching_dependencies() {
+            let sink_toml = _load(
+                "matching",
+                r#"
+default-owner = "owner"
+
+[dependencies]
+"owner/repo:*.zip" = "v1.0.0"
+"owner/repo:*.tar.gz" = "v1.0.0"
+"owner/other:file" = "latest"
+"someone/repo:file" = "latest"
+"#,
+            );
+            let matching = |pattern: &str| -> Vec<String> {
+                sink_toml
+                    .matching_dependencies(pattern)
+                    .unwrap()
+                    .iter()
+                    .map(|pathspec| pathspec.to_string())
+                    .collect()
+            };
+
+            assert_eq!(matching("owner/repo:*.zip"), vec!["owner/repo:*.zip"]);
+            assert_eq!(
+                matching("owner/repo:*"),
+                vec!["owner/repo:*.tar.gz", "owner/repo:*.zip"]
+            );
+            assert_eq!(
+                matching("owner/*"),
+                vec![
+                    "owner/other:file",
+                    "owner/repo:*.tar.gz",
+                    "owner/repo:*.zip"
+                ]
+            );
+            assert_eq!(matching("other:*"), vec!["owner/other:file"]);
+            assert_eq!(matching("*:file"), vec!["owner/other:file"]);
+            assert_eq!(
+                matching("*/*:file"),
+                vec!["owner/other:file", "someone/repo:file"]
+            );
+            assert!(matching("missing/*").is_empty());
+        }
+
+        #[test]
+        fn test_set_version() {
+            let path = _path("set-version");
+            fs::write(
+                &path,
+                r#"[dependencies]
+"owner/repo:short" = "v1.0.0" # pinned
+"owner/repo:full" = { version = "v1.0.0", destination = "dest" }
+"#,
+            )
+            .unwrap();
+
+            let short = github::GitHubPathspec::try_from(String::from("owner/repo:short")).unwrap();
+            let full = github::GitHubPathspec::try_from(String::from("owner/repo:full")).unwrap();
+            let version = github::GitHubVersion::from("v2.0.0");
+
+            let sink_toml = SinkTOML::from_file(&path).unwrap();
+            let sink_toml = sink_toml.set_version(&short, &version).unwrap();
+            let sink_toml = sink_toml.set_version(&full, &version).unwrap();
+
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                r#"[dependencies]
+"owner/repo:short" = "v2.0.0" # pinned
+"owner/repo:full" = { version = "v2.0.0", destination = "dest" }
+"#
+            );
+            assert!(matches!(
+                &sink_toml.dependencies[&full],
+                DependencyType::Full(dependency) if dependency.version.to_string() == "v2.0.0"
+            ));
+
+            let missing =
+                github::GitHubPathspec::try_from(String::from("owner/repo:missing")).unwrap();
+            assert!(sink_toml.set_version(&missing, &version).is_err());
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_remove_dependency() {
+            let path = _path("remove");
+            let contents = r#"# The default owner
+default-owner = "owner"
+
+[dependencies]
+# Tools
+
+# The first tool
+"owner/repo:first" = "v1.0.0" # pinned
+"owner/repo:second" = "latest" # floating
+
+# Archives
+"owner/repo:last" = "latest"
+
+# Complex dependencies
+
+    # The full dependency
+    [dependencies."owner/repo:full"]
+    version = "latest"
+
+[dependencies."owner/repo:other"] # other
+version = "v1.0.0"
+"#;
+            fs::write(&path, contents).unwrap();
+            let remove = |pathspec: &str| {
+                let pathspec = github::GitHubPathspec::try_from(String::from(pathspec)).unwrap();
+                SinkTOML::from_file(&path)
+                    .unwrap()
+                    .remove_dependency(&pathspec)
+                    .unwrap();
+                fs::read_to_string(&path).unwrap()
+            };
+
+            assert_eq!(
+                remove("owner/repo:first"),
+                r#"# The default owner
+default-owner = "owner"
+
+[dependencies]
+# Tools
+
+"owner/repo:second" = "latest" # floating
+
+# Archives
+"owner/repo:last" = "latest"
+
+# Complex dependencies
+
+    # The full dependency
+    [dependencies."owner/repo:full"]
+    version = "latest"
+
+[dependencies."owner/repo:other"] # other
+version = "v1.0.0"
+"#
+            );
+            assert_eq!(
+                remove("owner/repo:last"),
+                r#"# The default owner
+default-owner = "owner"
+
+[dependencies]
+# Tools
+
+"owner/repo:second" = "latest" # floating
+
+# Complex dependencies
+
+    # The full dependency
+    [dependencies."owner/repo:full"]
+    version = "latest"
+
+[dependencies."owner/repo:other"] # other
+version = "v1.0.0"
+"#
+            );
+            assert_eq!(
+                remove("owner/repo:full"),
+                r#"# The default owner
+default-owner = "owner"
+
+[dependencies]
+# Tools
+
+"owner/repo:second" = "latest" # floating
+
+# Complex dependencies
+
+[dependencies."owner/repo:other"] # other
+version = "v1.0.0"
+"#
+            );
+
+            let missing =
+                github::GitHubPathspec::try_from(String::from("owner/repo:missing")).unwrap();
+            assert!(SinkTOML::from_file(&path)
+                .unwrap()
+                .remove_dependency(&missing)
+                .is_err());
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_rename_dependency() {
+            let path = _path("rename");
+            fs::write(
+                &path,
+                r#"[dependencies]
+# The tool
+"owner/repo:tool" = "v1.0.0" # pinned
+"owner/repo:other" = "latest"
+
+[dependencies."owner/repo:full"]
+version = "latest"
+"#,
+            )
+            .unwrap();
+            let rename = |from: &str, to: &str| {
+                let from = github::GitHubPathspec::try_from(String::from(from)).unwrap();
+                let to = github::GitHubPathspec::try_from(String::from(to)).unwrap();
+                SinkTOML::from_file(&path)
+                    .unwrap()
+                    .rename_dependency(&from, to)
+            };
+
+            let sink_toml = rename("owner/repo:tool", "owner/repo:tool-*.tar.gz").unwrap();
+            let renamed =
+                github::GitHubPathspec::try_from(String::from("owner/repo:tool-*.tar.gz"));
+            assert!(sink_toml.dependencies.contains_key(&renamed.unwrap()));
+            rename("owner/repo:full", "owner/repo:full-*").unwrap();
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                r#"[dependencies]
+# The tool
+"owner/repo:tool-*.tar.gz" = "v1.0.0" # pinned
+"owner/repo:other" = "latest"
+
+[dependencies."owner/repo:full-*"]
+version = "latest"
+"#
+            );
+
+            assert!(rename("owner/repo:missing", "owner/repo:new").is_err());
+            assert!(rename("owner/repo:other", "owner/repo:full-*").is_err());
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_update_dependency() {
+            let path = _path("update");
+            fs::write(
+                &path,
+                r#"[dependencies]
+"owner/repo:short" = "v1.0.0" # pinned
+
+# The full dependency
+[dependencies."owner/repo:full"]
+version = "latest" # floating
+destination = "dest"
+"#,
+            )
+            .unwrap();
+            let short = github::GitHubPathspec::try_from(String::from("owner/repo:short")).unwrap();
+            let full = github::GitHubPathspec::try_from(String::from("owner/repo:full")).unwrap();
+
+            let sink_toml = SinkTOML::from_file(&path).unwrap();
+            let (sink_toml, previous) = sink_toml
+                .update_dependency(
+                    &short,
+                    &DependencyUpdate {
+                        gitignore: Some(false),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            assert!(
+                matches!(previous, DependencyType::Version(version) if version.to_string() == "v1.0.0")
+            );
+
+            let (sink_toml, previous) = sink_toml
+                .update_dependency(
+                    &full,
+                    &DependencyUpdate {
+                        version: Some(github::GitHubVersion::from("v2.0.0")),
+                        gitignore: Some(false),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            assert!(
+                matches!(previous, DependencyType::Full(dependency) if dependency.version.to_string() == "latest")
+            );
+
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                r#"[dependencies]
+"owner/repo:short" = { version = "v1.0.0", gitignore = false } # pinned
+
+# The full dependency
+[dependencies."owner/repo:full"]
+version = "v2.0.0" # floating
+destination = "dest"
+gitignore = false
+"#
+            );
+            assert!(matches!(
+                &sink_toml.dependencies[&short],
+                DependencyType::Full(dependency) if !dependency.gitignore
+            ));
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_new() {
+            let path = _path("new");
+            let sink_toml = SinkTOML::new(&path).with_default_owner("owner");
+            sink_toml.save().unwrap();
+
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                "default-owner = \"owner\"\n\n[dependencies]\n"
+            );
+
+            let loaded = SinkTOML::from_file(&path).unwrap();
+            assert_eq!(loaded.default_owner, Some(String::from("owner")));
+            assert!(loaded.dependencies.is_empty());
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_unset() {
+            let path = _path("unset");
+            fs::write(
+                &path,
+                r#"# The default owner
+default-owner = "owner"
+
+# Other files
+includes = [
+    "first.toml", # keep me
+    "second.toml",
+]
+
+[dependencies]
+"owner/repo:file" = "v1.0.0" # pinned
+
+[policy]
+allow = ["owner/*"]
+
+[workspace]
+members = ["tools"]
+
+[hooks]
+post-install = ["echo", "done"]
+
+[overrides]
+"owner/repo:other" = { version = "v2.0.0" }
+"#,
+            )
+            .unwrap();
+
+            let sink_toml = SinkTOML::from_file(&path).unwrap();
+            let sink_toml = sink_toml.unset("default-owner").unwrap();
+            let mut sink_toml = sink_toml.unset("includes=second.toml").unwrap();
+            for key in ["policy", "workspace", "hooks", "overrides"] {
+                sink_toml = sink_toml.unset(key).unwrap();
+            }
+
+            assert!(sink_toml.default_owner.is_none());
+            assert!(sink_toml.policy.allow.is_empty());
+            assert!(sink_toml.workspace.is_none());
+            assert_eq!(sink_toml.hooks, crate::hooks::Hooks::default());
+            assert!(sink_toml.overrides.is_empty());
+            assert_eq!(
+                sink_toml.includes,
+                vec![Include::from(PathBuf::from("first.toml"))]
+            );
+            assert_eq!(
+                fs::read_to_string(&path).unwrap(),
+                r#"
+# Other files
+includes = [
+    "first.toml", # keep me
+]
+
+[dependencies]
+"owner/repo:file" = "v1.0.0" # pinned
+"#
+            );
+
+            assert!(sink_toml.unset("dependencies").is_err());
+            fs::remove_file(&path).unwrap();
+        }
+
+        #[test]
+        fn test_includes() {
+            let shared = _path("shared");
+            fs::write(
+                &shared,
+                r#"[dependencies]
+"owner/tool:tool" = "v1.0.0"
+"owner/local:file" = { version = "v2.0.0", destination = "bin" }
+"other/repo:file" = { version = "v1.0.0", destination = "bin" }
+"#,
+            )
+            .unwrap();
+            let contents = format!(
+                r#"includes = [
+    {{ path = {shared:?}, prefix = "vendor/shared", owner = "owner" }},
+    {shared:?},
+]
+
+[dependencies]
+"owner/local:file" = {{ version = "v1.0.0", destination = "vendor" }}
+"#,
+                shared = shared.display().to_string()
+            );
+            let sink_toml = _load("including-namespaced", &contents);
+            let path = _path("including-strict");
+            fs::write(&path, &contents).unwrap();
+            let strict = SinkTOML::from_file_with(&path, true);
+            fs::remove_file(&path).unwrap();
+            fs::remove_file(&shared).unwrap();
+
+            let pathspec = |s: &str| github::GitHubPathspec::try_from(String::from(s)).unwrap();
+            let destination = |s: &str| {
+                sink_toml.dependencies[&pathspec(s)]
+                    .to_github_dependency(&pathspec(s))
+                    .unwrap()
+                    .destination
+            };
+            // Included dependencies land below the prefix, local ones win collisions
+            assert_eq!(
+                destination("owner/tool:tool"),
+                PathBuf::from("vendor/shared/.")
+            );
+            assert_eq!(destination("owner/local:file"), PathBuf::from("vendor"));
+            assert_eq!(sink_toml.included[&pathspec("owner/tool:tool")], shared);
+            assert!(!sink_toml
+                .included
+                .contains_key(&pathspec("owner/local:file")));
+            // Only the second include is not restricted to the owner
+            assert_eq!(sink_toml.included[&pathspec("other/repo:file")], shared);
+            assert_eq!(destination("other/repo:file"), PathBuf::from("bin"));
+            assert_eq!(sink_toml.dependencies.len(), 3);
+
+            // Collisions are errors in strict mode
+            assert!(strict.is_err());
+        }
+
+        #[test]
+        fn test_on_conflict() {
+            let shared = _path("conflicting");
+            fs::write(
+                &shared,
+                "[dependencies]\n\"owner/tool:tool\" = \"v2.0.0\"\n",
+            )
+            .unwrap();
+            let load = |name: &str, on_conflict: &str| {
+                let path = _path(name);
+                fs::write(
+                    &path,
+                    format!(
+                        "includes = [{{ path = {:?}{on_conflict} }}]\n\n[dependencies]\n\"owner/tool:tool\" = \"v1.0.0\"\n",
+                        shared.display().to_string()
+                    ),
+                )
+                .unwrap();
+                let sink_toml = SinkTOML::from_file(&path);
+                fs::remove_file(&path).unwrap();
+                sink_toml
+            };
+            let ours = load("conflict-ours", ", on-conflict = \"ours\"").unwrap();
+            let theirs = load("conflict-theirs", ", on-conflict = \"theirs\"").unwrap();
+            let error = load("conflict-error", ", on-conflict = \"error\"");
+            let unset = load("conflict-unset", "").unwrap();
+            fs::remove_file(&shared).unwrap();
+
+            let pathspec =
+                github::GitHubPathspec::try_from(String::from("owner/tool:tool")).unwrap();
+            let version = |sink_toml: &SinkTOML| {
+                sink_toml.dependencies[&pathspec]
+                    .to_github_dependency(&pathspec)
+                    .unwrap()
+                    .version
+                    .to_string()
+            };
+            assert_eq!(version(&ours), "v1.0.0");
+            assert!(ours.included.is_empty());
+            assert_eq!(version(&theirs), "v2.0.0");
+            assert_eq!(theirs.included[&pathspec], shared);
+            assert_eq!(version(&unset), "v1.0.0");
+            assert!(error.is_err());
+        }
+
+        #[test]
+        fn test_overrides() {
+            let shared = _path("overridden");
+            fs::write(
+                &shared,
+                "[dependencies]\n\"owner/tool:tool\" = { version = \"v1.0.0\", destination = \"bin\" }\n\"owner/other:other\" = \"v1.0.0\"\n",
+            )
+            .unwrap();
+            let sink_toml = _load(
+                "overriding",
+                &format!(
+                    r#"includes = [{:?}]
+
+[dependencies]
+"owner/local:local" = "v1.0.0"
+
+[overrides]
+"owner/tool:tool" = {{ version = "v2.0.0", destination = "tools" }}
+"owner/other:other" = "latest"
+"owner/local:local" = "v3.0.0"
+"owner/missing:missing" = "v1.0.0"
+"#,
+                    shared.display().to_string()
+                ),
+            );
+            let path = _path("overriding-outside");
+            fs::write(
+                &path,
+                format!(
+                    "includes = [{:?}]\n\n[dependencies]\n\n[overrides]\n\"owner/tool:tool\" = {{ destination = \"../outside\" }}\n",
+                    shared.display().to_string()
+                ),
+            )
+            .unwrap();
+            let outside = SinkTOML::from_file_with(&path, true);
+            fs::remove_file(&path).unwrap();
+            fs::remove_file(&shared).unwrap();
+
+            let dependency = |s: &str| {
+                let pathspec = github::GitHubPathspec::try_from(String::from(s)).unwrap();
+                sink_toml.dependencies[&pathspec]
+                    .to_github_dependency(&pathspec)
+                    .unwrap()
+            };
+            assert_eq!(dependency("owner/tool:tool").version.to_string(), "v2.0.0");
+            assert_eq!(
+                dependency("owner/tool:tool").destination,
+                PathBuf::from("tools")
+            );
+            assert_eq!(
+                dependency("owner/other:other").version.to_string(),
+                "latest"
+            );
+            // Local dependencies are not overridden
+            assert_eq!(
+                dependency("owner/local:local").version.to_string(),
+                "v1.0.0"
+            );
+            assert_eq!(sink_toml.dependencies.len(), 3);
+
+            // Overridden destinations are checked like any other
+            assert!(outside.is_err());
+        }
+
+        #[test]
+        fn test_source_errors() {
+            let error = |name: &str, contents: &str| {
+                let path = _path(name);
+                fs::write(&path, contents).unwrap();
+                let SinkError::Any(e) = SinkTOML::from_file(&path).unwrap_err();
+                fs::remove_file(&path).unwrap();
+                let source = e
+                    .chain()
+                    .find_map(|cause| cause.downcast_ref::<SourceError>())
+                    .expect("The error must point at the source");
+                (source.line, source.column, source.to_string())
+            };
+
+            let (line, column, rendered) = error(
+                "invalid-entry",
+                "[dependencies]\n\"owner/repo:a\" = \"v1.0.0\"\n\"owner/repo:b\" = 42\n",
+            );
+            assert_eq!((line, column), (3, 18));
+            assert!(rendered.starts_with(
+                "Invalid dependency entry for 'owner/repo:b': expected a version or a table, found integer"
+            ));
+            assert!(rendered.ends_with("3 | \"owner/repo:b\" = 42\n  |                  ^^"));
+
+            let (line, column, rendered) = error(
+                "invalid-field",
+                "[dependencies]\n\"owner/repo:a\" = { version = \"v1.0.0\", destination = 3 }\n",
+            );
+            assert_eq!((line, column), (2, 18));
+            assert!(rendered.contains("expected path string"));
+
+            let (line, column, _) = error("invalid-type", "strict = \"yes\"\n[dependencies]\n");
+            assert_eq!((line, column), (1, 10));
+        }
+
+        #[test]
+        fn test_unknown_fields() {
+            let message = |contents: &str| {
+                let path = _path("unknown-fields");
+                fs::write(&path, contents).unwrap();
+                let SinkError::Any(e) = SinkTOML::from_file(&path).unwrap_err();
+                fs::remove_file(&path).unwrap();
+                e.chain()
+                    .find_map(|cause| cause.downcast_ref::<SourceError>())
+                    .map(|source| source.message.clone())
+                    .unwrap()
+            };
+
+            assert_eq!(
+                message("stritc = true\n[dependencies]\n"),
+                "Unknown field `stritc` at the top level, did you mean `strict`?"
+            );
+            assert_eq!(
+                message("[dependencies]\n\n[hooks]\npost_install = []\n"),
+                "Unknown field `post_install` in `[hooks]`, did you mean `post-install`?"
+            );
+            assert!(message("[policy]\nfoo = 1\n[dependencies]\n")
+                .starts_with("Unknown field `foo` in `[policy]`, expected one of `allow`"));
+
+            let sink_toml = _load(
+                "unknown-dependency-field",
+                "[dependencies]\n\"owner/repo:a\" = { version = \"v1.0.0\", destinaton = \"bin\" }\n",
+            );
+            assert_eq!(
+                sink_toml._validate_toml_semantics()[0],
+                "Unknown field 'destinaton' in 'owner/repo:a', did you mean 'destination'?"
+            );
+        }
+
+        #[test]
+        fn test_external_destinations() {
+            let load = |name: &str, header: &str, destination: &str| {
+                let path = _path(name);
+                fs::write(
+                    &path,
+                    format!("{header}\n[dependencies]\n\"owner/repo:file\" = {{ version = \"v1.0.0\", destination = \"{destination}\" }}\n"),
+                )
+                .unwrap();
+                let sink_toml = SinkTOML::from_file_with(&path, true);
+                fs::remove_file(&path).unwrap();
+                sink_toml
+            };
+
+            assert!(load("inside", "", "bin/../vendor").is_ok());
+            assert!(load("parent", "", "vendor/../../outside").is_err());
+            assert!(load("absolute", "", "/usr/local/bin").is_err());
+            assert!(load(
+                "allowed",
+                "allow-external-destinations = true",
+                "/usr/local/bin"
+            )
+            .is_ok());
+
+            // An included sink TOML cannot allow itself to write anywhere
+            let included = _path("included");
+            fs::write(
+                &included,
+                "allow-external-destinations = true\n[dependencies]\n\"owner/repo:file\" = { version = \"v1.0.0\", destination = \"/etc\" }\n",
+            )
+            .unwrap();
+            assert!(SinkTOML::from_file(&included).is_ok());
+            let including = load(
+                "including",
+                &format!("includes = [{:?}]", included.display().to_string()),
+                "vendor",
+            );
+            fs::remove_file(&included).unwrap();
+            assert!(including.is_err());
+
+            let mut sink_toml = _load(
+                "update-destination",
+                "[dependencies]\n\"owner/repo:file\" = { version = \"v1.0.0\", destination = \"vendor\" }\n",
+            );
+            let pathspec = github::GitHubPathspec::new("owner", "repo", "file").unwrap();
+            let update = DependencyUpdate {
+                destination: Some(PathBuf::from("../outside")),
+                ..Default::default()
+            };
+            let e = sink_toml
+                ._update_dependency(&pathspec, &update)
+                .unwrap_err();
+            assert!(e.to_string().contains("is outside of"));
+        }
+    }
 }
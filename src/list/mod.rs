@@ -0,0 +1,200 @@
+use anyhow::Result;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+use tracing::warn;
+
+use crate::lock::SinkLock;
+use crate::SinkTOML;
+
+/// The output formats supported by `sink list`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum ListFormat {
+    /// Aligned, human readable columns
+    #[default]
+    Table,
+
+    /// A JSON array
+    Json,
+
+    /// An array of TOML tables
+    Toml,
+
+    /// Tab separated values without a header
+    Plain,
+}
+
+/// Restricts which dependencies are listed.
+#[derive(Debug, Default)]
+pub struct ListFilter {
+    /// Only list dependencies of this owner.
+    pub owner: Option<String>,
+
+    /// Only list dependencies of this repository.
+    pub repository: Option<String>,
+}
+
+/// A single row of `sink list`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ListEntry {
+    pub pathspec: String,
+    pub owner: String,
+    pub repository: String,
+    pub pattern: String,
+    pub version: String,
+    pub destination: String,
+
+    /// The version in the lockfile, only set when listing resolved versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TomlList<'a> {
+    dependencies: &'a [ListEntry],
+}
+
+/// Collect the dependencies of the sink TOML matching the filter, sorted by their pathspec.
+///
+/// If `sink_lock` is given, the locked version is added to each entry.
+pub fn entries(
+    sink_toml: &SinkTOML,
+    sink_lock: Option<&SinkLock>,
+    filter: &ListFilter,
+) -> Vec<ListEntry> {
+    let mut pathspecs: Vec<_> = sink_toml.dependencies.keys().collect();
+    pathspecs.sort();
+
+    let mut entries = Vec::new();
+    for pathspec in pathspecs {
+        if filter
+            .owner
+            .as_ref()
+            .is_some_and(|owner| *owner != pathspec.owner)
+            || filter
+                .repository
+                .as_ref()
+                .is_some_and(|repository| *repository != pathspec.repository)
+        {
+            continue;
+        }
+
+        let dependency = match sink_toml.dependencies[pathspec].to_github_dependency(pathspec) {
+            Ok(dependency) => dependency,
+            Err(e) => {
+                warn!("{e}");
+                continue;
+            }
+        };
+
+        entries.push(ListEntry {
+            pathspec: pathspec.to_string(),
+            owner: pathspec.owner.clone(),
+            repository: pathspec.repository.clone(),
+            pattern: pathspec.asset_pattern().to_string(),
+            version: dependency.version.to_string(),
+            destination: dependency.destination.display().to_string(),
+            locked: sink_lock.map(|sink_lock| match sink_lock.dependencies.get(pathspec) {
+                Some(locked) => locked.version.clone(),
+                None => String::from("-"),
+            }),
+        });
+    }
+
+    entries
+}
+
+/// Render the entries in the given format.
+pub fn render(entries: &[ListEntry], format: ListFormat) -> Result<String> {
+    let rendered = match format {
+        ListFormat::Json => serde_json::to_string_pretty(entries)?,
+        ListFormat::Toml => toml::to_string_pretty(&TomlList {
+            dependencies: entries,
+        })?,
+        ListFormat::Plain => entries
+            .iter()
+            .map(|entry| {
+                let mut columns = vec![entry.pathspec.as_str(), entry.version.as_str()];
+                if let Some(locked) = &entry.locked {
+                    columns.push(locked);
+                }
+                columns.push(entry.destination.as_str());
+                columns.join("\t")
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ListFormat::Table => {
+            let resolved = entries.iter().any(|entry| entry.locked.is_some());
+            let mut rows = vec![vec!["DEPENDENCY", "VERSION"]];
+            if resolved {
+                rows[0].push("LOCKED");
+            }
+            rows[0].push("DESTINATION");
+            for entry in entries {
+                let mut row = vec![entry.pathspec.as_str(), entry.version.as_str()];
+                if let Some(locked) = &entry.locked {
+                    row.push(locked);
+                }
+                row.push(entry.destination.as_str());
+                rows.push(row);
+            }
+            table(&rows)
+        }
+    };
+
+    Ok(rendered)
+}
+
+/// Render the rows as aligned columns, the first row being the header.
+pub fn table(rows: &[Vec<&str>]) -> String {
+    let widths: Vec<usize> = (0..rows.first().map_or(0, |header| header.len()))
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ")
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _entries() -> Vec<ListEntry> {
+        vec![ListEntry {
+            pathspec: String::from("owner/repo:pattern"),
+            owner: String::from("owner"),
+            repository: String::from("repo"),
+            pattern: String::from("pattern"),
+            version: String::from("latest"),
+            destination: String::from("."),
+            locked: Some(String::from("v1.0.0")),
+        }]
+    }
+
+    #[test]
+    fn test_render_table() {
+        assert_eq!(
+            render(&_entries(), ListFormat::Table).unwrap(),
+            "DEPENDENCY          VERSION  LOCKED  DESTINATION\nowner/repo:pattern  latest   v1.0.0  ."
+        );
+    }
+
+    #[test]
+    fn test_render_plain() {
+        assert_eq!(
+            render(&_entries(), ListFormat::Plain).unwrap(),
+            "owner/repo:pattern\tlatest\tv1.0.0\t."
+        );
+    }
+}
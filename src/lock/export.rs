@@ -0,0 +1,345 @@
+use anyhow::Result;
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::SinkLock;
+
+/// The formats supported by `sink lock export`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum ExportFormat {
+    /// A JSON array
+    #[default]
+    Json,
+
+    /// Comma separated values with a header
+    Csv,
+
+    /// A bash script downloading the locked files without sink
+    Shell,
+
+    /// A PowerShell script downloading the locked files without sink
+    Powershell,
+}
+
+/// A single installed file of a resolved dependency.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ExportRow {
+    pub pathspec: String,
+    pub owner: String,
+    pub repository: String,
+    pub pattern: String,
+    pub version: String,
+    pub release_id: Option<u64>,
+    pub release_url: Option<String>,
+    pub path: String,
+    pub checksum: String,
+    pub asset_id: Option<String>,
+    pub url: Option<String>,
+}
+
+/// The columns of the CSV export, in the order of [`ExportRow`].
+const CSV_HEADER: [&str; 11] = [
+    "pathspec",
+    "owner",
+    "repository",
+    "pattern",
+    "version",
+    "release_id",
+    "release_url",
+    "path",
+    "checksum",
+    "asset_id",
+    "url",
+];
+
+/// Flatten the lockfile into one row per installed file.
+///
+/// Dependencies without files are exported as a single row without a path.
+pub fn rows(sink_lock: &SinkLock) -> Vec<ExportRow> {
+    let mut rows = Vec::new();
+    for (pathspec, locked) in sink_lock.dependencies.iter() {
+        let row = |file: Option<&super::LockedFile>| ExportRow {
+            pathspec: pathspec.to_string(),
+            owner: pathspec.owner().to_string(),
+            repository: pathspec.repository().to_string(),
+            pattern: pathspec.asset_pattern().to_string(),
+            version: locked.version.clone(),
+            release_id: locked.release_id,
+            release_url: locked.release_url.clone(),
+            path: file
+                .map(|file| file.path.display().to_string())
+                .unwrap_or_default(),
+            checksum: file.map(|file| file.checksum.clone()).unwrap_or_default(),
+            asset_id: file.and_then(|file| file.asset_id.clone()),
+            url: file.and_then(|file| file.url.clone()),
+        };
+
+        match locked.files.is_empty() {
+            true => rows.push(row(None)),
+            false => rows.extend(locked.files.iter().map(|file| row(Some(file)))),
+        }
+    }
+
+    rows
+}
+
+/// Quote a CSV field if needed, see RFC 4180.
+fn _csv_field(field: &str) -> String {
+    match field.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", field.replace('"', "\"\"")),
+        false => field.to_string(),
+    }
+}
+
+/// The header of the bash script, defining how a single file is downloaded and verified.
+const SHELL_HEADER: &str = r#"#!/usr/bin/env bash
+# Downloads the files locked in sink.lock without sink, generated by 'sink lock export --format shell'.
+# Run it from the directory of the sink TOML. Links, hooks and the .gitignore are not taken care of.
+set -euo pipefail
+
+download() {
+    local path="$1" url="$2" checksum="$3"
+    mkdir -p "$(dirname "$path")"
+    curl --fail --silent --show-error --location --output "$path.part" "$url"
+    if [ -n "$checksum" ]; then
+        local bits="${checksum%%:*}" actual
+        bits="${bits#sha}"
+        if command -v "sha${bits}sum" > /dev/null; then
+            actual="$("sha${bits}sum" "$path.part" | cut -d ' ' -f 1)"
+        else
+            actual="$(shasum -a "$bits" "$path.part" | cut -d ' ' -f 1)"
+        fi
+        if [ "$actual" != "${checksum#*:}" ]; then
+            rm -f "$path.part"
+            echo "Checksum mismatch for '$path'!" >&2
+            exit 1
+        fi
+    fi
+    mv -f "$path.part" "$path"
+    echo "Downloaded '$path'!"
+}
+"#;
+
+/// The header of the PowerShell script, defining how a single file is downloaded and verified.
+const POWERSHELL_HEADER: &str = r#"# Downloads the files locked in sink.lock without sink, generated by 'sink lock export --format powershell'.
+# Run it from the directory of the sink TOML. Links, hooks and the .gitignore are not taken care of.
+$ErrorActionPreference = 'Stop'
+
+function Get-LockedFile([string]$Path, [string]$Url, [string]$Checksum) {
+    $Directory = Split-Path -Parent $Path
+    if ($Directory) { New-Item -ItemType Directory -Force -Path $Directory | Out-Null }
+    Invoke-WebRequest -Uri $Url -OutFile "$Path.part" -UseBasicParsing
+    if ($Checksum) {
+        $Algorithm, $Expected = $Checksum -split ':', 2
+        $Actual = (Get-FileHash -Algorithm $Algorithm -Path "$Path.part").Hash.ToLower()
+        if ($Actual -ne $Expected) {
+            Remove-Item -Force "$Path.part"
+            throw "Checksum mismatch for '$Path'!"
+        }
+    }
+    Move-Item -Force "$Path.part" $Path
+    Write-Output "Downloaded '$Path'!"
+}
+"#;
+
+/// Returns the checksum if the scripts can verify it with standard tools, which only know the SHA-2 family.
+fn _verifiable(checksum: &str) -> Option<&str> {
+    match super::Algorithm::of(checksum) {
+        Ok(super::Algorithm::Sha256 | super::Algorithm::Sha512) => Some(checksum),
+        _ => None,
+    }
+}
+
+/// Render the rows as a script of the given header and single-quoting function, calling `command` per file.
+///
+/// Files without a download URL, e.g. of lockfiles written by older versions of sink, are left out with a comment.
+/// Values in comments are escaped like Rust strings, so a line break in the lockfile can not end the comment.
+fn _script(
+    rows: &[ExportRow],
+    header: &str,
+    command: &str,
+    quote: impl Fn(&str) -> String,
+) -> String {
+    let mut lines = vec![header.to_string()];
+    for row in rows.iter().filter(|row| !row.path.is_empty()) {
+        let Some(url) = &row.url else {
+            lines.push(format!(
+                "# {:?} of {:?} has no download URL in the lockfile, install it with sink instead",
+                row.path, row.pathspec
+            ));
+            continue;
+        };
+        let checksum = _verifiable(&row.checksum).unwrap_or_default();
+        if checksum.is_empty() {
+            lines.push(format!(
+                "# The checksum {:?} cannot be verified without sink",
+                row.checksum
+            ));
+        }
+        lines.push(format!(
+            "{command} {} {} {}",
+            quote(&row.path),
+            quote(url),
+            quote(checksum)
+        ));
+    }
+
+    lines.join("\n")
+}
+
+/// Render the rows in the given format.
+pub fn render(rows: &[ExportRow], format: ExportFormat) -> Result<String> {
+    let rendered = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(rows)?,
+        ExportFormat::Csv => {
+            let mut lines = vec![CSV_HEADER.join(",")];
+            for row in rows {
+                let fields = [
+                    row.pathspec.clone(),
+                    row.owner.clone(),
+                    row.repository.clone(),
+                    row.pattern.clone(),
+                    row.version.clone(),
+                    row.release_id.map(|id| id.to_string()).unwrap_or_default(),
+                    row.release_url.clone().unwrap_or_default(),
+                    row.path.clone(),
+                    row.checksum.clone(),
+                    row.asset_id.clone().unwrap_or_default(),
+                    row.url.clone().unwrap_or_default(),
+                ];
+                lines.push(
+                    fields
+                        .iter()
+                        .map(|field| _csv_field(field))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                );
+            }
+            lines.join("\n")
+        }
+        ExportFormat::Shell => _script(rows, SHELL_HEADER, "download", |value| {
+            format!("'{}'", value.replace('\'', r"'\''"))
+        }),
+        // PowerShell ends single-quoted strings at typographic quotes as well
+        ExportFormat::Powershell => _script(rows, POWERSHELL_HEADER, "Get-LockedFile", |value| {
+            let escaped: String = value
+                .chars()
+                .flat_map(|c| match c {
+                    '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' => vec![c, c],
+                    _ => vec![c],
+                })
+                .collect();
+            format!("'{escaped}'")
+        }),
+    };
+
+    Ok(rendered)
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_csv() {
+        let sink_lock = SinkLock::parse(
+            r#"
+            version = 2
+
+            [dependencies."owner/repo:*.tar.gz"]
+            version = "v1.0.0"
+            release-id = 42
+
+            [[dependencies."owner/repo:*.tar.gz".files]]
+            path = "bin/tool, v1.tar.gz"
+            checksum = "sha256:abc"
+
+            [dependencies."owner/other"]
+            version = "v2.0.0"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&rows(&sink_lock), ExportFormat::Csv).unwrap(),
+            "pathspec,owner,repository,pattern,version,release_id,release_url,path,checksum,asset_id,url\n\
+             owner/other,owner,other,*,v2.0.0,,,,,,\n\
+             owner/repo:*.tar.gz,owner,repo,*.tar.gz,v1.0.0,42,,\"bin/tool, v1.tar.gz\",sha256:abc,,"
+        );
+    }
+
+    #[test]
+    fn test_render_script() {
+        let sink_lock = SinkLock::parse(
+            r#"
+            version = 2
+
+            [dependencies."owner/repo"]
+            version = "v1.0.0"
+
+            [[dependencies."owner/repo".files]]
+            path = "bin/it's"
+            checksum = "sha256:abc"
+            url = "https://github.com/owner/repo/releases/download/v1.0.0/it's"
+
+            [[dependencies."owner/repo".files]]
+            path = "bin/fast"
+            checksum = "blake3:def"
+            url = "https://github.com/owner/repo/releases/download/v1.0.0/fast"
+
+            [[dependencies."owner/repo".files]]
+            path = "bin/old"
+            checksum = "sha256:123"
+            "#,
+        )
+        .unwrap();
+        let rows = rows(&sink_lock);
+
+        let shell = render(&rows, ExportFormat::Shell).unwrap();
+        assert!(shell.starts_with(SHELL_HEADER));
+        assert!(shell.contains(
+            "download 'bin/it'\\''s' 'https://github.com/owner/repo/releases/download/v1.0.0/it'\\''s' 'sha256:abc'"
+        ));
+        assert!(shell.contains(
+            "# The checksum \"blake3:def\" cannot be verified without sink\ndownload 'bin/fast' "
+        ));
+        assert!(shell.contains("# \"bin/old\" of \"owner/repo\" has no download URL"));
+
+        let powershell = render(&rows, ExportFormat::Powershell).unwrap();
+        assert!(powershell.contains("Get-LockedFile 'bin/it''s' "));
+        assert!(powershell.contains("/fast' ''\n"));
+    }
+
+    #[test]
+    fn test_render_script_injection() {
+        let sink_lock = SinkLock::parse(
+            r#"
+            version = 2
+
+            [dependencies."owner/repo"]
+            version = "v1.0.0"
+
+            [[dependencies."owner/repo".files]]
+            path = "bin/old\nrm -rf ~"
+            checksum = "blake3:def\nrm -rf ~"
+
+            [[dependencies."owner/repo".files]]
+            path = "bin/new\u2019; rm -rf ~; \u2019"
+            checksum = "blake3:def\nrm -rf ~"
+            url = "https://github.com/owner/repo/releases/download/v1.0.0/new"
+            "#,
+        )
+        .unwrap();
+        let rows = rows(&sink_lock);
+
+        for format in [ExportFormat::Shell, ExportFormat::Powershell] {
+            let script = render(&rows, format).unwrap();
+            assert!(!script.lines().any(|line| line.starts_with("rm")));
+        }
+        let powershell = render(&rows, ExportFormat::Powershell).unwrap();
+        assert!(powershell.contains("'bin/new\u{2019}\u{2019}; rm -rf ~; \u{2019}\u{2019}'"));
+    }
+}
@@ -0,0 +1,874 @@
+pub mod export;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, error, info, warn};
+
+use crate::atomic;
+use crate::github::{GitHubPathspec, GitHubVersion};
+use crate::SinkTOML;
+
+/// The file name of the lockfile, which is placed next to the sink TOML.
+pub const LOCKFILE_NAME: &str = "sink.lock";
+
+/// The format version of the lockfile written by this version of sink.
+///
+/// 1. Initial format without an explicit version
+/// 2. Release and asset IDs and URLs
+pub const LOCKFILE_VERSION: u32 = 2;
+
+/// Provides the version of lockfiles without an explicit version for [`serde`].
+fn _legacy_version() -> u32 {
+    1
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct SinkLock {
+    /// The format version of the lockfile, see [`LOCKFILE_VERSION`].
+    #[serde(default = "_legacy_version")]
+    pub version: u32,
+
+    /// The resolved dependencies.
+    #[serde(default)]
+    pub dependencies: BTreeMap<GitHubPathspec, LockedDependency>,
+
+    /// The resolved dependencies of the workspace members by their directory, see [`crate::workspace`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub members: BTreeMap<String, BTreeMap<GitHubPathspec, LockedDependency>>,
+
+    /// Fields that are not known to this version of sink.
+    ///
+    /// These are kept, so they survive rewriting the lockfile.
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, toml::Value>,
+
+    /// Contains the path to this lockfile
+    #[serde(skip)]
+    pub path: PathBuf,
+
+    /// The workspace member whose dependencies this is, see [`SinkLock::member`].
+    #[serde(skip)]
+    pub member: Option<String>,
+}
+impl Default for SinkLock {
+    fn default() -> Self {
+        SinkLock {
+            version: LOCKFILE_VERSION,
+            dependencies: BTreeMap::new(),
+            members: BTreeMap::new(),
+            unknown_fields: BTreeMap::new(),
+            path: PathBuf::new(),
+            member: None,
+        }
+    }
+}
+impl SinkLock {
+    /// Returns the path of the lockfile belonging to the given sink TOML.
+    pub fn path_for(sink_toml: &SinkTOML) -> PathBuf {
+        sink_toml.root().join(LOCKFILE_NAME)
+    }
+
+    /// Migrate the lockfile from older format versions to [`LOCKFILE_VERSION`].
+    ///
+    /// Lockfiles written by newer versions of sink are left untouched.
+    fn _migrate(&mut self) {
+        if self.version > LOCKFILE_VERSION {
+            warn!(
+                "The lockfile has format version {}, which is newer than the supported version {}! Please upgrade sink.",
+                self.version, LOCKFILE_VERSION
+            );
+            return;
+        }
+
+        while self.version < LOCKFILE_VERSION {
+            info!(
+                "Migrating the lockfile from format version {} to {}...",
+                self.version,
+                self.version + 1
+            );
+            // 1 -> 2: The asset information is optional and filled by the next install
+            self.version += 1;
+        }
+    }
+
+    fn _from_str(contents: &str) -> Result<SinkLock> {
+        let mut sink_lock: SinkLock = toml::from_str(contents)?;
+        sink_lock._migrate();
+
+        Ok(sink_lock)
+    }
+
+    /// Parse a lockfile from its contents, e.g. read from a git ref.
+    ///
+    /// Older formats are migrated, see [`LOCKFILE_VERSION`].
+    pub fn parse(contents: &str) -> Result<SinkLock> {
+        match SinkLock::_from_str(contents) {
+            Ok(sink_lock) => Ok(sink_lock),
+            Err(e) => Err(e.context("Failed to parse lockfile!")),
+        }
+    }
+
+    fn _from_file(path: &Path) -> Result<SinkLock> {
+        debug!("Parsing lockfile from '{}'...", path.display());
+
+        let mut sink_lock: SinkLock = if path.exists() {
+            SinkLock::_from_str(&fs::read_to_string(path)?)?
+        } else {
+            debug!("'{}' does not exist, starting empty!", path.display());
+            SinkLock::default()
+        };
+        sink_lock.path = PathBuf::from(path);
+
+        debug!("Parsing done!");
+
+        Ok(sink_lock)
+    }
+    /// Load the lockfile belonging to the given sink TOML.
+    ///
+    /// A missing lockfile results in an empty [`SinkLock`].
+    /// Older formats are migrated, see [`LOCKFILE_VERSION`].
+    pub fn load(sink_toml: &SinkTOML) -> Result<SinkLock> {
+        let path = SinkLock::path_for(sink_toml);
+        match SinkLock::_from_file(&path) {
+            Ok(sink_lock) => Ok(sink_lock),
+            Err(e) => Err(e.context("Failed to load lockfile!")),
+        }
+    }
+
+    /// Returns the lockfile of the given workspace member, which is part of this lockfile.
+    ///
+    /// Saving it only replaces the entry of the member in [`SinkLock::members`] of the lockfile on disk.
+    pub fn member(&self, name: &str) -> SinkLock {
+        SinkLock {
+            version: self.version,
+            dependencies: self.members.get(name).cloned().unwrap_or_default(),
+            path: self.path.clone(),
+            member: Some(String::from(name)),
+            ..Default::default()
+        }
+    }
+
+    fn _save(&self) -> Result<()> {
+        if let Some(member) = &self.member {
+            let mut shared = SinkLock::_from_file(&self.path)?;
+            shared
+                .members
+                .insert(member.clone(), self.dependencies.clone());
+            return shared._save();
+        }
+
+        // Rewriting a newer format could silently discard data
+        if self.version > LOCKFILE_VERSION {
+            return Err(anyhow::anyhow!(
+                "The lockfile has format version {}, but this version of sink only supports up to {}! Please upgrade sink.",
+                self.version,
+                LOCKFILE_VERSION
+            ));
+        }
+
+        debug!("Saving lockfile to '{}'...", self.path.display());
+
+        atomic::write(&self.path, toml::to_string_pretty(self)?)?;
+
+        debug!("Saving done!");
+
+        Ok(())
+    }
+    /// Save the lockfile to [`SinkLock::path`].
+    pub fn save(&self) -> Result<()> {
+        match self._save() {
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.context("Failed to save lockfile!")),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedDependency {
+    /// The release tag the dependency was resolved to.
+    pub version: String,
+
+    /// The ID of the release on GitHub.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_id: Option<u64>,
+
+    /// The URL of the release on GitHub.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_url: Option<String>,
+
+    /// The SPDX identifier of the repository's license, e.g. 'MIT'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+
+    /// The symlink pointing to the installed asset, see [`crate::github::GitHubDependency::link`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<PathBuf>,
+
+    /// The files that were installed for this dependency.
+    #[serde(default)]
+    pub files: Vec<LockedFile>,
+
+    /// Fields that are not known to this version of sink.
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, toml::Value>,
+}
+impl LockedDependency {
+    /// Whether both entries locked the same version with identical files.
+    ///
+    /// Where the assets came from is not compared, only their paths and digests.
+    pub fn same_files(&self, other: &LockedDependency) -> bool {
+        let files = |locked: &LockedDependency| -> Vec<(PathBuf, String)> {
+            let mut files: Vec<_> = locked
+                .files
+                .iter()
+                .map(|file| (file.path.clone(), file.checksum.clone()))
+                .collect();
+            files.sort();
+            files
+        };
+
+        self.version == other.version && files(self) == files(other)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedFile {
+    /// The path of the installed file, relative to the directory of the sink TOML.
+    pub path: PathBuf,
+
+    /// The digest of the file in the form of 'algorithm:hex', see [`Algorithm`].
+    pub checksum: String,
+
+    /// The ID of the release asset the file was downloaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub asset_id: Option<String>,
+
+    /// The browser download URL of the release asset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// The REST API URL of the release asset, used to download it directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_url: Option<String>,
+
+    /// Fields that are not known to this version of sink.
+    #[serde(flatten)]
+    pub unknown_fields: BTreeMap<String, toml::Value>,
+}
+
+/* ---------- [ Functions ] ---------- */
+/// The algorithms checksums may be computed with, named by the prefix of the checksum, e.g. 'sha512:hex'.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    /// SHA-256, which sink computes for the lockfile.
+    #[default]
+    Sha256,
+    Sha512,
+    Blake3,
+}
+impl Algorithm {
+    /// Returns the algorithm named by the prefix of the checksum, e.g. 'blake3:hex'.
+    pub fn of(checksum: &str) -> Result<Self> {
+        let Some((prefix, _)) = checksum.split_once(':') else {
+            return Err(anyhow::anyhow!(
+                "Checksum '{checksum}' does not name its algorithm, e.g. 'sha256:{checksum}'!"
+            ));
+        };
+        match prefix.to_lowercase().as_str() {
+            "sha256" => Ok(Algorithm::Sha256),
+            "sha512" => Ok(Algorithm::Sha512),
+            "blake3" => Ok(Algorithm::Blake3),
+            _ => Err(anyhow::anyhow!(
+                "Unsupported checksum algorithm '{prefix}', expected 'sha256', 'sha512' or 'blake3'!"
+            )),
+        }
+    }
+
+    /// Computes the hex digest of everything read from the reader.
+    fn _hex(&self, mut reader: impl io::Read) -> io::Result<String> {
+        Ok(match self {
+            Algorithm::Sha256 => {
+                let mut hasher = Sha256::new();
+                io::copy(&mut reader, &mut hasher)?;
+                format!("{:x}", hasher.finalize())
+            }
+            Algorithm::Sha512 => {
+                let mut hasher = Sha512::new();
+                io::copy(&mut reader, &mut hasher)?;
+                format!("{:x}", hasher.finalize())
+            }
+            Algorithm::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                io::copy(&mut reader, &mut hasher)?;
+                hasher.finalize().to_hex().to_string()
+            }
+        })
+    }
+}
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Algorithm::Sha256 => write!(f, "sha256"),
+            Algorithm::Sha512 => write!(f, "sha512"),
+            Algorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+/// Computes the SHA-256 digest of the file at the given path in the form of 'sha256:hex'.
+pub fn digest(path: &Path) -> Result<String> {
+    digest_with(path, Algorithm::Sha256)
+}
+
+/// Computes the digest of the file at the given path in the form of 'algorithm:hex'.
+pub fn digest_with(path: &Path, algorithm: Algorithm) -> Result<String> {
+    let hex = algorithm._hex(File::open(path)?)?;
+    Ok(format!("{algorithm}:{hex}"))
+}
+
+/// Computes the SHA-256 digest of the given contents in the form of 'sha256:hex', see [`digest`].
+pub fn digest_bytes(contents: &[u8]) -> String {
+    digest_bytes_with(contents, Algorithm::Sha256)
+}
+
+/// Computes the digest of the given contents in the form of 'algorithm:hex', see [`digest_with`].
+pub fn digest_bytes_with(contents: &[u8], algorithm: Algorithm) -> String {
+    // Reading from a slice never fails
+    let hex = algorithm._hex(contents).unwrap_or_default();
+    format!("{algorithm}:{hex}")
+}
+
+/// Computes the digest of the file with the algorithm the given checksum was computed with, to compare it against.
+pub fn digest_like(path: &Path, checksum: &str) -> Result<String> {
+    digest_with(path, Algorithm::of(checksum)?)
+}
+
+/// Computes the digest of the contents with the algorithm the given checksum was computed with, see [`digest_like`].
+pub fn digest_bytes_like(contents: &[u8], checksum: &str) -> Result<String> {
+    Ok(digest_bytes_with(contents, Algorithm::of(checksum)?))
+}
+
+/// The result of verifying the installed files against the lockfile.
+#[derive(Serialize, Debug, Default)]
+pub struct VerifyReport {
+    /// Files whose digest matches the lockfile.
+    pub verified: Vec<PathBuf>,
+
+    /// Files whose digest differs from the lockfile.
+    pub tampered: Vec<PathBuf>,
+
+    /// Files that are recorded in the lockfile but do not exist.
+    pub missing: Vec<PathBuf>,
+
+    /// Dependencies of the sink TOML that have no lockfile entry.
+    pub unlocked: Vec<GitHubPathspec>,
+}
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.tampered.is_empty() && self.missing.is_empty() && self.unlocked.is_empty()
+    }
+}
+
+fn _verify(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Result<VerifyReport> {
+    let root = sink_toml.root();
+    let mut report = VerifyReport::default();
+
+    for pathspec in sink_toml.dependencies.keys() {
+        let Some(locked) = sink_lock.dependencies.get(pathspec) else {
+            warn!("{pathspec} is not locked!");
+            report.unlocked.push(pathspec.clone());
+            continue;
+        };
+
+        for file in locked.files.iter() {
+            let full_path = root.join(&file.path);
+            if !full_path.exists() {
+                error!("{pathspec}: '{}' is missing!", file.path.display());
+                report.missing.push(file.path.clone());
+                continue;
+            }
+
+            let checksum = digest_like(&full_path, &file.checksum)?;
+            if checksum != file.checksum {
+                error!(
+                    "{pathspec}: '{}' has been tampered with! Expected '{}', got '{checksum}'",
+                    file.path.display(),
+                    file.checksum
+                );
+                report.tampered.push(file.path.clone());
+            } else {
+                debug!("{pathspec}: '{}' is valid!", file.path.display());
+                report.verified.push(file.path.clone());
+            }
+        }
+    }
+
+    info!(
+        "Verified {} file(s): {} tampered, {} missing, {} dependencies not locked",
+        report.verified.len() + report.tampered.len() + report.missing.len(),
+        report.tampered.len(),
+        report.missing.len(),
+        report.unlocked.len()
+    );
+
+    Ok(report)
+}
+/// Verify the installed files of all dependencies against the digests stored in the lockfile.
+pub fn verify(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Result<VerifyReport> {
+    match _verify(sink_toml, sink_lock) {
+        Ok(report) => Ok(report),
+        Err(e) => Err(e.context("Failed to verify dependencies!")),
+    }
+}
+
+/// Returns `path` without `.` components.
+fn _strip_cur_dir(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+/// Returns `path` without `.` components, or `None` if it is not a plain relative path.
+///
+/// Absolute paths and `..` components would escape the directory the path is relative to.
+pub fn normalized(path: &Path) -> Option<PathBuf> {
+    let path = _strip_cur_dir(path);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_)))
+        .then_some(path)
+}
+
+/// Returns whether the locked `path` may be touched, see [`normalized`].
+fn _is_cleanable(path: &Path, allow_external: bool) -> bool {
+    if allow_external || normalized(path).is_some() {
+        return true;
+    }
+
+    warn!("'{}' is outside of the project, skipping!", path.display());
+    false
+}
+
+fn _clean(
+    root: &Path,
+    sink_lock: &SinkLock,
+    pathspec: Option<&GitHubPathspec>,
+    allow_external: bool,
+) -> Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+
+    for (locked_pathspec, locked) in sink_lock.dependencies.iter() {
+        if pathspec.is_some_and(|pathspec| pathspec != locked_pathspec) {
+            continue;
+        }
+
+        for file in locked.files.iter() {
+            if !_is_cleanable(&file.path, allow_external) {
+                continue;
+            }
+            let full_path = root.join(&file.path);
+            if !full_path.exists() {
+                debug!("'{}' does not exist, skipping!", file.path.display());
+                continue;
+            }
+
+            fs::remove_file(&full_path)?;
+            info!("Removed '{}'!", file.path.display());
+            removed.push(file.path.clone());
+        }
+        if let Some(link) = locked
+            .link
+            .as_ref()
+            .filter(|link| _is_cleanable(link, allow_external))
+        {
+            if remove_link(root, link)? {
+                removed.push(link.clone());
+            }
+        }
+    }
+
+    Ok(removed)
+}
+/// Remove the symlink at `link`, relative to `root`, which was created by sink.
+///
+/// Anything but a symlink is left untouched. Returns whether it was removed.
+pub fn remove_link(root: &Path, link: &Path) -> Result<bool> {
+    let full_path = root.join(link);
+    if !full_path.is_symlink() {
+        debug!("'{}' is not a symlink, skipping!", link.display());
+        return Ok(false);
+    }
+
+    fs::remove_file(&full_path)?;
+    info!("Removed '{}'!", link.display());
+
+    Ok(true)
+}
+
+/// Delete all files installed by sink, optionally only those of a single dependency.
+///
+/// Only files recorded in the lockfile are touched. Paths outside of `root` are skipped,
+/// unless `allow_external` is set, see [`SinkTOML::allow_external_destinations`].
+/// Returns the removed paths.
+pub fn clean(
+    root: &Path,
+    sink_lock: &SinkLock,
+    pathspec: Option<&GitHubPathspec>,
+    allow_external: bool,
+) -> Result<Vec<PathBuf>> {
+    if let Some(pathspec) = pathspec {
+        if !sink_lock.dependencies.contains_key(pathspec) {
+            return Err(anyhow::anyhow!("'{pathspec}' is not locked!"));
+        }
+    }
+
+    match _clean(root, sink_lock, pathspec, allow_external) {
+        Ok(removed) => Ok(removed),
+        Err(e) => Err(e.context("Failed to clean installed files!")),
+    }
+}
+
+/// Returns the reasons why the lockfile does not match the sink TOML.
+///
+/// The lockfile is in sync if it contains exactly the dependencies of the sink TOML,
+/// with pinned tags and destinations matching their locked state.
+pub fn out_of_sync(sink_toml: &SinkTOML, sink_lock: &SinkLock) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    let mut pathspecs: Vec<&GitHubPathspec> = sink_toml.dependencies.keys().collect();
+    pathspecs.sort();
+    for pathspec in pathspecs {
+        let Some(locked) = sink_lock.dependencies.get(pathspec) else {
+            reasons.push(format!("{pathspec} is not locked!"));
+            continue;
+        };
+        let Ok(dependency) = sink_toml.dependencies[pathspec].to_github_dependency(pathspec) else {
+            reasons.push(format!("{pathspec} is invalid!"));
+            continue;
+        };
+
+        if dependency.is_blocked(&locked.version) {
+            reasons.push(format!(
+                "{pathspec} is locked at '{}', which is blocked!",
+                locked.version
+            ));
+        }
+        if let GitHubVersion::Tag(tag) | GitHubVersion::Draft(tag) = &dependency.version {
+            if *tag != locked.version {
+                reasons.push(format!(
+                    "{pathspec} is pinned to '{tag}', but locked at '{}'!",
+                    locked.version
+                ));
+            }
+        }
+        if !sink_toml.allow_external_destinations
+            && locked
+                .files
+                .iter()
+                .any(|file| normalized(&file.path).is_none())
+        {
+            reasons.push(format!("{pathspec} is locked outside of the project!"));
+        }
+        // Ignore '.' components, as the default destination is the directory of the sink TOML
+        let destination = _strip_cur_dir(&dependency.destination);
+        if locked
+            .files
+            .iter()
+            .any(|file| !_strip_cur_dir(&file.path).starts_with(&destination))
+        {
+            reasons.push(format!(
+                "{pathspec} is locked outside of its destination '{}'!",
+                dependency.destination.display()
+            ));
+        }
+    }
+
+    for pathspec in sink_lock.dependencies.keys() {
+        if !sink_toml.dependencies.contains_key(pathspec) {
+            reasons.push(format!("{pathspec} is locked, but not in the sink TOML!"));
+        }
+    }
+
+    reasons
+}
+
+fn _prune(sink_toml: &SinkTOML, sink_lock: &mut SinkLock) -> Result<Vec<GitHubPathspec>> {
+    let stale: Vec<GitHubPathspec> = sink_lock
+        .dependencies
+        .keys()
+        .filter(|pathspec| !sink_toml.dependencies.contains_key(pathspec))
+        .cloned()
+        .collect();
+
+    for pathspec in stale.iter() {
+        info!("Pruning {pathspec}...");
+        _clean(
+            &sink_toml.root(),
+            sink_lock,
+            Some(pathspec),
+            sink_toml.allow_external_destinations,
+        )?;
+        sink_lock.dependencies.remove(pathspec);
+    }
+
+    Ok(stale)
+}
+/// Remove the installed files and lockfile entries of dependencies that no longer exist in the sink TOML.
+///
+/// Returns the pruned dependencies. The lockfile is **not** saved.
+pub fn prune(sink_toml: &SinkTOML, sink_lock: &mut SinkLock) -> Result<Vec<GitHubPathspec>> {
+    match _prune(sink_toml, sink_lock) {
+        Ok(pruned) => Ok(pruned),
+        Err(e) => Err(e.context("Failed to prune dependencies!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_digest() {
+        let path = std::env::temp_dir().join(format!("sink-test-digest-{}", std::process::id()));
+        fs::write(&path, "sink").unwrap();
+
+        assert_eq!(
+            digest(&path).unwrap(),
+            "sha256:1978baf2c153fd3bf3633f3a4b12fa6782507dc6056bbaf16909a6597f4a2548"
+        );
+        let sha512 = "sha512:7b1b3bdc1c05a53034222e0994cdf4789d64e7f0ec95951dbcbe34376f1998682aca0ec0f034eee2df01527a460ffd81eec150416f3d9512ebf269809e1133de";
+        assert_eq!(digest_with(&path, Algorithm::Sha512).unwrap(), sha512);
+        assert_eq!(digest_like(&path, sha512).unwrap(), sha512);
+        assert_eq!(
+            digest_bytes_with(b"", Algorithm::Blake3),
+            "blake3:af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+
+        assert_eq!(Algorithm::of("SHA512:abc").unwrap(), Algorithm::Sha512);
+        assert!(Algorithm::of("md5:abc").is_err());
+        assert!(Algorithm::of("abc").is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_clean_keeps_user_files() {
+        let root = std::env::temp_dir().join(format!("sink-test-clean-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("installed"), "asset").unwrap();
+        fs::write(root.join("user"), "mine").unwrap();
+
+        let mut sink_lock = SinkLock::default();
+        sink_lock.dependencies.insert(
+            GitHubPathspec::try_from(String::from("owner/repo:installed")).unwrap(),
+            LockedDependency {
+                version: String::from("v1.0.0"),
+                files: vec![LockedFile {
+                    path: PathBuf::from("installed"),
+                    checksum: String::new(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+
+        let removed = clean(&root, &sink_lock, None, false).unwrap();
+
+        assert_eq!(removed, vec![PathBuf::from("installed")]);
+        assert!(!root.join("installed").exists());
+        assert!(root.join("user").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_clean_outside_of_project() {
+        let parent =
+            std::env::temp_dir().join(format!("sink-test-clean-outside-{}", std::process::id()));
+        let root = parent.join("project");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(parent.join("relative"), "mine").unwrap();
+        fs::write(parent.join("absolute"), "mine").unwrap();
+
+        let mut sink_lock = SinkLock::default();
+        sink_lock.dependencies.insert(
+            GitHubPathspec::try_from(String::from("owner/repo:tampered")).unwrap(),
+            LockedDependency {
+                version: String::from("v1.0.0"),
+                files: vec![
+                    LockedFile {
+                        path: PathBuf::from("../relative"),
+                        ..Default::default()
+                    },
+                    LockedFile {
+                        path: parent.join("absolute"),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+
+        assert!(clean(&root, &sink_lock, None, false).unwrap().is_empty());
+        assert!(parent.join("relative").exists());
+        assert!(parent.join("absolute").exists());
+
+        // Unless external destinations are allowed
+        assert_eq!(clean(&root, &sink_lock, None, true).unwrap().len(), 2);
+        assert!(!parent.join("relative").exists());
+        assert!(!parent.join("absolute").exists());
+
+        fs::remove_dir_all(&parent).unwrap();
+    }
+
+    #[test]
+    fn test_lock_roundtrip() {
+        let mut sink_lock = SinkLock::default();
+        sink_lock.dependencies.insert(
+            GitHubPathspec::try_from(String::from("owner/repo:pattern")).unwrap(),
+            LockedDependency {
+                version: String::from("v1.0.0"),
+                files: vec![LockedFile {
+                    path: PathBuf::from("dest/file"),
+                    checksum: String::from("sha256:abc"),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        );
+
+        let serialized = toml::to_string_pretty(&sink_lock).unwrap();
+        let deserialized: SinkLock = toml::from_str(&serialized).unwrap();
+
+        assert_eq!(deserialized.dependencies, sink_lock.dependencies);
+    }
+
+    #[test]
+    fn test_out_of_sync() {
+        let path = std::env::temp_dir().join(format!("sink-test-sync-{}.toml", std::process::id()));
+        fs::write(
+            &path,
+            r#"[dependencies]
+"owner/repo:pinned" = "v1.0.0"
+"owner/repo:floating" = { version = "latest", destination = "dest" }
+"#,
+        )
+        .unwrap();
+        let sink_toml = SinkTOML::from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let locked = |version: &str, path: &str| LockedDependency {
+            version: String::from(version),
+            files: vec![LockedFile {
+                path: PathBuf::from(path),
+                checksum: String::from("sha256:abc"),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let pinned = GitHubPathspec::try_from(String::from("owner/repo:pinned")).unwrap();
+        let floating = GitHubPathspec::try_from(String::from("owner/repo:floating")).unwrap();
+
+        let mut sink_lock = SinkLock::default();
+        sink_lock
+            .dependencies
+            .insert(pinned.clone(), locked("v1.0.0", "pinned"));
+        assert_eq!(
+            out_of_sync(&sink_toml, &sink_lock),
+            vec!["owner/repo:floating is not locked!"]
+        );
+
+        sink_lock
+            .dependencies
+            .insert(floating.clone(), locked("v2.0.0", "dest/floating"));
+        assert!(out_of_sync(&sink_toml, &sink_lock).is_empty());
+
+        sink_lock
+            .dependencies
+            .insert(pinned, locked("v0.9.0", "pinned"));
+        sink_lock
+            .dependencies
+            .insert(floating, locked("v2.0.0", "elsewhere/floating"));
+        sink_lock.dependencies.insert(
+            GitHubPathspec::try_from(String::from("owner/repo:stale")).unwrap(),
+            locked("v1.0.0", "stale"),
+        );
+        assert_eq!(
+            out_of_sync(&sink_toml, &sink_lock),
+            vec![
+                "owner/repo:floating is locked outside of its destination 'dest'!",
+                "owner/repo:pinned is pinned to 'v1.0.0', but locked at 'v0.9.0'!",
+                "owner/repo:stale is locked, but not in the sink TOML!",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_files() {
+        let legacy: LockedDependency = toml::from_str(
+            r#"
+version = "v1.0.0"
+files = [{ path = "dest/file", checksum = "sha256:abc" }]
+"#,
+        )
+        .unwrap();
+        let mut traced = legacy.clone();
+        traced.release_id = Some(1);
+        traced.files[0].api_url = Some(String::from(
+            "https://api.github.com/repos/owner/repo/releases/assets/2",
+        ));
+
+        assert!(legacy.same_files(&traced));
+
+        traced.files[0].checksum = String::from("sha256:def");
+        assert!(!legacy.same_files(&traced));
+    }
+
+    #[test]
+    fn test_migrate_legacy() {
+        let sink_lock = SinkLock::_from_str(
+            r#"
+[dependencies."owner/repo:pattern"]
+version = "v1.0.0"
+files = [{ path = "dest/file", checksum = "sha256:abc" }]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(sink_lock.version, LOCKFILE_VERSION);
+        assert_eq!(sink_lock.dependencies.len(), 1);
+    }
+
+    #[test]
+    fn test_newer_format() {
+        let contents = r#"version = 99
+mirror = "https://example.com"
+
+[dependencies."owner/repo:pattern"]
+version = "v1.0.0"
+signature = "sig"
+files = [{ path = "dest/file", checksum = "sha256:abc", size = 42 }]
+"#;
+        let sink_lock = SinkLock::_from_str(contents).unwrap();
+
+        // Unknown fields survive a roundtrip, but a newer format is never written
+        assert_eq!(sink_lock.version, 99);
+        let reparsed = SinkLock::_from_str(&toml::to_string_pretty(&sink_lock).unwrap()).unwrap();
+        assert_eq!(reparsed.unknown_fields, sink_lock.unknown_fields);
+        assert_eq!(reparsed.dependencies, sink_lock.dependencies);
+        assert!(reparsed.dependencies.values().all(|locked| {
+            locked.unknown_fields.contains_key("signature")
+                && locked.files[0].unknown_fields.contains_key("size")
+        }));
+        assert!(sink_lock.save().is_err());
+    }
+}
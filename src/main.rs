@@ -1,100 +1,1321 @@
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 
-use clap::Parser;
-use env_logger::Env;
-use log::{debug, error, info};
+use serde_json::{json, Value};
+use tracing::{debug, error, info, warn};
 
 extern crate sink;
+use sink::actions;
+use sink::audit;
+use sink::backup;
+use sink::changelog;
 use sink::cli;
-use sink::github;
-use sink::toml::DependencyType;
+use sink::cli::completions::CompletionKind;
+use sink::client::{InstallOutcome, InstallReport, SinkClient};
+use sink::detect;
+use sink::diff;
+use sink::env;
+use sink::errors::{ErrorCode, ExitCode};
+use sink::exec;
+use sink::github::{self, app::GitHubApp, GitHubPathspec};
+use sink::gitignore;
+use sink::global;
+use sink::guard;
+use sink::hooks;
+use sink::import::{self, ImportSource};
+use sink::interrupt;
+use sink::list::{self, ListFilter};
+use sink::lock::{self, SinkLock};
+use sink::metadata;
+use sink::output::{self, OutputFormat, Report};
+use sink::self_update;
+use sink::status;
+use sink::workspace;
 use sink::SinkError;
 use sink::SinkTOML;
 
-fn main() {
-    let cli = cli::SinkCLI::parse();
+fn main() -> std::process::ExitCode {
+    let cli = cli::SinkCLI::parse_layered();
 
-    // Initialize logger
-    {
-        let logger_env =
-            Env::default().default_filter_or(if cli.verbose { "debug" } else { "info" });
-        env_logger::Builder::from_env(logger_env).init();
+    github::set_offline(cli.offline);
+    github::set_allow_draft(cli.allow_draft);
+    github::set_trace_http(cli.trace_http);
+    github::set_metadata_ttl(
+        (!cli.no_metadata_cache).then(|| std::time::Duration::from_secs(cli.metadata_ttl)),
+    );
+    hooks::set_disabled(cli.no_hooks);
+
+    // Completions must not print anything but the candidates, so skip logging entirely
+    if let cli::SinkSubcommands::Complete(params) = &cli.command {
+        return complete(&cli.file, params).into();
     }
 
-    // Load sink TOML
-    let mut path = PathBuf::from(&cli.file);
+    output::init_logger(cli.log_level(), cli.log_format, cli.trace_http);
+
+    if let (Some(app_id), Some(private_key)) = (&cli.app_id, &cli.app_private_key) {
+        match GitHubApp::new(app_id, private_key, cli.app_installation_id) {
+            Ok(app) => github::app::set_app(Some(app)),
+            Err(e) => {
+                output::error(&e);
+                return ExitCode::Config.into();
+            }
+        }
+    }
+
+    if let cli::SinkSubcommands::Completions(params) = &cli.command {
+        cli::completions::print_completions(params.shell);
+        return ExitCode::Success.into();
+    }
+
+    if let cli::SinkSubcommands::Man(params) = &cli.command {
+        if let Err(e) = cli::man::generate(params.dir.as_deref()) {
+            output::error(&e);
+            return ExitCode::Failure.into();
+        }
+        return ExitCode::Success.into();
+    }
+
+    // The exit code is the one of the executable, so there is no report either
+    if let cli::SinkSubcommands::Run(params) = &cli.command {
+        return match run_dependency(&cli, params) {
+            Ok(code) => std::process::ExitCode::from(u8::try_from(code).unwrap_or(1)),
+            Err(exit_code) => exit_code.into(),
+        };
+    }
+
+    handle_interrupts();
+
+    let output_format = cli.output;
+    let command = cli.command.name();
+    let check_for_update = !cli.no_update_check
+        && !cli.offline
+        && !matches!(cli.command, cli::SinkSubcommands::SelfUpdate(_))
+        && std::io::stderr().is_terminal()
+        && !cli::is_ci();
+    let (result, exit_code) = run(cli);
+
+    if output_format == OutputFormat::Json {
+        match serde_json::to_string_pretty(&Report::new(command, result, exit_code)) {
+            Ok(report) => println!("{report}"),
+            Err(e) => error!("Failed to serialize report: {e}"),
+        }
+    }
+
+    if check_for_update {
+        if let Some(notification) = self_update::notification() {
+            info!("{notification}");
+        }
+    }
+
+    exit_code.into()
+}
+
+/// Stop gracefully on Ctrl-C or SIGTERM, see [`interrupt`].
+///
+/// Downloads in flight are cancelled and no new ones are started, so sink exits soon with [`ExitCode::Interrupted`].
+/// A second signal exits right away.
+fn handle_interrupts() {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::flag;
+
+    for signal in [SIGINT, SIGTERM] {
+        // Registered first, so it only sees the flag set by an earlier signal
+        let registered = flag::register_conditional_shutdown(
+            signal,
+            ExitCode::Interrupted as i32,
+            interrupt::flag(),
+        )
+        .and_then(|_| flag::register(signal, interrupt::flag()));
+        if let Err(e) = registered {
+            debug!("Failed to handle signal {signal}: {e}");
+        }
+    }
+}
+
+/// Print the dynamic completion candidates, one per line.
+fn complete(file: &str, params: &cli::SubcommandComplete) -> ExitCode {
+    let sink_toml = SinkTOML::from_file(&PathBuf::from(file)).ok();
+    let candidates = match params.kind {
+        CompletionKind::Dependencies => match &sink_toml {
+            Some(sink_toml) => cli::completions::dependency_candidates(sink_toml),
+            None => return ExitCode::Config,
+        },
+        CompletionKind::Versions => {
+            let default_owner = sink_toml.and_then(|sink_toml| sink_toml.default_owner);
+            match cli::completions::version_candidates(
+                params.dependency.as_deref().unwrap_or_default(),
+                &default_owner,
+            ) {
+                Ok(candidates) => candidates,
+                Err(e) => return ExitCode::from_error(&e),
+            }
+        }
+    };
+
+    for candidate in candidates {
+        println!("{candidate}");
+    }
+
+    ExitCode::Success
+}
+
+/// Returns the path of the sink TOML to use, which is the global one with `--global`.
+///
+/// Falls back to 'docs/sink_example.toml' if the given sink TOML does not exist.
+fn sink_toml_path(cli: &cli::SinkCLI) -> Result<PathBuf, ExitCode> {
+    if cli.global {
+        return global::manifest().map_err(|e| {
+            output::error(&e);
+            ExitCode::Config
+        });
+    }
+
+    let path = PathBuf::from(&cli.file);
     if !path.exists() {
         debug!(
             "'{}' does not exist, failing back to 'docs/sink_example.toml'!",
             path.display()
         );
-        path = PathBuf::from("docs/sink_example.toml");
+        return Ok(PathBuf::from("docs/sink_example.toml"));
+    }
+    Ok(path)
+}
+
+/// Run the executable of a dependency, installing the dependency first if it is not installed.
+///
+/// Returns the exit code of the executable, or the exit code of sink if it could not be run.
+fn run_dependency(cli: &cli::SinkCLI, params: &cli::SubcommandRun) -> Result<i32, ExitCode> {
+    let path = sink_toml_path(cli)?;
+    let sink_toml = SinkTOML::from_file_with(&path, cli.strict).map_err(|e| {
+        error!("{e}");
+        ExitCode::Config
+    })?;
+    let sink_lock = SinkLock::load(&sink_toml).map_err(|e| {
+        output::error(&e);
+        ExitCode::Config
+    })?;
+    let fail = |e: anyhow::Error| {
+        let exit_code = ExitCode::from_error(&e);
+        output::error(&e);
+        exit_code
+    };
+
+    let pathspec = exec::find(&sink_toml, &sink_lock, &params.dependency).map_err(fail)?;
+    let root = sink_toml.root();
+    let mut client = SinkClient::new(sink_toml, sink_lock);
+
+    let installed = client
+        .sink_lock()
+        .dependencies
+        .get(&pathspec)
+        .filter(|locked| {
+            locked
+                .files
+                .iter()
+                .all(|file| root.join(&file.path).exists())
+        })
+        .cloned();
+    let locked = match installed {
+        Some(locked) => locked,
+        None => {
+            info!("Installing '{pathspec}'...");
+            let _guard = guard::acquire(&path).map_err(fail)?;
+            client.install_dependency(&pathspec).map_err(fail)?
+        }
+    };
+
+    let executable = exec::executable(&root, &locked, &params.dependency)
+        .map_err(|e| fail(e.context(format!("Failed to run '{pathspec}'!"))))?;
+    exec::run(&root.join(executable), &params.args).map_err(fail)
+}
+
+/// Returns the installed dependencies for the JSON report and the exit code of the first failed one.
+///
+/// Failed dependencies are reported under 'failed', along with why they failed.
+fn install_result(report: &InstallReport) -> (serde_json::Map<String, Value>, Option<ExitCode>) {
+    let mut installed: serde_json::Map<String, Value> = report
+        .installed()
+        .map(|(pathspec, locked)| (pathspec.to_string(), json!(locked)))
+        .collect();
+    let failed: serde_json::Map<String, Value> = report
+        .failures()
+        .map(|(pathspec, reason)| (pathspec.to_string(), json!(reason)))
+        .collect();
+    if !failed.is_empty() {
+        // Pathspecs always contain a slash, so this never collides with a dependency
+        installed.insert(String::from("failed"), Value::Object(failed));
+    }
+    let failure = report.outcomes.values().find_map(|outcome| match outcome {
+        InstallOutcome::Installed(_) | InstallOutcome::Optional(_) => None,
+        InstallOutcome::Invalid(_) => Some(ExitCode::Config),
+        InstallOutcome::Failed(e) => Some(ExitCode::from_error(e)),
+        InstallOutcome::Mismatch => Some(ExitCode::Verification),
+    });
+
+    (installed, failure)
+}
+
+/// Write the job summary and step outputs when running in GitHub Actions.
+///
+/// Failing to do so does not fail the install, which already happened.
+fn report_actions(reports: &[(&str, &InstallReport)]) {
+    if let Err(e) = actions::report(reports) {
+        warn!("{}", SinkError::Any(e));
+    }
+}
+
+/// Log the dependencies that could not be installed all at once, so they are not lost between the other log lines.
+///
+/// Only optional dependencies failing is merely a warning.
+fn report_failures(report: &InstallReport, prefix: &str) {
+    if let Some(failures) = report.failure_report() {
+        match report.is_ok() {
+            true => warn!("{prefix}{failures}"),
+            false => error!("{prefix}{failures}"),
+        }
+    }
+}
+
+/// Ask for confirmation before deleting installed files, listing what is affected on stderr.
+///
+/// Nothing is asked unless `ask` is set, see the global '--yes'.
+/// Returns the exit code to use if the user declined.
+fn confirm_deletion<T: std::fmt::Display>(
+    ask: bool,
+    header: &str,
+    affected: &[T],
+    question: &str,
+) -> Result<(), ExitCode> {
+    if !ask {
+        return Ok(());
+    }
+
+    eprintln!("{header}");
+    for item in affected {
+        eprintln!("    {item}");
+    }
+    match cli::confirm(question) {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            info!("Aborted!");
+            Err(ExitCode::NothingToDo)
+        }
+        Err(e) => {
+            error!("{e}");
+            Err(ExitCode::Failure)
+        }
+    }
+}
+
+/// Offer to persist the patterns of the dependencies whose assets were guessed.
+///
+/// Only asks if `interactive` is set, otherwise the patterns are merely reported.
+/// Returns the new pathspecs of the renamed dependencies.
+fn offer_patterns(
+    client: &mut SinkClient,
+    guessed: &BTreeMap<GitHubPathspec, String>,
+    interactive: bool,
+) -> BTreeMap<GitHubPathspec, GitHubPathspec> {
+    let mut renamed = BTreeMap::new();
+    for (pathspec, pattern) in guessed.iter() {
+        if !interactive {
+            info!("Set the pattern of '{pathspec}' to '{pattern}' to stop guessing its assets!");
+            continue;
+        }
+
+        match cli::confirm(&format!(
+            "Persist the guessed pattern '{pattern}' for '{pathspec}'?"
+        )) {
+            Ok(true) => match client.persist_pattern(pathspec, pattern) {
+                Ok(new) => {
+                    info!("Renamed '{pathspec}' to '{new}'!");
+                    renamed.insert(pathspec.clone(), new);
+                }
+                Err(e) => output::error(&e),
+            },
+            Ok(false) => {}
+            Err(e) => error!("{e}"),
+        }
+    }
+
+    renamed
+}
+
+/// Update the running sink binary to the latest release, or only report it with `--check`.
+fn update_self(params: &cli::SubcommandSelfUpdate) -> (Value, ExitCode) {
+    let update = match self_update::check() {
+        Ok(update) => update,
+        Err(e) => {
+            let exit_code = ExitCode::from_error(&e);
+            output::error(&e);
+            return (Value::Null, exit_code);
+        }
+    };
+    if !update.is_available() {
+        info!("sink {} is up to date!", update.current);
+        return (json!({ "update": update }), ExitCode::NothingToDo);
+    }
+    if params.check {
+        info!(
+            "sink {} is available, currently running {}!",
+            update.latest, update.current
+        );
+        return (json!({ "update": update }), ExitCode::Success);
+    }
+
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            error!("Unable to locate the running sink binary: {e}");
+            return (Value::Null, ExitCode::Failure);
+        }
+    };
+    match self_update::apply(&update, &exe) {
+        Ok(path) => {
+            info!(
+                "Updated sink from {} to {} at '{}'!",
+                update.current,
+                update.latest,
+                path.display()
+            );
+            (json!({ "update": update, "path": path }), ExitCode::Success)
+        }
+        Err(e) => {
+            let exit_code = ExitCode::from_error(&e);
+            output::error(&e);
+            (Value::Null, exit_code)
+        }
+    }
+}
+
+/// Show who sink talks to GitHub as and how much of the rate limits is left.
+fn api_status(human: bool) -> (Value, ExitCode) {
+    let status = match status::check() {
+        Ok(status) => status,
+        Err(e) => {
+            let exit_code = ExitCode::from_error(&e);
+            output::error(&e);
+            return (Value::Null, exit_code);
+        }
+    };
+
+    for (resource, limit) in status.rate_limits.iter() {
+        if limit.limit > 0 && limit.remaining == 0 {
+            warn!("The rate limit of '{resource}' is exhausted!");
+        }
+    }
+    if human {
+        println!("{}", status::render(&status, std::time::SystemTime::now()));
+    }
+
+    (json!(status), ExitCode::Success)
+}
+
+/// Explain the given error code, or list all of them.
+fn explain(params: &cli::SubcommandExplain, human: bool) -> (Value, ExitCode) {
+    let Some(id) = &params.code else {
+        let rows: Vec<Vec<&str>> = ErrorCode::ALL
+            .iter()
+            .map(|code| vec![code.id(), code.summary()])
+            .collect();
+        if human {
+            let mut table = vec![vec!["CODE", "SUMMARY"]];
+            table.extend(rows.iter().cloned());
+            println!("{}", list::table(&table));
+        }
+        let codes: serde_json::Map<String, Value> = rows
+            .into_iter()
+            .map(|row| (String::from(row[0]), json!(row[1])))
+            .collect();
+        return (Value::Object(codes), ExitCode::Success);
+    };
+
+    let Some(code) = ErrorCode::parse(id) else {
+        error!("Unknown error code '{id}'! Run 'sink explain' to list all of them.");
+        return (Value::Null, ExitCode::Failure);
+    };
+    if human {
+        println!("{code}: {}\n\n{}", code.summary(), code.explanation());
+    }
+    (
+        json!({
+            "code": code.id(),
+            "summary": code.summary(),
+            "explanation": code.explanation(),
+        }),
+        ExitCode::Success,
+    )
+}
+
+/// Run the subcommand and return its result for the JSON report as well as the exit code.
+///
+/// Errors are logged instead of returned, so they end up in both the logs and the report.
+fn run(cli: cli::SinkCLI) -> (Value, ExitCode) {
+    let human = cli.output == OutputFormat::Human;
+    let interactive = human && std::io::stdin().is_terminal() && !cli::is_ci();
+    let ask = interactive && !cli.yes;
+
+    // Updating sink itself does not involve any sink TOML, neither does talking to GitHub about itself
+    if let cli::SinkSubcommands::SelfUpdate(params) = &cli.command {
+        return update_self(params);
+    }
+    if let cli::SinkSubcommands::ApiStatus(_) = &cli.command {
+        return api_status(human);
+    }
+    if let cli::SinkSubcommands::Explain(params) = &cli.command {
+        return explain(params, human);
+    }
+
+    // Load sink TOML
+    let path = match sink_toml_path(&cli) {
+        Ok(path) => path,
+        Err(exit_code) => return (Value::Null, exit_code),
+    };
+
+    // Keep other sink processes from modifying the same files concurrently
+    let _guard = if cli.command.is_mutating() {
+        match guard::acquire(&path) {
+            Ok(guard) => Some(guard),
+            Err(e) => {
+                output::error(&e);
+                return (Value::Null, ExitCode::Failure);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Restoring must work, even if the current sink TOML is broken
+    if let cli::SinkSubcommands::Config(params) = &cli.command {
+        if params.restore {
+            return match backup::restore(&path) {
+                Ok(backup) => {
+                    info!("Restored '{}'!", path.display());
+                    (json!({ "restored": backup }), ExitCode::Success)
+                }
+                Err(e) => {
+                    output::error(&e);
+                    (Value::Null, ExitCode::Failure)
+                }
+            };
+        }
     }
-    let sink_toml = SinkTOML::from_file(&path);
+
+    let sink_toml = SinkTOML::from_file_with(&path, cli.strict);
 
     if let Err(sink_err) = sink_toml {
         error!("{sink_err}");
-        return;
+        return (Value::Null, ExitCode::Config);
     }
 
     let sink_toml = sink_toml.unwrap();
     debug!("Loaded sink TOML from '{}'!", path.display());
 
+    let mut sink_lock = match SinkLock::load(&sink_toml) {
+        Ok(sink_lock) => sink_lock,
+        Err(e) => {
+            output::error(&e);
+            return (Value::Null, ExitCode::Config);
+        }
+    };
+
     match cli.command {
         cli::SinkSubcommands::Config(params) => {
-            if params.all {
-                info!("{:#?}", sink_toml);
-            } else if params.toml {
-                info!("{}", sink_toml.to_toml());
-            }
-        }
-        cli::SinkSubcommands::Install(_) => {
-            for (pattern, dependency) in sink_toml.dependencies.iter() {
-                let github_dependency = match dependency {
-                    DependencyType::Full(github_dependency) => github_dependency,
-                    DependencyType::Version(version) => &match github::GitHubDependency::new(
-                        pattern.to_string(),
-                        None,
-                        Some(version.to_owned()),
-                        true,
-                        &None,
-                    ) {
-                        Ok(new_dependency) => new_dependency,
-                        Err(e) => {
-                            error!("{e}");
-                            continue;
+            if let Some(key) = params.get {
+                return match sink_toml.get_value(&key) {
+                    Ok(value) => {
+                        if human {
+                            println!("{value}");
                         }
-                    },
-                    DependencyType::Invalid(_) => {
-                        error!("Invalid dependency entry for '{}'!", pattern);
-                        continue;
+                        (json!(value), ExitCode::Success)
+                    }
+                    Err(e) => {
+                        error!("{e}");
+                        (Value::Null, ExitCode::Failure)
+                    }
+                };
+            }
+            if let Some(key) = params.unset {
+                return match sink_toml.unset(&key) {
+                    Ok(_) => {
+                        info!("Unset '{key}'!");
+                        (json!({ "unset": key }), ExitCode::Success)
+                    }
+                    Err(e) => {
+                        output::error(&e);
+                        (Value::Null, ExitCode::Failure)
                     }
                 };
-                if let Err(e) = github::download(github_dependency) {
-                    error!("{}", SinkError::Any(e));
+            }
+            if params.all {
+                if human {
+                    info!("{:#?}", sink_toml);
+                }
+                return (json!(sink_toml), ExitCode::Success);
+            } else if params.toml {
+                if human {
+                    info!("{}", sink_toml.to_toml());
+                }
+                return (json!(sink_toml.to_toml()), ExitCode::Success);
+            }
+            (Value::Null, ExitCode::NothingToDo)
+        }
+        cli::SinkSubcommands::Install(params) if params.workspace => {
+            let summarize = human && tracing::enabled!(tracing::Level::INFO);
+            let installs = match workspace::install(
+                sink_toml,
+                sink_lock,
+                params.frozen,
+                params.fail_fast,
+                params.preflight,
+            ) {
+                Ok(installs) => installs,
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    return (Value::Null, exit_code);
+                }
+            };
+
+            let mut members = serde_json::Map::new();
+            let mut any_installed = false;
+            let mut failure = None;
+            let mut reports = Vec::new();
+            for (member, (mut client, report)) in installs {
+                offer_patterns(&mut client, &report.guessed, interactive);
+
+                let (mut installed, member_failure) = install_result(&report);
+                any_installed |= report.installed().next().is_some();
+                failure = failure.or(member_failure);
+                if summarize && !report.summary.is_empty() {
+                    println!("[{member}]\n{}", report.summary_table());
+                }
+                if human {
+                    report_failures(&report, &format!("[{member}] "));
+                }
+                if params.timings {
+                    if human {
+                        println!("[{member}] {}", report.timings);
+                    }
+                    installed.insert(String::from("timings"), json!(report.timings));
+                }
+                members.insert(member.clone(), Value::Object(installed));
+                reports.push((member, report));
+            }
+            report_actions(
+                &reports
+                    .iter()
+                    .map(|(member, report)| (member.as_str(), report))
+                    .collect::<Vec<_>>(),
+            );
+
+            let exit_code = match failure {
+                None if !any_installed => {
+                    info!("No dependencies to install!");
+                    ExitCode::NothingToDo
+                }
+                None => ExitCode::Success,
+                Some(_) if any_installed => ExitCode::PartialInstall,
+                Some(exit_code) => exit_code,
+            };
+            (Value::Object(members), exit_code)
+        }
+        cli::SinkSubcommands::Install(params) => {
+            if params.frozen {
+                if !sink_lock.path.exists() {
+                    error!(
+                        "'{}' does not exist, refusing to install in frozen mode!",
+                        sink_lock.path.display()
+                    );
+                    return (Value::Null, ExitCode::Config);
+                }
+
+                let reasons = lock::out_of_sync(&sink_toml, &sink_lock);
+                if !reasons.is_empty() {
+                    for reason in reasons.iter() {
+                        error!("{reason}");
+                    }
+                    error!(
+                        "'{}' is out of sync with the sink TOML, refusing to install in frozen mode!",
+                        sink_lock.path.display()
+                    );
+                    return (json!({ "out_of_sync": reasons }), ExitCode::Config);
+                }
+            }
+
+            if sink_toml.dependencies.is_empty() {
+                info!("No dependencies to install!");
+                return (Value::Null, ExitCode::NothingToDo);
+            }
+
+            let mut client = SinkClient::new(sink_toml, sink_lock);
+            client.set_fail_fast(params.fail_fast);
+            if params.preflight {
+                if let Err(e) = client.preflight() {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    return (Value::Null, exit_code);
+                }
+            }
+            let report = match client.install(params.frozen) {
+                Ok(report) => report,
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    return (Value::Null, exit_code);
+                }
+            };
+
+            offer_patterns(&mut client, &report.guessed, interactive);
+
+            // The interleaved log lines are hard to scan, so sum them up in a table
+            let summarize = human && tracing::enabled!(tracing::Level::INFO);
+            let (mut installed, failure) = install_result(&report);
+            let exit_code = match failure {
+                None => ExitCode::Success,
+                Some(_) if report.installed().next().is_some() => ExitCode::PartialInstall,
+                Some(exit_code) => exit_code,
+            };
+            if summarize {
+                println!("{}", report.summary_table());
+            }
+            if human {
+                report_failures(&report, "");
+            }
+            report_actions(&[("", &report)]);
+            if params.timings {
+                if human {
+                    println!("{}", report.timings);
+                }
+                // Pathspecs always contain a slash, so this never collides with a dependency
+                installed.insert(String::from("timings"), json!(report.timings));
+            }
+            (Value::Object(installed), exit_code)
+        }
+        cli::SinkSubcommands::Lock(cli::SubcommandLock {
+            action: Some(cli::LockAction::Export(params)),
+        }) => {
+            if !sink_lock.path.exists() {
+                error!("'{}' does not exist!", sink_lock.path.display());
+                return (Value::Null, ExitCode::Config);
+            }
+
+            let rows = lock::export::rows(&sink_lock);
+            if human {
+                match lock::export::render(&rows, params.format) {
+                    Ok(rendered) => println!("{rendered}"),
+                    Err(e) => {
+                        error!("{e}");
+                        return (Value::Null, ExitCode::Failure);
+                    }
                 }
             }
+            (json!(rows), ExitCode::Success)
+        }
+        cli::SinkSubcommands::Lock(_) => {
+            // Workspace members are locked by installing them, see 'install --workspace'
+            let mut client = SinkClient::new(sink_toml, sink_lock);
+            if let Err(e) = client.lock() {
+                output::error(&e);
+                return (Value::Null, ExitCode::from_error(&e));
+            }
+
+            let locked = client.sink_lock();
+            info!(
+                "Locked {} dependencies into '{}'!",
+                locked.dependencies.len(),
+                locked.path.display()
+            );
+            (json!(locked.dependencies), ExitCode::Success)
         }
         cli::SinkSubcommands::Add(params) => {
+            // Global dependencies go to the shared user directory and are not tracked by git
+            let mut destination = params.destination;
+            let mut short = params.short;
+            if cli.global {
+                if short {
+                    warn!("Ignoring '--short', global dependencies need their destination!");
+                    short = false;
+                }
+                destination =
+                    destination.or_else(|| global::data_dir().map(|dir| dir.display().to_string()));
+            }
+
             match github::GitHubDependency::new(
                 params.dependency,
-                params.destination,
+                destination,
                 params.version,
-                !params.no_gitignore,
+                !params.no_gitignore && !cli.global,
                 &sink_toml.default_owner,
             ) {
                 Ok(dependency) => {
-                    if let Err(e) = github::add(sink_toml, dependency, params.short) {
-                        error!("{e}");
+                    let pathspec = dependency.pathspec.clone();
+                    let mut client = SinkClient::new(sink_toml, sink_lock);
+                    match client.add(dependency.clone(), short) {
+                        Ok(locked) => {
+                            let guessed: BTreeMap<GitHubPathspec, String> =
+                                detect::guessed_pattern(&dependency, &locked)
+                                    .map(|pattern| (pathspec.clone(), pattern))
+                                    .into_iter()
+                                    .collect();
+                            let renamed = offer_patterns(&mut client, &guessed, interactive);
+                            (
+                                json!({
+                                    "added": renamed.get(&pathspec).unwrap_or(&pathspec),
+                                    "locked": locked,
+                                    "guessed": guessed.get(&pathspec),
+                                }),
+                                ExitCode::Success,
+                            )
+                        }
+                        Err(e) => {
+                            error!("{e}");
+                            (Value::Null, ExitCode::from_error(&e))
+                        }
                     }
                 }
                 Err(sink_err) => {
                     error!("{sink_err}");
+                    (Value::Null, ExitCode::Failure)
                 }
             }
         }
         cli::SinkSubcommands::Remove(params) => {
-            info!("{:#?}", params);
+            let matching = match sink_toml.matching_dependencies(&params.dependency) {
+                Ok(matching) => matching,
+                Err(e) => {
+                    error!("Invalid pattern '{}': {e}", params.dependency);
+                    return (Value::Null, ExitCode::Failure);
+                }
+            };
+            if matching.is_empty() {
+                error!("No dependency matches '{}'!", params.dependency);
+                return (Value::Null, ExitCode::Failure);
+            }
+
+            // Removing the files of a single dependency is destructive as well
+            if matching.len() > 1 || !params.keep_files {
+                let question = match params.keep_files {
+                    true => format!("Remove {} dependencies?", matching.len()),
+                    false => format!(
+                        "Remove {} dependencies and delete their files?",
+                        matching.len()
+                    ),
+                };
+                if let Err(exit_code) = confirm_deletion(
+                    ask,
+                    "The following dependencies will be removed:",
+                    &matching,
+                    &question,
+                ) {
+                    return (Value::Null, exit_code);
+                }
+            }
+
+            let mut client = SinkClient::new(sink_toml, sink_lock);
+            match client.remove(&matching, params.keep_files) {
+                Ok(report) => (
+                    json!({ "removed": report.removed, "files": report.files }),
+                    ExitCode::Success,
+                ),
+                Err(e) => {
+                    output::error(&e);
+                    (Value::Null, ExitCode::Failure)
+                }
+            }
         }
-    };
+        cli::SinkSubcommands::Upgrade(params) => {
+            let mut selection = Vec::new();
+            for dependency in params.dependencies.iter() {
+                match sink_toml.matching_dependencies(dependency) {
+                    Ok(matching) if !matching.is_empty() => selection.extend(matching),
+                    Ok(_) => {
+                        error!("No dependency matches '{dependency}'!");
+                        return (Value::Null, ExitCode::Failure);
+                    }
+                    Err(e) => {
+                        error!("Invalid pattern '{dependency}': {e}");
+                        return (Value::Null, ExitCode::Failure);
+                    }
+                }
+            }
+
+            let dry_run = params.dry_run || params.preview;
+            let mut client = SinkClient::new(sink_toml, sink_lock);
+            let upgrades = match client.update(&selection, dry_run) {
+                Ok(upgrades) => upgrades,
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    return (Value::Null, exit_code);
+                }
+            };
+            if upgrades.is_empty() {
+                info!("All pinned dependencies are up to date!");
+                return (json!({ "upgrades": upgrades }), ExitCode::NothingToDo);
+            }
+
+            // Collect the release notes since the locked version, falling back to the pinned one
+            let mut changelogs = serde_json::Map::new();
+            if params.preview {
+                for upgrade in upgrades.iter() {
+                    let from = client
+                        .sink_lock()
+                        .dependencies
+                        .get(&upgrade.pathspec)
+                        .map_or(upgrade.from.as_str(), |locked| locked.version.as_str());
+                    match changelog::between(&upgrade.pathspec.get_full_origin(), from, &upgrade.to)
+                    {
+                        Ok(releases) => {
+                            changelogs.insert(
+                                upgrade.pathspec.to_string(),
+                                json!(changelog::digest(&releases)),
+                            );
+                        }
+                        Err(e) => {
+                            let exit_code = ExitCode::from_error(&e);
+                            output::error(&e);
+                            return (Value::Null, exit_code);
+                        }
+                    }
+                }
+            }
+
+            if human && (params.notes || params.preview) {
+                for upgrade in upgrades.iter() {
+                    println!("{}: {} -> {}", upgrade.pathspec, upgrade.from, upgrade.to);
+                    let notes = match changelogs.get(&upgrade.pathspec.to_string()) {
+                        Some(digest) => digest.as_str().unwrap_or_default().to_string(),
+                        None => changelog::render(&upgrade.notes),
+                    };
+                    match notes.is_empty() {
+                        true => println!("    No release notes."),
+                        false => notes.lines().for_each(|line| println!("    {line}")),
+                    }
+                    println!();
+                }
+            }
+            if dry_run {
+                info!("Dry run, not modifying the sink TOML!");
+                let mut result = json!({ "upgrades": upgrades });
+                if params.preview {
+                    result["changelogs"] = Value::Object(changelogs);
+                }
+                return (result, ExitCode::Success);
+            }
+
+            info!(
+                "Upgraded {} dependencies, run 'sink install' to download them!",
+                upgrades.len()
+            );
+            (json!({ "upgrades": upgrades }), ExitCode::Success)
+        }
+        cli::SinkSubcommands::Import(params) => {
+            let file = params
+                .file
+                .unwrap_or_else(|| sink_toml.root().join(params.from.file_name()));
+            let contents = match std::fs::read_to_string(&file) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    error!("Failed to read '{}': {e}", file.display());
+                    return (Value::Null, ExitCode::Failure);
+                }
+            };
+
+            let tools = match params.from {
+                ImportSource::ToolVersions => import::parse_tool_versions(&contents),
+            };
+            let imports = match import::plan(&sink_toml, &tools) {
+                Ok(imports) => imports,
+                Err(e) => {
+                    output::error(&e);
+                    return (Value::Null, ExitCode::Failure);
+                }
+            };
+            if imports.is_empty() {
+                info!("Nothing to import!");
+                return (json!({ "imported": imports }), ExitCode::NothingToDo);
+            }
+            if params.dry_run {
+                for import in imports.iter() {
+                    info!("{} -> {}@{}", import.tool, import.pathspec, import.version);
+                }
+                info!("Dry run, not modifying the sink TOML!");
+                return (json!({ "imported": imports }), ExitCode::Success);
+            }
+
+            if let Err(e) = backup::create(&sink_toml.path) {
+                output::error(&e);
+                return (Value::Null, ExitCode::Failure);
+            }
+            match import::apply(sink_toml, &imports) {
+                Ok(_) => {
+                    info!(
+                        "Imported {} dependencies, narrow down their patterns and run 'sink install' to download them!",
+                        imports.len()
+                    );
+                    (json!({ "imported": imports }), ExitCode::Success)
+                }
+                Err(e) => {
+                    output::error(&e);
+                    (Value::Null, ExitCode::Failure)
+                }
+            }
+        }
+        cli::SinkSubcommands::Audit(params) => {
+            let report = match audit::audit(&sink_toml, &sink_lock, params.severity) {
+                Ok(report) => report,
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    return (Value::Null, exit_code);
+                }
+            };
+
+            if report.is_empty() {
+                info!("No known vulnerabilities or license violations found!");
+                return (json!(report), ExitCode::Success);
+            }
+            for finding in report.vulnerabilities.iter() {
+                warn!(
+                    "{}@{}: {} ({}) {}",
+                    finding.pathspec,
+                    finding.version,
+                    finding.ghsa_id,
+                    finding.severity,
+                    finding.summary
+                );
+                if human {
+                    if let Some(patched) = &finding.patched_versions {
+                        eprintln!("    Patched versions: {patched}");
+                    }
+                    eprintln!("    {}", finding.url);
+                }
+            }
+            for violation in report.licenses.iter() {
+                warn!(
+                    "{}: The license '{}' is not allowed!",
+                    violation.pathspec,
+                    violation.license.as_deref().unwrap_or("unknown")
+                );
+            }
+
+            // License violations break the policy, which takes precedence
+            if !report.licenses.is_empty() {
+                error!("Found {} license violations!", report.licenses.len());
+                return (json!(report), ExitCode::Policy);
+            }
+            error!(
+                "Found {} known vulnerabilities!",
+                report.vulnerabilities.len()
+            );
+            (json!(report), ExitCode::Vulnerable)
+        }
+        cli::SinkSubcommands::Verify(_) => match lock::verify(&sink_toml, &sink_lock) {
+            Ok(report) if report.is_ok() => {
+                info!("All installed files are valid!");
+                (json!(report), ExitCode::Success)
+            }
+            Ok(report) => {
+                error!("Verification failed!");
+                (json!(report), ExitCode::Verification)
+            }
+            Err(e) => {
+                output::error(&e);
+                (Value::Null, ExitCode::Failure)
+            }
+        },
+        cli::SinkSubcommands::Clean(params) => {
+            let pathspec = match params
+                .dependency
+                .map(|dependency| GitHubPathspec::parse(dependency, &sink_toml.default_owner))
+                .transpose()
+            {
+                Ok(pathspec) => pathspec,
+                Err(e) => {
+                    error!("{e}");
+                    return (Value::Null, ExitCode::Failure);
+                }
+            };
+
+            let root = sink_toml.root();
+            let files: Vec<String> = sink_lock
+                .dependencies
+                .iter()
+                .filter(|(locked, _)| pathspec.as_ref().is_none_or(|pathspec| pathspec == *locked))
+                .flat_map(|(_, locked)| locked.files.iter())
+                .filter(|file| {
+                    sink_toml.allow_external_destinations || lock::normalized(&file.path).is_some()
+                })
+                .filter(|file| root.join(&file.path).exists())
+                .map(|file| file.path.display().to_string())
+                .collect();
+            if !files.is_empty() {
+                if let Err(exit_code) = confirm_deletion(
+                    ask,
+                    "The following files will be deleted:",
+                    &files,
+                    &format!("Delete {} file(s)?", files.len()),
+                ) {
+                    return (Value::Null, exit_code);
+                }
+            }
+
+            match lock::clean(
+                &root,
+                &sink_lock,
+                pathspec.as_ref(),
+                sink_toml.allow_external_destinations,
+            ) {
+                Ok(removed) => {
+                    info!("Removed {} file(s)!", removed.len());
+                    let exit_code = if removed.is_empty() {
+                        ExitCode::NothingToDo
+                    } else {
+                        ExitCode::Success
+                    };
+                    (json!({ "removed": removed }), exit_code)
+                }
+                Err(e) => {
+                    output::error(&e);
+                    (Value::Null, ExitCode::Failure)
+                }
+            }
+        }
+        cli::SinkSubcommands::Prune(_) => {
+            let stale: Vec<&GitHubPathspec> = sink_lock
+                .dependencies
+                .keys()
+                .filter(|pathspec| !sink_toml.dependencies.contains_key(pathspec))
+                .collect();
+            if !stale.is_empty() {
+                if let Err(exit_code) = confirm_deletion(
+                    ask,
+                    "The files of the following dependencies will be deleted:",
+                    &stale,
+                    &format!("Prune {} dependencies?", stale.len()),
+                ) {
+                    return (Value::Null, exit_code);
+                }
+            }
+
+            let result = lock::prune(&sink_toml, &mut sink_lock).and_then(|pruned| {
+                sink_lock.save()?;
+                gitignore::sync(&sink_toml, &sink_lock)?;
+                env::sync(&sink_toml, &sink_lock)?;
+                Ok(pruned)
+            });
+            match result {
+                Ok(pruned) => {
+                    info!("Pruned {} dependencies!", pruned.len());
+                    let exit_code = if pruned.is_empty() {
+                        ExitCode::NothingToDo
+                    } else {
+                        ExitCode::Success
+                    };
+                    (json!({ "pruned": pruned }), exit_code)
+                }
+                Err(e) => {
+                    output::error(&e);
+                    (Value::Null, ExitCode::Failure)
+                }
+            }
+        }
+        cli::SinkSubcommands::List(params) => {
+            let filter = ListFilter {
+                owner: params.owner,
+                repository: params.repository,
+            };
+            let entries = list::entries(&sink_toml, params.resolved.then_some(&sink_lock), &filter);
+            if human {
+                match list::render(&entries, params.format) {
+                    Ok(rendered) => println!("{rendered}"),
+                    Err(e) => {
+                        error!("{e}");
+                        return (Value::Null, ExitCode::Failure);
+                    }
+                }
+            }
+            (json!(entries), ExitCode::Success)
+        }
+        cli::SinkSubcommands::Metadata(params) => {
+            let metadata = match params.renovate_config {
+                true => metadata::renovate_config(),
+                false => json!(metadata::metadata(&sink_toml)),
+            };
+            if human {
+                match serde_json::to_string_pretty(&metadata) {
+                    Ok(rendered) => println!("{rendered}"),
+                    Err(e) => {
+                        error!("{e}");
+                        return (Value::Null, ExitCode::Failure);
+                    }
+                }
+            }
+            (metadata, ExitCode::Success)
+        }
+        cli::SinkSubcommands::Diff(params) => {
+            let is_lock = params.lock || params.old.ends_with(".lock");
+            let new_path = params.new.unwrap_or(match is_lock {
+                true => sink_lock.path.clone(),
+                false => sink_toml.path.clone(),
+            });
+
+            let old = match params.old.as_str() {
+                "-" => std::io::read_to_string(std::io::stdin()),
+                path => std::fs::read_to_string(path),
+            };
+            let contents = old
+                .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", params.old))
+                .and_then(|old| {
+                    let new = std::fs::read_to_string(&new_path).map_err(|e| {
+                        anyhow::anyhow!("Failed to read '{}': {e}", new_path.display())
+                    })?;
+                    Ok((old, new))
+                });
+            let versions = contents.and_then(|(old, new)| match is_lock {
+                true => Ok((
+                    diff::sink_lock_versions(&old)?,
+                    diff::sink_lock_versions(&new)?,
+                )),
+                false => Ok((
+                    diff::sink_toml_versions(&old)?,
+                    diff::sink_toml_versions(&new)?,
+                )),
+            });
+
+            match versions {
+                Ok((old, new)) => {
+                    let diff = diff::diff(&old, &new);
+                    if human {
+                        match diff.is_empty() {
+                            true => info!("No differences!"),
+                            false => println!("{}", diff::format(&diff)),
+                        }
+                    }
+                    (json!(diff), ExitCode::Success)
+                }
+                Err(e) => {
+                    output::error(&e);
+                    (Value::Null, ExitCode::Config)
+                }
+            }
+        }
+        cli::SinkSubcommands::Env(params) => {
+            let dir = match std::path::absolute(env::bin_dir(&sink_toml)) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    output::error(&e.into());
+                    return (Value::Null, ExitCode::Failure);
+                }
+            };
+            if !sink_toml.link_executables {
+                warn!(
+                    "'link-executables' is not enabled, '{}' stays empty!",
+                    dir.display()
+                );
+            }
+
+            let export = env::export(&dir, params.shell);
+            if human {
+                println!("{export}");
+            }
+            (json!({ "bin": dir, "export": export }), ExitCode::Success)
+        }
+        cli::SinkSubcommands::Which(params) => {
+            let which =
+                exec::find(&sink_toml, &sink_lock, &params.dependency).and_then(|pathspec| {
+                    let root = sink_toml.root();
+                    let locked = sink_lock
+                        .dependencies
+                        .get(&pathspec)
+                        .filter(|locked| {
+                            locked
+                                .files
+                                .iter()
+                                .all(|file| root.join(&file.path).exists())
+                        })
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "'{pathspec}' is not installed, run 'sink install' first!"
+                            )
+                        })?;
+                    let path = std::path::absolute(root.join(exec::artifact(
+                        &root,
+                        locked,
+                        &params.dependency,
+                    )?))?;
+                    Ok((pathspec, locked.version.clone(), path))
+                });
+
+            match which {
+                Ok((pathspec, version, path)) => {
+                    info!("'{pathspec}' is installed at version '{version}'!");
+                    if human {
+                        println!("{}", path.display());
+                    }
+                    (
+                        json!({ "dependency": pathspec, "version": version, "path": path }),
+                        ExitCode::Success,
+                    )
+                }
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    (Value::Null, exit_code)
+                }
+            }
+        }
+        cli::SinkSubcommands::Use(params) => {
+            let pathspec = match exec::find(&sink_toml, &sink_lock, &params.dependency) {
+                Ok(pathspec) => pathspec,
+                Err(e) => {
+                    output::error(&e);
+                    return (Value::Null, ExitCode::Failure);
+                }
+            };
+
+            let mut client = SinkClient::new(sink_toml, sink_lock);
+            match client.use_version(&pathspec, &params.version) {
+                Ok(locked) => {
+                    info!("Using {pathspec}@{}!", locked.version);
+                    (
+                        json!({ "dependency": pathspec, "locked": locked }),
+                        ExitCode::Success,
+                    )
+                }
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    (Value::Null, exit_code)
+                }
+            }
+        }
+        #[cfg(feature = "tui")]
+        cli::SinkSubcommands::Ui(_) => {
+            if !interactive {
+                error!("'sink ui' needs an interactive terminal!");
+                return (Value::Null, ExitCode::Failure);
+            }
+
+            match cli::ui::run(SinkClient::new(sink_toml, sink_lock)) {
+                Ok(()) => (Value::Null, ExitCode::Success),
+                Err(e) => {
+                    let exit_code = ExitCode::from_error(&e);
+                    output::error(&e);
+                    (Value::Null, exit_code)
+                }
+            }
+        }
+        cli::SinkSubcommands::SelfUpdate(_)
+        | cli::SinkSubcommands::ApiStatus(_)
+        | cli::SinkSubcommands::Explain(_)
+        | cli::SinkSubcommands::Completions(_)
+        | cli::SinkSubcommands::Man(_)
+        | cli::SinkSubcommands::Run(_)
+        | cli::SinkSubcommands::Complete(_) => {
+            unreachable!("Handled before loading the sink TOML")
+        }
+    }
 }
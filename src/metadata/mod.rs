@@ -0,0 +1,178 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::github::{GitHubVersion, UpdatePolicy};
+use crate::SinkTOML;
+
+/// The Renovate datasource looking up the releases of a GitHub repository.
+pub const DATASOURCE: &str = "github-releases";
+
+/// A dependency an update bot can bump, in the fields of a Renovate custom manager.
+///
+/// See <https://docs.renovatebot.com/modules/manager/jsonata/>.
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataDep {
+    /// The repository of the dependency, in the form of 'owner/repository'.
+    pub dep_name: String,
+
+    /// The name the datasource looks the releases up by, which is the repository as well.
+    pub package_name: String,
+
+    /// The tag the dependency is pinned to, e.g. 'v1.2.3'.
+    pub current_value: String,
+
+    /// Always [`DATASOURCE`].
+    pub datasource: String,
+
+    /// The pathspec of the dependency in the sink TOML.
+    pub pathspec: String,
+
+    /// The sink TOML declaring the dependency, which differs from the loaded one for included dependencies.
+    pub file: String,
+}
+
+/// The output of `sink metadata`.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Metadata {
+    pub deps: Vec<MetadataDep>,
+}
+
+/// Collect the dependencies pinned to a tag, sorted by their pathspec.
+///
+/// Dependencies that follow `latest` or another moving version, and those with the update policy `pinned`, are left out,
+/// as there is nothing for an update bot to bump.
+pub fn metadata(sink_toml: &SinkTOML) -> Metadata {
+    let mut pathspecs: Vec<_> = sink_toml.dependencies.keys().collect();
+    pathspecs.sort();
+
+    let mut deps = Vec::new();
+    for pathspec in pathspecs {
+        let Ok(dependency) = sink_toml.dependencies[pathspec].to_github_dependency(pathspec) else {
+            debug!("{pathspec} is invalid, skipping!");
+            continue;
+        };
+        let GitHubVersion::Tag(tag) = &dependency.version else {
+            debug!(
+                "{pathspec} is not pinned to a tag ('{}'), skipping!",
+                dependency.version
+            );
+            continue;
+        };
+        if dependency.update_policy == UpdatePolicy::Pinned {
+            debug!("{pathspec} must not be upgraded, skipping!");
+            continue;
+        }
+
+        let origin = pathspec.get_full_origin();
+        let file = sink_toml.included.get(pathspec).unwrap_or(&sink_toml.path);
+        deps.push(MetadataDep {
+            dep_name: origin.clone(),
+            package_name: origin,
+            current_value: tag.clone(),
+            datasource: String::from(DATASOURCE),
+            pathspec: pathspec.to_string(),
+            file: file.display().to_string(),
+        });
+    }
+
+    Metadata { deps }
+}
+
+/// Returns the `customManagers` of a Renovate config bumping the versions of sink TOMLs in place.
+///
+/// Both the short form `"owner/repo:pattern" = "v1.2.3"` and tables with a `version` are matched.
+/// Versions that are not a tag, e.g. 'latest', are skipped by Renovate since they are no valid versions.
+pub fn renovate_config() -> Value {
+    let dependency = r#""(?<depName>[^/"\s]+/[^/":\s]+)(:[^"]*)?""#;
+    let version = r#""(?<currentValue>[^"]+)""#;
+    json!({
+        "customManagers": [{
+            "customType": "regex",
+            "managerFilePatterns": ["/(^|/)sink\\.toml$/"],
+            "matchStrings": [
+                format!(r"{dependency}\s*=\s*{version}"),
+                format!(r"{dependency}\s*=\s*\{{[^}}]*?version\s*=\s*{version}"),
+                format!(r"\[dependencies\.{dependency}\][^\[]*?version\s*=\s*{version}"),
+            ],
+            "datasourceTemplate": DATASOURCE,
+        }]
+    })
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempProject;
+    use regex::Regex;
+
+    #[test]
+    fn test_metadata() {
+        let project = TempProject::new("metadata").unwrap();
+        let sink_toml = project
+            .sink_toml()
+            .dependency("owner/repo:tool", "v1.2.3")
+            .dependency("owner/latest", "latest")
+            .dependency_table(
+                "owner/pinned",
+                "{ version = \"v1.0.0\", update-policy = \"pinned\" }",
+            )
+            .write()
+            .unwrap();
+
+        assert_eq!(
+            metadata(&sink_toml),
+            Metadata {
+                deps: vec![MetadataDep {
+                    dep_name: String::from("owner/repo"),
+                    package_name: String::from("owner/repo"),
+                    current_value: String::from("v1.2.3"),
+                    datasource: String::from(DATASOURCE),
+                    pathspec: String::from("owner/repo:tool"),
+                    file: project.sink_toml_path().display().to_string(),
+                }]
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(metadata(&sink_toml)).unwrap()["deps"][0]["currentValue"],
+            "v1.2.3"
+        );
+    }
+
+    #[test]
+    fn test_renovate_config() {
+        let contents = r#"
+[dependencies]
+"owner/short:tool-*.tar.gz" = "v1.0.0"
+"owner/inline" = { destination = "bin", version = "v2.0.0" }
+
+[dependencies."owner/table:*.zip"]
+destination = "tools"
+version = "v3.0.0"
+"#;
+        let config = renovate_config();
+        let matches: Vec<(String, String)> = config["customManagers"][0]["matchStrings"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|pattern| {
+                // Renovate uses the same named groups as the regex crate
+                let re = Regex::new(pattern.as_str().unwrap()).unwrap();
+                re.captures_iter(contents)
+                    .map(|captures| {
+                        (
+                            captures["depName"].to_string(),
+                            captures["currentValue"].to_string(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert!(matches.contains(&(String::from("owner/short"), String::from("v1.0.0"))));
+        assert!(matches.contains(&(String::from("owner/inline"), String::from("v2.0.0"))));
+        assert!(matches.contains(&(String::from("owner/table"), String::from("v3.0.0"))));
+    }
+}
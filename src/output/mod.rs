@@ -0,0 +1,302 @@
+#[cfg(feature = "cli")]
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+use std::cell::Cell;
+#[cfg(feature = "cli")]
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+#[cfg(feature = "cli")]
+use tracing::{field::Field, span, Event, Level, Subscriber};
+#[cfg(feature = "cli")]
+use tracing_subscriber::{
+    field::Visit, filter, layer::Context, prelude::*, registry::LookupSpan, EnvFilter, Layer,
+    Registry,
+};
+
+#[cfg(feature = "cli")]
+use crate::errors::ErrorCode;
+use crate::errors::{self, ExitCode};
+
+/// The output formats of sink's results.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum OutputFormat {
+    /// Human readable logs and tables
+    #[default]
+    Human,
+
+    /// A single JSON object on stdout, logs stay on stderr
+    Json,
+}
+
+/// The formats of the logs written to stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "cli", derive(ValueEnum))]
+pub enum LogFormat {
+    /// A line of text per event, prefixed by the spans it happened in
+    #[default]
+    Human,
+
+    /// A JSON object per line, with the fields of the event and its spans, e.g. for log pipelines in CI
+    Json,
+}
+
+/// All warnings logged during the current run.
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// All errors logged during the current run.
+static ERRORS: Mutex<Vec<ReportedError>> = Mutex::new(Vec::new());
+
+/// Separates the causes in the message of a logged error, see [`errors::describe`].
+#[cfg(feature = "cli")]
+const CAUSE_SEPARATOR: &str = " Caused by: ";
+
+thread_local! {
+    /// The exit code matching the error currently logged by [`error`].
+    static CODE: Cell<Option<ExitCode>> = const { Cell::new(None) };
+}
+
+/// Whether logs are currently not written to stderr, see [`set_muted`].
+static MUTED: AtomicBool = AtomicBool::new(false);
+
+/// Stop (or resume) writing logs to stderr, e.g. while a terminal UI is shown.
+///
+/// Warnings and errors are still recorded for the JSON report.
+pub fn set_muted(muted: bool) {
+    MUTED.store(muted, Ordering::Relaxed);
+}
+
+/// Log the error along with its causes.
+///
+/// Unlike logging it directly, the JSON report knows which kind of error it is, see [`ReportedError::code`].
+pub fn error(e: &anyhow::Error) {
+    CODE.set(Some(ExitCode::from_error(e)));
+    tracing::error!("{}", errors::describe(e));
+    CODE.set(None);
+}
+
+/// An error logged during the current run, as reported by `--output json`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReportedError {
+    /// The exit code matching the kind of the error, see [`ExitCode::from_error`].
+    ///
+    /// Errors not logged by [`error`] are reported as [`ExitCode::Failure`].
+    pub code: u8,
+
+    /// The stable code of the category of the error, e.g. 'SINK-E010', see [`errors::ErrorCode`].
+    pub error_code: Option<String>,
+
+    /// What failed.
+    pub message: String,
+
+    /// The pathspec of the dependency the error happened for, if any.
+    pub dependency: Option<String>,
+
+    /// Why it failed, from the outermost to the root cause.
+    pub causes: Vec<String>,
+}
+impl ReportedError {
+    /// Split the logged message into its error code, what failed and its causes.
+    #[cfg(feature = "cli")]
+    fn new(message: &str, code: ExitCode, dependency: Option<String>) -> Self {
+        let error_code = message
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .and_then(|(id, rest)| Some((ErrorCode::parse(id)?, rest)));
+        let message = error_code.map_or(message, |(_, rest)| rest);
+        let mut causes = message.split(CAUSE_SEPARATOR).map(String::from);
+        ReportedError {
+            code: code as u8,
+            error_code: error_code.map(|(error_code, _)| error_code.to_string()),
+            message: causes.next().unwrap_or_default(),
+            dependency,
+            causes: causes.collect(),
+        }
+    }
+}
+
+/// Collects the given field of an event or span.
+#[cfg(feature = "cli")]
+struct FieldVisitor {
+    name: &'static str,
+    value: Option<String>,
+}
+#[cfg(feature = "cli")]
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == self.name {
+            self.value = Some(format!("{value:?}"));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == self.name {
+            self.value = Some(String::from(value));
+        }
+    }
+}
+
+/// The pathspec of the dependency a span belongs to, see [`RecordingLayer`].
+#[cfg(feature = "cli")]
+struct DependencySpan(String);
+
+/// Layer recording warnings and errors for the JSON report.
+///
+/// Errors logged inside of a `dependency` span are attributed to its `pathspec`.
+#[cfg(feature = "cli")]
+struct RecordingLayer;
+#[cfg(feature = "cli")]
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordingLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "dependency" {
+            return;
+        }
+
+        let mut visitor = FieldVisitor {
+            name: "pathspec",
+            value: None,
+        };
+        attrs.record(&mut visitor);
+        if let (Some(pathspec), Some(span)) = (visitor.value, ctx.span(id)) {
+            span.extensions_mut().insert(DependencySpan(pathspec));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor {
+            name: "message",
+            value: None,
+        };
+        event.record(&mut visitor);
+        let message = visitor.value.unwrap_or_default();
+
+        if *event.metadata().level() != Level::ERROR {
+            if let Ok(mut warnings) = WARNINGS.lock() {
+                warnings.push(message);
+            }
+            return;
+        }
+
+        let dependency = ctx.event_scope(event).and_then(|scope| {
+            scope
+                .into_iter()
+                .find_map(|span| Some(span.extensions().get::<DependencySpan>()?.0.clone()))
+        });
+        let code = CODE.get().unwrap_or(ExitCode::Failure);
+        if let Ok(mut errors) = ERRORS.lock() {
+            errors.push(ReportedError::new(&message, code, dependency));
+        }
+    }
+}
+
+/// Returns the [`RecordingLayer`], only recording warnings and errors but keeping track of all spans.
+#[cfg(feature = "cli")]
+fn _recorder<S: Subscriber + for<'a> LookupSpan<'a>>() -> impl Layer<S> {
+    RecordingLayer.with_filter(filter::filter_fn(|metadata| {
+        metadata.is_span() || *metadata.level() <= Level::WARN
+    }))
+}
+
+/// Install the global subscriber, writing logs of at most the given level to stderr in the given format.
+///
+/// The `RUST_LOG` environment variable takes precedence over the level.
+/// With `trace_http`, the HTTP requests are logged regardless of both, see [`crate::github::set_trace_http`].
+/// Warnings and errors are always recorded, regardless of the configured filter.
+#[cfg(feature = "cli")]
+pub fn init_logger(level: filter::LevelFilter, format: LogFormat, trace_http: bool) {
+    let mut env_filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level.to_string()));
+    if trace_http {
+        if let Ok(directive) = format!("{}=info", crate::github::api::HTTP_TARGET).parse() {
+            env_filter = env_filter.add_directive(directive);
+        }
+    }
+    let unmuted = filter::filter_fn(|_| !MUTED.load(Ordering::Relaxed));
+    let logs = match format {
+        LogFormat::Human => tracing_subscriber::fmt::layer()
+            .with_ansi(std::io::stderr().is_terminal())
+            .with_writer(std::io::stderr)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_writer(std::io::stderr)
+            .boxed(),
+    };
+
+    Registry::default()
+        .with(logs.with_filter(env_filter).with_filter(unmuted))
+        .with(_recorder())
+        .try_init()
+        .expect("Logger must only be initialized once!");
+}
+
+/// The JSON document printed to stdout with `--output json`.
+#[derive(Serialize, Debug)]
+pub struct Report {
+    /// The subcommand that was executed.
+    pub command: String,
+
+    /// Whether the command finished without errors.
+    pub success: bool,
+
+    /// The exit code of the process, see [`ExitCode`].
+    pub exit_code: u8,
+
+    /// The command specific result.
+    pub result: Value,
+
+    pub warnings: Vec<String>,
+    pub errors: Vec<ReportedError>,
+}
+impl Report {
+    /// Create the report of a command from its result and the recorded log messages.
+    pub fn new(command: &str, result: Value, exit_code: ExitCode) -> Self {
+        let errors = ERRORS
+            .lock()
+            .map(|errors| errors.clone())
+            .unwrap_or_default();
+        Report {
+            command: command.to_string(),
+            success: errors.is_empty(),
+            exit_code: exit_code as u8,
+            result,
+            warnings: WARNINGS
+                .lock()
+                .map(|warnings| warnings.clone())
+                .unwrap_or_default(),
+            errors,
+        }
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+#[cfg(feature = "cli")]
+mod tests {
+    use super::*;
+    use crate::errors::GitHubError;
+
+    #[test]
+    fn test_recording_layer() {
+        let subscriber = Registry::default().with(_recorder());
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("dependency", pathspec = %"owner/repo:tool").entered();
+            let e = anyhow::Error::new(GitHubError(String::from("Not Found")))
+                .context("Failed to resolve 'owner/repo:tool'!");
+            error(&e);
+        });
+
+        let errors = ERRORS.lock().unwrap().clone();
+        assert!(errors.contains(&ReportedError {
+            code: ExitCode::Network as u8,
+            error_code: Some(String::from("SINK-E002")),
+            message: String::from("Failed to resolve 'owner/repo:tool'!"),
+            dependency: Some(String::from("owner/repo:tool")),
+            causes: vec![String::from("Not Found")],
+        }));
+    }
+}
@@ -0,0 +1,533 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use tracing::debug;
+
+use crate::errors::{CodedError, ErrorCode, PolicyError};
+use crate::github::{GitHubAsset, GitHubPathspec, GitHubRelease};
+use crate::integrity;
+use crate::lock;
+
+/// Provides a default value of `true` for [`serde`].
+fn _default_true() -> bool {
+    true
+}
+
+/// A size in bytes, given in the sink TOML as a number of bytes or with a unit, e.g. '100MB' or '1.5 GiB'.
+///
+/// Units are case-insensitive. 'KB', 'MB' and 'GB' (or just 'K', 'M' and 'G') are powers of 1000,
+/// 'KiB', 'MiB' and 'GiB' powers of 1024.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "_ByteSizeValue", into = "u64")]
+pub struct ByteSize(pub u64);
+impl ByteSize {
+    /// Parse a size with an optional unit, see [`ByteSize`].
+    pub fn parse(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let split = s
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: f64 = number
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid size '{s}'!"))?;
+        let factor: u64 = match unit.trim().to_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" => 1000,
+            "m" | "mb" => 1000 * 1000,
+            "g" | "gb" => 1000 * 1000 * 1000,
+            "kib" => 1 << 10,
+            "mib" => 1 << 20,
+            "gib" => 1 << 30,
+            unit => return Err(anyhow::anyhow!("Unknown unit '{unit}' of size '{s}'!")),
+        };
+
+        Ok(ByteSize((number * factor as f64).round() as u64))
+    }
+}
+impl Display for ByteSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let units = [
+            (1000 * 1000 * 1000, "GB"),
+            (1000 * 1000, "MB"),
+            (1000, "KB"),
+        ];
+        match units.iter().find(|(factor, _)| self.0 >= *factor) {
+            Some((factor, unit)) => write!(f, "{:.1} {unit}", self.0 as f64 / *factor as f64),
+            None => write!(f, "{} B", self.0),
+        }
+    }
+}
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+/// The representations of a [`ByteSize`] in the sink TOML.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum _ByteSizeValue {
+    Bytes(u64),
+    Text(String),
+}
+impl TryFrom<_ByteSizeValue> for ByteSize {
+    type Error = String;
+
+    fn try_from(value: _ByteSizeValue) -> Result<Self, Self::Error> {
+        match value {
+            _ByteSizeValue::Bytes(bytes) => Ok(ByteSize(bytes)),
+            _ByteSizeValue::Text(text) => ByteSize::parse(&text).map_err(|e| e.to_string()),
+        }
+    }
+}
+
+/// Restricts the repositories dependencies may come from and the releases that may be installed.
+///
+/// Both lists contain glob patterns of 'owner/repository', e.g. 'my-org/*'. Patterns are matched case-insensitively,
+/// as GitHub does for owners and repositories.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(
+    rename_all(deserialize = "kebab-case", serialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct Policy {
+    /// If not empty, only repositories matching one of these patterns are allowed.
+    #[serde(default)]
+    pub allow: Vec<String>,
+
+    /// Repositories matching one of these patterns are never allowed, even if they are allowed explicitly.
+    #[serde(default)]
+    pub deny: Vec<String>,
+
+    /// Whether dependencies may resolve to prereleases.
+    ///
+    /// This defaults to true.
+    #[serde(default = "_default_true")]
+    pub allow_prerelease: bool,
+
+    /// Whether every downloaded asset must match a published SHA-256 digest, see [`integrity::published_digest`].
+    ///
+    /// This defaults to false.
+    #[serde(default)]
+    pub require_checksum: bool,
+
+    /// If not empty, only repositories with one of these licenses are allowed, given as SPDX identifiers like 'MIT'.
+    ///
+    /// Identifiers are matched case-insensitively. Repositories without a license GitHub can identify are not allowed.
+    #[serde(default)]
+    pub licenses: Vec<String>,
+
+    /// The maximum size of a single asset, as declared by GitHub.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_asset_size: Option<ByteSize>,
+
+    /// The maximum combined size of the assets downloaded by a single run, e.g. of `sink install`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<ByteSize>,
+
+    /// The minisign public keys the assets of all repositories of an owner are signed with, by owner.
+    ///
+    /// Owners are matched case-insensitively. The key of a dependency takes precedence, see
+    /// [`crate::github::GitHubDependency::minisign_key`].
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub minisign_keys: BTreeMap<String, String>,
+}
+impl Default for Policy {
+    fn default() -> Self {
+        Policy {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            allow_prerelease: true,
+            require_checksum: false,
+            licenses: Vec::new(),
+            max_asset_size: None,
+            max_total_size: None,
+            minisign_keys: BTreeMap::new(),
+        }
+    }
+}
+impl Policy {
+    /// Whether the policy allows everything.
+    pub fn is_empty(&self) -> bool {
+        *self == Policy::default()
+    }
+
+    /// Returns the minisign public key of the owner of the dependency, see [`Policy::minisign_keys`].
+    pub fn minisign_key(&self, pathspec: &GitHubPathspec) -> Option<&str> {
+        self.minisign_keys
+            .iter()
+            .find(|(owner, _)| owner.eq_ignore_ascii_case(pathspec.owner()))
+            .map(|(_, key)| key.as_str())
+    }
+
+    /// Returns the first of the patterns matching the repository of the pathspec.
+    fn _matching<'a>(patterns: &'a [String], pathspec: &GitHubPathspec) -> Result<Option<&'a str>> {
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        let origin = pathspec.get_full_origin();
+        for pattern in patterns {
+            if glob::Pattern::new(pattern)?.matches_with(&origin, options) {
+                return Ok(Some(pattern));
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn _check(&self, pathspec: &GitHubPathspec) -> Result<()> {
+        let origin = pathspec.get_full_origin();
+        if let Some(pattern) = Policy::_matching(&self.deny, pathspec)? {
+            return Err(anyhow::Error::new(PolicyError(format!(
+                "'{origin}' is denied by '{pattern}'!"
+            ))));
+        }
+        if !self.allow.is_empty() && Policy::_matching(&self.allow, pathspec)?.is_none() {
+            return Err(anyhow::Error::new(PolicyError(format!(
+                "'{origin}' is not in the allowlist!"
+            ))));
+        }
+
+        Ok(())
+    }
+    /// Check whether the policy allows the dependency.
+    ///
+    /// Fails with a [`PolicyError`] if it does not.
+    pub fn check(&self, pathspec: &GitHubPathspec) -> Result<()> {
+        match self._check(pathspec) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.context(format!("Policy violation of '{pathspec}'!"))),
+        }
+    }
+
+    /// Check whether the policy allows installing the release.
+    ///
+    /// Fails with a [`PolicyError`] if it is a prerelease and those are not allowed.
+    pub fn check_release(&self, pathspec: &GitHubPathspec, release: &GitHubRelease) -> Result<()> {
+        if release.is_prerelease && !self.allow_prerelease {
+            return Err(anyhow::Error::new(PolicyError(format!(
+                "'{}' is a prerelease, which are not allowed!",
+                release.tag_name
+            )))
+            .context(format!("Policy violation of '{pathspec}'!")));
+        }
+
+        Ok(())
+    }
+
+    /// Whether the policy allows the license, given as SPDX identifier.
+    pub fn allows_license(&self, license: Option<&str>) -> bool {
+        self.licenses.is_empty()
+            || license.is_some_and(|license| {
+                self.licenses
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(license))
+            })
+    }
+
+    /// Check whether the policy allows the license of the dependency, see [`Policy::licenses`].
+    ///
+    /// Fails with a [`PolicyError`] if it does not.
+    pub fn check_license(&self, pathspec: &GitHubPathspec, license: Option<&str>) -> Result<()> {
+        if !self.allows_license(license) {
+            return Err(anyhow::Error::new(PolicyError(format!(
+                "The license '{}' is not allowed!",
+                license.unwrap_or("unknown")
+            )))
+            .context(format!("Policy violation of '{pathspec}'!")));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the policy for downloading more assets after `spent` bytes have been downloaded already.
+    ///
+    /// Lowers [`Policy::max_total_size`] by what has been spent.
+    pub fn remaining(&self, spent: u64) -> Policy {
+        let mut policy = self.clone();
+        policy.max_total_size = self
+            .max_total_size
+            .map(|max| ByteSize(max.0.saturating_sub(spent)));
+        policy
+    }
+
+    /// Check the declared sizes of the assets of the release before downloading them.
+    ///
+    /// Fails with a [`PolicyError`] if an asset exceeds [`Policy::max_asset_size`]
+    /// or all of them together exceed [`Policy::max_total_size`].
+    pub fn check_sizes(
+        &self,
+        pathspec: &GitHubPathspec,
+        release: &GitHubRelease,
+        assets: &[&GitHubAsset],
+    ) -> Result<()> {
+        let violation = |message: String| {
+            Err(anyhow::Error::new(PolicyError(message))
+                .context(format!("Policy violation of '{pathspec}'!")))
+        };
+
+        if let Some(max) = self.max_asset_size {
+            if let Some(asset) = assets.iter().find(|asset| asset.size > max.0) {
+                return violation(format!(
+                    "'{}' is {}, more than the maximum asset size of {max}!",
+                    asset.name,
+                    ByteSize(asset.size)
+                ));
+            }
+        }
+        if let Some(max) = self.max_total_size {
+            let total = ByteSize(assets.iter().map(|asset| asset.size).sum());
+            if total > max {
+                return violation(format!(
+                    "The assets of '{}' are {total}, more than the remaining total size of {max}!",
+                    release.tag_name
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn _check_asset(
+        &self,
+        origin: &str,
+        release: &GitHubRelease,
+        asset: &GitHubAsset,
+        contents: &[u8],
+    ) -> Result<()> {
+        if !self.require_checksum {
+            return Ok(());
+        }
+
+        let Some(expected) = integrity::published_digest(origin, release, asset)? else {
+            return Err(anyhow::Error::new(PolicyError(format!(
+                "'{}' has no published checksum!",
+                asset.name
+            ))));
+        };
+        let actual = lock::digest_bytes_like(contents, &expected)?;
+        if actual != expected {
+            return Err(CodedError(
+                ErrorCode::ChecksumMismatch,
+                format!(
+                    "'{}' does not match its published checksum! Expected '{expected}', got '{actual}'",
+                    asset.name
+                ),
+            )
+            .into());
+        }
+        debug!("'{}' matches its published checksum!", asset.name);
+
+        Ok(())
+    }
+    /// Check whether the policy allows installing the downloaded contents of the asset.
+    ///
+    /// If checksums are required, the contents must match the published digest of the asset.
+    /// Fails with a [`PolicyError`] if there is none.
+    pub fn check_asset(
+        &self,
+        origin: &str,
+        release: &GitHubRelease,
+        asset: &GitHubAsset,
+        contents: &[u8],
+    ) -> Result<()> {
+        match self._check_asset(origin, release, asset, contents) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(e.context(format!("Refusing to install '{}'!", asset.name))),
+        }
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let policy = Policy {
+            allow: vec![String::from("my-org/*"), String::from("BurntSushi/ripgrep")],
+            deny: vec![String::from("my-org/legacy-*")],
+            ..Default::default()
+        };
+        let check = |pathspec: &str| {
+            policy.check(&GitHubPathspec::try_from(String::from(pathspec)).unwrap())
+        };
+
+        assert!(check("my-org/tool:*.tar.gz").is_ok());
+        assert!(check("burntsushi/ripgrep").is_ok());
+        assert!(check("other/ripgrep").is_err());
+        assert!(check("my-org/legacy-tool").is_err());
+
+        let e = check("my-org/legacy-tool").unwrap_err();
+        assert!(e.chain().any(|cause| cause.is::<PolicyError>()));
+
+        assert!(Policy::default()
+            .check(&GitHubPathspec::new("any", "repo", "").unwrap())
+            .is_ok());
+    }
+
+    #[test]
+    fn test_byte_size() {
+        assert_eq!(ByteSize::parse("1024").unwrap(), ByteSize(1024));
+        assert_eq!(ByteSize::parse("100MB").unwrap(), ByteSize(100_000_000));
+        assert_eq!(ByteSize::parse("1.5 GiB").unwrap(), ByteSize(1_610_612_736));
+        assert_eq!(ByteSize::parse("2k").unwrap(), ByteSize(2000));
+        assert!(ByteSize::parse("1 PB").is_err());
+        assert!(ByteSize::parse("MB").is_err());
+
+        let policy: Policy =
+            toml::from_str("max-asset-size = \"10 MiB\"\nmax-total-size = 500").unwrap();
+        assert_eq!(policy.max_asset_size, Some(ByteSize(10 << 20)));
+        assert_eq!(policy.max_total_size, Some(ByteSize(500)));
+        assert!(toml::from_str::<Policy>("max-asset-size = \"huge\"").is_err());
+
+        assert_eq!(ByteSize(1_500_000).to_string(), "1.5 MB");
+        assert_eq!(ByteSize(999).to_string(), "999 B");
+    }
+
+    #[test]
+    fn test_check_sizes() {
+        let asset = |name: &str, size: u64| GitHubAsset {
+            name: String::from(name),
+            size,
+            ..Default::default()
+        };
+        let (small, large) = (asset("small", 10), asset("large", 100));
+        let pathspec = GitHubPathspec::new("owner", "repo", "").unwrap();
+        let release = GitHubRelease::default();
+        let policy = Policy {
+            max_asset_size: Some(ByteSize(50)),
+            max_total_size: Some(ByteSize(15)),
+            ..Default::default()
+        };
+
+        assert!(policy.check_sizes(&pathspec, &release, &[&small]).is_ok());
+        assert!(policy
+            .check_sizes(&pathspec, &release, &[&small, &large])
+            .is_err());
+        assert!(policy
+            .check_sizes(&pathspec, &release, &[&small, &small])
+            .is_err());
+        assert!(policy
+            .remaining(10)
+            .check_sizes(&pathspec, &release, &[&small])
+            .is_err());
+        assert!(Policy::default()
+            .check_sizes(&pathspec, &release, &[&large])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_lock_license() {
+        use crate::github::{self, GitHubDependency};
+        use crate::test_utils::{MockRelease, MockReleaseServer};
+
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v1.0.0").asset("tool.tar.gz", "test"),
+            )
+            .with_release(
+                "owner/other",
+                MockRelease::new("v1.0.0").asset("tool.tar.gz", "test"),
+            )
+            .with_license("owner/repo", "MIT")
+            .with_license("owner/other", "GPL-3.0");
+        let _guard = server.install();
+
+        let policy = Policy {
+            licenses: vec![String::from("mit"), String::from("Apache-2.0")],
+            ..Default::default()
+        };
+        let lock = |pathspec: &str| {
+            let dependency =
+                GitHubDependency::new(String::from(pathspec), None, None, true, &None).unwrap();
+            github::lock(&dependency, &policy)
+        };
+
+        assert_eq!(
+            lock("owner/repo:tool.tar.gz").unwrap().license,
+            Some(String::from("MIT"))
+        );
+        let e = lock("owner/other:tool.tar.gz").unwrap_err();
+        assert!(e.chain().any(|cause| cause.is::<PolicyError>()));
+
+        assert!(!policy.allows_license(None));
+        assert!(Policy::default().allows_license(None));
+    }
+
+    #[test]
+    fn test_download() {
+        use crate::github::{self, GitHubDependency, GitHubVersion};
+        use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+
+        let checksums =
+            "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08  tool.tar.gz\n";
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v2.0.0-rc.1")
+                    .prerelease()
+                    .asset("tool.tar.gz", "test"),
+            )
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v1.1.0")
+                    .asset("tool.tar.gz", "tampered")
+                    .asset("checksums.txt", checksums),
+            )
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v1.0.0")
+                    .asset("tool.tar.gz", "test")
+                    .asset("checksums.txt", checksums),
+            )
+            .with_release(
+                "owner/other",
+                MockRelease::new("v1.0.0").asset("tool.tar.gz", "test"),
+            );
+        let _guard = server.install();
+        let project = TempProject::new("policy").unwrap();
+
+        let policy = Policy {
+            allow_prerelease: false,
+            require_checksum: true,
+            ..Default::default()
+        };
+        let download = |pathspec: &str, version: &str| {
+            let dependency = GitHubDependency::new(
+                String::from(pathspec),
+                None,
+                Some(GitHubVersion::from(version)),
+                true,
+                &None,
+            )
+            .unwrap();
+            github::download(&dependency, &project.root, &policy, None)
+        };
+        let is_violation = |e: anyhow::Error| e.chain().any(|cause| cause.is::<PolicyError>());
+
+        assert!(download("owner/repo:tool.tar.gz", "v1.0.0").is_ok());
+        assert!(is_violation(
+            download("owner/repo:tool.tar.gz", "prerelease").unwrap_err()
+        ));
+        assert!(is_violation(
+            download("owner/other:tool.tar.gz", "v1.0.0").unwrap_err()
+        ));
+
+        // A mismatching checksum is an integrity failure, not a policy violation
+        assert!(!is_violation(
+            download("owner/repo:tool.tar.gz", "v1.1.0").unwrap_err()
+        ));
+        assert_eq!(
+            std::fs::read_to_string(project.root.join("tool.tar.gz")).unwrap(),
+            "test"
+        );
+    }
+}
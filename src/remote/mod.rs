@@ -0,0 +1,285 @@
+use anyhow::Result;
+use regex::Regex;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::errors::{CodedError, ErrorCode};
+use crate::toml::Include;
+use crate::{cache, github, lock};
+
+/// How long a fetched sink TOML is used before fetching it again.
+///
+/// Includes pinned by their checksum are never fetched again while the cached contents match.
+const INCLUDE_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// A sink TOML in a GitHub repository, included by another sink TOML.
+///
+/// Written as `owner/repo:path/sink.toml@reference`, or as the URL of the file on GitHub.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteInclude {
+    /// The repository containing the sink TOML, in the form of 'owner/repository'.
+    pub origin: String,
+
+    /// The path of the sink TOML inside of the repository.
+    pub path: String,
+
+    /// The git reference to fetch the sink TOML at, e.g. a tag.
+    pub reference: String,
+}
+impl RemoteInclude {
+    /// Parse the path of an include.
+    ///
+    /// Returns `None` for local paths, and an error for URLs not pointing to a file on GitHub.
+    /// Supported are `owner/repo:path@reference`, `https://github.com/owner/repo/blob/reference/path`
+    /// and `https://raw.githubusercontent.com/owner/repo/reference/path`.
+    pub fn parse(value: &str) -> Option<Result<Self>> {
+        let re =
+            Regex::new(r"^(?<owner>[^/:\\]+)/(?<repo>[^/:\\]+):(?<path>.+)@(?<reference>[^@]+)$")
+                .unwrap();
+        if let Some(captures) = re.captures(value) {
+            return Some(Ok(RemoteInclude {
+                origin: format!("{}/{}", &captures["owner"], &captures["repo"]),
+                path: String::from(&captures["path"]),
+                reference: String::from(&captures["reference"]),
+            }));
+        }
+
+        let (_, url) = value.split_once("://")?;
+        let segments: Vec<&str> = url.split('/').filter(|s| !s.is_empty()).collect();
+        let remote = match segments.as_slice() {
+            ["github.com", owner, repo, "blob", reference, path @ ..]
+            | ["raw.githubusercontent.com", owner, repo, reference, path @ ..]
+                if !path.is_empty() =>
+            {
+                RemoteInclude {
+                    origin: format!("{owner}/{repo}"),
+                    path: path.join("/"),
+                    reference: String::from(*reference),
+                }
+            }
+            _ => {
+                return Some(Err(anyhow::anyhow!(
+                    "'{value}' is not the URL of a file on GitHub!"
+                )))
+            }
+        };
+
+        Some(Ok(remote))
+    }
+}
+impl Display for RemoteInclude {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}@{}", self.origin, self.path, self.reference)
+    }
+}
+
+/// Normalize a checksum to the form of 'algorithm:hex', see [`lock::Algorithm`].
+///
+/// Checksums without an algorithm are taken as SHA-256.
+fn _checksum(checksum: &str) -> String {
+    match checksum.split_once(':') {
+        Some((algorithm, hex)) => format!(
+            "{}:{}",
+            algorithm.to_ascii_lowercase(),
+            hex.to_ascii_lowercase()
+        ),
+        None => format!("sha256:{}", checksum.to_ascii_lowercase()),
+    }
+}
+
+/// Returns the cache key of the remote sink TOML, which contains the host like the release cache.
+fn _cache_key(remote: &RemoteInclude) -> String {
+    format!("include-{}/{remote}", github::api::host())
+}
+
+fn _fetch(remote: &RemoteInclude, checksum: Option<&str>) -> Result<PathBuf> {
+    let key = _cache_key(remote);
+    let path = cache::path_for(&key)
+        .ok_or_else(|| anyhow::anyhow!("Unable to determine the cache directory!"))?;
+    let expected = checksum.map(_checksum);
+    if let Some(expected) = &expected {
+        lock::Algorithm::of(expected)?;
+    }
+
+    let cached = match &expected {
+        // Pinned contents never change, so their age does not matter
+        Some(expected) => cache::read(&key, Duration::MAX).filter(|contents| {
+            lock::digest_bytes_like(contents.as_bytes(), expected).is_ok_and(|d| d == *expected)
+        }),
+        None => cache::read(&key, INCLUDE_TTL),
+    };
+    if cached.is_some() {
+        return Ok(path);
+    }
+
+    debug!("Fetching '{remote}'...");
+    let contents =
+        match github::api::api().view_file(&remote.origin, &remote.path, &remote.reference) {
+            Ok(contents) => String::from_utf8(contents)
+                .map_err(|_| anyhow::anyhow!("'{remote}' is not valid UTF-8!"))?,
+            Err(e) => {
+                // An outdated copy is better than nothing, unless it is pinned to other contents
+                if expected.is_none() && cache::read(&key, Duration::MAX).is_some() {
+                    warn!("Failed to fetch '{remote}', using the cached copy: {e}");
+                    return Ok(path);
+                }
+                return Err(e);
+            }
+        };
+
+    if let Some(expected) = &expected {
+        let actual = lock::digest_bytes_like(contents.as_bytes(), expected)?;
+        if actual != *expected {
+            return Err(CodedError(
+                ErrorCode::ChecksumMismatch,
+                format!("Checksum mismatch, expected '{expected}' but got '{actual}'!"),
+            )
+            .into());
+        }
+    }
+    cache::write(&key, &contents)?;
+
+    Ok(path)
+}
+/// Fetch the remote sink TOML, optionally verifying its checksum.
+///
+/// The sink TOML is cached for an hour, see [`cache::dir`]. If fetching fails, an outdated copy is used instead.
+/// Returns the path of the cached copy.
+pub fn fetch(remote: &RemoteInclude, checksum: Option<&str>) -> Result<PathBuf> {
+    match _fetch(remote, checksum) {
+        Ok(path) => Ok(path),
+        Err(e) => Err(e.context(format!("Failed to fetch '{remote}'!"))),
+    }
+}
+
+fn _resolve(include: &Include) -> Result<PathBuf> {
+    let value = include.path.to_string_lossy();
+    if let Some(remote) = RemoteInclude::parse(&value) {
+        return fetch(&remote?, include.checksum.as_deref());
+    }
+
+    if let Some(checksum) = &include.checksum {
+        let expected = _checksum(checksum);
+        let actual = lock::digest_like(&include.path, &expected)?;
+        if actual != expected {
+            return Err(CodedError(
+                ErrorCode::ChecksumMismatch,
+                format!("Checksum mismatch, expected '{expected}' but got '{actual}'!"),
+            )
+            .into());
+        }
+    }
+
+    Ok(include.path.clone())
+}
+/// Returns the local path of the included sink TOML, fetching it first if it is remote.
+///
+/// The checksum of the include is verified for both local and remote sink TOMLs.
+pub fn resolve(include: &Include) -> Result<PathBuf> {
+    match _resolve(include) {
+        Ok(path) => Ok(path),
+        Err(e) => Err(e.context(format!(
+            "Failed to resolve include '{}'!",
+            include.path.display()
+        ))),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{use_cache, MockReleaseServer, TempProject};
+    use crate::SinkTOML;
+    use std::fs;
+
+    #[test]
+    fn test_parse() {
+        let remote = |origin: &str, path: &str, reference: &str| RemoteInclude {
+            origin: String::from(origin),
+            path: String::from(path),
+            reference: String::from(reference),
+        };
+        let parse = |value: &str| RemoteInclude::parse(value).map(|remote| remote.unwrap());
+
+        assert_eq!(
+            parse("org/tools:manifests/sink.toml@v1.2.0"),
+            Some(remote("org/tools", "manifests/sink.toml", "v1.2.0"))
+        );
+        assert_eq!(
+            parse("https://github.com/org/tools/blob/main/sink.toml"),
+            Some(remote("org/tools", "sink.toml", "main"))
+        );
+        assert_eq!(
+            parse("https://raw.githubusercontent.com/org/tools/v1.2.0/a/sink.toml"),
+            Some(remote("org/tools", "a/sink.toml", "v1.2.0"))
+        );
+        assert_eq!(parse("shared/sink.toml"), None);
+        assert_eq!(parse("../shared.toml"), None);
+        assert!(RemoteInclude::parse("https://example.com/sink.toml")
+            .unwrap()
+            .is_err());
+        assert_eq!(
+            remote("org/tools", "sink.toml", "v1").to_string(),
+            "org/tools:sink.toml@v1"
+        );
+    }
+
+    #[test]
+    fn test_resolve() {
+        let contents = "[dependencies]\n\"owner/repo:tool\" = \"v1.0.0\"\n";
+        let sha256 = lock::digest_bytes(contents.as_bytes());
+        let server = MockReleaseServer::start().unwrap().with_file(
+            "remote-include/manifests",
+            "v1.0.0",
+            "tools/sink.toml",
+            contents,
+        );
+        let _guard = server.install();
+        let value = "remote-include/manifests:tools/sink.toml@v1.0.0";
+        let remote = RemoteInclude::parse(value).unwrap().unwrap();
+        let project = TempProject::new("remote_resolve").unwrap();
+        let _cache = use_cache(&project.root.join("cache"));
+        let cached = cache::path_for(&_cache_key(&remote)).unwrap();
+        fs::write(
+            project.sink_toml_path(),
+            format!("includes = [{{ path = {value:?}, sha256 = {sha256:?} }}]\n\n[dependencies]\n"),
+        )
+        .unwrap();
+        let sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+        let pathspec = github::GitHubPathspec::try_from(String::from("owner/repo:tool")).unwrap();
+        assert!(sink_toml.dependencies.contains_key(&pathspec));
+        assert_eq!(sink_toml.included[&pathspec], PathBuf::from(value));
+        assert_eq!(fs::read_to_string(&cached).unwrap(), contents);
+
+        // Cached copies are used while the server is unreachable
+        drop(_guard);
+        drop(server);
+        let _guard = MockReleaseServer::start().unwrap().install();
+        assert_eq!(fetch(&remote, None).unwrap(), cached);
+        assert_eq!(fetch(&remote, Some(&sha256)).unwrap(), cached);
+        assert!(fetch(&remote, Some("sha256:0000")).is_err());
+        fs::remove_file(&cached).unwrap();
+        assert!(fetch(&remote, None).is_err());
+
+        // Local includes are verified as well
+        let local = project.root.join("local.toml");
+        fs::write(&local, contents).unwrap();
+        let mut include = Include::from(local.clone());
+        include.checksum = Some(sha256.trim_start_matches("sha256:").to_uppercase());
+        assert_eq!(resolve(&include).unwrap(), local);
+        include.checksum = Some(String::from("sha256:0000"));
+        assert!(resolve(&include).is_err());
+
+        // Other algorithms are told apart by their prefix
+        include.checksum = Some(lock::digest_bytes_with(
+            contents.as_bytes(),
+            lock::Algorithm::Blake3,
+        ));
+        assert_eq!(resolve(&include).unwrap(), local);
+        include.checksum = Some(String::from("md5:0000"));
+        assert!(resolve(&include).is_err());
+    }
+}
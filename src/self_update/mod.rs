@@ -0,0 +1,283 @@
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info};
+
+use crate::errors::{CodedError, ErrorCode};
+use crate::github::{api, GitHubAsset, GitHubRelease};
+use crate::{atomic, cache, detect, integrity, lock};
+
+/// The repository sink itself is released from.
+const ORIGIN: &str = "Stausssi/sink";
+
+/// The cache entry remembering the latest release of sink, see [`notification`].
+const LATEST_KEY: &str = "self-update-latest";
+
+/// How long the latest release of sink is remembered before looking it up again.
+const LATEST_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The latest release of sink, compared to the running version.
+#[derive(Serialize, Debug, Clone)]
+pub struct SelfUpdate {
+    /// The version of the running binary, e.g. '0.1.0'.
+    pub current: String,
+
+    /// The tag of the latest release, e.g. 'v0.2.0'.
+    pub latest: String,
+
+    /// The asset built for the running platform, if the latest release contains one.
+    pub asset: Option<String>,
+
+    #[serde(skip)]
+    release: GitHubRelease,
+}
+impl SelfUpdate {
+    /// Whether the latest release is newer than the running binary.
+    pub fn is_available(&self) -> bool {
+        _is_newer(&self.latest, &self.current)
+    }
+}
+
+/// Returns the numeric components of the version, ignoring a leading 'v' and prerelease or build suffixes.
+fn _components(version: &str) -> Option<Vec<u64>> {
+    let version = version.trim_start_matches('v');
+    let core = version.split(['-', '+']).next().unwrap_or(version);
+    core.split('.').map(|part| part.parse().ok()).collect()
+}
+
+/// Whether the version of the release is newer than the current one.
+///
+/// Versions that are not numeric are only compared for equality.
+fn _is_newer(latest: &str, current: &str) -> bool {
+    match (_components(latest), _components(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => latest.trim_start_matches('v') != current.trim_start_matches('v'),
+    }
+}
+
+/// Returns the asset of the release built for the running platform.
+///
+/// sink cannot unpack itself, so only bare binaries are considered.
+fn _asset(release: &GitHubRelease) -> Option<&GitHubAsset> {
+    let binaries: Vec<GitHubAsset> = release
+        .assets
+        .iter()
+        .filter(|asset| !detect::is_archive(&asset.name))
+        .cloned()
+        .collect();
+    let name = detect::guess("sink", &binaries)?.name.clone();
+    release.assets.iter().find(|asset| asset.name == name)
+}
+
+fn _check() -> Result<SelfUpdate> {
+    debug!("Looking up the latest release of '{ORIGIN}'...");
+    let release = api::api().view_release(ORIGIN, None)?;
+
+    Ok(SelfUpdate {
+        current: String::from(env!("CARGO_PKG_VERSION")),
+        latest: release.tag_name.clone(),
+        asset: _asset(&release).map(|asset| asset.name.clone()),
+        release,
+    })
+}
+/// Look up the latest release of sink on GitHub.
+pub fn check() -> Result<SelfUpdate> {
+    match _check() {
+        Ok(update) => Ok(update),
+        Err(e) => Err(e.context("Failed to check for a newer version of sink!")),
+    }
+}
+
+/// Replace the executable with the given contents.
+///
+/// Windows refuses to overwrite a running executable, but allows renaming it. So it is moved aside first.
+fn _replace(exe: &Path, contents: &[u8]) -> Result<()> {
+    if !cfg!(windows) {
+        return atomic::write(exe, contents);
+    }
+
+    let old = exe.with_extension("old.exe");
+    let _ = fs::remove_file(&old);
+    fs::rename(exe, &old)?;
+    if let Err(e) = atomic::write(exe, contents) {
+        let _ = fs::rename(&old, exe);
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+fn _apply(update: &SelfUpdate, exe: &Path) -> Result<PathBuf> {
+    let release = &update.release;
+    let asset = _asset(release).ok_or_else(|| {
+        anyhow::anyhow!(
+            "{} contains no binary for {}/{}!",
+            release.tag_name,
+            crate::template::os(),
+            crate::template::arch()
+        )
+    })?;
+    let expected = integrity::published_digest(ORIGIN, release, asset)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No checksum is published for '{}', refusing to replace sink!",
+            asset.name
+        )
+    })?;
+
+    info!("Downloading '{}'...", asset.name);
+    let contents = api::api().download_asset(ORIGIN, &release.tag_name, asset)?;
+    let actual = lock::digest_bytes_like(&contents, &expected)?;
+    if actual != expected {
+        return Err(CodedError(
+            ErrorCode::ChecksumMismatch,
+            format!("Checksum mismatch, expected '{expected}' but got '{actual}'!"),
+        )
+        .into());
+    }
+
+    // Replace the binary itself, not a link pointing to it
+    let exe = fs::canonicalize(exe)?;
+    debug!("Replacing '{}'...", exe.display());
+    _replace(&exe, &contents)?;
+
+    Ok(exe)
+}
+/// Download the latest release of sink and replace the given executable with it.
+///
+/// The asset is verified against the checksum published with the release. Releases without one are refused.
+/// Returns the path of the replaced executable.
+pub fn apply(update: &SelfUpdate, exe: &Path) -> Result<PathBuf> {
+    match _apply(update, exe) {
+        Ok(exe) => Ok(exe),
+        Err(e) => Err(e.context(format!("Failed to update sink to {}!", update.latest))),
+    }
+}
+
+/// Returns the tag of the latest release of sink, looking it up at most once per [`LATEST_TTL`].
+fn _latest() -> Option<String> {
+    let latest = match cache::read(LATEST_KEY, LATEST_TTL) {
+        Some(latest) => latest,
+        None => {
+            let latest = match check() {
+                Ok(update) => update.latest,
+                Err(e) => {
+                    debug!("{e:#}");
+                    String::new()
+                }
+            };
+            // Failed lookups are remembered as well, so they are not repeated by every command
+            if let Err(e) = cache::write(LATEST_KEY, &latest) {
+                debug!("{e:#}");
+            }
+            latest
+        }
+    };
+
+    Some(latest).filter(|latest| !latest.is_empty())
+}
+
+/// Returns a hint to run `sink self-update` if a newer release of sink exists.
+///
+/// The latest release is looked up at most once a day and cached, see [`cache::dir`].
+pub fn notification() -> Option<String> {
+    let latest = _latest()?;
+    let current = env!("CARGO_PKG_VERSION");
+    _is_newer(&latest, current).then(|| {
+        format!("sink {latest} is available (running {current}), run 'sink self-update' to update!")
+    })
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::template;
+    use crate::test_utils::{use_cache, MockRelease, MockReleaseServer, TempProject};
+
+    #[test]
+    fn test_is_newer() {
+        assert!(_is_newer("v0.2.0", "0.1.0"));
+        assert!(_is_newer("v0.10.0", "0.9.3"));
+        assert!(!_is_newer("v0.1.0", "0.1.0"));
+        assert!(!_is_newer("v0.1.0", "0.2.0"));
+        assert!(!_is_newer("v0.1.0-rc.1", "0.1.0"));
+        assert!(_is_newer("nightly", "0.1.0"));
+    }
+
+    #[test]
+    fn test_apply() {
+        let binary = format!("sink-{}-{}", template::os(), template::arch());
+        let contents = b"new sink";
+        let checksums = format!(
+            "{}  {binary}\n",
+            lock::digest_bytes(contents).trim_start_matches("sha256:")
+        );
+        let server = MockReleaseServer::start().unwrap().with_release(
+            ORIGIN,
+            MockRelease::new("v99.0.0")
+                .asset(&format!("{binary}.tar.gz"), "archive")
+                .asset(&binary, contents.as_slice())
+                .asset("checksums.txt", checksums),
+        );
+        let _guard = server.install();
+
+        let update = check().unwrap();
+        assert!(update.is_available());
+        assert_eq!(update.latest, "v99.0.0");
+        assert_eq!(update.asset.as_deref(), Some(binary.as_str()));
+
+        let project = TempProject::new("self_update").unwrap();
+        let exe = project.root.join("sink");
+        fs::write(&exe, "old sink").unwrap();
+        apply(&update, &exe).unwrap();
+        assert_eq!(fs::read(&exe).unwrap(), contents);
+
+        // Unverifiable and tampered binaries are refused
+        let mut unverified = update.clone();
+        unverified
+            .release
+            .assets
+            .retain(|asset| asset.name != "checksums.txt");
+        fs::write(&exe, "old sink").unwrap();
+        assert!(apply(&unverified, &exe).is_err());
+        drop(_guard);
+        let server = MockReleaseServer::start().unwrap().with_release(
+            ORIGIN,
+            MockRelease::new("v99.0.0")
+                .asset(&binary, "tampered")
+                .asset("checksums.txt", format!("{}  {binary}\n", "0".repeat(64))),
+        );
+        let _guard = server.install();
+        assert!(apply(&check().unwrap(), &exe).is_err());
+        assert_eq!(fs::read_to_string(&exe).unwrap(), "old sink");
+    }
+
+    #[test]
+    fn test_notification() {
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release(ORIGIN, MockRelease::new("v99.0.0"));
+        let _guard = server.install();
+        let project = TempProject::new("self-update-notification").unwrap();
+        let _cache = use_cache(&project.root);
+        let cached = cache::path_for(LATEST_KEY).unwrap();
+        assert!(cached.starts_with(&project.root));
+        assert!(notification().unwrap().contains("v99.0.0"));
+        assert_eq!(fs::read_to_string(&cached).unwrap(), "v99.0.0");
+
+        // The cached release is used without asking GitHub again
+        drop(_guard);
+        drop(server);
+        let _guard = MockReleaseServer::start().unwrap().install();
+        assert!(notification().is_some());
+        cache::write(LATEST_KEY, "v0.0.1").unwrap();
+        assert_eq!(notification(), None);
+
+        // Failed lookups are not repeated either
+        fs::remove_file(&cached).unwrap();
+        assert_eq!(notification(), None);
+        assert_eq!(fs::read_to_string(&cached).unwrap(), "");
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::github::{api, GitHubStatus};
+use crate::list;
+
+fn _check() -> Result<GitHubStatus> {
+    api::api().view_status()
+}
+/// Ask GitHub who sink talks to it as and how much of the rate limits is left.
+pub fn check() -> Result<GitHubStatus> {
+    match _check() {
+        Ok(status) => Ok(status),
+        Err(e) => Err(e.context("Failed to query the status of the GitHub API!")),
+    }
+}
+
+/// Describe the time from `now` until `reset`, both in seconds since the Unix epoch, e.g. 'in 42m 10s'.
+fn _until(reset: u64, now: u64) -> String {
+    let seconds = reset.saturating_sub(now);
+    match seconds {
+        0 => String::from("now"),
+        1..60 => format!("in {seconds}s"),
+        60..3600 => format!("in {}m {}s", seconds / 60, seconds % 60),
+        _ => format!("in {}h {}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
+
+/// Render the status as the host and user, followed by a table of the rate limits.
+pub fn render(status: &GitHubStatus, now: SystemTime) -> String {
+    let now = now
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+
+    let limits: Vec<[String; 5]> = status
+        .rate_limits
+        .iter()
+        .map(|(resource, limit)| {
+            [
+                resource.clone(),
+                limit.used.to_string(),
+                limit.remaining.to_string(),
+                limit.limit.to_string(),
+                _until(limit.reset, now),
+            ]
+        })
+        .collect();
+    let mut rows = vec![vec!["RESOURCE", "USED", "REMAINING", "LIMIT", "RESET"]];
+    rows.extend(
+        limits
+            .iter()
+            .map(|row| row.iter().map(String::as_str).collect()),
+    );
+
+    format!(
+        "Host: {}\nUser: {}\n\n{}",
+        status.host,
+        status
+            .user
+            .as_deref()
+            .unwrap_or("none, not authenticated as a user"),
+        list::table(&rows)
+    )
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::GitHubRateLimit;
+    use crate::test_utils::MockReleaseServer;
+    use std::time::Duration;
+
+    #[test]
+    fn test_until() {
+        assert_eq!(_until(100, 200), "now");
+        assert_eq!(_until(242, 200), "in 42s");
+        assert_eq!(_until(2730, 200), "in 42m 10s");
+        assert_eq!(_until(200 + 2 * 3600 + 5 * 60, 200), "in 2h 5m");
+    }
+
+    #[test]
+    fn test_render() {
+        let server = MockReleaseServer::start().unwrap();
+        let _guard = server.install();
+        let mut status = check().unwrap();
+        assert!(status.host.starts_with("127.0.0.1:"));
+        assert_eq!(status.user, None);
+        assert_eq!(status.rate_limits["core"].remaining, 60);
+
+        status.host = String::from("github.com");
+        status.user = Some(String::from("octocat"));
+        status.rate_limits.insert(
+            String::from("core"),
+            GitHubRateLimit {
+                limit: 5000,
+                used: 12,
+                remaining: 4988,
+                reset: 1000 + 42 * 60,
+            },
+        );
+        assert_eq!(
+            render(&status, UNIX_EPOCH + Duration::from_secs(1000)),
+            "Host: github.com\nUser: octocat\n\n\
+             RESOURCE  USED  REMAINING  LIMIT  RESET\n\
+             core      12    4988       5000   in 42m 0s"
+        );
+    }
+}
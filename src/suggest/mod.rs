@@ -0,0 +1,48 @@
+/// Returns the number of single character insertions, deletions and substitutions turning `a` into `b`.
+fn _distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+/// Returns the candidate closest to the given value, if any is close enough to be a typo of it.
+///
+/// Up to a third of the characters of the value may differ, but at least one.
+pub fn closest<'a>(value: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let max_distance = (value.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (_distance(value, candidate), *candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest() {
+        assert_eq!(_distance("destinaton", "destination"), 1);
+        assert_eq!(_distance("stritc", "strict"), 2);
+        assert_eq!(_distance("", "abc"), 3);
+
+        let fields = ["version", "destination", "post-install", "pre-install"];
+        assert_eq!(closest("destinaton", &fields), Some("destination"));
+        assert_eq!(closest("post_install", &fields), Some("post-install"));
+        assert_eq!(closest("verison", &fields), Some("version"));
+        assert_eq!(closest("checksum", &fields), None);
+        assert_eq!(closest("x", &fields), None);
+    }
+}
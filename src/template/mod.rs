@@ -0,0 +1,192 @@
+use anyhow::Result;
+use regex::{Captures, Regex};
+use std::collections::BTreeMap;
+
+/// The values the variables of an asset template are replaced with.
+///
+/// Templates follow the asset templates of the [aqua registry](https://github.com/aquaproj/aqua-registry),
+/// e.g. 'tool_{{.Version}}_{{.OS}}_{{.Arch}}.{{.Format}}'.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateValues {
+    /// The operating system in Go notation, e.g. 'linux' or 'darwin'.
+    pub os: String,
+
+    /// The CPU architecture in Go notation, e.g. 'amd64' or 'arm64'.
+    pub arch: String,
+
+    /// The release tag.
+    pub version: String,
+
+    /// The archive format, e.g. 'tar.gz'.
+    pub format: Option<String>,
+}
+impl TemplateValues {
+    /// Returns the values for the running platform.
+    ///
+    /// `format_overrides` maps an operating system to the format used for it instead of `format`,
+    /// `replacements` replace the operating system and architecture, e.g. 'amd64' by 'x86_64'.
+    pub fn current(
+        version: &str,
+        format: Option<&str>,
+        format_overrides: &BTreeMap<String, String>,
+        replacements: &BTreeMap<String, String>,
+    ) -> Self {
+        let os = os();
+        let arch = arch();
+
+        TemplateValues {
+            format: format_overrides
+                .get(os)
+                .map(String::as_str)
+                .or(format)
+                .map(String::from),
+            os: replacements.get(os).cloned().unwrap_or(os.to_string()),
+            arch: replacements.get(arch).cloned().unwrap_or(arch.to_string()),
+            version: version.to_string(),
+        }
+    }
+}
+
+/// Returns the running operating system in Go notation (`GOOS`), as used by asset templates.
+pub fn os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        os => os,
+    }
+}
+
+/// Returns the running CPU architecture in Go notation (`GOARCH`), as used by asset templates.
+pub fn arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "x86" => "386",
+        "powerpc64" => "ppc64",
+        "loongarch64" => "loong64",
+        arch => arch,
+    }
+}
+
+/// Whether the given asset pattern contains template variables.
+pub fn is_template(pattern: &str) -> bool {
+    pattern.contains("{{")
+}
+
+fn _render(template: &str, values: &TemplateValues) -> Result<String> {
+    let re = Regex::new(r"\{\{-?\s*(?:(?<function>\w+)\s+)?\.(?<variable>\w+)\s*-?\}\}").unwrap();
+
+    let mut error = None;
+    let rendered = re.replace_all(template, |captures: &Captures| {
+        let value = match &captures["variable"] {
+            "OS" => Ok(values.os.clone()),
+            "Arch" => Ok(values.arch.clone()),
+            "Version" => Ok(values.version.clone()),
+            "SemVer" => Ok(values.version.trim_start_matches('v').to_string()),
+            "Format" => values
+                .format
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("'{{{{.Format}}}}' requires a 'format'!")),
+            variable => Err(anyhow::anyhow!("Unknown template variable '.{variable}'!")),
+        };
+        let value = value.and_then(|value| match captures.name("function") {
+            None => Ok(value),
+            Some(function) => match function.as_str() {
+                "trimV" => Ok(value.trim_start_matches('v').to_string()),
+                "title" => {
+                    let mut chars = value.chars();
+                    Ok(chars.next().map_or_else(String::new, |first| {
+                        first.to_uppercase().chain(chars).collect()
+                    }))
+                }
+                "upper" => Ok(value.to_uppercase()),
+                "lower" => Ok(value.to_lowercase()),
+                function => Err(anyhow::anyhow!("Unknown template function '{function}'!")),
+            },
+        });
+
+        match value {
+            Ok(value) => value,
+            Err(e) => {
+                error.get_or_insert(e);
+                String::new()
+            }
+        }
+    });
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if is_template(&rendered) {
+        return Err(anyhow::anyhow!(
+            "Unsupported template syntax in '{template}'!"
+        ));
+    }
+
+    Ok(rendered.to_string())
+}
+/// Replace the variables of an asset template with the given values.
+///
+/// Supported are the variables '.OS', '.Arch', '.Version', '.SemVer' (the version without a leading 'v') and
+/// '.Format', optionally passed to one of the functions 'trimV', 'title', 'upper' or 'lower',
+/// e.g. '{{trimV .Version}}'.
+pub fn render(template: &str, values: &TemplateValues) -> Result<String> {
+    match _render(template, values) {
+        Ok(rendered) => Ok(rendered),
+        Err(e) => Err(e.context(format!("Failed to render asset template '{template}'!"))),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values() -> TemplateValues {
+        TemplateValues {
+            os: String::from("darwin"),
+            arch: String::from("arm64"),
+            version: String::from("v1.2.3"),
+            format: Some(String::from("tar.gz")),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("tool_{{.SemVer}}_{{.OS}}_{{.Arch}}.{{.Format}}", &values()).unwrap(),
+            "tool_1.2.3_darwin_arm64.tar.gz"
+        );
+        assert_eq!(
+            render("tool-{{ .Version }}-{{title .OS}}*", &values()).unwrap(),
+            "tool-v1.2.3-Darwin*"
+        );
+        assert_eq!(render("tool-*.zip", &values()).unwrap(), "tool-*.zip");
+
+        assert!(render(
+            "tool.{{.Format}}",
+            &TemplateValues {
+                format: None,
+                ..values()
+            }
+        )
+        .is_err());
+        assert!(render("tool-{{.Asset}}", &values()).is_err());
+        assert!(render("tool-{{trimPrefix \"v\" .Version}}", &values()).is_err());
+    }
+
+    #[test]
+    fn test_current() {
+        let os = os().to_string();
+        let overrides = BTreeMap::from([(os.clone(), String::from("zip"))]);
+        let replacements = BTreeMap::from([(os, String::from("replaced"))]);
+
+        let values = TemplateValues::current("v1.0.0", Some("tar.gz"), &overrides, &replacements);
+        assert_eq!(values.format, Some(String::from("zip")));
+        assert_eq!(values.os, "replaced");
+        assert_eq!(values.arch, arch());
+
+        let values =
+            TemplateValues::current("v1.0.0", Some("tar.gz"), &BTreeMap::new(), &BTreeMap::new());
+        assert_eq!(values.format, Some(String::from("tar.gz")));
+    }
+}
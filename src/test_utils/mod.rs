@@ -0,0 +1,718 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use tracing::{debug, trace};
+
+use crate::cache;
+use crate::github::{
+    self, GitHubAdvisory, GitHubApi, GitHubAsset, GitHubLicense, GitHubRateLimit, GitHubRelease,
+    GitHubRepository, GitHubStatus,
+};
+use crate::lock;
+use crate::SinkTOML;
+
+/// A release served by the [`MockReleaseServer`].
+#[derive(Debug, Clone)]
+pub struct MockRelease {
+    pub tag_name: String,
+    pub is_prerelease: bool,
+    pub is_draft: bool,
+
+    /// The release notes in markdown.
+    pub body: String,
+
+    /// The assets by name, along with their contents.
+    pub assets: BTreeMap<String, Vec<u8>>,
+
+    /// Whether the digests of the assets are served, like GitHub does for newer releases.
+    pub digests: bool,
+}
+impl MockRelease {
+    pub fn new(tag_name: &str) -> Self {
+        MockRelease {
+            tag_name: String::from(tag_name),
+            is_prerelease: false,
+            is_draft: false,
+            body: String::new(),
+            assets: BTreeMap::new(),
+            digests: false,
+        }
+    }
+
+    pub fn prerelease(mut self) -> Self {
+        self.is_prerelease = true;
+        self
+    }
+
+    pub fn draft(mut self) -> Self {
+        self.is_draft = true;
+        self
+    }
+
+    pub fn body(mut self, body: &str) -> Self {
+        self.body = String::from(body);
+        self
+    }
+
+    pub fn asset(mut self, name: &str, contents: impl Into<Vec<u8>>) -> Self {
+        self.assets.insert(String::from(name), contents.into());
+        self
+    }
+
+    pub fn digests(mut self) -> Self {
+        self.digests = true;
+        self
+    }
+}
+
+/// The releases of all repositories served by a [`MockReleaseServer`], newest first.
+type Releases = BTreeMap<String, Vec<MockRelease>>;
+
+/// The security advisories of all repositories served by a [`MockReleaseServer`].
+type Advisories = BTreeMap<String, Vec<GitHubAdvisory>>;
+
+/// The SPDX identifiers of the licenses of all repositories served by a [`MockReleaseServer`].
+type Licenses = BTreeMap<String, String>;
+
+/// The files of all repositories served by a [`MockReleaseServer`], by 'owner/repository', git reference and path.
+type Files = BTreeMap<(String, String, String), Vec<u8>>;
+
+/// The repositories a [`MockReleaseServer`] pretends not to be allowed to read, by 'owner/repository'.
+type Private = BTreeSet<String>;
+
+/// A local HTTP server serving fake release metadata and assets.
+///
+/// Release metadata is served in the format of the GitHub CLI's JSON output:
+/// - `GET /repos/<owner>/<repo>/releases?per_page=<limit>`
+/// - `GET /repos/<owner>/<repo>/releases/latest`
+/// - `GET /repos/<owner>/<repo>/releases/tags/<tag>`
+/// - `GET /repos/<owner>/<repo>/releases/download/<tag>/<asset>`
+/// - `GET /repos/<owner>/<repo>/security-advisories`
+/// - `GET /repos/<owner>/<repo>`, only containing the license
+/// - `GET /repos/<owner>/<repo>/contents/<path>?ref=<reference>`, the raw contents of the file
+/// - `GET /rate_limit`, an untouched rate limit of the REST API resetting in an hour
+///
+/// Requests are never authenticated, so `GET /user` is not found.
+/// Neither is anything of the repositories added with [`MockReleaseServer::with_private`].
+///
+/// Use [`MockReleaseServer::api`] to talk to it, or [`MockReleaseServer::install`] to route all of sink through it.
+/// The server is shut down when it is dropped.
+pub struct MockReleaseServer {
+    address: String,
+    releases: Arc<Mutex<Releases>>,
+    advisories: Arc<Mutex<Advisories>>,
+    licenses: Arc<Mutex<Licenses>>,
+    files: Arc<Mutex<Files>>,
+    private: Arc<Mutex<Private>>,
+    stopped: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+impl MockReleaseServer {
+    /// Start serving on a random local port.
+    pub fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let address = listener.local_addr()?.to_string();
+        let releases: Arc<Mutex<Releases>> = Arc::default();
+        let advisories: Arc<Mutex<Advisories>> = Arc::default();
+        let licenses: Arc<Mutex<Licenses>> = Arc::default();
+        let files: Arc<Mutex<Files>> = Arc::default();
+        let private: Arc<Mutex<Private>> = Arc::default();
+        let stopped: Arc<AtomicBool> = Arc::default();
+
+        let handle = {
+            let address = address.clone();
+            let releases = releases.clone();
+            let advisories = advisories.clone();
+            let licenses = licenses.clone();
+            let files = files.clone();
+            let private = private.clone();
+            let stopped = stopped.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let served = _serve(
+                            stream,
+                            &address,
+                            &releases,
+                            &advisories,
+                            &licenses,
+                            &files,
+                            &private,
+                        );
+                        if let Err(e) = served {
+                            debug!("Mock release server failed to respond: {e}");
+                        }
+                    }
+                }
+            })
+        };
+        debug!("Mock release server listening on '{address}'!");
+
+        Ok(MockReleaseServer {
+            address,
+            releases,
+            advisories,
+            licenses,
+            files,
+            private,
+            stopped,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the base URL of the server, e.g. 'http://127.0.0.1:1234'.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Add a release to the given 'owner/repository'.
+    ///
+    /// Releases are listed in the order they are added, so add the newest release first.
+    pub fn with_release(self, origin: &str, release: MockRelease) -> Self {
+        if let Ok(mut releases) = self.releases.lock() {
+            releases
+                .entry(String::from(origin))
+                .or_default()
+                .push(release);
+        }
+        self
+    }
+
+    /// Add a published security advisory to the given 'owner/repository'.
+    pub fn with_advisory(self, origin: &str, advisory: GitHubAdvisory) -> Self {
+        if let Ok(mut advisories) = self.advisories.lock() {
+            advisories
+                .entry(String::from(origin))
+                .or_default()
+                .push(advisory);
+        }
+        self
+    }
+
+    /// Set the license of the given 'owner/repository' by its SPDX identifier.
+    pub fn with_license(self, origin: &str, spdx_id: &str) -> Self {
+        if let Ok(mut licenses) = self.licenses.lock() {
+            licenses.insert(String::from(origin), String::from(spdx_id));
+        }
+        self
+    }
+
+    /// Pretend the given 'owner/repository' is private and cannot be read, so nothing of it is found.
+    pub fn with_private(self, origin: &str) -> Self {
+        if let Ok(mut private) = self.private.lock() {
+            private.insert(String::from(origin));
+        }
+        self
+    }
+
+    /// Add a file with the given contents to the given 'owner/repository', at the given git reference.
+    pub fn with_file(self, origin: &str, reference: &str, path: &str, contents: &str) -> Self {
+        if let Ok(mut files) = self.files.lock() {
+            files.insert(
+                (
+                    String::from(origin),
+                    String::from(reference),
+                    String::from(path),
+                ),
+                contents.as_bytes().to_vec(),
+            );
+        }
+        self
+    }
+
+    /// Returns an API talking to this server.
+    pub fn api(&self) -> Arc<dyn GitHubApi> {
+        Arc::new(HttpApi::new(&self.url()))
+    }
+
+    /// Route all communication with GitHub through this server until the guard is dropped.
+    ///
+    /// See [`use_api`].
+    pub fn install(&self) -> ApiGuard {
+        use_api(self.api())
+    }
+}
+impl Drop for MockReleaseServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        // Wake up the listener, so it notices it has been stopped
+        let _ = TcpStream::connect(&self.address);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Returns the release in the format of the GitHub CLI.
+fn _release_json(address: &str, origin: &str, id: usize, release: &MockRelease) -> Value {
+    let url = format!(
+        "http://{address}/repos/{origin}/releases/tag/{}",
+        release.tag_name
+    );
+    let assets: Vec<Value> = release
+        .assets
+        .iter()
+        .map(|(name, contents)| {
+            let url = format!(
+                "http://{address}/repos/{origin}/releases/download/{}/{name}",
+                release.tag_name
+            );
+            json!({
+                "name": name,
+                "id": format!("{id}-{name}"),
+                "size": contents.len(),
+                "url": url,
+                "apiUrl": url,
+                "digest": release.digests.then(|| lock::digest_bytes(contents)),
+            })
+        })
+        .collect();
+
+    json!({
+        "tagName": release.tag_name,
+        "databaseId": id,
+        "url": url,
+        "isPrerelease": release.is_prerelease,
+        "isDraft": release.is_draft,
+        "body": release.body,
+        "assets": assets,
+    })
+}
+
+/// Answer a single request of the [`MockReleaseServer`].
+fn _serve(
+    mut stream: TcpStream,
+    address: &str,
+    releases: &Mutex<Releases>,
+    advisories: &Mutex<Advisories>,
+    licenses: &Mutex<Licenses>,
+    files: &Mutex<Files>,
+    private: &Mutex<Private>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    debug!("Mock release server: GET {target}");
+
+    let releases = releases
+        .lock()
+        .map_err(|_| anyhow::anyhow!("The releases are poisoned!"))?;
+    let private = private
+        .lock()
+        .map_err(|_| anyhow::anyhow!("The private repositories are poisoned!"))?;
+    let response: Option<Vec<u8>> = match segments.as_slice() {
+        ["repos", owner, repo, ..] if private.contains(&format!("{owner}/{repo}")) => None,
+        ["rate_limit"] => {
+            let reset = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs()
+                + 60 * 60;
+            let core = GitHubRateLimit {
+                limit: 60,
+                used: 0,
+                remaining: 60,
+                reset,
+            };
+            Some(serde_json::to_vec(
+                &json!({ "resources": { "core": core } }),
+            )?)
+        }
+        ["repos", owner, repo] => {
+            let licenses = licenses
+                .lock()
+                .map_err(|_| anyhow::anyhow!("The licenses are poisoned!"))?;
+            let repository = GitHubRepository {
+                license: licenses
+                    .get(&format!("{owner}/{repo}"))
+                    .map(|spdx_id| GitHubLicense {
+                        spdx_id: Some(spdx_id.clone()),
+                    }),
+            };
+            Some(serde_json::to_vec(&repository)?)
+        }
+        ["repos", owner, repo, "contents", rest @ ..] => {
+            let files = files
+                .lock()
+                .map_err(|_| anyhow::anyhow!("The files are poisoned!"))?;
+            let reference = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("ref="))
+                .unwrap_or_default();
+            files
+                .get(&(
+                    format!("{owner}/{repo}"),
+                    String::from(reference),
+                    rest.join("/"),
+                ))
+                .cloned()
+        }
+        ["repos", owner, repo, "security-advisories"] => {
+            let advisories = advisories
+                .lock()
+                .map_err(|_| anyhow::anyhow!("The advisories are poisoned!"))?;
+            let repo_advisories = advisories
+                .get(&format!("{owner}/{repo}"))
+                .cloned()
+                .unwrap_or_default();
+            Some(serde_json::to_vec(&repo_advisories)?)
+        }
+        ["repos", owner, repo, "releases", rest @ ..] => {
+            let origin = format!("{owner}/{repo}");
+            let repo_releases = releases.get(&origin).cloned().unwrap_or_default();
+            let json = |(id, release): (usize, &MockRelease)| {
+                _release_json(address, &origin, id + 1, release)
+            };
+
+            match rest {
+                [] => {
+                    let limit = query
+                        .split('&')
+                        .find_map(|pair| pair.strip_prefix("per_page="))
+                        .and_then(|limit| limit.parse().ok())
+                        .unwrap_or(30);
+                    let list: Vec<Value> = repo_releases
+                        .iter()
+                        .enumerate()
+                        .take(limit)
+                        .map(json)
+                        .collect();
+                    Some(serde_json::to_vec(&list)?)
+                }
+                ["latest"] => repo_releases
+                    .iter()
+                    .enumerate()
+                    .find(|(_, release)| !release.is_prerelease && !release.is_draft)
+                    .map(json)
+                    .map(|release| release.to_string().into_bytes()),
+                ["tags", tag] => repo_releases
+                    .iter()
+                    .enumerate()
+                    .find(|(_, release)| release.tag_name == *tag)
+                    .map(json)
+                    .map(|release| release.to_string().into_bytes()),
+                ["download", tag, name] => repo_releases
+                    .iter()
+                    .find(|release| release.tag_name == *tag)
+                    .and_then(|release| release.assets.get(*name).cloned()),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let (status, body) = match response {
+        Some(body) => ("200 OK", body),
+        None => ("404 Not Found", b"Not Found".to_vec()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(&body)?;
+    stream.flush()?;
+
+    Ok(())
+}
+
+/// A [`GitHubApi`] talking to a [`MockReleaseServer`] via plain HTTP.
+#[derive(Debug)]
+pub struct HttpApi {
+    base_url: String,
+}
+impl HttpApi {
+    pub fn new(base_url: &str) -> Self {
+        HttpApi {
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Send a GET request to the given path or URL and return the body of the response.
+    fn get(&self, target: &str) -> Result<Vec<u8>> {
+        let url = match target.starts_with("http://") {
+            true => target.to_string(),
+            false => format!("{}{target}", self.base_url),
+        };
+        let Some((address, path)) = url
+            .strip_prefix("http://")
+            .map(|rest| rest.split_once('/').unwrap_or((rest, "")))
+        else {
+            return Err(anyhow::anyhow!("Only plain HTTP is supported: '{url}'!"));
+        };
+
+        trace!("Request: GET {url}");
+        let mut stream = TcpStream::connect(address)?;
+        write!(
+            stream,
+            "GET /{path} HTTP/1.1\r\nHost: {address}\r\nConnection: close\r\n\r\n"
+        )?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+
+        let Some(split) = response.windows(4).position(|window| window == b"\r\n\r\n") else {
+            return Err(anyhow::anyhow!("Malformed response from '{url}'!"));
+        };
+        let head = String::from_utf8_lossy(&response[..split]).to_string();
+        let status = head.split_whitespace().nth(1).unwrap_or_default();
+        trace!("Response: {status}, {} byte(s)", response.len() - split - 4);
+        if status != "200" {
+            return Err(anyhow::Error::new(crate::errors::GitHubError(format!(
+                "GET '{url}' failed with status {status}!"
+            ))));
+        }
+
+        Ok(response[split + 4..].to_vec())
+    }
+}
+impl GitHubApi for HttpApi {
+    fn list_releases(&self, origin: &str, limit: usize) -> Result<Vec<GitHubRelease>> {
+        let body = self.get(&format!("/repos/{origin}/releases?per_page={limit}"))?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn view_release(&self, origin: &str, tag: Option<&str>) -> Result<GitHubRelease> {
+        let body = match tag {
+            Some(tag) => self.get(&format!("/repos/{origin}/releases/tags/{tag}"))?,
+            None => self.get(&format!("/repos/{origin}/releases/latest"))?,
+        };
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn download_asset(&self, origin: &str, tag: &str, asset: &GitHubAsset) -> Result<Vec<u8>> {
+        match asset.api_url.is_empty() {
+            true => self.get(&format!(
+                "/repos/{origin}/releases/download/{tag}/{}",
+                asset.name
+            )),
+            false => self.get(&asset.api_url),
+        }
+    }
+
+    fn list_advisories(&self, origin: &str) -> Result<Vec<GitHubAdvisory>> {
+        let body = self.get(&format!("/repos/{origin}/security-advisories"))?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn view_license(&self, origin: &str) -> Result<Option<String>> {
+        let body = self.get(&format!("/repos/{origin}"))?;
+        let repository: GitHubRepository = serde_json::from_slice(&body)?;
+        Ok(repository.spdx_id())
+    }
+
+    fn view_file(&self, origin: &str, path: &str, reference: &str) -> Result<Vec<u8>> {
+        self.get(&format!("/repos/{origin}/contents/{path}?ref={reference}"))
+    }
+
+    fn view_status(&self) -> Result<GitHubStatus> {
+        let user = self.get("/user").ok().and_then(|body| {
+            let user: Value = serde_json::from_slice(&body).ok()?;
+            user["login"].as_str().map(String::from)
+        });
+        let rate_limit: Value = serde_json::from_slice(&self.get("/rate_limit")?)?;
+
+        Ok(GitHubStatus {
+            host: self.base_url.trim_start_matches("http://").to_string(),
+            user,
+            rate_limits: serde_json::from_value(rate_limit["resources"].clone())?,
+        })
+    }
+}
+
+/// Serializes replacing the global API, as tests run in parallel.
+static API_LOCK: Mutex<()> = Mutex::new(());
+
+/// Restores the default API when dropped, see [`use_api`].
+pub struct ApiGuard {
+    _lock: MutexGuard<'static, ()>,
+}
+impl Drop for ApiGuard {
+    fn drop(&mut self) {
+        github::set_api(None);
+    }
+}
+
+/// Route all communication with GitHub through the given API until the guard is dropped.
+///
+/// Only one guard exists at a time, others wait for it to be dropped.
+pub fn use_api(api: Arc<dyn GitHubApi>) -> ApiGuard {
+    let lock = API_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    github::set_api(Some(api));
+    ApiGuard { _lock: lock }
+}
+
+/// Restores the user-level cache directory when dropped, see [`use_cache`].
+pub struct CacheGuard;
+impl Drop for CacheGuard {
+    fn drop(&mut self) {
+        cache::set_dir(None);
+    }
+}
+
+/// Keep the cache entries of the current thread in the given directory until the guard is dropped.
+pub fn use_cache(dir: &Path) -> CacheGuard {
+    cache::set_dir(Some(dir.to_path_buf()));
+    CacheGuard
+}
+
+/// A temporary directory containing a sink TOML, deleted when dropped.
+pub struct TempProject {
+    pub root: PathBuf,
+}
+impl TempProject {
+    /// Create an empty project in a unique temporary directory.
+    pub fn new(name: &str) -> Result<Self> {
+        let root = std::env::temp_dir().join(format!("sink-{name}-{}", std::process::id()));
+        if root.exists() {
+            fs::remove_dir_all(&root)?;
+        }
+        fs::create_dir_all(&root)?;
+
+        Ok(TempProject { root })
+    }
+
+    /// Returns the path of the sink TOML.
+    pub fn sink_toml_path(&self) -> PathBuf {
+        self.root.join("sink.toml")
+    }
+
+    /// Start writing the sink TOML of this project.
+    pub fn sink_toml(&self) -> SinkTOMLBuilder {
+        SinkTOMLBuilder::new(&self.sink_toml_path())
+    }
+}
+impl Drop for TempProject {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Builds a sink TOML fixture on disk.
+pub struct SinkTOMLBuilder {
+    path: PathBuf,
+    default_owner: Option<String>,
+    dependencies: Vec<(String, String)>,
+}
+impl SinkTOMLBuilder {
+    pub fn new(path: &Path) -> Self {
+        SinkTOMLBuilder {
+            path: path.to_path_buf(),
+            default_owner: None,
+            dependencies: Vec::new(),
+        }
+    }
+
+    pub fn default_owner(mut self, owner: &str) -> Self {
+        self.default_owner = Some(String::from(owner));
+        self
+    }
+
+    /// Add a dependency in the short form, i.e. only with its version.
+    pub fn dependency(mut self, pathspec: &str, version: &str) -> Self {
+        self.dependencies
+            .push((String::from(pathspec), format!("{version:?}")));
+        self
+    }
+
+    /// Add a dependency in the table form, e.g. `{ version = "v1.0.0", destination = "bin" }`.
+    pub fn dependency_table(mut self, pathspec: &str, table: &str) -> Self {
+        self.dependencies
+            .push((String::from(pathspec), String::from(table)));
+        self
+    }
+
+    /// Write the sink TOML and load it.
+    pub fn write(self) -> Result<SinkTOML> {
+        let mut contents = String::new();
+        if let Some(owner) = &self.default_owner {
+            contents.push_str(&format!("default-owner = {owner:?}\n\n"));
+        }
+        contents.push_str("[dependencies]\n");
+        for (pathspec, value) in self.dependencies.iter() {
+            contents.push_str(&format!("{pathspec:?} = {value}\n"));
+        }
+        fs::write(&self.path, contents)?;
+
+        match SinkTOML::from_file(&self.path) {
+            Ok(sink_toml) => Ok(sink_toml),
+            Err(crate::SinkError::Any(e)) => Err(e),
+        }
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::SinkClient;
+
+    #[test]
+    fn test_install() {
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v1.1.0").asset("tool.tar.gz", "new"),
+            )
+            .with_release(
+                "owner/repo",
+                MockRelease::new("v1.0.0")
+                    .asset("tool.tar.gz", "old")
+                    .asset("tool.zip", "zip"),
+            );
+        let _guard = server.install();
+
+        let project = TempProject::new("test-utils-install").unwrap();
+        project
+            .sink_toml()
+            .default_owner("owner")
+            .dependency("owner/repo:*.tar.gz", "latest")
+            .dependency_table(
+                "owner/repo",
+                r#"{ version = "v1.0.0", destination = "all" }"#,
+            )
+            .write()
+            .unwrap();
+
+        let mut client = SinkClient::load(&project.sink_toml_path(), false).unwrap();
+        let report = client.install(false).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(
+            fs::read_to_string(project.root.join("tool.tar.gz")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            fs::read_to_string(project.root.join("all/tool.zip")).unwrap(),
+            "zip"
+        );
+
+        // Frozen installs download exactly the locked assets again
+        fs::remove_file(project.root.join("all/tool.zip")).unwrap();
+        let report = client.install(true).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(
+            fs::read_to_string(project.root.join("all/tool.zip")).unwrap(),
+            "zip"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::client::InstallObserver;
+use crate::github::{
+    GitHubAdvisory, GitHubApi, GitHubAsset, GitHubPathspec, GitHubRelease, GitHubStatus,
+};
+use crate::policy::ByteSize;
+
+/// How many of the slowest dependencies are listed in the summary.
+const SLOWEST: usize = 5;
+
+/// Serialize a duration as fractional seconds.
+fn _seconds<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// What installing a single dependency took.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Timing {
+    /// The wall time of resolving, downloading and linking the dependency, hooks included.
+    #[serde(rename = "seconds", serialize_with = "_seconds")]
+    pub duration: Duration,
+
+    /// The size of the downloaded assets.
+    pub bytes: u64,
+
+    /// How many assets were downloaded.
+    pub downloads: usize,
+}
+impl Timing {
+    /// Whether the dependency was installed without downloading anything, e.g. because it was installed already.
+    pub fn is_cached(&self) -> bool {
+        self.downloads == 0
+    }
+}
+
+/// Where the time of an install went, see [`crate::client::InstallReport::timings`].
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Timings {
+    /// The wall time of the whole install.
+    #[serde(rename = "seconds", serialize_with = "_seconds")]
+    pub total: Duration,
+
+    /// The timing of every dependency that was installed.
+    pub dependencies: BTreeMap<GitHubPathspec, Timing>,
+}
+impl Timings {
+    /// Returns the size of all downloaded assets.
+    pub fn bytes(&self) -> u64 {
+        self.dependencies.values().map(|timing| timing.bytes).sum()
+    }
+
+    /// Returns how many dependencies were installed without downloading anything.
+    pub fn cache_hits(&self) -> usize {
+        self.dependencies
+            .values()
+            .filter(|timing| timing.is_cached())
+            .count()
+    }
+
+    /// Returns the given number of dependencies that took the longest, slowest first.
+    pub fn slowest(&self, count: usize) -> Vec<(&GitHubPathspec, &Timing)> {
+        let mut slowest: Vec<_> = self.dependencies.iter().collect();
+        slowest.sort_by_key(|(_, timing)| std::cmp::Reverse(timing.duration));
+        slowest.truncate(count);
+        slowest
+    }
+}
+impl Display for Timings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cache_hits = self.cache_hits();
+        write!(
+            f,
+            "Installed {} dependencies in {:.2}s, downloading {} ({} downloaded, {cache_hits} cached).",
+            self.dependencies.len(),
+            self.total.as_secs_f64(),
+            ByteSize(self.bytes()),
+            self.dependencies.len() - cache_hits,
+        )?;
+        for (pathspec, timing) in self.slowest(SLOWEST) {
+            write!(
+                f,
+                "\n    {:>7.2}s  {pathspec}",
+                timing.duration.as_secs_f64()
+            )?;
+            match timing.is_cached() {
+                true => write!(f, " (cached)")?,
+                false => write!(f, " ({})", ByteSize(timing.bytes))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Counts the assets downloaded through the wrapped API, see [`crate::github::api::scoped_api`].
+pub struct MeteredApi {
+    inner: Arc<dyn GitHubApi>,
+    bytes: AtomicU64,
+    downloads: AtomicUsize,
+    observed: Mutex<Option<(GitHubPathspec, Arc<dyn InstallObserver>)>>,
+}
+impl MeteredApi {
+    pub fn new(inner: Arc<dyn GitHubApi>) -> Self {
+        MeteredApi {
+            inner,
+            bytes: AtomicU64::new(0),
+            downloads: AtomicUsize::new(0),
+            observed: Mutex::new(None),
+        }
+    }
+
+    /// Report the progress of the following downloads to the observer, as downloads of the given dependency.
+    pub fn observe(&self, observed: Option<(GitHubPathspec, Arc<dyn InstallObserver>)>) {
+        if let Ok(mut current) = self.observed.lock() {
+            *current = observed;
+        }
+    }
+
+    /// Report the bytes downloaded of the asset so far to the observer, see [`MeteredApi::observe`].
+    fn _progress(&self, asset: &GitHubAsset, bytes: u64) {
+        if let Ok(observed) = self.observed.lock() {
+            if let Some((pathspec, observer)) = observed.as_ref() {
+                observer.on_download_progress(pathspec, asset, bytes);
+            }
+        }
+    }
+
+    /// Returns the size and number of all assets downloaded so far.
+    pub fn totals(&self) -> (u64, usize) {
+        (
+            self.bytes.load(Ordering::Relaxed),
+            self.downloads.load(Ordering::Relaxed),
+        )
+    }
+}
+impl GitHubApi for MeteredApi {
+    fn list_releases(&self, origin: &str, limit: usize) -> Result<Vec<GitHubRelease>> {
+        self.inner.list_releases(origin, limit)
+    }
+
+    fn view_release(&self, origin: &str, tag: Option<&str>) -> Result<GitHubRelease> {
+        self.inner.view_release(origin, tag)
+    }
+
+    fn download_asset(&self, origin: &str, tag: &str, asset: &GitHubAsset) -> Result<Vec<u8>> {
+        self._progress(asset, 0);
+        let contents = self.inner.download_asset(origin, tag, asset)?;
+        self._progress(asset, contents.len() as u64);
+        self.bytes
+            .fetch_add(contents.len() as u64, Ordering::Relaxed);
+        self.downloads.fetch_add(1, Ordering::Relaxed);
+        Ok(contents)
+    }
+
+    fn list_advisories(&self, origin: &str) -> Result<Vec<GitHubAdvisory>> {
+        self.inner.list_advisories(origin)
+    }
+
+    fn view_license(&self, origin: &str) -> Result<Option<String>> {
+        self.inner.view_license(origin)
+    }
+
+    fn view_file(&self, origin: &str, path: &str, reference: &str) -> Result<Vec<u8>> {
+        self.inner.view_file(origin, path, reference)
+    }
+
+    fn latest_tags(&self, origins: &[String]) -> Result<BTreeMap<String, String>> {
+        self.inner.latest_tags(origins)
+    }
+
+    fn view_status(&self) -> Result<GitHubStatus> {
+        self.inner.view_status()
+    }
+}
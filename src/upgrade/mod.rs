@@ -0,0 +1,96 @@
+use anyhow::Result;
+use serde::Serialize;
+use tracing::{debug, info};
+
+use crate::github::{self, GitHubPathspec, GitHubVersion, UpdatePolicy};
+use crate::SinkTOML;
+
+/// A pinned version of a dependency that can be bumped.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Upgrade {
+    pub pathspec: GitHubPathspec,
+    pub from: String,
+    pub to: String,
+
+    /// The release notes of the new version in markdown, see [`crate::changelog::render`].
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+fn _plan(sink_toml: &SinkTOML, selection: &[GitHubPathspec]) -> Result<Vec<Upgrade>> {
+    let mut pathspecs: Vec<&GitHubPathspec> = sink_toml.dependencies.keys().collect();
+    pathspecs.sort();
+
+    let mut candidates = Vec::new();
+    for pathspec in pathspecs {
+        if !selection.is_empty() && !selection.contains(pathspec) {
+            continue;
+        }
+
+        let dependency = sink_toml.dependencies[pathspec].to_github_dependency(pathspec)?;
+        let GitHubVersion::Tag(current) = &dependency.version else {
+            debug!("{pathspec} is not pinned to a tag, skipping!");
+            continue;
+        };
+        if dependency.update_policy == UpdatePolicy::Pinned {
+            debug!("{pathspec} is pinned by its update policy, skipping!");
+            continue;
+        }
+        candidates.push((pathspec, current.clone(), dependency));
+    }
+
+    // Most dependencies are up to date, which the latest releases of all repositories tell in a few requests
+    let mut origins: Vec<String> = candidates
+        .iter()
+        .map(|(pathspec, _, _)| pathspec.get_full_origin())
+        .collect();
+    origins.sort();
+    origins.dedup();
+    let latest = github::latest_tags(&origins)?;
+
+    let mut upgrades = Vec::new();
+    for (pathspec, current, dependency) in candidates {
+        if latest.get(&pathspec.get_full_origin()) == Some(&current) {
+            debug!("{pathspec} is pinned to the latest release!");
+            continue;
+        }
+
+        match github::newest_release(&dependency)? {
+            Some(release) => {
+                info!("{pathspec}: {current} -> {}", release.tag_name);
+                upgrades.push(Upgrade {
+                    pathspec: pathspec.clone(),
+                    from: current,
+                    to: release.tag_name,
+                    notes: release.body,
+                });
+            }
+            None => debug!("{pathspec} is up to date!"),
+        }
+    }
+
+    Ok(upgrades)
+}
+/// Find newer releases for all dependencies pinned to a tag.
+///
+/// If `selection` is not empty, only those dependencies are checked.
+/// Dependencies tracking 'latest' or 'prerelease' are skipped, as are those with the update policy 'pinned'.
+pub fn plan(sink_toml: &SinkTOML, selection: &[GitHubPathspec]) -> Result<Vec<Upgrade>> {
+    match _plan(sink_toml, selection) {
+        Ok(upgrades) => Ok(upgrades),
+        Err(e) => Err(e.context("Failed to check for upgrades!")),
+    }
+}
+
+/// Write the new versions of the upgrades to the sink TOML.
+///
+/// Only the versions are touched, the formatting of the document is kept.
+pub fn apply(sink_toml: SinkTOML, upgrades: &[Upgrade]) -> Result<SinkTOML> {
+    let mut sink_toml = sink_toml;
+    for upgrade in upgrades {
+        let version = GitHubVersion::from(upgrade.to.as_str());
+        sink_toml = sink_toml.set_version(&upgrade.pathspec, &version)?;
+    }
+
+    Ok(sink_toml)
+}
@@ -0,0 +1,379 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, info_span, warn};
+
+use crate::client::{InstallReport, SinkClient};
+use crate::errors::{GitHubError, SinkError};
+use crate::github::{self, GitHubAdvisory, GitHubApi, GitHubAsset, GitHubRelease, GitHubStatus};
+use crate::lock::SinkLock;
+use crate::SinkTOML;
+
+/// The name of the root of the workspace among its members.
+pub const ROOT: &str = ".";
+
+/// The `[workspace]` table of a sink TOML, turning it into the root of a workspace.
+///
+/// The dependencies of all members are installed with `sink install --workspace` and locked in the lockfile of the root.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(
+    rename_all(deserialize = "kebab-case", serialize = "snake_case"),
+    deny_unknown_fields
+)]
+pub struct Workspace {
+    /// The directories of the members relative to the root, each containing a 'sink.toml'.
+    ///
+    /// Glob patterns like 'tools/*' are expanded, matched directories without a sink TOML are skipped.
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+fn _members(sink_toml: &SinkTOML) -> Result<BTreeMap<String, PathBuf>> {
+    let Some(workspace) = &sink_toml.workspace else {
+        return Err(anyhow::anyhow!(
+            "'{}' has no [workspace] table!",
+            sink_toml.path.display()
+        ));
+    };
+
+    let root = sink_toml.root();
+    let mut members = BTreeMap::new();
+    for pattern in workspace.members.iter() {
+        let is_pattern = glob::Pattern::escape(pattern) != *pattern;
+        let mut matched = false;
+        for dir in glob::glob(&root.join(pattern).to_string_lossy())? {
+            let dir = dir?;
+            let name = dir
+                .strip_prefix(&root)
+                .ok()
+                .filter(|name| {
+                    name.components()
+                        .all(|component| matches!(component, Component::Normal(_)))
+                })
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Member '{}' is outside of the workspace!", dir.display())
+                })?
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+
+            let path = dir.join("sink.toml");
+            if !path.is_file() {
+                if is_pattern {
+                    debug!("Skipping '{}' without a sink TOML!", dir.display());
+                    continue;
+                }
+                return Err(anyhow::anyhow!(
+                    "Member '{name}' has no sink TOML at '{}'!",
+                    path.display()
+                ));
+            }
+            if name.is_empty() {
+                continue;
+            }
+
+            matched = true;
+            members.insert(name, path);
+        }
+
+        if !matched && !is_pattern {
+            return Err(anyhow::anyhow!("Member '{pattern}' does not exist!"));
+        }
+    }
+
+    Ok(members)
+}
+/// Returns the sink TOMLs of the members of the workspace, by their directory relative to the root.
+///
+/// Fails if the sink TOML is not the root of a workspace, or a member is outside of it.
+pub fn members(sink_toml: &SinkTOML) -> Result<BTreeMap<String, PathBuf>> {
+    match _members(sink_toml) {
+        Ok(members) => Ok(members),
+        Err(e) => Err(e.context("Failed to resolve the workspace members!")),
+    }
+}
+
+/// Shares resolved releases and downloaded assets between the members of a workspace.
+///
+/// Members depending on the same release download each of its assets only once.
+struct SharedApi {
+    inner: Arc<dyn GitHubApi>,
+    releases: Mutex<HashMap<(String, Option<String>), GitHubRelease>>,
+    assets: Mutex<HashMap<(String, String, String), Vec<u8>>>,
+}
+impl GitHubApi for SharedApi {
+    fn list_releases(&self, origin: &str, limit: usize) -> Result<Vec<GitHubRelease>> {
+        self.inner.list_releases(origin, limit)
+    }
+
+    fn view_release(&self, origin: &str, tag: Option<&str>) -> Result<GitHubRelease> {
+        let key = (String::from(origin), tag.map(String::from));
+        if let Some(release) = self.releases.lock().ok().and_then(|r| r.get(&key).cloned()) {
+            return Ok(release);
+        }
+
+        let release = self.inner.view_release(origin, tag)?;
+        if let Ok(mut releases) = self.releases.lock() {
+            releases.insert(key, release.clone());
+        }
+        Ok(release)
+    }
+
+    fn download_asset(&self, origin: &str, tag: &str, asset: &GitHubAsset) -> Result<Vec<u8>> {
+        let key = (String::from(origin), String::from(tag), asset.name.clone());
+        if let Some(contents) = self.assets.lock().ok().and_then(|a| a.get(&key).cloned()) {
+            debug!("Reusing '{}' of {origin}@{tag}!", asset.name);
+            return Ok(contents);
+        }
+
+        let contents = self.inner.download_asset(origin, tag, asset)?;
+        if let Ok(mut assets) = self.assets.lock() {
+            assets.insert(key, contents.clone());
+        }
+        Ok(contents)
+    }
+
+    fn list_advisories(&self, origin: &str) -> Result<Vec<GitHubAdvisory>> {
+        self.inner.list_advisories(origin)
+    }
+
+    fn view_license(&self, origin: &str) -> Result<Option<String>> {
+        self.inner.view_license(origin)
+    }
+
+    fn view_file(&self, origin: &str, path: &str, reference: &str) -> Result<Vec<u8>> {
+        self.inner.view_file(origin, path, reference)
+    }
+
+    fn latest_tags(&self, origins: &[String]) -> Result<BTreeMap<String, String>> {
+        self.inner.latest_tags(origins)
+    }
+
+    fn view_status(&self) -> Result<GitHubStatus> {
+        self.inner.view_status()
+    }
+}
+
+fn _install(
+    sink_toml: SinkTOML,
+    sink_lock: SinkLock,
+    frozen: bool,
+    fail_fast: bool,
+    preflight: bool,
+) -> Result<BTreeMap<String, (SinkClient, InstallReport)>> {
+    // Load all members first, so a broken one does not leave the workspace half installed
+    let mut clients = Vec::new();
+    for (name, path) in members(&sink_toml)? {
+        let member = SinkTOML::from_file_with(&path, sink_toml.strict)
+            .map_err(|SinkError::Any(e)| e.context(format!("Failed to load member '{name}'!")))?;
+        let member_lock = sink_lock.member(&name);
+        clients.push((name, SinkClient::new(member, member_lock)));
+    }
+    clients.insert(
+        0,
+        (String::from(ROOT), SinkClient::new(sink_toml, sink_lock)),
+    );
+
+    // Check every member before installing any, so all inaccessible dependencies are reported at once
+    if preflight {
+        let mut failed = Vec::new();
+        for (name, client) in clients.iter() {
+            let _span = info_span!("member", %name).entered();
+            if client.preflight().is_err() {
+                failed.push(name.as_str());
+            }
+        }
+        if !failed.is_empty() {
+            return Err(GitHubError(format!(
+                "Cannot access the dependencies of {}, not installing anything!",
+                failed.join(", ")
+            ))
+            .into());
+        }
+    }
+
+    let previous = github::api::api();
+    github::set_api(Some(Arc::new(SharedApi {
+        inner: previous.clone(),
+        releases: Mutex::new(HashMap::new()),
+        assets: Mutex::new(HashMap::new()),
+    })));
+
+    let mut installs = BTreeMap::new();
+    let mut result = Ok(());
+    for (name, mut client) in clients {
+        let _span = info_span!("member", %name).entered();
+        info!("Installing workspace member '{name}'...");
+        client.set_fail_fast(fail_fast);
+        match client.install(frozen) {
+            Ok(report) if fail_fast && !report.is_ok() => {
+                warn!("Failing fast, not installing the remaining members!");
+                installs.insert(name, (client, report));
+                break;
+            }
+            Ok(report) => {
+                installs.insert(name, (client, report));
+            }
+            Err(e) => {
+                result = Err(e.context(format!("Failed to install member '{name}'!")));
+                break;
+            }
+        }
+    }
+    github::set_api(Some(previous));
+
+    result.map(|_| installs)
+}
+/// Install the dependencies of the root and all members of the workspace, see [`SinkClient::install`].
+///
+/// Every member is locked in the lockfile of the root, see [`SinkLock::member`].
+/// Assets of releases several members depend on are only downloaded once.
+/// With `fail_fast`, no further members are installed once a dependency failed, see [`SinkClient::set_fail_fast`].
+/// With `preflight`, the access to the dependencies of all members is checked first, see [`SinkClient::preflight`].
+/// Returns the clients and reports by member, the root is named [`ROOT`].
+pub fn install(
+    sink_toml: SinkTOML,
+    sink_lock: SinkLock,
+    frozen: bool,
+    fail_fast: bool,
+    preflight: bool,
+) -> Result<BTreeMap<String, (SinkClient, InstallReport)>> {
+    match _install(sink_toml, sink_lock, frozen, fail_fast, preflight) {
+        Ok(installs) => Ok(installs),
+        Err(e) => Err(e.context("Failed to install the workspace!")),
+    }
+}
+
+/* ---------- [ Tests ] ---------- */
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{MockRelease, MockReleaseServer, TempProject};
+    use std::fs;
+
+    #[test]
+    fn test_install() {
+        let server = MockReleaseServer::start()
+            .unwrap()
+            .with_release("owner/repo", MockRelease::new("v1.0.0").asset("tool", "1"));
+        let _guard = server.install();
+
+        let project = TempProject::new("workspace").unwrap();
+        for member in ["tools/lint", "tools/format", "services/api"] {
+            let dir = project.root.join(member);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("sink.toml"),
+                "[dependencies]\n\"owner/repo:tool\" = { version = \"v1.0.0\", destination = \"bin\" }\n",
+            )
+            .unwrap();
+        }
+        fs::create_dir_all(project.root.join("tools/empty")).unwrap();
+        fs::write(
+            project.sink_toml_path(),
+            "[workspace]\nmembers = [\"tools/*\", \"services/api\"]\n\n[dependencies]\n\"owner/repo:tool\" = \"v1.0.0\"\n",
+        )
+        .unwrap();
+        let sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+        assert_eq!(
+            members(&sink_toml).unwrap().keys().collect::<Vec<_>>(),
+            vec!["services/api", "tools/format", "tools/lint"]
+        );
+
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        let installs = install(sink_toml, sink_lock, false, false, false).unwrap();
+        assert_eq!(installs.len(), 4);
+        assert!(installs.values().all(|(_, report)| report.is_ok()));
+        assert!(project.root.join("tool").is_file());
+        assert!(project.root.join("tools/lint/bin/tool").is_file());
+        assert!(project.root.join("services/api/bin/tool").is_file());
+
+        // All members are locked in the lockfile of the root
+        assert!(!project.root.join("tools/lint/sink.lock").exists());
+        let sink_toml = SinkTOML::from_file(&project.sink_toml_path()).unwrap();
+        let sink_lock = SinkLock::load(&sink_toml).unwrap();
+        assert_eq!(sink_lock.dependencies.len(), 1);
+        assert_eq!(sink_lock.members.len(), 3);
+        assert_eq!(
+            sink_lock
+                .member("tools/lint")
+                .dependencies
+                .values()
+                .next()
+                .unwrap()
+                .files[0]
+                .path,
+            PathBuf::from("bin/tool")
+        );
+
+        // Members must stay inside of the workspace
+        let mut outside = sink_toml;
+        outside.workspace = Some(Workspace {
+            members: vec![String::from("..")],
+        });
+        assert!(members(&outside).is_err());
+    }
+
+    #[test]
+    fn test_shared_api() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts the downloads, which always return the asset name.
+        #[derive(Default)]
+        struct CountingApi(AtomicUsize);
+        impl GitHubApi for CountingApi {
+            fn list_releases(&self, _: &str, _: usize) -> Result<Vec<GitHubRelease>> {
+                Ok(Vec::new())
+            }
+            fn view_release(&self, _: &str, _: Option<&str>) -> Result<GitHubRelease> {
+                Ok(GitHubRelease::default())
+            }
+            fn download_asset(&self, _: &str, _: &str, asset: &GitHubAsset) -> Result<Vec<u8>> {
+                self.0.fetch_add(1, Ordering::Relaxed);
+                Ok(asset.name.clone().into_bytes())
+            }
+            fn list_advisories(&self, _: &str) -> Result<Vec<GitHubAdvisory>> {
+                Ok(Vec::new())
+            }
+            fn view_license(&self, _: &str) -> Result<Option<String>> {
+                Ok(None)
+            }
+            fn view_file(&self, _: &str, _: &str, _: &str) -> Result<Vec<u8>> {
+                Ok(Vec::new())
+            }
+            fn view_status(&self) -> Result<GitHubStatus> {
+                Ok(GitHubStatus::default())
+            }
+        }
+
+        let counting = Arc::new(CountingApi::default());
+        let shared = SharedApi {
+            inner: counting.clone(),
+            releases: Mutex::new(HashMap::new()),
+            assets: Mutex::new(HashMap::new()),
+        };
+        let asset = |name: &str| GitHubAsset {
+            name: String::from(name),
+            ..Default::default()
+        };
+
+        for _ in 0..3 {
+            assert_eq!(
+                shared
+                    .download_asset("owner/repo", "v1.0.0", &asset("tool"))
+                    .unwrap(),
+                b"tool"
+            );
+        }
+        shared
+            .download_asset("owner/repo", "v2.0.0", &asset("tool"))
+            .unwrap();
+        shared
+            .download_asset("owner/repo", "v1.0.0", &asset("other"))
+            .unwrap();
+        assert_eq!(counting.0.load(Ordering::Relaxed), 3);
+    }
+}